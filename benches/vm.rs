@@ -0,0 +1,207 @@
+//! Times the VM on a few small scrolls, each stressing a different part of it.
+//! Run them with `cargo bench`, or `cargo bench -- <name>` for the ones matching a name.
+//!
+//! `--opcodes` also tells which opcodes every scroll runs, and what one costs on average.
+//! `--save <file>` keeps the timings, and `--compare <file>` fails the run when a scroll
+//! got slower than the kept timings by more than the tolerance.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    process::ExitCode,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use eira::{EiraVM, Program, compile, runtime::OpCode};
+
+const RUNS: usize = 5;
+
+/// How much slower than the kept timing a scroll may get before it counts as a regression
+const TOLERANCE: f64 = 0.10;
+
+const SCROLLS: &[(&str, &str)] = &[
+    // the dispatch loop and number arithmetic
+    ("count", "cycle i from 0 to 10000000 {\n    mark j = i + 1;\n}"),
+    // stepping a counter by hand
+    ("while", "{\n    mark i = 0;\n    while i < 10000000 {\n        i = i + 1;\n    }\n}"),
+    // reading and writing globals
+    ("globals", "mark total = 0;\ncycle i from 0 to 1000000 {\n    total = total + i;\n}"),
+    // casting spells and releasing from them
+    (
+        "fib",
+        "spell fib(n: Num):: Num {\n    fate n < 2 {\n        release n;\n    }\n    release (cast fib with n - 1) + (cast fib with n - 2);\n}\ncast fib with 30;",
+    ),
+    // casting deep down and releasing all the way back up
+    (
+        "calls",
+        "spell down(n: Num):: Num {\n    fate n == 0 {\n        release 0;\n    }\n    release (cast down with n - 1) + 1;\n}\ncycle i from 0 to 2000 {\n    cast down with 200;\n}",
+    ),
+    // building texts
+    ("concat", "mark text = \"\";\ncycle i from 0 to 10000 {\n    text = text + \"a\";\n}"),
+    // reading from decks
+    (
+        "deck",
+        "bind d: Deck<Num, 4> = [1, 2, 3, 4];\nmark sum = 0;\ncycle i from 0 to 1000000 {\n    sum = sum + d[i % 4];\n}",
+    ),
+];
+
+struct Options {
+    filter: Option<String>,
+    opcodes: bool,
+    save: Option<String>,
+    compare: Option<String>,
+}
+
+impl Options {
+    fn parse() -> Options {
+        let mut options = Options { filter: None, opcodes: false, save: None, compare: None };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--opcodes" => options.opcodes = true,
+                "--save" => options.save = args.next(),
+                "--compare" => options.compare = args.next(),
+                // the flags cargo passes along, like --bench
+                _ if arg.starts_with('-') => {}
+                _ => options.filter = Some(arg),
+            }
+        }
+        options
+    }
+
+    fn wants(&self, name: &str) -> bool {
+        self.filter.as_ref().is_none_or(|f| name.contains(f.as_str()))
+    }
+}
+
+/// The best of a few runs, so a hiccup of the machine doesn't count
+fn best_of(mut f: impl FnMut() -> Duration) -> f64 {
+    (0..RUNS).map(|_| f().as_secs_f64()).fold(f64::MAX, f64::min)
+}
+
+fn time_run(program: &Program) -> f64 {
+    best_of(|| {
+        let mut vm = EiraVM::new();
+        let start = Instant::now();
+        program.run(&mut vm).expect("runs");
+        start.elapsed()
+    })
+}
+
+fn time_compile(source: &str) -> f64 {
+    best_of(|| {
+        let start = Instant::now();
+        compile(source).expect("compiles");
+        start.elapsed()
+    })
+}
+
+/// A scroll of many spells and marks, so the compiler has something sizable to chew on
+fn large_scroll() -> String {
+    let mut source = String::new();
+    for i in 0..2000 {
+        source.push_str(&format!(
+            "spell step{i}(n: Num):: Num {{\n    mark m = n * {i};\n    fate m > 10 {{\n        release m - 1;\n    }}\n    release m + 1;\n}}\n"
+        ));
+    }
+    source.push_str("mark total = 0;\n");
+    for i in 0..2000 {
+        source.push_str(&format!("total = total + (cast step{i} with {i});\n"));
+    }
+    source
+}
+
+/// Counts the opcodes the scroll runs through a step hook, and splits the [best] time of the
+/// scroll between them. The hook costs far more than an opcode, so it can't time them itself.
+fn print_opcodes(program: &Program, best: f64) {
+    let counts: Rc<RefCell<HashMap<u8, u64>>> = Rc::default();
+
+    let mut vm = EiraVM::new();
+    {
+        let counts = counts.clone();
+        vm.set_step_hook(move |state| {
+            *counts.borrow_mut().entry(state.instruction.opcode() as u8).or_default() += 1;
+        });
+    }
+    program.run(&mut vm).expect("runs");
+
+    let counts = counts.borrow();
+    let total: u64 = counts.values().sum();
+    println!("    {} instructions, {:.1}ns each", total, best * 1e9 / total as f64);
+
+    let mut rows: Vec<_> = counts.iter().collect();
+    rows.sort_by(|a, b| b.1.cmp(a.1));
+    for (op, count) in rows {
+        let op = OpCode::try_from(*op).expect("opcode");
+        println!(
+            "    {:<16} {:>10} runs {:>5.1}%",
+            format!("{:?}", op),
+            count,
+            100.0 * *count as f64 / total as f64
+        );
+    }
+}
+
+/// The timings kept by an earlier `--save`, a `name seconds` per line
+fn read_timings(path: &str) -> HashMap<String, f64> {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| panic!("can't read {}: {}", path, e));
+    text.lines()
+        .filter_map(|line| {
+            let (name, secs) = line.split_once(' ')?;
+            Some((name.to_string(), secs.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn main() -> ExitCode {
+    let options = Options::parse();
+    let mut timings: Vec<(String, f64)> = vec![];
+
+    for (name, source) in SCROLLS {
+        if !options.wants(name) {
+            continue;
+        }
+
+        let program = compile(source).expect("compiles");
+        let best = time_run(&program);
+        println!("{:<10} {:>8.3}s (best of {})", name, best, RUNS);
+        timings.push((name.to_string(), best));
+
+        if options.opcodes {
+            print_opcodes(&program, best);
+        }
+    }
+
+    if options.wants("compile") {
+        let best = time_compile(&large_scroll());
+        println!("{:<10} {:>8.3}s (best of {})", "compile", best, RUNS);
+        timings.push(("compile".to_string(), best));
+    }
+
+    if let Some(path) = &options.save {
+        let text: String = timings.iter().map(|(name, secs)| format!("{} {}\n", name, secs)).collect();
+        fs::write(path, text).unwrap_or_else(|e| panic!("can't write {}: {}", path, e));
+    }
+
+    if let Some(path) = &options.compare {
+        let kept = read_timings(path);
+        let mut regressed = false;
+        for (name, secs) in &timings {
+            let Some(before) = kept.get(name) else {
+                continue;
+            };
+            let change = secs / before - 1.0;
+            if change > TOLERANCE {
+                println!("{} got {:.1}% slower ({:.3}s, was {:.3}s)", name, 100.0 * change, secs, before);
+                regressed = true;
+            }
+        }
+        if regressed {
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,12 @@
+//! Compiles whatever source it is given, the compiler should refuse the broken ones with
+//! errors instead of panicking or overflowing its stack.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        let _ = eira::compile(source);
+    }
+});
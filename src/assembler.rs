@@ -1,9 +1,101 @@
 use crate::runtime::Instruction;
 
+/// A place in the instructions of a spell for the jumps to land on. The code generator writes the
+/// jumps to labels, their offsets are only worked out by the assembler once the spell is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Label(pub usize);
+
 pub struct Assembler {}
 
 impl Assembler {
-    pub fn convert_to_byte_code(instructions: &Vec<Instruction>) -> Vec<u8> {
+    /// Writes the byte offsets into the jumps of [instructions], each jump lands on the instruction
+    /// [targets] gives for it (the length of the spell lands past its end).
+    ///
+    /// The first pass lays the instructions out, widening the jumps reaching further than two bytes
+    /// of offset can into their long forms. Widening pushes the instructions after it further apart,
+    /// so the pass runs again until they settle. The second pass writes the offsets.
+    pub fn link_jumps(instructions: &[Instruction], targets: &[Option<usize>]) -> Result<Vec<Instruction>, String> {
+        let mut instructions = instructions.to_vec();
+
+        let starts = loop {
+            let starts = Self::starts(&instructions);
+            let mut widened = false;
+            for (i, target) in targets.iter().enumerate() {
+                let Some(target) = *target else {
+                    continue;
+                };
+                let offset = Self::offset(&instructions[i], &starts, i, target)?;
+                if offset > u16::MAX as usize && Self::widen(&mut instructions[i]) {
+                    widened = true;
+                }
+            }
+            if !widened {
+                break starts;
+            }
+        };
+
+        for (i, target) in targets.iter().enumerate() {
+            let Some(target) = *target else {
+                continue;
+            };
+            let offset = Self::offset(&instructions[i], &starts, i, target)?;
+            let Ok(long) = u32::try_from(offset) else {
+                return Err("The magic is too complex(long) to jump over!".to_string());
+            };
+            match &mut instructions[i] {
+                Instruction::Jump { offset: o }
+                | Instruction::JumpIfFalse { offset: o, .. }
+                | Instruction::Loop { offset: o }
+                | Instruction::Attempt { offset: o, .. } => *o = long as u16,
+                Instruction::JumpLong { offset: o }
+                | Instruction::JumpIfFalseLong { offset: o, .. }
+                | Instruction::LoopLong { offset: o }
+                | Instruction::AttemptLong { offset: o, .. } => *o = long,
+                inst => return Err(format!("Expected a 'JUMP' instruction to land, got {:?}", inst)),
+            }
+        }
+
+        Ok(instructions)
+    }
+
+    /// The byte offset of each instruction, and the length of the code last
+    fn starts(instructions: &[Instruction]) -> Vec<usize> {
+        let mut starts = Vec::with_capacity(instructions.len() + 1);
+        let mut offset = 0;
+        for inst in instructions {
+            starts.push(offset);
+            offset += inst.len();
+        }
+        starts.push(offset);
+        starts
+    }
+
+    /// The offset taking the jump [inst] at [i] to [target], loops jump back from their own end
+    fn offset(inst: &Instruction, starts: &[usize], i: usize, target: usize) -> Result<usize, String> {
+        let end = starts[i + 1];
+        let Some(&start) = starts.get(target) else {
+            return Err(format!("The jump at {} lands outside of the spell", i));
+        };
+        let offset = match inst {
+            Instruction::Loop { .. } | Instruction::LoopLong { .. } => end.checked_sub(start),
+            _ => start.checked_sub(end),
+        };
+        offset.ok_or_else(|| format!("The jump at {} can't land on {}, it goes the other way", i, target))
+    }
+
+    /// Turns the jump [inst] into its long form, false if it already is
+    fn widen(inst: &mut Instruction) -> bool {
+        *inst = match *inst {
+            Instruction::Jump { .. } => Instruction::JumpLong { offset: 0 },
+            Instruction::JumpIfFalse { condition_reg, .. } => Instruction::JumpIfFalseLong { condition_reg, offset: 0 },
+            Instruction::Loop { .. } => Instruction::LoopLong { offset: 0 },
+            Instruction::Attempt { error_reg, .. } => Instruction::AttemptLong { error_reg, offset: 0 },
+            _ => return false,
+        };
+        true
+    }
+
+    pub fn convert_to_byte_code(instructions: &[Instruction]) -> Vec<u8> {
         let mut bc: Vec<u8> = vec![];
         for inst in instructions {
             let instruction_bytes = inst.get_byte_code();
@@ -11,4 +103,35 @@ impl Assembler {
         }
         bc
     }
+
+    /// Compresses the per instruction positions into (bytecode offset, line) pairs,
+    /// a pair is only added when the line changes
+    pub fn convert_line_table(instructions: &[Instruction], positions: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        let mut table: Vec<(usize, usize)> = vec![];
+        let mut offset = 0;
+        for (inst, (line, _)) in instructions.iter().zip(positions) {
+            if table.last().map(|(_, l)| l) != Some(line) {
+                table.push((offset, *line));
+            }
+            offset += inst.len();
+        }
+        table
+    }
+
+    /// Compresses the per instruction (line, column) positions into (bytecode offset, line, column)
+    /// triples, a triple is only added when the position changes
+    pub fn convert_position_table(
+        instructions: &[Instruction],
+        positions: &[(usize, usize)],
+    ) -> Vec<(usize, usize, usize)> {
+        let mut table: Vec<(usize, usize, usize)> = vec![];
+        let mut offset = 0;
+        for (inst, &(line, column)) in instructions.iter().zip(positions) {
+            if table.last().map(|&(_, l, c)| (l, c)) != Some((line, column)) {
+                table.push((offset, line, column));
+            }
+            offset += inst.len();
+        }
+        table
+    }
 }
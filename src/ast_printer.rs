@@ -2,6 +2,7 @@ use crate::compiler::{
     Expr, Stmt, WovenExpr, WovenStmt,
     mark::{EtchedMark, Mark, WovenEtchedMark, WovenMark},
     reagents::{Reagent, WovenReagent},
+    scanner::Token,
 };
 
 const PIPE: &str = "│   ";
@@ -32,6 +33,13 @@ impl AstPrinter {
         format!("{}{}", prefix, if is_last { EMPTY } else { PIPE })
     }
 
+    /// The name of a loop, or of the loop a sever or a flow leaves
+    fn label_of(label: &Option<Token>) -> String {
+        label
+            .as_ref()
+            .map_or(String::new(), |label| format!(" '{}'", label.lexeme))
+    }
+
     // ===== Parsed AST (Stmt/Expr) =====
 
     pub fn print_stmts(&mut self, stmts: &[Stmt]) -> String {
@@ -46,7 +54,7 @@ impl AstPrinter {
 
     fn print_stmt(&mut self, prefix: &str, stmt: &Stmt, is_last: bool) {
         match stmt {
-            Stmt::ExprStmt { expr } => {
+            Stmt::ExprStmt { expr, .. } => {
                 self.write(prefix, is_last, "ExprStmt");
                 self.print_expr(&Self::next_prefix(prefix, is_last), expr, true);
             }
@@ -55,6 +63,7 @@ impl AstPrinter {
                 mutable,
                 initializer,
                 weave,
+                ..
             } => {
                 let mut_str = if *mutable { "mut " } else { "" };
                 self.write(
@@ -77,6 +86,7 @@ impl AstPrinter {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 self.write(prefix, is_last, "Fate");
                 let next = Self::next_prefix(prefix, is_last);
@@ -96,19 +106,110 @@ impl AstPrinter {
                     self.print_stmt(&Self::next_prefix(&next, true), then_branch, true);
                 }
             }
-            Stmt::While { condition, body } => {
-                self.write(prefix, is_last, "While");
+            Stmt::While {
+                label,
+                condition,
+                body,
+                ..
+            } => {
+                self.write(prefix, is_last, &format!("While{}", Self::label_of(label)));
                 let next = Self::next_prefix(prefix, is_last);
                 self.write(&next, false, "condition:");
                 self.print_expr(&Self::next_prefix(&next, false), condition, true);
                 self.write(&next, true, "body:");
                 self.print_stmt(&Self::next_prefix(&next, true), body, true);
             }
-            Stmt::Chant { expression } => {
-                self.write(prefix, is_last, "Chant");
-                self.print_expr(&Self::next_prefix(prefix, is_last), expression, true);
+            Stmt::Cycle {
+                token: _,
+                label,
+                variable,
+                start,
+                end,
+                body,
+                ..
+            } => {
+                self.write(
+                    prefix,
+                    is_last,
+                    &format!("Cycle{}: {}", Self::label_of(label), variable.lexeme),
+                );
+                let next = Self::next_prefix(prefix, is_last);
+                self.write(&next, false, "from:");
+                self.print_expr(&Self::next_prefix(&next, false), start, true);
+                self.write(&next, false, "to:");
+                self.print_expr(&Self::next_prefix(&next, false), end, true);
+                self.write(&next, true, "body:");
+                self.print_stmt(&Self::next_prefix(&next, true), body, true);
+            }
+            Stmt::Circle {
+                label,
+                body,
+                condition,
+                ..
+            } => {
+                self.write(prefix, is_last, &format!("Circle{}", Self::label_of(label)));
+                let next = Self::next_prefix(prefix, is_last);
+                self.write(&next, false, "body:");
+                self.print_stmt(&Self::next_prefix(&next, false), body, true);
+                self.write(&next, true, "until:");
+                self.print_expr(&Self::next_prefix(&next, true), condition, true);
+            }
+            Stmt::FlowThrough {
+                token: _,
+                label,
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                self.write(
+                    prefix,
+                    is_last,
+                    &format!("FlowThrough{}: {}", Self::label_of(label), variable.lexeme),
+                );
+                let next = Self::next_prefix(prefix, is_last);
+                self.write(&next, false, "through:");
+                self.print_expr(&Self::next_prefix(&next, false), iterable, true);
+                self.write(&next, true, "body:");
+                self.print_stmt(&Self::next_prefix(&next, true), body, true);
+            }
+            Stmt::Judge {
+                token: _,
+                subject,
+                arms,
+                divert,
+                ..
+            } => {
+                self.write(prefix, is_last, "Judge");
+                let next = Self::next_prefix(prefix, is_last);
+                let no_arms = arms.is_empty() && divert.is_none();
+                self.write(&next, no_arms, "subject:");
+                self.print_expr(&Self::next_prefix(&next, no_arms), subject, true);
+                for (i, (value, body)) in arms.iter().enumerate() {
+                    let last = i == arms.len() - 1 && divert.is_none();
+                    self.write(&next, last, "arm:");
+                    let arm = Self::next_prefix(&next, last);
+                    self.print_expr(&arm, value, false);
+                    self.print_stmt(&arm, body, true);
+                }
+                if let Some(divert) = divert {
+                    self.write(&next, true, "divert:");
+                    self.print_stmt(&Self::next_prefix(&next, true), divert, true);
+                }
             }
-            Stmt::Block { statements } => {
+            Stmt::Chant {
+                expressions,
+                newline,
+                ..
+            } => {
+                self.write(prefix, is_last, if *newline { "Chant" } else { "ChantRaw" });
+                let next = Self::next_prefix(prefix, is_last);
+                let len = expressions.len();
+                for (i, e) in expressions.iter().enumerate() {
+                    self.print_expr(&next, e, i == len - 1);
+                }
+            }
+            Stmt::Block { statements, .. } => {
                 self.write(prefix, is_last, "Block");
                 let next = Self::next_prefix(prefix, is_last);
                 let len = statements.len();
@@ -116,11 +217,46 @@ impl AstPrinter {
                     self.print_stmt(&next, s, i == len - 1);
                 }
             }
-            Stmt::Sever { token: _ } => {
-                self.write(prefix, is_last, "Sever");
+            Stmt::Sever { token: _, label, .. } => {
+                self.write(prefix, is_last, &format!("Sever{}", Self::label_of(label)));
+            }
+            Stmt::Flow { token: _, label, .. } => {
+                self.write(prefix, is_last, &format!("Flow{}", Self::label_of(label)));
+            }
+            Stmt::Attempt {
+                token: _,
+                body,
+                error,
+                mend,
+                ..
+            } => {
+                self.write(prefix, is_last, "Attempt");
+                let next = Self::next_prefix(prefix, is_last);
+                self.write(&next, false, "body:");
+                self.print_stmt(&Self::next_prefix(&next, false), body, true);
+                self.write(&next, true, &format!("mend: {}", error.lexeme));
+                self.print_stmt(&Self::next_prefix(&next, true), mend, true);
+            }
+            Stmt::Doom { token: _, value, .. } => {
+                self.write(prefix, is_last, "Doom");
+                self.print_expr(&Self::next_prefix(prefix, is_last), value, true);
             }
-            Stmt::Flow { token: _ } => {
-                self.write(prefix, is_last, "Flow");
+            Stmt::Offer { token: _, value, .. } => {
+                self.write(prefix, is_last, "Offer");
+                self.print_expr(&Self::next_prefix(prefix, is_last), value, true);
+            }
+            Stmt::Expect {
+                token: _,
+                condition,
+                message,
+                ..
+            } => {
+                self.write(prefix, is_last, "Expect");
+                let next = Self::next_prefix(prefix, is_last);
+                self.print_expr(&next, condition, message.is_none());
+                if let Some(message) = message {
+                    self.print_expr(&next, message, true);
+                }
             }
             Stmt::Spell {
                 name,
@@ -128,6 +264,7 @@ impl AstPrinter {
                 body,
                 return_weave,
                 attuned_to,
+                ..
             } => {
                 let ret_str = if let Some(rw) = return_weave {
                     format!(" -> {}", rw.base.lexeme)
@@ -154,13 +291,13 @@ impl AstPrinter {
                 self.write(&next, true, "body:");
                 self.print_stmt(&Self::next_prefix(&next, true), body, true);
             }
-            Stmt::Release { token: _, expr } => {
+            Stmt::Release { token: _, expr, .. } => {
                 self.write(prefix, is_last, "Release");
                 if let Some(e) = expr {
                     self.print_expr(&Self::next_prefix(prefix, is_last), e, true);
                 }
             }
-            Stmt::Sign { name, marks } => {
+            Stmt::Sign { name, marks, .. } => {
                 self.write(prefix, is_last, &format!("Sign: {}", name.lexeme));
                 let next = Self::next_prefix(prefix, is_last);
                 let len = marks.len();
@@ -168,11 +305,35 @@ impl AstPrinter {
                     self.print_mark(&next, m, i == len - 1);
                 }
             }
-            Stmt::Vanish { target, token } => {
+            Stmt::Glyph { name, variants, .. } => {
+                self.write(prefix, is_last, &format!("Glyph: {}", name.lexeme));
+                let next = Self::next_prefix(prefix, is_last);
+                let len = variants.len();
+                for (i, v) in variants.iter().enumerate() {
+                    self.write(&next, i == len - 1, &format!("Variant: {}", v.lexeme));
+                }
+            }
+            Stmt::Seal {
+                name,
+                initializer,
+                weave,
+                ..
+            } => {
+                self.write(prefix, is_last, &format!("Seal: {}", name.lexeme));
+                self.print_expr(&Self::next_prefix(prefix, is_last), initializer, weave.is_none());
+                if let Some(weave) = weave {
+                    self.write(
+                        &Self::next_prefix(prefix, is_last),
+                        true,
+                        &format!("weave: {}", weave.base.lexeme),
+                    );
+                }
+            }
+            Stmt::Vanish { target, token, .. } => {
                 self.write(prefix, is_last, &format!("Vanish: {}", token.lexeme));
                 self.print_expr(&Self::next_prefix(prefix, is_last), target, true);
             }
-            Stmt::Attune { sign, spells } => {
+            Stmt::Attune { sign, spells, .. } => {
                 self.write(prefix, is_last, &format!("Attune: {}", sign.lexeme));
                 let next = Self::next_prefix(prefix, is_last);
                 let len = spells.len();
@@ -180,11 +341,38 @@ impl AstPrinter {
                     self.print_stmt(&next, s, i == len - 1);
                 }
             }
+            Stmt::Tome {
+                name,
+                origin,
+                marks,
+                spells,
+                ..
+            } => {
+                let origin_str = if let Some(o) = origin {
+                    format!(" refers {}", o.lexeme)
+                } else {
+                    String::new()
+                };
+                self.write(
+                    prefix,
+                    is_last,
+                    &format!("Tome: {}{}", name.lexeme, origin_str),
+                );
+                let next = Self::next_prefix(prefix, is_last);
+                let len = marks.len() + spells.len();
+                for (i, m) in marks.iter().enumerate() {
+                    self.print_mark(&next, m, i == len - 1);
+                }
+                for (i, s) in spells.iter().enumerate() {
+                    self.print_stmt(&next, s, marks.len() + i == len - 1);
+                }
+            }
             Stmt::Tether {
                 token,
                 path,
                 bind_to,
                 is_path,
+                ..
             } => {
                 let bind_str = if let Some(bt) = bind_to {
                     format!(" bind to {}", bt.lexeme)
@@ -192,10 +380,10 @@ impl AstPrinter {
                     String::new()
                 };
                 let path_str = if *is_path {
-                    path[0].lexeme.clone()
+                    path[0].lexeme.to_string()
                 } else {
                     path.iter()
-                        .map(|t| t.lexeme.clone())
+                        .map(|t| t.lexeme.to_string())
                         .collect::<Vec<_>>()
                         .join(".")
                 };
@@ -214,56 +402,76 @@ impl AstPrinter {
                 left,
                 right,
                 operator,
+                ..
             } => {
                 self.write(prefix, is_last, &format!("Binary: {}", operator.lexeme));
                 let next = Self::next_prefix(prefix, is_last);
                 self.print_expr(&next, left, false);
                 self.print_expr(&next, right, true);
             }
-            Expr::Unary { operand, operator } => {
+            Expr::Unary { operand, operator, .. } => {
                 self.write(prefix, is_last, &format!("Unary: {}", operator.lexeme));
                 self.print_expr(&Self::next_prefix(prefix, is_last), operand, true);
             }
-            Expr::Literal { value, token: _ } => {
+            Expr::Literal { value, token: _, .. } => {
                 self.write(prefix, is_last, &format!("Literal: {:?}", value));
             }
-            Expr::Variable { name } => {
+            Expr::Variable { name, .. } => {
                 self.write(prefix, is_last, &format!("Variable: {}", name.lexeme));
             }
-            Expr::Grouping { expression } => {
+            Expr::Grouping { expression, .. } => {
                 self.write(prefix, is_last, "Grouping");
                 self.print_expr(&Self::next_prefix(prefix, is_last), expression, true);
             }
-            Expr::Assignment { name, value } => {
+            Expr::Interpolation { value, .. } => {
+                self.write(prefix, is_last, "Interpolation");
+                self.print_expr(&Self::next_prefix(prefix, is_last), value, true);
+            }
+            Expr::Assignment { name, value, .. } => {
                 self.write(prefix, is_last, &format!("Assign: {}", name.lexeme));
                 self.print_expr(&Self::next_prefix(prefix, is_last), value, true);
             }
             Expr::Cast {
                 reagents,
+                named,
                 callee,
                 token: _,
+                ..
             } => {
                 self.write(prefix, is_last, &format!("Cast",));
                 self.print_expr(&Self::next_prefix(prefix, is_last), callee, true);
                 let next = Self::next_prefix(prefix, is_last);
                 let len = reagents.len();
                 for (i, r) in reagents.iter().enumerate() {
-                    self.print_expr(&next, r, i == len - 1);
+                    self.print_expr(&next, r, i == len - 1 && named.is_empty());
+                }
+                for (i, (name, r)) in named.iter().enumerate() {
+                    let last = i == named.len() - 1;
+                    self.write(&next, last, &format!("{}:", name.lexeme));
+                    self.print_expr(&Self::next_prefix(&next, last), r, true);
                 }
             }
-            Expr::Draw { marks, callee } => {
+            Expr::Draw {
+                reagents,
+                marks,
+                callee,
+                ..
+            } => {
                 self.write(prefix, is_last, &format!("Draw: {}", callee.lexeme));
                 let next = Self::next_prefix(prefix, is_last);
+                for (i, r) in reagents.iter().enumerate() {
+                    self.print_expr(&next, r, i == reagents.len() - 1 && marks.is_empty());
+                }
                 let len = marks.len();
                 for (i, m) in marks.iter().enumerate() {
                     self.print_etched_mark(&next, m, i == len - 1);
                 }
             }
-            Expr::Access { material, property } => {
+            Expr::Access { material, property, .. } => {
                 self.write(prefix, is_last, &format!("Access: .{}", property.lexeme));
                 self.print_expr(&Self::next_prefix(prefix, is_last), material, true);
             }
-            Expr::Deck { elements, token: _ } => {
+            Expr::Deck { elements, token: _, .. } => {
                 self.write(prefix, is_last, "Deck");
                 let next = Self::next_prefix(prefix, is_last);
                 if elements.is_empty() {
@@ -279,17 +487,32 @@ impl AstPrinter {
                 deck,
                 index,
                 token: _,
+                ..
             } => {
                 self.write(prefix, is_last, "Extract");
                 let next = Self::next_prefix(prefix, is_last);
                 self.print_expr(&next, deck, false);
                 self.print_expr(&next, index, true);
             }
+            Expr::Slice {
+                target,
+                start,
+                end,
+                token: _,
+                ..
+            } => {
+                self.write(prefix, is_last, "Slice");
+                let next = Self::next_prefix(prefix, is_last);
+                self.print_expr(&next, target, false);
+                self.print_expr(&next, start, false);
+                self.print_expr(&next, end, true);
+            }
             Expr::DeckSet {
                 deck,
                 index,
                 value,
                 token: _,
+                ..
             } => {
                 self.write(prefix, is_last, "DeckSet");
                 let next = Self::next_prefix(prefix, is_last);
@@ -301,21 +524,22 @@ impl AstPrinter {
                 material,
                 property,
                 value,
+                ..
             } => {
                 self.write(prefix, is_last, &format!("FieldSet: .{}", property.lexeme));
                 let next = Self::next_prefix(prefix, is_last);
                 self.print_expr(&next, material, false);
                 self.print_expr(&next, value, true);
             }
-            Expr::Blank { token } => {
+            Expr::Blank { token, .. } => {
                 self.write(prefix, is_last, &format!("Blank: {}", token.lexeme));
             }
-            Expr::Manifests { value, token } => {
+            Expr::Manifests { value, token, .. } => {
                 self.write(prefix, is_last, &format!("Manifests: {}", token.lexeme));
                 let next = Self::next_prefix(prefix, is_last);
                 self.print_expr(&next, value, true);
             }
-            Expr::SafeAccess { material, property } => {
+            Expr::SafeAccess { material, property, .. } => {
                 self.write(
                     prefix,
                     is_last,
@@ -323,18 +547,43 @@ impl AstPrinter {
                 );
                 self.print_expr(&Self::next_prefix(prefix, is_last), material, true);
             }
-            Expr::AssertSafe { operand, operator } => {
+            Expr::AssertSafe { operand, operator, .. } => {
                 self.write(prefix, is_last, &format!("AssertSafe: {}", operator.lexeme));
                 self.print_expr(&Self::next_prefix(prefix, is_last), operand, true);
             }
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                token: _,
+                ..
+            } => {
+                self.write(prefix, is_last, "Conditional");
+                let next = Self::next_prefix(prefix, is_last);
+                self.print_expr(&next, condition, false);
+                self.print_expr(&next, then_branch, false);
+                self.print_expr(&next, else_branch, true);
+            }
+            Expr::Heed { token: _, .. } => {
+                self.write(prefix, is_last, "Heed");
+            }
+            Expr::Conjure { token: _, cast, .. } => {
+                self.write(prefix, is_last, "Conjure");
+                self.print_expr(&Self::next_prefix(prefix, is_last), cast, true);
+            }
+            Expr::Claim { token: _, conjuring, .. } => {
+                self.write(prefix, is_last, "Claim");
+                self.print_expr(&Self::next_prefix(prefix, is_last), conjuring, true);
+            }
         }
     }
 
     fn print_reagent(&mut self, prefix: &str, reagent: &Reagent, is_last: bool) {
+        let gathered = if reagent.gathered { "..." } else { "" };
         self.write(
             prefix,
             is_last,
-            &format!("{}: {}", reagent.name.lexeme, reagent.weave.base.lexeme),
+            &format!("{}: {}{}", reagent.name.lexeme, gathered, reagent.weave.base.lexeme),
         );
     }
 
@@ -344,6 +593,9 @@ impl AstPrinter {
             is_last,
             &format!("{}: {}", mark.name.lexeme, mark.parsed_weave.base.lexeme),
         );
+        if let Some(default) = &mark.default {
+            self.print_expr(&Self::next_prefix(prefix, is_last), default, true);
+        }
     }
 
     fn print_etched_mark(&mut self, prefix: &str, mark: &EtchedMark, is_last: bool) {
@@ -365,7 +617,7 @@ impl AstPrinter {
 
     fn print_woven_stmt(&mut self, prefix: &str, stmt: &WovenStmt, is_last: bool) {
         match stmt {
-            WovenStmt::ExprStmt { expr } => {
+            WovenStmt::ExprStmt { expr, .. } => {
                 self.write(prefix, is_last, "ExprStmt");
                 self.print_woven_expr(&Self::next_prefix(prefix, is_last), expr, true);
             }
@@ -374,6 +626,7 @@ impl AstPrinter {
                 mutable,
                 initializer,
                 symbol,
+                ..
             } => {
                 let mut_str = if *mutable { "mut " } else { "" };
                 let sym_info = self.symbol_info(symbol);
@@ -390,6 +643,7 @@ impl AstPrinter {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 self.write(prefix, is_last, "Fate");
                 let next = Self::next_prefix(prefix, is_last);
@@ -409,19 +663,119 @@ impl AstPrinter {
                     self.print_woven_stmt(&Self::next_prefix(&next, true), then_branch, true);
                 }
             }
-            WovenStmt::While { condition, body } => {
-                self.write(prefix, is_last, "While");
+            WovenStmt::While {
+                label,
+                condition,
+                body,
+                ..
+            } => {
+                self.write(prefix, is_last, &format!("While{}", Self::label_of(label)));
                 let next = Self::next_prefix(prefix, is_last);
                 self.write(&next, false, "condition:");
                 self.print_woven_expr(&Self::next_prefix(&next, false), condition, true);
                 self.write(&next, true, "body:");
                 self.print_woven_stmt(&Self::next_prefix(&next, true), body, true);
             }
-            WovenStmt::Chant { expression } => {
-                self.write(prefix, is_last, "Chant");
-                self.print_woven_expr(&Self::next_prefix(prefix, is_last), expression, true);
+            WovenStmt::Cycle {
+                token: _,
+                label,
+                variable,
+                start,
+                end,
+                end_symbol: _,
+                body,
+                ..
+            } => {
+                self.write(
+                    prefix,
+                    is_last,
+                    &format!(
+                        "Cycle{}: {} [{:?}]",
+                        Self::label_of(label),
+                        variable.name,
+                        variable.weave
+                    ),
+                );
+                let next = Self::next_prefix(prefix, is_last);
+                self.write(&next, false, "from:");
+                self.print_woven_expr(&Self::next_prefix(&next, false), start, true);
+                self.write(&next, false, "to:");
+                self.print_woven_expr(&Self::next_prefix(&next, false), end, true);
+                self.write(&next, true, "body:");
+                self.print_woven_stmt(&Self::next_prefix(&next, true), body, true);
+            }
+            WovenStmt::Circle {
+                label,
+                body,
+                condition,
+                ..
+            } => {
+                self.write(prefix, is_last, &format!("Circle{}", Self::label_of(label)));
+                let next = Self::next_prefix(prefix, is_last);
+                self.write(&next, false, "body:");
+                self.print_woven_stmt(&Self::next_prefix(&next, false), body, true);
+                self.write(&next, true, "until:");
+                self.print_woven_expr(&Self::next_prefix(&next, true), condition, true);
+            }
+            WovenStmt::FlowThrough {
+                label,
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                self.write(
+                    prefix,
+                    is_last,
+                    &format!(
+                        "FlowThrough{}: {} [{:?}]",
+                        Self::label_of(label),
+                        variable.name,
+                        variable.weave
+                    ),
+                );
+                let next = Self::next_prefix(prefix, is_last);
+                self.write(&next, false, "through:");
+                self.print_woven_expr(&Self::next_prefix(&next, false), iterable, true);
+                self.write(&next, true, "body:");
+                self.print_woven_stmt(&Self::next_prefix(&next, true), body, true);
             }
-            WovenStmt::Block { statements } => {
+            WovenStmt::Judge {
+                subject,
+                arms,
+                divert,
+                ..
+            } => {
+                self.write(prefix, is_last, "Judge");
+                let next = Self::next_prefix(prefix, is_last);
+                let no_arms = arms.is_empty() && divert.is_none();
+                self.write(&next, no_arms, "subject:");
+                self.print_woven_expr(&Self::next_prefix(&next, no_arms), subject, true);
+                for (i, (value, body)) in arms.iter().enumerate() {
+                    let last = i == arms.len() - 1 && divert.is_none();
+                    self.write(&next, last, "arm:");
+                    let arm = Self::next_prefix(&next, last);
+                    self.print_woven_expr(&arm, value, false);
+                    self.print_woven_stmt(&arm, body, true);
+                }
+                if let Some(divert) = divert {
+                    self.write(&next, true, "divert:");
+                    self.print_woven_stmt(&Self::next_prefix(&next, true), divert, true);
+                }
+            }
+            WovenStmt::Chant {
+                expressions,
+                newline,
+                ..
+            } => {
+                self.write(prefix, is_last, if *newline { "Chant" } else { "ChantRaw" });
+                let next = Self::next_prefix(prefix, is_last);
+                let len = expressions.len();
+                for (i, e) in expressions.iter().enumerate() {
+                    self.print_woven_expr(&next, e, i == len - 1);
+                }
+            }
+            WovenStmt::Block { statements, .. } => {
                 self.write(prefix, is_last, "Block");
                 let next = Self::next_prefix(prefix, is_last);
                 let len = statements.len();
@@ -429,17 +783,51 @@ impl AstPrinter {
                     self.print_woven_stmt(&next, s, i == len - 1);
                 }
             }
-            WovenStmt::Sever { token: _ } => {
-                self.write(prefix, is_last, "Sever");
+            WovenStmt::Sever { token: _, label, .. } => {
+                self.write(prefix, is_last, &format!("Sever{}", Self::label_of(label)));
             }
-            WovenStmt::Flow { token: _ } => {
-                self.write(prefix, is_last, "Flow");
+            WovenStmt::Flow { token: _, label, .. } => {
+                self.write(prefix, is_last, &format!("Flow{}", Self::label_of(label)));
+            }
+            WovenStmt::Attempt {
+                token: _,
+                body,
+                error_symbol,
+                mend,
+                ..
+            } => {
+                self.write(prefix, is_last, "Attempt");
+                let next = Self::next_prefix(prefix, is_last);
+                self.write(&next, false, "body:");
+                self.print_woven_stmt(&Self::next_prefix(&next, false), body, true);
+                self.write(&next, true, &format!("mend: {}", error_symbol.name));
+                self.print_woven_stmt(&Self::next_prefix(&next, true), mend, true);
+            }
+            WovenStmt::Doom { token: _, value, .. } => {
+                self.write(prefix, is_last, "Doom");
+                self.print_woven_expr(&Self::next_prefix(prefix, is_last), value, true);
+            }
+            WovenStmt::Offer { token: _, value, .. } => {
+                self.write(prefix, is_last, "Offer");
+                self.print_woven_expr(&Self::next_prefix(prefix, is_last), value, true);
+            }
+            WovenStmt::Expect {
+                token: _,
+                condition,
+                message,
+                ..
+            } => {
+                self.write(prefix, is_last, "Expect");
+                let next = Self::next_prefix(prefix, is_last);
+                self.print_woven_expr(&next, condition, false);
+                self.print_woven_expr(&next, message, true);
             }
             WovenStmt::Spell {
                 name,
                 reagents,
                 body,
                 spell_symbol,
+                ..
             } => {
                 let ret_str = format!(
                     " -> {:?}",
@@ -467,7 +855,7 @@ impl AstPrinter {
                 self.write(&next, true, "body:");
                 self.print_woven_stmt(&Self::next_prefix(&next, true), body, true);
             }
-            WovenStmt::Release { token: _, expr } => {
+            WovenStmt::Release { token: _, expr, .. } => {
                 self.write(prefix, is_last, "Release");
                 if let Some(e) = expr {
                     self.print_woven_expr(&Self::next_prefix(prefix, is_last), e, true);
@@ -477,6 +865,7 @@ impl AstPrinter {
                 name,
                 marks,
                 sign_symbol,
+                ..
             } => {
                 let info_str = if self.verbosity >= 1 {
                     let info = sign_symbol.kind.borrow().get_sign_info().unwrap();
@@ -499,7 +888,25 @@ impl AstPrinter {
                     self.print_woven_mark(&next, m, i == len - 1);
                 }
             }
-            WovenStmt::Attune { sign, spells } => {
+            WovenStmt::Glyph { name, glyph_symbol, .. } => {
+                self.write(prefix, is_last, &format!("Glyph: {}", name.lexeme));
+                let next = Self::next_prefix(prefix, is_last);
+                let info = glyph_symbol.kind.borrow().get_glyph_info().unwrap();
+                let len = info.variants.len();
+                for (i, v) in info.variants.iter().enumerate() {
+                    self.write(&next, i == len - 1, &format!("Variant: {} [{}]", v, i));
+                }
+            }
+            WovenStmt::Seal { name, seal_symbol, .. } => {
+                let value = seal_symbol.kind.borrow().get_seal_value().unwrap();
+                let sym_info = self.symbol_info(seal_symbol);
+                self.write(
+                    prefix,
+                    is_last,
+                    &format!("Seal: {}{} = {:?}", name.lexeme, sym_info, value),
+                );
+            }
+            WovenStmt::Attune { sign, spells, .. } => {
                 self.write(prefix, is_last, &format!("Attune: {}", sign.lexeme));
                 let next = Self::next_prefix(prefix, is_last);
                 let len = spells.len();
@@ -507,10 +914,44 @@ impl AstPrinter {
                     self.print_woven_stmt(&next, s, i == len - 1);
                 }
             }
+            WovenStmt::Tome {
+                name,
+                marks,
+                spells,
+                tome_symbol,
+                inherited,
+                ..
+            } => {
+                let info_str = if self.verbosity >= 1 {
+                    let info = tome_symbol.kind.borrow().get_sign_info().unwrap();
+                    format!(
+                        " [slot:{}, fields:{}, inherited spells:{}]",
+                        tome_symbol.slot_idx,
+                        info.schema.field_count(),
+                        inherited.len()
+                    )
+                } else {
+                    String::new()
+                };
+                self.write(
+                    prefix,
+                    is_last,
+                    &format!("Tome: {}{}", name.lexeme, info_str),
+                );
+                let next = Self::next_prefix(prefix, is_last);
+                let len = marks.len() + spells.len();
+                for (i, m) in marks.iter().enumerate() {
+                    self.print_woven_mark(&next, m, i == len - 1);
+                }
+                for (i, s) in spells.iter().enumerate() {
+                    self.print_woven_stmt(&next, s, marks.len() + i == len - 1);
+                }
+            }
             WovenStmt::Tether {
                 statements:_,
                 bind_to,
-                path
+                path,
+                ..
             } => {
                 let bind_str = if let Some(bt) = bind_to {
                     format!(" bind to {}", bt.lexeme)
@@ -534,6 +975,7 @@ impl AstPrinter {
                 right,
                 operator,
                 weave,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 self.write(
@@ -549,6 +991,7 @@ impl AstPrinter {
                 operand,
                 operator,
                 weave,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 self.write(
@@ -562,6 +1005,7 @@ impl AstPrinter {
                 value,
                 token: _,
                 weave,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 self.write(prefix, is_last, &format!("Literal: {:?}{}", value, tap));
@@ -570,6 +1014,7 @@ impl AstPrinter {
                 name,
                 weave,
                 symbol,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 let sym = self.symbol_info(symbol);
@@ -579,7 +1024,7 @@ impl AstPrinter {
                     &format!("Variable: {}{}{}", name.lexeme, sym, tap),
                 );
             }
-            WovenExpr::Grouping { expression, weave } => {
+            WovenExpr::Grouping { expression, weave, .. } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 self.write(prefix, is_last, &format!("Grouping{}", tap));
                 self.print_woven_expr(&Self::next_prefix(prefix, is_last), expression, true);
@@ -589,6 +1034,7 @@ impl AstPrinter {
                 value,
                 weave,
                 symbol,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 let sym = self.symbol_info(symbol);
@@ -604,6 +1050,7 @@ impl AstPrinter {
                 callee,
                 weave,
                 spell_symbol,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 let sym = self.symbol_info(spell_symbol);
@@ -618,11 +1065,32 @@ impl AstPrinter {
                     self.print_woven_expr(&next, r, i == len - 1);
                 }
             }
+            WovenExpr::Invoke {
+                reagents,
+                callee,
+                weave,
+                spell_symbol,
+                ..
+            } => {
+                let tap = self.tapestry_info(&weave.get_tapestry());
+                let sym = self.symbol_info(spell_symbol);
+                self.write(
+                    prefix,
+                    is_last,
+                    &format!("Invoke: {}{}{}", callee.lexeme, sym, tap),
+                );
+                let next = Self::next_prefix(prefix, is_last);
+                let len = reagents.len();
+                for (i, r) in reagents.iter().enumerate() {
+                    self.print_woven_expr(&next, r, i == len - 1);
+                }
+            }
             WovenExpr::Draw {
                 marks,
                 callee,
                 weave,
                 sign_symbol,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 let info_str = if self.verbosity >= 1 {
@@ -656,6 +1124,7 @@ impl AstPrinter {
                 property,
                 field_name_idx,
                 weave,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 let idx_str = if self.verbosity >= 1 {
@@ -670,7 +1139,7 @@ impl AstPrinter {
                 );
                 self.print_woven_expr(&Self::next_prefix(prefix, is_last), material, true);
             }
-            WovenExpr::Deck { elements, weave } => {
+            WovenExpr::Deck { elements, weave, .. } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 self.write(prefix, is_last, &format!("Deck{}", tap));
                 let next = Self::next_prefix(prefix, is_last);
@@ -688,6 +1157,7 @@ impl AstPrinter {
                 index,
                 token: _,
                 weave,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 self.write(prefix, is_last, &format!("Extract{}", tap));
@@ -695,12 +1165,28 @@ impl AstPrinter {
                 self.print_woven_expr(&next, deck, false);
                 self.print_woven_expr(&next, index, true);
             }
+            WovenExpr::Slice {
+                target,
+                start,
+                end,
+                token: _,
+                weave,
+                ..
+            } => {
+                let tap = self.tapestry_info(&weave.get_tapestry());
+                self.write(prefix, is_last, &format!("Slice{}", tap));
+                let next = Self::next_prefix(prefix, is_last);
+                self.print_woven_expr(&next, target, false);
+                self.print_woven_expr(&next, start, false);
+                self.print_woven_expr(&next, end, true);
+            }
             WovenExpr::DeckSet {
                 deck,
                 index,
                 value,
                 token: _,
                 weave,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 self.write(prefix, is_last, &format!("DeckSet{}", tap));
@@ -715,6 +1201,7 @@ impl AstPrinter {
                 value,
                 field_name_idx,
                 weave,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 let idx_str = if self.verbosity >= 1 {
@@ -735,6 +1222,7 @@ impl AstPrinter {
                 value,
                 token,
                 weave,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 self.write(
@@ -750,6 +1238,7 @@ impl AstPrinter {
                 property,
                 field_name_idx,
                 weave,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 let idx_str = if self.verbosity >= 1 {
@@ -768,6 +1257,7 @@ impl AstPrinter {
                 operand,
                 operator,
                 weave,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 self.write(
@@ -782,6 +1272,7 @@ impl AstPrinter {
                 callee,
                 weave,
                 native_spell,
+                ..
             } => {
                 let tap = self.tapestry_info(&weave.get_tapestry());
                 let sym = native_spell;
@@ -796,6 +1287,40 @@ impl AstPrinter {
                     self.print_woven_expr(&next, r, i == len - 1);
                 }
             }
+            WovenExpr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                token: _,
+                weave,
+                ..
+            } => {
+                let tap = self.tapestry_info(&weave.get_tapestry());
+                self.write(prefix, is_last, &format!("Conditional{}", tap));
+                let next = Self::next_prefix(prefix, is_last);
+                self.print_woven_expr(&next, condition, false);
+                self.print_woven_expr(&next, then_branch, false);
+                self.print_woven_expr(&next, else_branch, true);
+            }
+            WovenExpr::Heed { token: _, .. } => {
+                let tap = self.tapestry_info(&expr.weave().get_tapestry());
+                self.write(prefix, is_last, &format!("Heed{}", tap));
+            }
+            WovenExpr::ToText { token: _, value, .. } => {
+                let tap = self.tapestry_info(&expr.weave().get_tapestry());
+                self.write(prefix, is_last, &format!("ToText{}", tap));
+                self.print_woven_expr(&Self::next_prefix(prefix, is_last), value, true);
+            }
+            WovenExpr::Conjure { token: _, cast, weave, .. } => {
+                let tap = self.tapestry_info(&weave.get_tapestry());
+                self.write(prefix, is_last, &format!("Conjure{}", tap));
+                self.print_woven_expr(&Self::next_prefix(prefix, is_last), cast, true);
+            }
+            WovenExpr::Claim { token: _, conjuring, weave, .. } => {
+                let tap = self.tapestry_info(&weave.get_tapestry());
+                self.write(prefix, is_last, &format!("Claim{}", tap));
+                self.print_woven_expr(&Self::next_prefix(prefix, is_last), conjuring, true);
+            }
         }
     }
 
@@ -851,3 +1376,13 @@ pub fn print_woven_ast(stmts: &[WovenStmt], verbosity: u8) {
         println!("{}", printer.print_woven_stmts(stmts));
     }
 }
+
+/// Renders the AST as JSON, every node is tagged with its `kind`
+pub fn ast_to_json(stmts: &[Stmt]) -> String {
+    serde_json::to_string_pretty(stmts).expect("the AST is always serializable")
+}
+
+/// Renders the woven AST as JSON, along with the weaves and the resolved symbols
+pub fn woven_ast_to_json(stmts: &[WovenStmt]) -> String {
+    serde_json::to_string_pretty(stmts).expect("the woven AST is always serializable")
+}
@@ -0,0 +1,6 @@
+use std::io;
+
+/// The language server of eira, the editors talk to it over its standard streams
+fn main() -> io::Result<()> {
+    eira::lsp::serve(io::stdin().lock(), io::stdout().lock())
+}
@@ -0,0 +1,442 @@
+use std::{path::Path, rc::Rc};
+
+use crate::{
+    compiler::compiler::CompiledCode,
+    runtime::verifier::MAX_SPELL_DEPTH,
+    values::{
+        glyph::GlyphObject,
+        native_spell::NativeSpell,
+        sign::SignSchema,
+        spell::{Capture, ClosureObject, DebugInfo, LocalSymbol, SpellObject},
+        value::Value,
+    },
+};
+
+/// Every precompiled scroll (.eirac) starts with these bytes
+pub const MAGIC: &[u8; 4] = b"EIRA";
+
+/// Bumped whenever the layout of the file or the instruction set changes
+pub const FORMAT_VERSION: u16 = 17;
+
+// tags of the constants
+const TAG_NUMBER: u8 = 0;
+const TAG_STRING: u8 = 1;
+const TAG_BOOL: u8 = 2;
+const TAG_EMPTINESS: u8 = 3;
+const TAG_SPELL: u8 = 4;
+const TAG_CLOSURE: u8 = 5;
+const TAG_SIGN_SCHEMA: u8 = 6;
+const TAG_GLYPH: u8 = 7;
+const TAG_NATIVE_SPELL: u8 = 8;
+const TAG_INT: u8 = 9;
+
+/// Turns the compiled code into the bytes of a .eirac file
+pub fn serialize(code: &CompiledCode) -> Result<Vec<u8>, String> {
+    let mut writer = Writer { bytes: vec![] };
+    writer.bytes.extend_from_slice(MAGIC);
+    writer.write_u16(FORMAT_VERSION);
+
+    // the origin of the scroll is stored like any other spell
+    writer.write_spell(&SpellObject {
+        name: None,
+        arity: 0,
+        captures: vec![],
+        constants: code.constants.clone(),
+        bytecode: code.bytecode.clone(),
+        flowing: false,
+        lines: code.lines.clone(),
+        register_count: code.register_count,
+        debug: code.debug.clone(),
+    })?;
+
+    writer.write_names(&code.globals)?;
+    writer.write_names(&code.symbols)?;
+
+    Ok(writer.bytes)
+}
+
+/// Reads the compiled code back from the bytes of a .eirac file
+pub fn deserialize(bytes: &[u8]) -> Result<CompiledCode, String> {
+    let mut reader = Reader { bytes, pos: 0, depth: 0 };
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err("The scroll is not a precompiled eira scroll.".to_string());
+    }
+
+    let version = reader.read_u16()?;
+    if version != FORMAT_VERSION {
+        return Err(format!(
+            "The scroll was precompiled with format version {}, but this eira reads version {}.",
+            version, FORMAT_VERSION
+        ));
+    }
+
+    let origin = reader.read_spell()?;
+
+    let globals = reader.read_names()?;
+    let symbols = reader.read_names()?;
+
+    if reader.pos != bytes.len() {
+        return Err("The precompiled scroll has trailing bytes.".to_string());
+    }
+
+    Ok(CompiledCode {
+        bytecode: origin.bytecode,
+        instructions: vec![],
+        constants: origin.constants,
+        lines: origin.lines,
+        register_count: origin.register_count,
+        debug: origin.debug,
+        globals,
+        symbols,
+        warnings: vec![],
+    })
+}
+
+pub fn write_file(code: &CompiledCode, path: &Path) -> Result<(), String> {
+    let bytes = serialize(code)?;
+    std::fs::write(path, bytes)
+        .map_err(|e| format!("Couldn't write the scroll '{}': {}", path.display(), e))
+}
+
+pub fn read_file(path: &Path) -> Result<CompiledCode, String> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| format!("Couldn't read the scroll '{}': {}", path.display(), e))?;
+    deserialize(&bytes)
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: usize) -> Result<(), String> {
+        let value = u32::try_from(value)
+            .map_err(|_| format!("The value {} is too large to be precompiled.", value))?;
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn write_str(&mut self, value: &str) -> Result<(), String> {
+        self.write_u32(value.len())?;
+        self.bytes.extend_from_slice(value.as_bytes());
+        Ok(())
+    }
+
+    fn write_names(&mut self, names: &[String]) -> Result<(), String> {
+        self.write_u32(names.len())?;
+        for name in names {
+            self.write_str(name)?;
+        }
+        Ok(())
+    }
+
+    fn write_spell(&mut self, spell: &SpellObject) -> Result<(), String> {
+        match &spell.name {
+            Some(name) => {
+                self.write_u8(1);
+                self.write_str(name)?;
+            }
+            None => self.write_u8(0),
+        }
+        self.write_u8(spell.arity);
+        self.write_u8(spell.flowing as u8);
+        self.write_u32(spell.register_count)?;
+
+        self.write_u32(spell.captures.len())?;
+        for capture in &spell.captures {
+            match capture {
+                Capture::Local(reg) => {
+                    self.write_u8(0);
+                    self.write_u8(*reg);
+                }
+                Capture::Enclosing(index) => {
+                    self.write_u8(1);
+                    self.write_u8(*index);
+                }
+            }
+        }
+
+        self.write_u32(spell.bytecode.len())?;
+        self.bytes.extend_from_slice(&spell.bytecode);
+
+        self.write_u32(spell.lines.len())?;
+        for (offset, line) in &spell.lines {
+            self.write_u32(*offset)?;
+            self.write_u32(*line)?;
+        }
+
+        // the debug info is optional, a scroll compiled without it only knows its lines
+        match &spell.debug {
+            Some(debug) => {
+                self.write_u8(1);
+                self.write_u32(debug.positions.len())?;
+                for (offset, line, column) in &debug.positions {
+                    self.write_u32(*offset)?;
+                    self.write_u32(*line)?;
+                    self.write_u32(*column)?;
+                }
+                self.write_u32(debug.locals.len())?;
+                for local in &debug.locals {
+                    self.write_str(&local.name)?;
+                    self.write_u8(local.register);
+                    self.write_u32(local.lines.0)?;
+                    self.write_u32(local.lines.1)?;
+                }
+            }
+            None => self.write_u8(0),
+        }
+
+        self.write_u32(spell.constants.len())?;
+        for constant in &spell.constants {
+            self.write_value(constant)?;
+        }
+        Ok(())
+    }
+
+    fn write_value(&mut self, value: &Value) -> Result<(), String> {
+        match value {
+            Value::Number(n) => {
+                self.write_u8(TAG_NUMBER);
+                self.bytes.extend_from_slice(&n.to_le_bytes());
+            }
+            Value::Int(i) => {
+                self.write_u8(TAG_INT);
+                self.bytes.extend_from_slice(&i.to_le_bytes());
+            }
+            Value::String(s) => {
+                self.write_u8(TAG_STRING);
+                self.write_str(s)?;
+            }
+            Value::Bool(b) => {
+                self.write_u8(TAG_BOOL);
+                self.write_u8(*b as u8);
+            }
+            Value::Emptiness => self.write_u8(TAG_EMPTINESS),
+            Value::Spell(spell) => {
+                self.write_u8(TAG_SPELL);
+                self.write_spell(spell)?;
+            }
+            Value::Closure(closure) => {
+                // the upvalues are captured again by the vm, only the spell is kept
+                self.write_u8(TAG_CLOSURE);
+                self.write_spell(&closure.spell)?;
+            }
+            Value::SignSchema(schema) => {
+                self.write_u8(TAG_SIGN_SCHEMA);
+                self.write_str(&schema.name)?;
+                self.write_u32(schema.field_names.len())?;
+                for field in &schema.field_names {
+                    self.write_str(field)?;
+                }
+                self.write_u32(schema.attunements().len())?;
+                for (spell, slot) in schema.attunements() {
+                    self.write_u16(*spell);
+                    self.write_u16(*slot);
+                }
+            }
+            Value::Glyph(glyph) => {
+                self.write_u8(TAG_GLYPH);
+                self.write_str(&glyph.glyph)?;
+                self.write_str(&glyph.variant)?;
+                self.write_u32(glyph.index)?;
+            }
+            Value::NativeSpell(spell) => {
+                // native spells are looked up again by their name while loading
+                let info = NativeSpell::get_spell_info((**spell).clone())?;
+                self.write_u8(TAG_NATIVE_SPELL);
+                self.write_str(&info.name)?;
+            }
+            Value::Sign(_) | Value::Deck(_) | Value::Stream(_) | Value::Conjuring(_) | Value::Error(_) => {
+                return Err("Signs, decks, streams and errors only live at runtime and can't be precompiled.".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize, // the spells being read, nested in one another's constants
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(count)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("The precompiled scroll ended unexpectedly.")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<usize, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    fn read_str(&mut self) -> Result<String, String> {
+        let len = self.read_u32()?;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| "The precompiled scroll holds a malformed text.".to_string())
+    }
+
+    fn read_names(&mut self) -> Result<Vec<String>, String> {
+        let count = self.read_u32()?;
+        let mut names = Vec::with_capacity(count.min(self.bytes.len()));
+        for _ in 0..count {
+            names.push(self.read_str()?);
+        }
+        Ok(names)
+    }
+
+    fn read_spell(&mut self) -> Result<SpellObject, String> {
+        // a scroll made up to nest its spells endlessly would overflow the stack reading them
+        if self.depth >= MAX_SPELL_DEPTH {
+            return Err(format!("The precompiled scroll nests its spells deeper than {}.", MAX_SPELL_DEPTH));
+        }
+        self.depth += 1;
+        let spell = self.read_spell_body();
+        self.depth -= 1;
+        spell
+    }
+
+    fn read_spell_body(&mut self) -> Result<SpellObject, String> {
+        let name = match self.read_u8()? {
+            0 => None,
+            _ => Some(self.read_str()?),
+        };
+        let arity = self.read_u8()?;
+        let flowing = self.read_u8()? != 0;
+        let register_count = self.read_u32()?;
+
+        let capture_count = self.read_u32()?;
+        let mut captures = Vec::with_capacity(capture_count.min(self.bytes.len()));
+        for _ in 0..capture_count {
+            let capture = match self.read_u8()? {
+                0 => Capture::Local(self.read_u8()?),
+                _ => Capture::Enclosing(self.read_u8()?),
+            };
+            captures.push(capture);
+        }
+
+        let bytecode_len = self.read_u32()?;
+        let bytecode = self.take(bytecode_len)?.to_vec();
+
+        let line_count = self.read_u32()?;
+        let mut lines = Vec::with_capacity(line_count.min(self.bytes.len()));
+        for _ in 0..line_count {
+            lines.push((self.read_u32()?, self.read_u32()?));
+        }
+
+        let debug = match self.read_u8()? {
+            0 => None,
+            _ => Some(self.read_debug_info()?),
+        };
+
+        let constant_count = self.read_u32()?;
+        let mut constants = Vec::with_capacity(constant_count.min(self.bytes.len()));
+        for _ in 0..constant_count {
+            constants.push(self.read_value()?);
+        }
+
+        Ok(SpellObject {
+            name,
+            arity,
+            captures,
+            constants,
+            bytecode,
+            flowing,
+            lines,
+            register_count,
+            debug,
+        })
+    }
+
+    fn read_debug_info(&mut self) -> Result<DebugInfo, String> {
+        let position_count = self.read_u32()?;
+        let mut positions = Vec::with_capacity(position_count.min(self.bytes.len()));
+        for _ in 0..position_count {
+            positions.push((self.read_u32()?, self.read_u32()?, self.read_u32()?));
+        }
+
+        let local_count = self.read_u32()?;
+        let mut locals = Vec::with_capacity(local_count.min(self.bytes.len()));
+        for _ in 0..local_count {
+            locals.push(LocalSymbol {
+                name: self.read_str()?,
+                register: self.read_u8()?,
+                lines: (self.read_u32()?, self.read_u32()?),
+            });
+        }
+        Ok(DebugInfo { positions, locals })
+    }
+
+    fn read_value(&mut self) -> Result<Value, String> {
+        let value = match self.read_u8()? {
+            TAG_NUMBER => {
+                let bytes = self.take(8)?;
+                Value::Number(f64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            TAG_INT => {
+                let bytes = self.take(8)?;
+                Value::Int(i64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            TAG_STRING => Value::String(Rc::new(self.read_str()?)),
+            TAG_BOOL => Value::Bool(self.read_u8()? != 0),
+            TAG_EMPTINESS => Value::Emptiness,
+            TAG_SPELL => Value::Spell(Rc::new(self.read_spell()?)),
+            TAG_CLOSURE => Value::Closure(Rc::new(ClosureObject {
+                spell: Rc::new(self.read_spell()?),
+                upvalues: vec![],
+            })),
+            TAG_SIGN_SCHEMA => {
+                let mut schema = SignSchema::new(self.read_str()?);
+                let field_count = self.read_u32()?;
+                for _ in 0..field_count {
+                    schema.add_field(self.read_str()?);
+                }
+                let attunement_count = self.read_u32()?;
+                let mut attunements = Vec::with_capacity(attunement_count.min(self.bytes.len()));
+                for _ in 0..attunement_count {
+                    attunements.push((self.read_u16()?, self.read_u16()?));
+                }
+                schema.set_attunements(attunements);
+                Value::SignSchema(Rc::new(schema))
+            }
+            TAG_GLYPH => Value::Glyph(Rc::new(GlyphObject {
+                glyph: self.read_str()?,
+                variant: self.read_str()?,
+                index: self.read_u32()?,
+            })),
+            TAG_NATIVE_SPELL => {
+                let name = self.read_str()?;
+                // anything the prelude doesn't know about was registered by the host
+                let spell = NativeSpell::resolve(&name).unwrap_or_else(|_| NativeSpell::host(&name));
+                Value::NativeSpell(Rc::new(spell))
+            }
+            tag => return Err(format!("Unknown constant tag {} in the precompiled scroll.", tag)),
+        };
+        Ok(value)
+    }
+}
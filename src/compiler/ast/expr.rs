@@ -1,148 +1,234 @@
+use std::rc::Rc;
+
+use serde::Serialize;
+
 use crate::{
     compiler::{
         mark::{EtchedMark, WovenEtchedMark},
-        scanner::Token,
+        scanner::{Span, Token},
         symbol_table::Symbol,
         weaves::Weave,
     },
     values::{Value, native_spell::NativeSpell},
 };
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind")]
 pub enum Expr {
     Binary {
         left: Box<Expr>,
         right: Box<Expr>,
         operator: Token,
+        span: Span,
     },
     Unary {
         operand: Box<Expr>,
         operator: Token,
+        span: Span,
     },
     Literal {
         value: Value,
         token: Token,
+        span: Span,
     },
     Variable {
         name: Token,
+        span: Span,
     },
     Grouping {
         expression: Box<Expr>,
+        span: Span,
+    },
+    /// A value within a text's '@(', chanted into the text whatever its weave
+    Interpolation {
+        value: Box<Expr>,
+        token: Token, // the '@('
+        span: Span,
     },
     Assignment {
         name: Token,
         value: Box<Expr>,
+        span: Span,
     },
     Cast {
         reagents: Vec<Expr>,
+        named: Vec<(Token, Expr)>, // the reagents given by their names, after the others
         callee: Box<Expr>,
         token: Token,
+        span: Span,
     },
     Draw {
+        reagents: Vec<Expr>, // the marks given in the order the sign inscribes them
         marks: Vec<EtchedMark>,
         callee: Token,
+        span: Span,
     },
     Access {
         material: Box<Expr>,
         property: Token,
+        span: Span,
     },
     Deck {
         elements: Vec<Expr>,
         token: Token,
+        span: Span,
     },
     Extract {
         deck: Box<Expr>,
         index: Box<Expr>,
         token: Token,
+        span: Span,
+    },
+    Slice {
+        target: Box<Expr>,
+        start: Box<Expr>,
+        end: Box<Expr>,
+        token: Token,
+        span: Span,
     },
     DeckSet {
         deck: Box<Expr>,
         index: Box<Expr>,
         value: Box<Expr>,
         token: Token,
+        span: Span,
     },
     FieldSet {
         material: Box<Expr>,
         property: Token,
         value: Box<Expr>,
+        span: Span,
     },
     Blank {
         token: Token,
+        span: Span,
     },
     Manifests {
         value: Box<Expr>,
         token: Token,
+        span: Span,
     },
     SafeAccess {
         material: Box<Expr>,
         property: Token,
+        span: Span,
     },
     AssertSafe {
         operand: Box<Expr>,
         operator: Token,
+        span: Span,
+    },
+    Conditional {
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+        token: Token,
+        span: Span,
+    },
+    Heed {
+        token: Token,
+        span: Span,
+    },
+    Conjure {
+        token: Token,
+        cast: Box<Expr>,
+        span: Span,
+    },
+    Claim {
+        token: Token,
+        conjuring: Box<Expr>,
+        span: Span,
     },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind")]
 pub enum WovenExpr {
     Binary {
         left: Box<WovenExpr>,
         right: Box<WovenExpr>,
         operator: Token,
         weave: Weave,
+        span: Span,
     },
     Unary {
         operand: Box<WovenExpr>,
         operator: Token,
         weave: Weave,
+        span: Span,
     },
     Literal {
         value: Value,
         token: Token,
         weave: Weave,
+        span: Span,
     },
     Variable {
         name: Token,
         weave: Weave,
-        symbol: Symbol,
+        symbol: Rc<Symbol>,
+        span: Span,
     },
     Grouping {
         expression: Box<WovenExpr>,
         weave: Weave,
+        span: Span,
     },
     Assignment {
         name: Token,
         value: Box<WovenExpr>,
         weave: Weave,
-        symbol: Symbol,
+        symbol: Rc<Symbol>,
+        span: Span,
     },
     Cast {
         reagents: Vec<WovenExpr>,
         callee: Token,
         weave: Weave,
-        spell_symbol: Symbol,
+        spell_symbol: Rc<Symbol>,
+        span: Span,
+    },
+    // Cast of a tome's attunement, resolved on the runtime tome of reagents[0] (ego)
+    Invoke {
+        reagents: Vec<WovenExpr>,
+        callee: Token,
+        weave: Weave,
+        spell_symbol: Rc<Symbol>,
+        span: Span,
     },
     Draw {
         marks: Vec<WovenEtchedMark>,
         callee: Token,
         weave: Weave,
-        sign_symbol: Symbol,
+        sign_symbol: Rc<Symbol>,
+        span: Span,
     },
     Access {
         material: Box<WovenExpr>,
         property: Token,
         field_name_idx: u16,
         weave: Weave,
+        span: Span,
     },
     Deck {
         elements: Vec<WovenExpr>,
         weave: Weave,
+        span: Span,
     },
     Extract {
         deck: Box<WovenExpr>,
         index: Box<WovenExpr>,
         token: Token,
         weave: Weave,
+        span: Span,
+    },
+    Slice {
+        target: Box<WovenExpr>,
+        start: Box<WovenExpr>,
+        end: Box<WovenExpr>,
+        token: Token,
+        weave: Weave,
+        span: Span,
     },
     DeckSet {
         deck: Box<WovenExpr>,
@@ -150,6 +236,7 @@ pub enum WovenExpr {
         value: Box<WovenExpr>,
         token: Token,
         weave: Weave,
+        span: Span,
     },
     FieldSet {
         material: Box<WovenExpr>,
@@ -157,32 +244,130 @@ pub enum WovenExpr {
         value: Box<WovenExpr>,
         field_name_idx: u16,
         weave: Weave,
+        span: Span,
     },
     Manifests {
         value: Box<WovenExpr>,
         token: Token,
         weave: Weave,
+        span: Span,
     },
     SafeAccess {
         material: Box<WovenExpr>,
         property: Token,
         field_name_idx: u16,
         weave: Weave,
+        span: Span,
     },
     AssertSafe {
         operand: Box<WovenExpr>,
         operator: Token,
         weave: Weave,
+        span: Span,
     },
     NativeCast {
         reagents: Vec<WovenExpr>,
         callee: Token,
         weave: Weave,
+        #[serde(skip)] // named by the callee
         native_spell: NativeSpell,
+        span: Span,
+    },
+    Conditional {
+        condition: Box<WovenExpr>,
+        then_branch: Box<WovenExpr>,
+        else_branch: Box<WovenExpr>,
+        token: Token,
+        weave: Weave,
+        span: Span,
+    },
+    // always woven as Text
+    Heed {
+        token: Token,
+        span: Span,
+    },
+    Conjure {
+        token: Token,
+        cast: Box<WovenExpr>,
+        weave: Weave, // Conjuring<W> of the release of the cast
+        span: Span,
+    },
+    // a value turned into a text, the way it is chanted. Always woven as Text
+    ToText {
+        token: Token,
+        value: Box<WovenExpr>,
+        span: Span,
+    },
+    Claim {
+        token: Token,
+        conjuring: Box<WovenExpr>,
+        weave: Weave,
+        span: Span,
     },
 }
 
+impl Expr {
+    /// Where the expression was written, from its first token to its last
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Binary { span, .. }
+            | Expr::Unary { span, .. }
+            | Expr::Literal { span, .. }
+            | Expr::Variable { span, .. }
+            | Expr::Grouping { span, .. }
+            | Expr::Interpolation { span, .. }
+            | Expr::Assignment { span, .. }
+            | Expr::Cast { span, .. }
+            | Expr::Draw { span, .. }
+            | Expr::Access { span, .. }
+            | Expr::Deck { span, .. }
+            | Expr::Extract { span, .. }
+            | Expr::Slice { span, .. }
+            | Expr::DeckSet { span, .. }
+            | Expr::FieldSet { span, .. }
+            | Expr::Blank { span, .. }
+            | Expr::Manifests { span, .. }
+            | Expr::SafeAccess { span, .. }
+            | Expr::AssertSafe { span, .. }
+            | Expr::Conditional { span, .. }
+            | Expr::Heed { span, .. }
+            | Expr::Conjure { span, .. }
+            | Expr::Claim { span, .. } => *span,
+        }
+    }
+}
+
 impl WovenExpr {
+    /// Where the expression woven was written
+    pub fn span(&self) -> Span {
+        match self {
+            WovenExpr::Binary { span, .. }
+            | WovenExpr::Unary { span, .. }
+            | WovenExpr::Literal { span, .. }
+            | WovenExpr::Variable { span, .. }
+            | WovenExpr::Grouping { span, .. }
+            | WovenExpr::Assignment { span, .. }
+            | WovenExpr::Cast { span, .. }
+            | WovenExpr::Invoke { span, .. }
+            | WovenExpr::Draw { span, .. }
+            | WovenExpr::Access { span, .. }
+            | WovenExpr::Deck { span, .. }
+            | WovenExpr::Extract { span, .. }
+            | WovenExpr::Slice { span, .. }
+            | WovenExpr::DeckSet { span, .. }
+            | WovenExpr::FieldSet { span, .. }
+            | WovenExpr::Manifests { span, .. }
+            | WovenExpr::SafeAccess { span, .. }
+            | WovenExpr::AssertSafe { span, .. }
+            | WovenExpr::NativeCast { span, .. }
+            | WovenExpr::Conditional { span, .. }
+            | WovenExpr::Heed { span, .. }
+            | WovenExpr::Conjure { span, .. }
+            | WovenExpr::ToText { span, .. }
+            | WovenExpr::Claim { span, .. } => *span,
+        }
+    }
+
     pub fn weave(&self) -> Weave {
         match self {
             WovenExpr::Binary {
@@ -190,63 +375,82 @@ impl WovenExpr {
                 right: _,
                 operator: _,
                 weave,
+                span: _,
             } => weave.clone(),
             WovenExpr::Grouping {
                 expression: _,
                 weave,
+                span: _,
             } => weave.clone(),
             WovenExpr::Literal {
                 value: _,
                 weave,
                 token: _,
+                span: _,
             } => weave.clone(),
             WovenExpr::Unary {
                 operand: _,
                 operator: _,
                 weave,
+                span: _,
             } => weave.clone(),
             WovenExpr::Variable {
                 name: _,
                 weave,
                 symbol: _,
+                span: _,
             } => weave.clone(),
             WovenExpr::Assignment {
                 name: _,
                 value: _,
                 weave,
                 symbol: _,
+                span: _,
             } => weave.clone(),
             WovenExpr::Cast {
                 reagents: _,
                 callee: _,
                 weave,
                 spell_symbol: _,
+                span: _,
+            } => weave.clone(),
+            WovenExpr::Invoke {
+                reagents: _,
+                callee: _,
+                weave,
+                spell_symbol: _,
+                span: _,
             } => weave.clone(),
             WovenExpr::Draw {
                 marks: _,
                 callee: _,
                 weave,
                 sign_symbol: _,
+                span: _,
             } => weave.clone(),
             WovenExpr::Access {
                 material: _,
                 property: _,
                 field_name_idx: _,
                 weave,
+                span: _,
             } => weave.clone(),
-            WovenExpr::Deck { elements: _, weave } => weave.clone(),
+            WovenExpr::Deck { elements: _, weave, .. } => weave.clone(),
             WovenExpr::Extract {
                 deck: _,
                 index: _,
                 token: _,
                 weave,
+                span: _,
             } => weave.clone(),
+            WovenExpr::Slice { weave, .. } => weave.clone(),
             WovenExpr::DeckSet {
                 deck: _,
                 index: _,
                 value: _,
                 token: _,
                 weave,
+                span: _,
             } => weave.clone(),
             WovenExpr::FieldSet {
                 material: _,
@@ -254,57 +458,83 @@ impl WovenExpr {
                 value: _,
                 field_name_idx: _,
                 weave,
+                span: _,
             } => weave.clone(),
             WovenExpr::Manifests {
                 value: _,
                 token: _,
                 weave,
+                span: _,
             } => weave.clone(),
             WovenExpr::SafeAccess {
                 material: _,
                 property: _,
                 weave,
                 field_name_idx: _,
+                span: _,
             } => weave.clone(),
             WovenExpr::AssertSafe {
                 operand: _,
                 operator: _,
                 weave,
+                span: _,
             } => weave.clone(),
             WovenExpr::NativeCast {
                 reagents: _,
                 callee: _,
                 weave,
                 native_spell: _,
+                span: _,
             } => weave.clone(),
+            WovenExpr::Conditional {
+                condition: _,
+                then_branch: _,
+                else_branch: _,
+                token: _,
+                weave,
+                span: _,
+            } => weave.clone(),
+            WovenExpr::Heed { .. } | WovenExpr::ToText { .. } => Weave::Text,
+            WovenExpr::Conjure { weave, .. } | WovenExpr::Claim { weave, .. } => weave.clone(),
         }
     }
 
     // might stay unused
-    pub fn symbol(&self) -> Option<&Symbol> {
+    pub fn symbol(&self) -> Option<&Rc<Symbol>> {
         match self {
             WovenExpr::Variable {
                 name: _,
                 weave: _,
                 symbol,
+                span: _,
             } => Some(symbol),
             WovenExpr::Assignment {
                 name: _,
                 value: _,
                 weave: _,
                 symbol,
+                span: _,
             } => Some(symbol),
             WovenExpr::Cast {
                 reagents: _,
                 callee: _,
                 weave: _,
                 spell_symbol,
+                span: _,
+            } => Some(spell_symbol),
+            WovenExpr::Invoke {
+                reagents: _,
+                callee: _,
+                weave: _,
+                spell_symbol,
+                span: _,
             } => Some(spell_symbol),
             WovenExpr::Draw {
                 marks: _,
                 callee: _,
                 weave: _,
                 sign_symbol,
+                span: _,
             } => Some(&sign_symbol),
             _ => None,
         }
@@ -317,66 +547,86 @@ impl WovenExpr {
                 right: _,
                 operator,
                 weave: _,
+                span: _,
             } => operator.clone(),
             WovenExpr::Grouping {
-                expression: _,
+                expression,
                 weave: _,
-            } => Token::dummy(),
+                span: _,
+            } => expression.token(),
             WovenExpr::Literal {
                 value: _,
                 weave: _,
                 token,
+                span: _,
             } => token.clone(),
             WovenExpr::Unary {
                 operand: _,
                 operator,
                 weave: _,
+                span: _,
             } => operator.clone(),
             WovenExpr::Variable {
                 name,
                 weave: _,
                 symbol: _,
+                span: _,
             } => name.clone(),
             WovenExpr::Assignment {
                 name,
                 value: _,
                 weave: _,
                 symbol: _,
+                span: _,
             } => name.clone(),
             WovenExpr::Cast {
                 reagents: _,
                 callee,
                 weave: _,
                 spell_symbol: _,
+                span: _,
+            } => callee.clone(),
+            WovenExpr::Invoke {
+                reagents: _,
+                callee,
+                weave: _,
+                spell_symbol: _,
+                span: _,
             } => callee.clone(),
             WovenExpr::Draw {
                 marks: _,
                 callee,
                 weave: _,
                 sign_symbol: _,
+                span: _,
             } => callee.clone(),
             WovenExpr::Access {
                 material: _,
                 property,
                 field_name_idx: _,
                 weave: _,
+                span: _,
             } => property.clone(),
             WovenExpr::Deck {
                 elements: _,
                 weave: _,
+                span: _,
             } => Token::dummy(),
             WovenExpr::Extract {
                 deck: _,
                 index: _,
                 token,
                 weave: _,
+                span: _,
             } => token.clone(),
+            WovenExpr::Slice { token, .. } => token.clone(),
             WovenExpr::DeckSet {
                 deck: _,
                 index: _,
                 value: _,
                 token,
                 weave: _,
+                span: _,
             } => token.clone(),
             WovenExpr::FieldSet {
                 material: _,
@@ -384,29 +634,44 @@ impl WovenExpr {
                 value: _,
                 field_name_idx: _,
                 weave: _,
+                span: _,
             } => property.clone(),
             WovenExpr::Manifests {
                 value: _,
                 token,
                 weave: _,
+                span: _,
             } => token.clone(),
             WovenExpr::SafeAccess {
                 material: _,
                 property,
                 weave: _,
                 field_name_idx: _,
+                span: _,
             } => property.clone(),
             WovenExpr::AssertSafe {
                 operand: _,
                 operator,
                 weave: _,
+                span: _,
             } => operator.clone(),
             WovenExpr::NativeCast {
                 reagents: _,
                 callee,
                 weave: _,
                 native_spell: _,
+                span: _,
             } => callee.clone(),
+            WovenExpr::Conditional {
+                condition: _,
+                then_branch: _,
+                else_branch: _,
+                token,
+                weave: _,
+                span: _,
+            } => token.clone(),
+            WovenExpr::Heed { token, .. } | WovenExpr::ToText { token, .. } => token.clone(),
+            WovenExpr::Conjure { token, .. } | WovenExpr::Claim { token, .. } => token.clone(),
         }
     }
 }
@@ -1,45 +1,110 @@
+use std::rc::Rc;
+
+use serde::Serialize;
+
 use crate::{
     compiler::{
         Expr, WovenExpr,
         mark::{Mark, WovenMark},
         parser::types::ParsedWeave,
         reagents::{Reagent, WovenReagent},
-        scanner::Token,
+        scanner::{Span, Token},
         symbol_table::Symbol,
     },
 };
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind")]
 pub enum Stmt {
     ExprStmt {
         expr: Expr,
+        span: Span,
     },
     VarDeclaration {
         name: Token,
         mutable: bool,
         initializer: Option<Expr>,
         weave: Option<ParsedWeave>,
+        span: Span,
     },
     Fate {
         condition: Expr,
         then_branch: Box<Stmt>,
         else_branch: Option<Box<Stmt>>,
+        span: Span,
     },
     While {
+        label: Option<Token>,
         condition: Expr,
         body: Box<Stmt>,
+        span: Span,
+    },
+    Cycle {
+        token: Token,
+        label: Option<Token>,
+        variable: Token,
+        start: Expr,
+        end: Expr,
+        body: Box<Stmt>,
+        span: Span,
+    },
+    Circle {
+        label: Option<Token>,
+        body: Box<Stmt>,
+        condition: Expr, // checked after the body, the circle ends once it holds
+        span: Span,
+    },
+    FlowThrough {
+        token: Token,
+        label: Option<Token>,
+        variable: Token,
+        iterable: Expr,
+        body: Box<Stmt>,
+        span: Span,
+    },
+    Judge {
+        token: Token,
+        subject: Expr,
+        arms: Vec<(Expr, Stmt)>, // (value, body)
+        divert: Option<Box<Stmt>>,
+        span: Span,
     },
     Chant {
-        expression: Expr,
+        expressions: Vec<Expr>,
+        newline: bool,
+        span: Span,
     },
     Block {
         statements: Vec<Stmt>,
+        span: Span,
     },
     Sever {
         token: Token,
+        label: Option<Token>, // the loop severed, the innermost one when None
+        span: Span,
     },
     Flow {
         token: Token,
+        label: Option<Token>,
+        span: Span,
+    },
+    Attempt {
+        token: Token,
+        body: Box<Stmt>,
+        error: Token,
+        mend: Box<Stmt>,
+        span: Span,
+    },
+    Doom {
+        token: Token,
+        value: Expr,
+        span: Span,
+    },
+    Expect {
+        token: Token,
+        condition: Expr,
+        message: Option<Expr>,
+        span: Span,
     },
     Spell {
         name: Token,
@@ -47,86 +112,273 @@ pub enum Stmt {
         body: Box<Stmt>,
         return_weave: Option<ParsedWeave>,
 
-        attuned_to: Option<Token>
+        attuned_to: Option<Token>,
+        span: Span,
     },
     Release {
         token: Token,
         expr: Option<Expr>,
+        span: Span,
+    },
+    Offer {
+        token: Token,
+        value: Expr,
+        span: Span,
     },
     Sign {
         name: Token,
         marks: Vec<Mark>,
+        span: Span,
+    },
+    Glyph {
+        name: Token,
+        variants: Vec<Token>,
+        span: Span,
+    },
+    Seal {
+        name: Token,
+        initializer: Expr,
+        weave: Option<ParsedWeave>,
+        span: Span,
     },
     Vanish {
         target: Expr,
         token: Token,
+        span: Span,
     },
     Attune {
         sign: Token,
-        spells: Vec<Box<Stmt>>
+        spells: Vec<Box<Stmt>>,
+        span: Span,
+    },
+    Tome {
+        name: Token,
+        origin: Option<Token>,
+        marks: Vec<Mark>,
+        spells: Vec<Stmt>,
+        span: Span,
     },
     Tether {
         token: Token,
         path: Vec<Token>,
         bind_to: Option<Token>,
         is_path: bool,
+        span: Span,
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind")]
 pub enum WovenStmt {
     ExprStmt {
         expr: WovenExpr,
+        span: Span,
     },
     VarDeclaration {
         name: Token,
         mutable: bool,
         initializer: Option<WovenExpr>,
-        symbol: Symbol,
+        symbol: Rc<Symbol>,
+        span: Span,
     },
     Fate {
         condition: WovenExpr,
         then_branch: Box<WovenStmt>,
         else_branch: Option<Box<WovenStmt>>,
+        span: Span,
     },
     While {
+        label: Option<Token>,
+        condition: WovenExpr,
+        body: Box<WovenStmt>,
+        span: Span,
+    },
+    Cycle {
+        token: Token,
+        label: Option<Token>,
+        variable: Rc<Symbol>,
+        start: WovenExpr,
+        end: WovenExpr,
+        end_symbol: Rc<Symbol>, // hidden local holding the evaluated end bound
+        body: Box<WovenStmt>,
+        span: Span,
+    },
+    Circle {
+        label: Option<Token>,
+        body: Box<WovenStmt>,
         condition: WovenExpr,
+        span: Span,
+    },
+    FlowThrough {
+        token: Token,
+        label: Option<Token>,
+        variable: Rc<Symbol>,
+        iterable: WovenExpr,
+        iterable_symbol: Rc<Symbol>, // hidden local holding the evaluated iterable
+        index_symbol: Rc<Symbol>,    // hidden local counting the rounds
         body: Box<WovenStmt>,
+        span: Span,
+    },
+    Judge {
+        token: Token,
+        subject: WovenExpr,
+        subject_symbol: Rc<Symbol>, // hidden local holding the judged value
+        arms: Vec<(WovenExpr, WovenStmt)>,
+        divert: Option<Box<WovenStmt>>,
+        span: Span,
     },
     Chant {
-        expression: WovenExpr,
+        expressions: Vec<WovenExpr>,
+        newline: bool,
+        span: Span,
     },
     Block {
         statements: Vec<WovenStmt>,
+        span: Span,
     },
     Sever {
         token: Token,
+        label: Option<Token>, // the loop severed, the innermost one when None
+        span: Span,
     },
     Flow {
         token: Token,
+        label: Option<Token>,
+        span: Span,
+    },
+    Attempt {
+        token: Token,
+        body: Box<WovenStmt>,
+        error_symbol: Rc<Symbol>, // local of the mend holding the error
+        mend: Box<WovenStmt>,
+        span: Span,
+    },
+    Doom {
+        token: Token,
+        value: WovenExpr,
+        span: Span,
+    },
+    Expect {
+        token: Token,
+        condition: WovenExpr,
+        message: WovenExpr,
+        span: Span,
     },
     Spell {
         name: Token,
         reagents: Vec<WovenReagent>,
         body: Box<WovenStmt>,
-        spell_symbol: Symbol,
+        spell_symbol: Rc<Symbol>,
+        span: Span,
     },
     Release {
         token: Token,
         expr: Option<WovenExpr>,
+        span: Span,
+    },
+    Offer {
+        token: Token,
+        value: WovenExpr,
+        span: Span,
     },
     Sign {
         name: Token,
         marks: Vec<WovenMark>,
-        sign_symbol: Symbol,
+        sign_symbol: Rc<Symbol>,
+        span: Span,
+    },
+    Glyph {
+        name: Token,
+        glyph_symbol: Rc<Symbol>,
+        span: Span,
+    },
+    Seal {
+        name: Token,
+        seal_symbol: Rc<Symbol>,
+        span: Span,
     },
     Attune {
         sign: Token,
         spells: Vec<Box<WovenStmt>>,
+        span: Span,
+    },
+    Tome {
+        name: Token,
+        marks: Vec<WovenMark>,
+        spells: Vec<WovenStmt>,
+        tome_symbol: Rc<Symbol>,
+        inherited: Vec<(String, String)>, // (attunement of this tome, attunement of the referred tome it points to)
+        span: Span,
     },
     Tether {
         statements: Vec<WovenStmt>,
         path: String,
         bind_to: Option<Token>,
+        span: Span,
     },
 }
+
+impl Stmt {
+    /// Where the statement was written, from its first token to its last
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::ExprStmt { span, .. }
+            | Stmt::VarDeclaration { span, .. }
+            | Stmt::Fate { span, .. }
+            | Stmt::While { span, .. }
+            | Stmt::Cycle { span, .. }
+            | Stmt::Circle { span, .. }
+            | Stmt::FlowThrough { span, .. }
+            | Stmt::Judge { span, .. }
+            | Stmt::Chant { span, .. }
+            | Stmt::Block { span, .. }
+            | Stmt::Sever { span, .. }
+            | Stmt::Flow { span, .. }
+            | Stmt::Attempt { span, .. }
+            | Stmt::Doom { span, .. }
+            | Stmt::Expect { span, .. }
+            | Stmt::Spell { span, .. }
+            | Stmt::Release { span, .. }
+            | Stmt::Offer { span, .. }
+            | Stmt::Sign { span, .. }
+            | Stmt::Glyph { span, .. }
+            | Stmt::Seal { span, .. }
+            | Stmt::Vanish { span, .. }
+            | Stmt::Attune { span, .. }
+            | Stmt::Tome { span, .. }
+            | Stmt::Tether { span, .. } => *span,
+        }
+    }
+}
+
+impl WovenStmt {
+    /// Where the statement woven was written
+    pub fn span(&self) -> Span {
+        match self {
+            WovenStmt::ExprStmt { span, .. }
+            | WovenStmt::VarDeclaration { span, .. }
+            | WovenStmt::Fate { span, .. }
+            | WovenStmt::While { span, .. }
+            | WovenStmt::Cycle { span, .. }
+            | WovenStmt::Circle { span, .. }
+            | WovenStmt::FlowThrough { span, .. }
+            | WovenStmt::Judge { span, .. }
+            | WovenStmt::Chant { span, .. }
+            | WovenStmt::Block { span, .. }
+            | WovenStmt::Sever { span, .. }
+            | WovenStmt::Flow { span, .. }
+            | WovenStmt::Attempt { span, .. }
+            | WovenStmt::Doom { span, .. }
+            | WovenStmt::Expect { span, .. }
+            | WovenStmt::Spell { span, .. }
+            | WovenStmt::Release { span, .. }
+            | WovenStmt::Offer { span, .. }
+            | WovenStmt::Sign { span, .. }
+            | WovenStmt::Glyph { span, .. }
+            | WovenStmt::Seal { span, .. }
+            | WovenStmt::Attune { span, .. }
+            | WovenStmt::Tome { span, .. }
+            | WovenStmt::Tether { span, .. } => *span,
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use std::{collections::HashMap, rc::Rc, str, u8, vec};
 
 use crate::{
-    assembler::Assembler,
+    assembler::{Assembler, Label},
+    optimizer::Optimizer,
     compiler::{
         WovenExpr, WovenStmt,
+        diagnostics::{CompilationPhase, Diagnostic, EiraError},
         mark::{WovenEtchedMark, WovenMark},
         reagents::WovenReagent,
         scanner::Token,
+        strand::{CONDITIONAL_STRAND, NEGATABLE_STRAND},
         symbol_table::Symbol,
         token_type::TokenType,
         weaves::Weave,
@@ -15,8 +18,9 @@ use crate::{
     runtime::Instruction,
     values::{
         Value,
+        interner::{Interner, SymbolId},
         native_spell::NativeSpell,
-        spell::{ClosureObject, SpellObject},
+        spell::{Capture, ClosureObject, DebugInfo, LocalSymbol, SpellObject},
     },
 };
 
@@ -28,27 +32,67 @@ pub struct GenError {
 type GenResult<T> = Result<T, GenError>;
 
 struct LoopBlock {
-    severs: Vec<usize>,
-    flows: Vec<usize>,
+    label: Option<String>,
+    exit: Label, // where the severs land
+    next: Label, // where the flows land, to start the next round
+    // the attempts open inside the body, which are ended before severing or flowing out of them
+    attempts: usize,
+    // the registers of the body's locals, closed over before flowing when the body makes closures
+    closes_from: Option<u8>,
+}
+
+/// The woven cycle, counting [variable] up to the end it keeps in a hidden local
+struct Cycle {
+    token: Token,
+    label: Option<Token>,
+    variable: Rc<Symbol>,
+    start: WovenExpr,
+    end: WovenExpr,
+    end_symbol: Rc<Symbol>, // the hidden local holding the end, evaluated only once
+    body: WovenStmt,
+}
+
+/// The woven flow through, its value walked and its index counted in hidden locals
+struct FlowThrough {
+    token: Token,
+    label: Option<Token>,
+    variable: Rc<Symbol>,
+    iterable: WovenExpr,
+    iterable_symbol: Rc<Symbol>, // the hidden local holding the value walked
+    index_symbol: Rc<Symbol>,    // the hidden local counting through it
+    body: WovenStmt,
 }
 
 pub struct CodeGen {
     pub print_instructions: bool,
     pub print_bytecode: bool,
+    pub debug_info: bool, // whether the spells carry the columns and the names of their locals
 
     woven_ast: Vec<WovenStmt>,
     instructions: Vec<Instruction>,
+    positions: Vec<(usize, usize)>, // (line, column) in the source of each instruction
+    labels: Vec<Option<usize>>, // the instruction each label of the spell is placed on
+    jumps: Vec<(usize, Label)>, // the jumps of the spell, along with the labels they land on
+    last_position: (usize, usize),
+    furthest_line: usize, // the last source line generated in the scope of the debug symbols
+    locals: Vec<LocalSymbol>, // the debug symbols of the spell being generated
 
     register_index: u8,
+    locals_floor: u8, // registers below this hold the locals in scope, temporaries live above
+    register_count: usize, // the registers needed by the frame of the spell being generated
 
     constants: Vec<Vec<Value>>,
-    constants_idx_map: Vec<HashMap<Value, u16>>, // Stack of maps, one per constant pool
+    constants_idx_map: Vec<HashMap<Value, u32>>, // Stack of maps, one per constant pool
+
+    globals: Vec<String>, // names of the globals, addressed by their slot at runtime
+    global_slots: HashMap<String, u16>,
+    interner: Interner, // the texts of the constants and the names the VM looks up by id
 
     loop_blocks: Vec<LoopBlock>,
 
     in_spell: bool, // track if context is within a spell
-    curr_upval_count: usize,
-    upval_map: HashMap<(usize, usize), usize>, // map of (depth, slot_idx) to register index
+    upval_map: HashMap<(usize, usize), u8>, // map of (depth, slot_idx) to the index of the upvalue
+    current_spell: Option<(usize, usize)>, // (depth, slot_idx) of the spell being generated
 }
 
 impl CodeGen {
@@ -56,15 +100,27 @@ impl CodeGen {
         CodeGen {
             woven_ast: w_ast,
             instructions: vec![],
+            positions: vec![],
+            labels: vec![],
+            jumps: vec![],
+            last_position: (0, 0),
+            furthest_line: 0,
+            locals: vec![],
             register_index: 0,
+            locals_floor: 0,
+            register_count: 0,
             constants: vec![vec![]],
             constants_idx_map: vec![HashMap::new()], // Initialize with one map for main pool
+            globals: vec![],
+            global_slots: HashMap::new(),
+            interner: Interner::default(),
             loop_blocks: vec![],
             in_spell: false,
-            curr_upval_count: 0,
             upval_map: HashMap::new(),
+            current_spell: None,
             print_instructions,
             print_bytecode,
+            debug_info: true,
         }
     }
 
@@ -77,12 +133,19 @@ impl CodeGen {
     //--------------- Interface/ Public fns ---------------
 
     // Create instructions
-    pub fn summon_instructions(&mut self) -> GenResult<Vec<Instruction>> {
-        let stmts = self.woven_ast.clone();
+    pub fn summon_instructions(&mut self) -> Result<Vec<Instruction>, EiraError> {
+        let stmts = std::mem::take(&mut self.woven_ast);
 
-        let _ = self.gen_from_stmts(stmts)?;
+        let result = self.gen_origin(stmts).map_err(|e| {
+            EiraError::from(Diagnostic::error(CompilationPhase::CodeGen, &e.msg, None))
+        })?;
 
-        self.instructions.push(Instruction::Halt {});
+        self.instructions.push(Instruction::Halt { src: result });
+        self.note_position(self.last_position);
+        self.close_locals((0, 0));
+        self.finish_instructions().map_err(|e| {
+            EiraError::from(Diagnostic::error(CompilationPhase::CodeGen, &e.msg, None))
+        })?;
 
         if self.print_instructions {
             print_instructions(
@@ -95,17 +158,108 @@ impl CodeGen {
         Ok(self.instructions.clone())
     }
 
+    /// The registers needed by the frame of the origin scroll
+    pub fn get_register_count(&self) -> usize {
+        self.register_count
+    }
+
+    /// The line table of the origin scroll, pairs of (bytecode offset, source line)
+    pub fn get_line_table(&self) -> Vec<(usize, usize)> {
+        Assembler::convert_line_table(&self.instructions, &self.positions)
+    }
+
+    /// The columns and the named registers of the origin scroll, unless it is compiled without debug info
+    pub fn get_debug_info(&self) -> Option<DebugInfo> {
+        self.debug_info.then(|| DebugInfo {
+            positions: Assembler::convert_position_table(&self.instructions, &self.positions),
+            locals: self.locals.clone(),
+        })
+    }
+
     //--------------- Helpers ---------------
 
+    /// Attributes the instructions emitted since the last note to the (line, column) [position]
+    fn note_position(&mut self, position: (usize, usize)) {
+        if position.0 == 0 {
+            return;
+        }
+        self.last_position = position;
+        self.furthest_line = self.furthest_line.max(position.0);
+        self.positions.resize(self.instructions.len(), position);
+    }
+
+    /// Where [token] starts in the source. The scanner leaves the columns at the end of the tokens.
+    fn position_of(token: &Token) -> (usize, usize) {
+        let column = (token.column + 1).saturating_sub(token.lexeme.chars().count().max(1));
+        (token.line, column)
+    }
+
+    /// Names the register of a local in the debug symbols, in scope from [line] on.
+    /// The globals and upvalues are found by their own names, they aren't noted.
+    fn declare_local(&mut self, name: &str, register: u8, line: usize) {
+        self.locals.push(LocalSymbol {
+            name: name.to_string(),
+            register,
+            lines: (line, usize::MAX),
+        });
+    }
+
+    /// Opens a scope of debug symbols, the locals declared from here on are ended with `close_locals`
+    fn open_locals(&mut self) -> (usize, usize) {
+        (self.locals.len(), std::mem::take(&mut self.furthest_line))
+    }
+
+    /// Ends the scope of the locals declared since it was opened, at the furthest line generated in it
+    fn close_locals(&mut self, (from, outer_line): (usize, usize)) {
+        let line = self.furthest_line;
+        for local in &mut self.locals[from..] {
+            if local.lines.1 == usize::MAX {
+                local.lines.1 = line.max(local.lines.0);
+            }
+        }
+        self.furthest_line = line.max(outer_line);
+    }
+
+    /// Runs the peephole optimizer over the instructions of the spell being generated, then has
+    /// the assembler land its jumps
+    fn finish_instructions(&mut self) -> GenResult<()> {
+        let targets = self.jump_targets()?;
+        let (instructions, targets, positions) = Optimizer::optimize(
+            &self.instructions,
+            &targets,
+            &self.positions,
+            self.constants.last().unwrap(),
+        );
+        self.instructions = match Assembler::link_jumps(&instructions, &targets) {
+            Ok(instructions) => instructions,
+            Err(msg) => return self.error(&msg),
+        };
+        self.positions = positions;
+        Ok(())
+    }
+
     /// Returns the next free register
     fn get_next_register(&mut self) -> GenResult<u8> {
         if self.register_index == u8::MAX {
             return self.error("Maximum registers allocated! Register overflow?!");
         }
         self.register_index += 1;
+        self.register_count = self.register_count.max(self.register_index as usize);
         Ok(self.register_index - 1)
     }
 
+    /// Frees the temporaries allocated since [mark], the registers of the locals are kept
+    fn free_registers(&mut self, mark: u8) {
+        self.register_index = mark.max(self.locals_floor);
+    }
+
+    /// Keeps the register of a local from being handed out as a temporary while it is in scope
+    fn pin_local(&mut self, reg: u8) {
+        self.locals_floor = self.locals_floor.max(reg + 1);
+        self.register_index = self.register_index.max(self.locals_floor);
+        self.register_count = self.register_count.max(self.locals_floor as usize);
+    }
+
     fn get_last_allocated_register(&self) -> u8 {
         if self.register_index == 0 {
             return 0;
@@ -113,14 +267,22 @@ impl CodeGen {
         self.register_index - 1
     }
 
-    fn add_constant(&mut self, value: Value) -> GenResult<u16> {
+    fn add_constant(&mut self, value: Value) -> GenResult<u32> {
+        // equal texts share one copy across all the pools, so the VM can compare them by pointer
+        let value = match value {
+            Value::String(text) => Value::String(self.interned(&text).clone()),
+            other => other,
+        };
+
         // Check if constant exists in current pool's map
         if let Some(val) = self.constants_idx_map.last().unwrap().get(&value) {
             return Ok(*val);
         }
 
         // else add the constant to table and return the index
-        let ind = self.constants.last().unwrap().len() as u16;
+        let Ok(ind) = u32::try_from(self.constants.last().unwrap().len()) else {
+            return self.error("Too many constants in a spell! Try splitting it into a few smaller spells.");
+        };
         self.constants.last_mut().unwrap().push(value.clone());
         self.constants_idx_map
             .last_mut()
@@ -129,6 +291,35 @@ impl CodeGen {
         Ok(ind)
     }
 
+    fn interned(&mut self, text: &str) -> &Rc<String> {
+        let id = self.interner.intern(text);
+        self.interner.text(id)
+    }
+
+    /// The symbol id of [name], as an operand of the instructions
+    fn symbol(&mut self, name: &str) -> GenResult<u16> {
+        let id = self.interner.intern(name);
+        if id > u16::MAX as SymbolId {
+            return self.error("Too many names in a scroll!");
+        }
+        Ok(id as u16)
+    }
+
+    /// The slot of the global [name], shared by all the spells of the scroll
+    fn global_slot(&mut self, name: String) -> GenResult<u16> {
+        if let Some(slot) = self.global_slots.get(&name) {
+            return Ok(*slot);
+        }
+
+        if self.globals.len() > u16::MAX as usize {
+            return self.error("Too many globals in a scroll!");
+        }
+        let slot = self.globals.len() as u16;
+        self.globals.push(name.clone());
+        self.global_slots.insert(name, slot);
+        Ok(slot)
+    }
+
     /// Writes a [Constant] instruction to the bytecode.
     /// Returns the register where the constant is stored.
     ///
@@ -136,165 +327,294 @@ impl CodeGen {
     fn write_constant(&mut self, value: Value) -> GenResult<u8> {
         let reg = self.get_next_register()?;
         let const_index = self.add_constant(value)?;
-        self.instructions.push(Instruction::Constant {
-            dest: reg,
-            const_index: const_index,
-        });
+        // the constants past the reach of two bytes are loaded with a wider index
+        match u16::try_from(const_index) {
+            Ok(const_index) => self.instructions.push(Instruction::Constant {
+                dest: reg,
+                const_index: const_index,
+            }),
+            Err(_) => self.instructions.push(Instruction::ConstantLong {
+                dest: reg,
+                const_index,
+            }),
+        }
         Ok(reg)
     }
 
-    fn write_jump(&mut self, inst: Instruction) -> usize {
-        self.instructions.push(inst);
-        self.instructions.len() - 1
+    /// A new label for the jumps to land on, placed with [place_label] once its spot is reached
+    fn new_label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
     }
 
-    fn patch_jump(&mut self, jump_idx: usize) -> GenResult<()> {
-        let mut offset = 0;
+    /// The jumps to [label] land on the next instruction written
+    fn place_label(&mut self, label: Label) {
+        self.labels[label.0] = Some(self.instructions.len());
+    }
 
-        for i in (jump_idx + 1)..self.instructions.len() {
-            offset += self.instructions[i].len();
-        }
+    /// A label placed on the next instruction written, for the loops to jump back to
+    fn label_here(&mut self) -> Label {
+        let label = self.new_label();
+        self.place_label(label);
+        label
+    }
 
-        if offset > u16::MAX as usize {
-            return self.error("The magic is too complex(long) to jump over!");
-        }
+    /// Writes the jump [inst] landing on [label], its offset is left for the assembler to work out
+    fn write_jump(&mut self, inst: Instruction, label: Label) {
+        self.jumps.push((self.instructions.len(), label));
+        self.instructions.push(inst);
+    }
 
-        match &mut self.instructions[jump_idx] {
-            Instruction::JumpIfFalse { offset: o, .. } => *o = offset as u16,
-            Instruction::Jump { offset: o } => *o = offset as u16,
-            _ => {
+    /// The index of the instruction each jump of the spell being generated lands on
+    fn jump_targets(&self) -> GenResult<Vec<Option<usize>>> {
+        let mut targets = vec![None; self.instructions.len()];
+        for &(jump, label) in &self.jumps {
+            let Some(target) = self.labels[label.0] else {
                 return self.error(&format!(
                     "Hmmm... this error shouldnt be thrown! If you are encountering this, congrats! I see a good future in you.Error: Jump patch failed.\
-                    \nExpected a 'JUMP' instruction, got {:?}",
-                    self.instructions[jump_idx]
+                    \nThe {:?} lands on a label never placed",
+                    self.instructions[jump]
                 ));
-            }
+            };
+            targets[jump] = Some(target);
         }
-
-        Ok(())
+        Ok(targets)
     }
 
-    fn write_loop(&mut self, start: usize) -> GenResult<usize> {
-        let body_bytes_size: usize = self.instructions[start..]
-            .iter()
-            .map(|inst| inst.len())
-            .sum();
-
-        // 2 byte for u16's size, 1 for opcode (2+1=3 iydk)
-        let total_offset = body_bytes_size + 3;
+    pub fn get_constants(&mut self) -> Vec<Value> {
+        self.constants.last_mut().unwrap().clone()
+    }
 
-        if total_offset > u16::MAX as usize {
-            return self.error("Loop Jump Offset exceeds the 2byte limit.");
-        }
+    /// The names of the globals of the scroll, indexed by their slot
+    pub fn get_globals(&self) -> Vec<String> {
+        self.globals.clone()
+    }
 
-        self.instructions.push(Instruction::Loop {
-            offset: total_offset as u16,
-        });
-        Ok(self.instructions.len() - 1)
+    /// The interned texts of the scroll, indexed by their symbol ids
+    pub fn get_symbols(&self) -> Vec<String> {
+        self.interner.texts()
     }
 
-    fn patch_jump_to(&mut self, jump_idx: usize, target_idx: usize) -> GenResult<()> {
-        if jump_idx >= self.instructions.len() || target_idx >= self.instructions.len() {
-            return self.error("Invalid jump patch indices!");
-        }
+    //--------------- Actual Core parts ---------------
 
-        // Compute byte distance from the instruction after the jump to the target instruction
-        let mut offset = 0usize;
-        for i in (jump_idx + 1)..target_idx {
-            offset += self.instructions[i].len();
-        }
+    /// A Helper like function to iterate through the statement list
+    /// Generates the scroll's top level statements, the register holding the
+    /// value of the scroll (its last expression, emptiness otherwise) is returned
+    fn gen_origin(&mut self, mut stmts: Vec<WovenStmt>) -> GenResult<u8> {
+        let last = match stmts.last() {
+            Some(WovenStmt::ExprStmt { .. }) => stmts.pop(),
+            _ => None,
+        };
 
-        if offset > u16::MAX as usize {
-            return self.error("Jump offset exceeds 16-bit limit!");
-        }
+        self.gen_from_stmts(stmts)?;
 
-        match &mut self.instructions[jump_idx] {
-            Instruction::Jump { offset: o } => *o = offset as u16,
-            Instruction::JumpIfFalse { offset: o, .. } => *o = offset as u16,
-            _ => {
-                return self.error(&format!(
-                    "Patch target at index {} is not a jump instruction: {:?}",
-                    jump_idx, self.instructions[jump_idx]
-                ));
-            }
+        match last {
+            Some(WovenStmt::ExprStmt { expr, .. }) => self.gen_from_expr(expr),
+            _ => self.write_constant(Value::Emptiness),
         }
-
-        Ok(())
-    }
-
-    pub fn get_constants(&mut self) -> Vec<Value> {
-        self.constants.last_mut().unwrap().clone()
     }
 
-    //--------------- Actual Core parts ---------------
-
-    /// A Helper like function to iterate through the statement list
+    /// The spells of the statements are made first, with their signatures hoisted by the analyzer
+    /// they can be cast above their declaration and cast each other
     fn gen_from_stmts(&mut self, stmts: Vec<WovenStmt>) -> GenResult<u8> {
-        for stmt in stmts {
+        let (spells, rest): (Vec<_>, Vec<_>) = stmts.into_iter().partition(|stmt| {
+            // the attuned spells are made with their sign
+            matches!(stmt, WovenStmt::Spell { name, spell_symbol, .. } if *name.lexeme == *spell_symbol.name)
+        });
+        for stmt in spells.into_iter().chain(rest) {
             self.gen_from_stmt(stmt)?;
         }
         Ok(0) // dummy result, since statements doesnt care about values produced
     }
 
+    /// The locals of the block go out of scope at its end, so do their registers
+    fn gen_block_instructions(&mut self, stmts: Vec<WovenStmt>) -> GenResult<u8> {
+        let saved_floor = self.locals_floor;
+        let saved_reg_idx = self.register_index;
+        let makes_closures = stmts.iter().any(Self::declares_spells);
+        let opened = self.open_locals();
+
+        self.gen_from_stmts(stmts)?;
+        self.close_locals(opened);
+
+        // the closures made in the block keep its locals once their registers are reused
+        if makes_closures {
+            self.instructions
+                .push(Instruction::CloseUpvalues { from: saved_floor });
+        }
+
+        self.locals_floor = saved_floor;
+        self.register_index = saved_reg_idx;
+        Ok(saved_reg_idx)
+    }
+
     /// Match the type of stmt and generate corresponding instruction
     fn gen_from_stmt(&mut self, stmt: WovenStmt) -> GenResult<u8> {
         match stmt {
-            WovenStmt::ExprStmt { expr } => self.gen_from_expr(expr),
+            WovenStmt::ExprStmt { expr, .. } => {
+                // nothing uses the value of an expression statement, its registers are freed right away
+                let mark = self.register_index;
+                let reg = self.gen_from_expr(expr)?;
+                self.free_registers(mark);
+                Ok(reg)
+            }
             WovenStmt::VarDeclaration {
-                name: _,
+                name,
                 mutable: _,
                 initializer,
                 symbol,
-            } => self.gen_var_decl_instruction(initializer, symbol),
+                ..
+            } => self.gen_var_decl_instruction(name, initializer, symbol),
             WovenStmt::Fate {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => self.gen_fate_instructions(condition, *then_branch, else_branch),
-            WovenStmt::While { condition, body } => self.gen_while_instructions(condition, *body),
-            WovenStmt::Chant { expression } => self.gen_chant_stmt(expression),
-            WovenStmt::Block { statements } => self.gen_from_stmts(statements),
-            WovenStmt::Sever { token: _ } => self.gen_sever_instructions(),
-            WovenStmt::Flow { token: _ } => self.gen_flow_instructions(),
+            WovenStmt::While {
+                label,
+                condition,
+                body,
+                ..
+            } => self.gen_while_instructions(label, condition, *body),
+            WovenStmt::Cycle {
+                token,
+                label,
+                variable,
+                start,
+                end,
+                end_symbol,
+                body,
+                ..
+            } => self.gen_cycle_instructions(Cycle {
+                token,
+                label,
+                variable,
+                start,
+                end,
+                end_symbol,
+                body: *body,
+            }),
+            WovenStmt::Circle {
+                label,
+                body,
+                condition,
+                ..
+            } => self.gen_circle_instructions(label, *body, condition),
+            WovenStmt::FlowThrough {
+                token,
+                label,
+                variable,
+                iterable,
+                iterable_symbol,
+                index_symbol,
+                body,
+                ..
+            } => self.gen_flow_through_instructions(FlowThrough {
+                token,
+                label,
+                variable,
+                iterable,
+                iterable_symbol,
+                index_symbol,
+                body: *body,
+            }),
+            WovenStmt::Judge {
+                token,
+                subject,
+                subject_symbol,
+                arms,
+                divert,
+                ..
+            } => self.gen_judge_instructions(token, subject, subject_symbol, arms, divert),
+            WovenStmt::Chant {
+                expressions,
+                newline,
+                ..
+            } => self.gen_chant_stmt(expressions, newline),
+            WovenStmt::Block { statements, .. } => self.gen_block_instructions(statements),
+            WovenStmt::Sever { token: _, label, .. } => self.gen_sever_instructions(label),
+            WovenStmt::Flow { token: _, label, .. } => self.gen_flow_instructions(label),
+            WovenStmt::Attempt {
+                token: _,
+                body,
+                error_symbol,
+                mend,
+                ..
+            } => self.gen_attempt_instructions(*body, error_symbol, *mend),
+            WovenStmt::Doom { token, value, .. } => self.gen_doom_instructions(token, value),
+            WovenStmt::Offer { token: _, value, .. } => self.gen_offer_instructions(value),
+            WovenStmt::Expect {
+                token,
+                condition,
+                message,
+                ..
+            } => self.gen_expect_instructions(token, condition, message),
             WovenStmt::Spell {
                 name,
                 reagents,
                 body,
                 spell_symbol,
+                ..
             } => self.gen_spell_instructions(name, reagents, *body, spell_symbol),
-            WovenStmt::Release { token: _, expr } => self.gen_release_instructions(expr),
+            WovenStmt::Release { token: _, expr, .. } => self.gen_release_instructions(expr),
             WovenStmt::Sign {
                 name,
                 marks,
                 sign_symbol,
+                ..
             } => self.gen_sign_instructions(name, marks, sign_symbol),
-            WovenStmt::Attune { sign, spells } => self.gen_attune_instructions(sign, spells),
+            WovenStmt::Glyph { .. } | WovenStmt::Seal { .. } => {
+                // glyph variants are folded into literals by the analyzer, and seals are inlined where read
+                Ok(self.get_last_allocated_register())
+            }
+            WovenStmt::Attune { sign, spells, .. } => self.gen_attune_instructions(sign, spells),
+            WovenStmt::Tome {
+                name,
+                marks,
+                spells,
+                tome_symbol,
+                inherited,
+                ..
+            } => self.gen_tome_instructions(name, marks, spells, tome_symbol, inherited),
             WovenStmt::Tether {
                 statements,
                 bind_to,
                 path,
+                ..
             } => self.gen_tether_instructions(statements),
         }
     }
 
-    /// Match the type of expr and generate corresponding instruction
+    /// Generate the instructions of expr and note its position
     fn gen_from_expr(&mut self, expr: WovenExpr) -> GenResult<u8> {
+        let position = Self::position_of(&expr.token());
+        let reg = self.gen_expr_instructions(expr)?;
+        self.note_position(position);
+        Ok(reg)
+    }
+
+    /// Match the type of expr and generate corresponding instruction
+    fn gen_expr_instructions(&mut self, expr: WovenExpr) -> GenResult<u8> {
         match expr {
             WovenExpr::Binary {
                 left,
                 right,
                 operator,
                 weave,
+                ..
             } => self.gen_binary_instruction(*left, *right, operator, weave),
             WovenExpr::Unary {
                 operand,
                 operator,
-                weave: _,
-            } => self.gen_unary_instruction(*operand, operator),
+                weave,
+                ..
+            } => self.gen_unary_instruction(*operand, operator, weave),
             WovenExpr::Literal {
                 value,
                 weave: _,
                 token: _,
+                ..
             } => {
                 let val = self.write_constant(value)?;
                 Ok(val)
@@ -303,48 +623,69 @@ impl CodeGen {
                 name: _,
                 weave: _,
                 symbol,
+                ..
             } => self.gen_variable_instruction(&symbol),
             WovenExpr::Grouping {
                 expression,
                 weave: _,
+                ..
             } => self.gen_from_expr(*expression),
             WovenExpr::Assignment {
                 name: _,
                 value,
                 weave: _,
                 symbol,
+                ..
             } => self.gen_assignment_instruction(*value, symbol),
             WovenExpr::Cast {
                 reagents,
                 callee,
                 weave,
                 spell_symbol,
+                ..
             } => self.gen_cast_instruction(reagents, callee, weave, spell_symbol),
+            WovenExpr::Invoke {
+                reagents,
+                callee,
+                weave: _,
+                spell_symbol: _,
+                ..
+            } => self.gen_invoke_instruction(reagents, callee),
             WovenExpr::Draw {
                 marks,
                 callee,
                 weave,
                 sign_symbol,
+                ..
             } => self.gen_draw_instruction(marks, callee, weave, sign_symbol),
             WovenExpr::Access {
                 material,
                 property,
                 field_name_idx,
                 weave,
+                ..
             } => self.gen_access_instruction(*material, property, field_name_idx, weave),
-            WovenExpr::Deck { elements, weave } => self.gen_deck_instruction(elements, weave),
+            WovenExpr::Deck { elements, weave, .. } => self.gen_deck_instruction(elements, weave),
             WovenExpr::Extract {
                 deck,
                 index,
                 token,
                 weave,
+                ..
             } => self.gen_extract_instruction(*deck, *index, token, weave),
+            WovenExpr::Slice {
+                target,
+                start,
+                end,
+                ..
+            } => self.gen_slice_instruction(*target, *start, *end),
             WovenExpr::DeckSet {
                 deck,
                 index,
                 value,
                 token,
                 weave,
+                ..
             } => self.gen_deck_set_instruction(*deck, *index, *value, token, weave),
             WovenExpr::FieldSet {
                 material,
@@ -352,29 +693,69 @@ impl CodeGen {
                 value,
                 field_name_idx,
                 weave,
+                ..
             } => self.gen_field_set_instruction(*material, property, *value, field_name_idx, weave),
             WovenExpr::Manifests {
                 value,
                 token: _,
                 weave: _,
+                ..
             } => self.gen_manifest_instruction(*value),
             WovenExpr::SafeAccess {
                 material,
                 property,
                 field_name_idx,
                 weave,
+                ..
             } => self.gen_safe_access_instruction(*material, property, field_name_idx, weave),
             WovenExpr::AssertSafe {
                 operand,
                 operator: _,
                 weave,
+                ..
             } => self.gen_assert_safe_instruction(*operand, weave),
             WovenExpr::NativeCast {
                 reagents,
                 callee,
                 weave: _,
                 native_spell,
+                ..
             } => self.gen_native_cast_instruction(reagents, callee, native_spell),
+            WovenExpr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                token: _,
+                weave: _,
+                ..
+            } => self.gen_conditional_instruction(*condition, *then_branch, *else_branch),
+            WovenExpr::Heed { token: _, .. } => {
+                let dest = self.get_next_register()?;
+                self.instructions.push(Instruction::Heed { dest });
+                Ok(dest)
+            }
+            WovenExpr::ToText { token: _, value, .. } => {
+                let mark = self.register_index;
+                let r1 = self.gen_from_expr(*value)?;
+                self.free_registers(mark);
+                let dest = self.get_next_register()?;
+                self.instructions.push(Instruction::ToText { dest, r1 });
+                Ok(dest)
+            }
+            WovenExpr::Conjure { token: _, cast, weave: _, .. } => self.gen_conjure_instruction(*cast),
+            WovenExpr::Claim {
+                token: _,
+                conjuring,
+                weave: _,
+                ..
+            } => {
+                let mark = self.register_index;
+                let handle = self.gen_from_expr(*conjuring)?;
+                self.free_registers(mark);
+                let dest = self.get_next_register()?;
+                self.instructions.push(Instruction::Claim { dest, handle });
+                Ok(dest)
+            }
         }
     }
 
@@ -404,13 +785,15 @@ impl CodeGen {
         _callee: Token,
         native_spell: NativeSpell,
     ) -> GenResult<u8> {
-        let dest = self.get_next_register()?;
-        let spell_idx = self.add_constant(Value::NativeSpell(native_spell))?;
+        let mark = self.register_index;
+        let Ok(spell_idx) = u16::try_from(self.add_constant(Value::NativeSpell(Rc::new(native_spell)))?) else {
+            return self.error("The native spell was cast after the first 65536 constants of the spell! Try casting it in a smaller spell.");
+        };
 
         let mut reagent_regs: Vec<u8> = vec![];
 
-        for reagent in reagents.iter() {
-            let r = self.gen_from_expr(reagent.clone())?;
+        for reagent in reagents {
+            let r = self.gen_from_expr(reagent)?;
             reagent_regs.push(r);
         }
 
@@ -444,6 +827,10 @@ impl CodeGen {
             }
         };
 
+        // the reagents are read before the release is written, so dest can take their place
+        self.free_registers(mark);
+        let dest = self.get_next_register()?;
+
         self.instructions.push(Instruction::NativeCast {
             dest,
             nat_spell: spell_idx,
@@ -459,7 +846,7 @@ impl CodeGen {
         self.instructions
             .push(Instruction::AssertSafe { r1: operand_reg });
 
-        Ok(self.get_last_allocated_register())
+        Ok(operand_reg)
     }
 
     fn gen_safe_access_instruction(
@@ -469,8 +856,10 @@ impl CodeGen {
         field_name_idx: u16,
         _weave: Weave,
     ) -> GenResult<u8> {
+        let mark = self.register_index;
         let mat_reg = self.gen_from_expr(material)?;
 
+        self.free_registers(mark);
         let dest = self.get_next_register()?;
 
         self.instructions.push(Instruction::SafeGetField {
@@ -483,7 +872,10 @@ impl CodeGen {
     }
 
     fn gen_manifest_instruction(&mut self, value: WovenExpr) -> GenResult<u8> {
+        let mark = self.register_index;
         let val_reg = self.gen_from_expr(value)?;
+
+        self.free_registers(mark);
         let dest = self.get_next_register()?;
 
         self.instructions.push(Instruction::IsEmptiness {
@@ -544,9 +936,11 @@ impl CodeGen {
         _token: Token,
         _weave: Weave,
     ) -> GenResult<u8> {
+        let mark = self.register_index;
         let deck_reg = self.gen_from_expr(deck)?;
         let index_reg = self.gen_from_expr(index)?;
 
+        self.free_registers(mark);
         let dest = self.get_next_register()?;
 
         self.instructions.push(Instruction::ExtractFromDeck {
@@ -558,6 +952,30 @@ impl CodeGen {
         Ok(dest)
     }
 
+    fn gen_slice_instruction(
+        &mut self,
+        target: WovenExpr,
+        start: WovenExpr,
+        end: WovenExpr,
+    ) -> GenResult<u8> {
+        let mark = self.register_index;
+        let target_reg = self.gen_from_expr(target)?;
+        let start_reg = self.gen_from_expr(start)?;
+        let end_reg = self.gen_from_expr(end)?;
+
+        self.free_registers(mark);
+        let dest = self.get_next_register()?;
+
+        self.instructions.push(Instruction::Slice {
+            dest,
+            r1: target_reg,
+            start: start_reg,
+            end: end_reg,
+        });
+
+        Ok(dest)
+    }
+
     fn gen_deck_instruction(&mut self, elements: Vec<WovenExpr>, weave: Weave) -> GenResult<u8> {
         // let start_reg = self.register_index;
 
@@ -567,12 +985,11 @@ impl CodeGen {
             return self.error("Deck size exceeds the maximum of 255 elements!");
         }
 
-        for element in &elements {
-            elem_regs.push(self.gen_from_expr(element.clone())?);
+        let mark = self.register_index;
+        for element in elements {
+            elem_regs.push(self.gen_from_expr(element)?);
         }
 
-        let deck_reg = self.get_next_register()?;
-
         let start_reg = if elem_regs.is_empty() {
             self.register_index
         } else if elem_regs.len() == 1 {
@@ -601,19 +1018,22 @@ impl CodeGen {
             }
         };
 
+        self.free_registers(mark);
+        let deck_reg = self.get_next_register()?;
+
         match weave {
             Weave::Deck(_, capacity) => {
                 if let Some(c) = capacity {
                     self.instructions.push(Instruction::NewFixedDeck {
                                         dest: deck_reg,
-                                        count: elements.len() as u8,
+                                        count: elem_regs.len() as u8,
                                         start_reg: start_reg,
                                         capacity: c as u16,
                                     });
                 } else {
                     self.instructions.push(Instruction::NewDeck {
                         dest: deck_reg,
-                        count: elements.len() as u8,
+                        count: elem_regs.len() as u8,
                         start_reg: start_reg,
                     });
                 }
@@ -632,8 +1052,10 @@ impl CodeGen {
         field_name_idx: u16,
         _weave: Weave,
     ) -> GenResult<u8> {
+        let mark = self.register_index;
         let s_reg = self.gen_from_expr(material)?;
 
+        self.free_registers(mark);
         let dest = self.get_next_register()?;
 
         let inst = Instruction::GetField {
@@ -651,29 +1073,31 @@ impl CodeGen {
         marks: Vec<WovenEtchedMark>,
         _callee: Token,
         _weave: Weave,
-        sign_symbol: Symbol,
+        sign_symbol: Rc<Symbol>,
     ) -> GenResult<u8> {
+        let new_sign_reg = self.get_next_register()?;
+        let temps = self.register_index;
+
         let reg = self.gen_variable_instruction(&sign_symbol)?;
         let mut mark_regs: Vec<u8> = Vec::with_capacity(marks.len());
 
-        let new_sign_reg = self.get_next_register()?;
-
         let inst = Instruction::NewSign {
             dest: new_sign_reg,
             schema_reg: reg,
         };
 
         self.instructions.push(inst);
+        self.free_registers(temps);
 
         let sign_info = sign_symbol.kind.borrow().get_sign_info().unwrap();
 
         let schema = sign_info.schema;
 
-        for mark in marks.iter() {
-            let r = self.gen_from_expr(mark.expr.clone())?;
+        for mark in marks {
+            let r = self.gen_from_expr(mark.expr)?;
             mark_regs.push(r);
 
-            let field_name_idx = schema.get_field_index(mark.name.lexeme.clone());
+            let field_name_idx = schema.get_field_index(mark.name.lexeme.to_string());
 
             if field_name_idx.is_none() {
                 return self.error(&format!(
@@ -688,6 +1112,7 @@ impl CodeGen {
                 val_reg: r,
             };
             self.instructions.push(set_inst);
+            self.free_registers(temps);
         }
 
         Ok(new_sign_reg)
@@ -698,49 +1123,81 @@ impl CodeGen {
         reagents: Vec<WovenExpr>,
         _callee: Token,
         _weave: Weave,
-        spell_symbol: Symbol,
+        spell_symbol: Rc<Symbol>,
     ) -> GenResult<u8> {
+        let mark = self.register_index;
         let spell_reg = self.gen_variable_instruction(&spell_symbol)?;
         // Evaluate reagents and capture their result registers in order
         let mut reagent_regs: Vec<u8> = Vec::with_capacity(reagents.len());
-        for reagent in reagents.iter() {
-            let r = self.gen_from_expr(reagent.clone())?;
+        for reagent in reagents {
+            let r = self.gen_from_expr(reagent)?;
+            reagent_regs.push(r);
+        }
+
+        self.write_cast(mark, spell_reg, reagent_regs)
+    }
+
+    /// Casts the attunement of the tome held by the first reagent (ego), looked up at runtime
+    /// so that the referring tomes get their own version of the spell.
+    fn gen_invoke_instruction(&mut self, reagents: Vec<WovenExpr>, callee: Token) -> GenResult<u8> {
+        let mark = self.register_index;
+        let mut reagent_regs: Vec<u8> = Vec::with_capacity(reagents.len());
+        for reagent in reagents {
+            let r = self.gen_from_expr(reagent)?;
             reagent_regs.push(r);
         }
 
-        // self.register_index = reg_idx;
+        let spell_reg = self.get_next_register()?;
+        let spell = self.symbol(&callee.lexeme)?;
+        self.instructions.push(Instruction::GetAttunement {
+            dest: spell_reg,
+            sign_reg: reagent_regs[0],
+            spell,
+        });
+
+        self.write_cast(mark, spell_reg, reagent_regs)
+    }
+
+    /// The cast is written like any other, only its spell is started alongside the caster
+    fn gen_conjure_instruction(&mut self, cast: WovenExpr) -> GenResult<u8> {
+        self.gen_from_expr(cast)?;
+        match self.instructions.pop() {
+            Some(Instruction::Cast { dest, spell_reg, reg_start }) => {
+                self.instructions.push(Instruction::Conjure { dest, spell_reg, reg_start });
+                Ok(dest)
+            }
+            _ => self.error("Only a cast of a spell can be conjured!"),
+        }
+    }
 
-        if reagents.len() > u8::MAX as usize {
+    /// Writes the [Cast] instruction, packing the reagents into a contiguous block if needed.
+    /// The temporaries from [mark] are freed, the release takes the first of them.
+    fn write_cast(&mut self, mark: u8, spell_reg: u8, reagent_regs: Vec<u8>) -> GenResult<u8> {
+        if reagent_regs.len() > u8::MAX as usize {
             return self.error(
                 "Too many reagents passed to cast! What are you scheming with all these reagents?!",
             );
         }
 
-        let dest = self.get_next_register()?;
-
         let reg_start = if reagent_regs.is_empty() {
             self.register_index
         } else if reagent_regs.len() == 1 {
             reagent_regs[0]
         } else {
-            let mut contiguous = true;
-            for w in reagent_regs.windows(2) {
-                if w[1] != w[0].saturating_add(1) {
-                    contiguous = false;
-                    break;
-                }
-            }
+            let contiguous = reagent_regs
+                .windows(2)
+                .all(|w| w[1] == w[0].saturating_add(1));
 
             if contiguous {
                 reagent_regs[0]
             } else {
                 // Pack reagents into a fresh contiguous block
                 let start = self.register_index; // first one goes here
-                for (_, &src) in reagent_regs.iter().enumerate() {
+                for &src in reagent_regs.iter() {
                     let dest = self.get_next_register()?;
                     // dest should be start + i
                     self.instructions.push(Instruction::Move {
-                        dest: dest,
+                        dest,
                         source: src as u16,
                     });
                 }
@@ -748,6 +1205,10 @@ impl CodeGen {
             }
         };
 
+        // the reagents are copied into the new frame when casting, so dest can take their place
+        self.free_registers(mark);
+        let dest = self.get_next_register()?;
+
         self.instructions.push(Instruction::Cast {
             dest,
             spell_reg,
@@ -759,6 +1220,7 @@ impl CodeGen {
 
     fn gen_release_instructions(&mut self, expr: Option<WovenExpr>) -> GenResult<u8> {
         // Generate release value (or Emptiness if none) and emit Release instruction
+        let mark = self.register_index;
         let dest = if let Some(e) = expr {
             self.gen_from_expr(e)?
         } else {
@@ -768,20 +1230,167 @@ impl CodeGen {
         };
 
         self.instructions.push(Instruction::Release { dest });
+        self.free_registers(mark);
         Ok(dest)
     }
 
+    fn gen_offer_instructions(&mut self, value: WovenExpr) -> GenResult<u8> {
+        let mark = self.register_index;
+        let src = self.gen_from_expr(value)?;
+        self.instructions.push(Instruction::Offer { src });
+        self.free_registers(mark);
+        Ok(src)
+    }
+
+    fn gen_doom_instructions(&mut self, token: Token, value: WovenExpr) -> GenResult<u8> {
+        let mark = self.register_index;
+        let reg = self.gen_from_expr(value)?;
+        self.instructions.push(Instruction::Throw { r1: reg });
+        // the breakdown is reported at the doom, not at its value
+        self.note_position(Self::position_of(&token));
+        self.free_registers(mark);
+        Ok(reg)
+    }
+
+    /// A met expectation jumps over the doom of the failed one
+    fn gen_expect_instructions(
+        &mut self,
+        token: Token,
+        condition: WovenExpr,
+        message: WovenExpr,
+    ) -> GenResult<u8> {
+        let mark = self.register_index;
+        let cond_reg = self.gen_from_expr(condition)?;
+        let (failed, met) = (self.new_label(), self.new_label());
+        self.write_jump(
+            Instruction::JumpIfFalse {
+                condition_reg: cond_reg,
+                offset: 0,
+            },
+            failed,
+        );
+        self.write_jump(Instruction::Jump { offset: 0 }, met);
+        self.free_registers(mark);
+        self.place_label(failed);
+
+        self.gen_doom_instructions(token, message)?;
+        self.place_label(met);
+
+        Ok(cond_reg)
+    }
+
+    /// Whether every path through the statement ends up in a release
+    fn always_releases(stmt: &WovenStmt) -> bool {
+        match stmt {
+            // a doom never falls through either, it leaves the spell for the mend or breaks the VM down
+            WovenStmt::Release { .. } | WovenStmt::Doom { .. } => true,
+            WovenStmt::Block { statements, .. } => statements.iter().any(Self::always_releases),
+            WovenStmt::Fate {
+                then_branch,
+                else_branch: Some(else_branch),
+                ..
+            } => Self::always_releases(then_branch) && Self::always_releases(else_branch),
+            WovenStmt::Attempt { body, mend, .. } => {
+                Self::always_releases(body) && Self::always_releases(mend)
+            }
+            WovenStmt::Judge {
+                arms,
+                divert: Some(divert),
+                ..
+            } => {
+                arms.iter().all(|(_, body)| Self::always_releases(body))
+                    && Self::always_releases(divert)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether [stmt] declares a spell in the scope it runs in, which may capture its locals
+    fn declares_spells(stmt: &WovenStmt) -> bool {
+        match stmt {
+            WovenStmt::Spell { .. } | WovenStmt::Tome { .. } | WovenStmt::Attune { .. } => true,
+            WovenStmt::Block { statements, .. } | WovenStmt::Tether { statements, .. } => {
+                statements.iter().any(Self::declares_spells)
+            }
+            WovenStmt::Fate {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                Self::declares_spells(then_branch)
+                    || else_branch.as_deref().is_some_and(Self::declares_spells)
+            }
+            WovenStmt::While { body, .. }
+            | WovenStmt::Cycle { body, .. }
+            | WovenStmt::Circle { body, .. }
+            | WovenStmt::FlowThrough { body, .. } => Self::declares_spells(body),
+            WovenStmt::Attempt { body, mend, .. } => {
+                Self::declares_spells(body) || Self::declares_spells(mend)
+            }
+            WovenStmt::Judge { arms, divert, .. } => {
+                arms.iter().any(|(_, body)| Self::declares_spells(body))
+                    || divert.as_deref().is_some_and(Self::declares_spells)
+            }
+            _ => false,
+        }
+    }
+
+    fn gen_tome_instructions(
+        &mut self,
+        name: Token,
+        marks: Vec<WovenMark>,
+        spells: Vec<WovenStmt>,
+        tome_symbol: Rc<Symbol>,
+        inherited: Vec<(String, String)>,
+    ) -> GenResult<u8> {
+        let reg = self.gen_sign_instructions(name, marks, tome_symbol)?;
+
+        for spell in spells {
+            self.gen_from_stmt(spell)?;
+        }
+
+        // the attunements which are not overridden point to the referred tome's spell, so that
+        // runtime lookups on this tome can find them
+        for (own, origin) in inherited {
+            let origin_slot = self.global_slot(origin)?;
+            let mark = self.register_index;
+            let spell_reg = self.get_next_register()?;
+            self.instructions.push(Instruction::GetGlobal {
+                dest: spell_reg,
+                slot: origin_slot,
+            });
+
+            let own_slot = self.global_slot(own)?;
+            self.instructions.push(Instruction::SetGlobal {
+                src_reg: spell_reg,
+                slot: own_slot,
+            });
+            self.free_registers(mark);
+        }
+
+        Ok(reg)
+    }
+
     fn gen_sign_instructions(
         &mut self,
         _name: Token,
         _marks: Vec<WovenMark>,
-        sign_symbol: Symbol,
+        sign_symbol: Rc<Symbol>,
     ) -> GenResult<u8> {
         // unwrap cus its almost sure that sign info is contained it the symbol
-        let sign_info = sign_symbol.kind.borrow().get_sign_info().unwrap();
+        let mut sign_info = sign_symbol.kind.borrow().get_sign_info().unwrap();
 
+        // the attuned spells live in globals, the schema points the runtime lookups to them
+        let mut attunements = Vec::with_capacity(sign_info.attunements.len());
+        for (spell, global) in &sign_info.attunements {
+            attunements.push((self.symbol(spell)?, self.global_slot(global.clone())?));
+        }
+        sign_info.schema.set_attunements(attunements);
+
+        let mark = self.register_index;
         let reg = self.write_constant(Value::SignSchema(Rc::new(sign_info.schema)))?;
-        self.set_value_instruction(sign_symbol, reg)?;
+        self.set_value_instruction(&sign_symbol, reg)?;
+        self.free_registers(mark);
 
         Ok(reg)
     }
@@ -791,49 +1400,85 @@ impl CodeGen {
         name: Token,
         reagents: Vec<WovenReagent>,
         body: WovenStmt,
-        spell_symbol: Symbol,
+        spell_symbol: Rc<Symbol>,
     ) -> GenResult<u8> {
         // Save current state before entering spell compilation context
         let saved_reg_idx = self.register_index;
+        let saved_floor = self.locals_floor;
+        let saved_reg_count = self.register_count;
         let mut spell_instructions = Vec::new();
-        let saved_curr_upval_count = self.curr_upval_count;
+        let mut spell_positions = Vec::new();
+        let mut spell_labels = Vec::new();
+        let mut spell_jumps = Vec::new();
+        let mut spell_locals = Vec::new();
         let saved_inspell = self.in_spell;
         let saved_upval_map = self.upval_map.clone();
+        let saved_current_spell = self.current_spell;
 
         let spell_info = spell_symbol.kind.borrow().get_spell_info().unwrap();
 
+        if spell_info.upvalues.len() > u8::MAX as usize + 1 {
+            return self.error("A spell can't capture more than 256 upvalues!");
+        }
+
+        // where the closure finds its upvalues, from the frame creating it
+        let captures = spell_info
+            .upvalues
+            .iter()
+            .map(|upv| match self.upval_map.get(&(upv.depth, upv.index)) {
+                Some(index) => Capture::Enclosing(*index),
+                None => Capture::Local(upv.index as u8),
+            })
+            .collect();
+
         // Temporarily swap instructions to compile spell body
         std::mem::swap(&mut self.instructions, &mut spell_instructions);
+        std::mem::swap(&mut self.positions, &mut spell_positions);
+        std::mem::swap(&mut self.labels, &mut spell_labels);
+        std::mem::swap(&mut self.jumps, &mut spell_jumps);
+        std::mem::swap(&mut self.locals, &mut spell_locals);
 
         // state modifications for upvalues management
-        let upval_count = spell_info.upvalues.len();
         self.in_spell = true;
-        self.curr_upval_count = upval_count;
+        self.current_spell = Some((spell_symbol.depth, spell_symbol.slot_idx));
 
+        // the upvalues of the enclosing spell are reached through this one's own
+        self.upval_map.clear();
         for (i, upv) in spell_info.upvalues.iter().enumerate() {
             // Use (depth, index) as key to avoid collisions between upvalues and locals
-            self.upval_map.insert((upv.depth, upv.index), i);
+            self.upval_map.insert((upv.depth, upv.index), i as u8);
         }
 
         // Push a new constant pool and index map for spell
         self.constants.push(vec![]);
         self.constants_idx_map.push(HashMap::new());
-        self.register_index = (upval_count + reagents.len()) as u8; // Reserve registers for reagents
+        self.register_index = reagents.len() as u8; // Reserve registers for reagents
+        self.locals_floor = self.register_index;
+        self.register_count = self.register_index as usize;
+        let opened = self.open_locals();
+        for (register, reagent) in reagents.iter().enumerate() {
+            if let Some(reagent_name) = &reagent.name {
+                self.declare_local(reagent_name, register as u8, name.line);
+            }
+        }
+
+        let needs_return = !Self::always_releases(&body);
 
         // Compile the body
         self.gen_from_stmt(body)?;
 
-        // Add implicit return if missing
-        // let needs_return = !matches!(self.instructions.last(), Some(Instruction::Release { .. }));
-        // if needs_return {
+        // Release emptiness for the paths falling off the end of the spell
+        if needs_return {
+            let ret_reg = self.get_next_register()?;
+            self.instructions
+                .push(Instruction::Emptiness { dest: ret_reg });
+            self.instructions
+                .push(Instruction::Release { dest: ret_reg });
+        }
 
-        // TODO: THIS IS A TEMPORARY FIX! add explicit return
-        let ret_reg = self.get_next_register()?;
-        self.instructions
-            .push(Instruction::Emptiness { dest: ret_reg });
-        self.instructions
-            .push(Instruction::Release { dest: ret_reg });
-        // }
+        self.note_position(self.last_position);
+        self.close_locals(opened);
+        self.finish_instructions()?;
 
         if self.print_instructions {
             print_instructions(
@@ -845,6 +1490,11 @@ impl CodeGen {
 
         // Get the compiled results
         let spell_bytecode = Assembler::convert_to_byte_code(&self.instructions);
+        let line_table = Assembler::convert_line_table(&self.instructions, &self.positions);
+        let debug = self.debug_info.then(|| DebugInfo {
+            positions: Assembler::convert_position_table(&self.instructions, &self.positions),
+            locals: std::mem::take(&mut self.locals),
+        });
 
         if self.print_bytecode {
             println!("spell: {}", spell_info.name);
@@ -855,159 +1505,698 @@ impl CodeGen {
         self.constants_idx_map.pop(); // Pop the spell's constant map
 
         let spell = SpellObject {
-            name: Some(name.lexeme.clone()),
+            name: Some(name.lexeme.to_string()),
             arity: reagents.len() as u8,
-            upvalue_count: upval_count as i32,
+            captures,
             constants: spell_constants,
             bytecode: spell_bytecode,
+            flowing: matches!(spell_info.release_weave, Weave::Stream(_)),
+            lines: line_table,
+            register_count: self.register_count,
+            debug,
         };
         let closure = ClosureObject {
             spell: Rc::new(spell),
-            upvalues: spell_info.upvalues.clone(),
+            upvalues: vec![], // filled in by the vm as the closure is made
         };
 
         // Restore the main instructions and register state
         std::mem::swap(&mut self.instructions, &mut spell_instructions);
+        std::mem::swap(&mut self.positions, &mut spell_positions);
+        std::mem::swap(&mut self.labels, &mut spell_labels);
+        std::mem::swap(&mut self.jumps, &mut spell_jumps);
+        std::mem::swap(&mut self.locals, &mut spell_locals);
         self.register_index = saved_reg_idx;
+        self.locals_floor = saved_floor;
+        self.register_count = saved_reg_count;
         self.in_spell = saved_inspell;
-        self.curr_upval_count = saved_curr_upval_count;
         self.upval_map = saved_upval_map;
+        self.current_spell = saved_current_spell;
 
         // Write the constant and set the value
         let const_idx = self.write_constant(Value::Closure(Rc::new(closure)))?;
-        self.set_value_instruction(spell_symbol, const_idx)?;
+        if spell_symbol.depth > 0 && !self.upval_map.contains_key(&(spell_symbol.depth, spell_symbol.slot_idx)) {
+            self.declare_local(&name.lexeme, spell_symbol.slot_idx as u8, name.line);
+        }
+        self.set_value_instruction(&spell_symbol, const_idx)?;
+        self.note_position(Self::position_of(&name));
+        self.free_registers(saved_reg_idx);
 
         Ok(const_idx)
     }
 
-    fn gen_flow_instructions(&mut self) -> GenResult<u8> {
-        if self.loop_blocks.is_empty() {
+    fn gen_flow_instructions(&mut self, label: Option<Token>) -> GenResult<u8> {
+        let Some(target) = self.loop_named(&label) else {
             return self.error("flow can only be performed inside a loop block!");
+        };
+        self.end_open_attempts(target);
+        if let Some(from) = self.loop_blocks[target].closes_from {
+            self.instructions.push(Instruction::CloseUpvalues { from });
         }
-        let ind = self.write_jump(Instruction::Jump { offset: 0xffff });
-        self.loop_blocks.last_mut().unwrap().flows.push(ind);
+        self.write_jump(Instruction::Jump { offset: 0 }, self.loop_blocks[target].next);
 
         Ok(self.register_index) // dummy
     }
 
-    fn gen_sever_instructions(&mut self) -> GenResult<u8> {
-        if self.loop_blocks.is_empty() {
+    fn gen_sever_instructions(&mut self, label: Option<Token>) -> GenResult<u8> {
+        let Some(target) = self.loop_named(&label) else {
             return self.error("Only the loops can be severed.");
-        }
-        let ind = self.write_jump(Instruction::Jump { offset: 0xffff });
-        self.loop_blocks.last_mut().unwrap().severs.push(ind);
+        };
+        self.end_open_attempts(target);
+        self.write_jump(Instruction::Jump { offset: 0 }, self.loop_blocks[target].exit);
 
         // dummy return
         Ok(self.register_index)
     }
 
-    fn gen_while_instructions(&mut self, condition: WovenExpr, body: WovenStmt) -> GenResult<u8> {
-        let start = self.instructions.len();
+    /// The loop block a sever or a flow jumps out of, the innermost one unless another is named
+    fn loop_named(&self, label: &Option<Token>) -> Option<usize> {
+        match label {
+            Some(label) => self
+                .loop_blocks
+                .iter()
+                .rposition(|block| block.label.as_deref() == Some(&*label.lexeme)),
+            None => self.loop_blocks.len().checked_sub(1),
+        }
+    }
+
+    /// Ends the attempts a sever or a flow leaves behind, as it jumps out of the target loop and
+    /// the ones inside it
+    fn end_open_attempts(&mut self, target: usize) {
+        let attempts: usize = self.loop_blocks[target..]
+            .iter()
+            .map(|block| block.attempts)
+            .sum();
+        for _ in 0..attempts {
+            self.instructions.push(Instruction::EndAttempt {});
+        }
+    }
+
+    /// The mend is jumped to with the error when the body breaks down, and jumped over when it doesn't
+    fn gen_attempt_instructions(
+        &mut self,
+        body: WovenStmt,
+        error_symbol: Rc<Symbol>,
+        mend: WovenStmt,
+    ) -> GenResult<u8> {
+        let saved_floor = self.locals_floor;
+        let saved_reg_idx = self.register_index;
+        let error_reg = error_symbol.slot_idx as u8;
+        let body_makes_closures = Self::declares_spells(&body);
+        let mend_makes_closures = Self::declares_spells(&mend);
+        let body_releases = Self::always_releases(&body);
+
+        let mend_label = self.new_label();
+        self.write_jump(Instruction::Attempt { error_reg, offset: 0 }, mend_label);
+        self.pin_local(error_reg);
+        if let Some(block) = self.loop_blocks.last_mut() {
+            block.attempts += 1;
+        }
+
+        self.gen_from_stmt(body)?;
+
+        if let Some(block) = self.loop_blocks.last_mut() {
+            block.attempts -= 1;
+        }
+        // a body that always releases leaves the frame, along with its attempt
+        let skip = if body_releases {
+            None
+        } else {
+            self.instructions.push(Instruction::EndAttempt {});
+            let skip = self.new_label();
+            self.write_jump(Instruction::Jump { offset: 0 }, skip);
+            Some(skip)
+        };
+
+        self.place_label(mend_label);
+        let opened = self.open_locals();
+        self.declare_local(&error_symbol.name, error_reg, self.last_position.0);
+
+        // the body broke down before reaching its end, the closures it made keep its locals
+        if body_makes_closures {
+            self.instructions
+                .push(Instruction::CloseUpvalues { from: saved_floor });
+        }
+
+        self.gen_from_stmt(mend)?;
+        self.close_locals(opened);
+
+        // the error and the locals of the mend go out of scope
+        if mend_makes_closures {
+            self.instructions
+                .push(Instruction::CloseUpvalues { from: saved_floor });
+        }
+        if let Some(skip) = skip {
+            self.place_label(skip);
+        }
+
+        self.locals_floor = saved_floor;
+        self.register_index = saved_reg_idx;
+        Ok(saved_reg_idx)
+    }
+
+    fn gen_while_instructions(
+        &mut self,
+        label: Option<Token>,
+        condition: WovenExpr,
+        body: WovenStmt,
+    ) -> GenResult<u8> {
+        let start = self.label_here();
+        let (exit, next) = (self.new_label(), self.new_label());
+        let mark = self.register_index;
+        let position = Self::position_of(&condition.token());
         let cond_reg = self.gen_from_expr(condition)?;
 
-        let exit = self.write_jump(Instruction::JumpIfFalse {
-            condition_reg: cond_reg,
-            offset: 0xffff,
-        });
+        self.write_jump(
+            Instruction::JumpIfFalse {
+                condition_reg: cond_reg,
+                offset: 0,
+            },
+            exit,
+        );
+        self.note_position(position);
+        self.free_registers(mark);
+
+        let makes_closures = Self::declares_spells(&body);
 
         // Add a loop block before the body to manipulate iteration incase of severs or flows
         self.loop_blocks.push(LoopBlock {
-            severs: vec![],
-            flows: vec![],
+            label: label.map(|label| label.lexeme.to_string()),
+            exit,
+            next,
+            attempts: 0,
+            closes_from: makes_closures.then_some(self.locals_floor),
         });
 
         self.gen_from_stmt(body)?;
 
-        // get the final index of the loop for the flow to jump
-        let loop_idx = self.write_loop(start)?;
+        // the flows land on the loop back to the condition
+        self.place_label(next);
+        self.write_jump(Instruction::Loop { offset: 0 }, start);
+        self.note_position(position);
+
+        // severs land along with the exit
+        self.place_label(exit);
+        self.loop_blocks.pop();
 
-        self.patch_jump(exit)?;
+        // severing skips the end of the body, its locals are closed over here instead
+        if makes_closures {
+            self.instructions.push(Instruction::CloseUpvalues {
+                from: self.locals_floor,
+            });
+        }
+
+        Ok(cond_reg)
+    }
 
-        let block = self.loop_blocks.pop().unwrap();
-        let severs = block.severs;
-        let flows = block.flows;
+    /// A cycle is desugared into an init, a condition (mark < end) and an increment
+    /// which runs after the body (and on every flow).
+    fn gen_cycle_instructions(&mut self, cycle: Cycle) -> GenResult<u8> {
+        let Cycle {
+            token,
+            label,
+            variable,
+            start,
+            end,
+            end_symbol,
+            body,
+        } = cycle;
+
+        // the mark and the bound are locals of the cycle, released along with it
+        let saved_floor = self.locals_floor;
+        let saved_reg_idx = self.register_index;
 
-        for jump in severs {
-            self.patch_jump(jump)?;
+        // init: the bounds are evaluated only once
+        let opened = self.open_locals();
+        let start_reg = self.gen_from_expr(start)?;
+        if variable.depth > 0 {
+            self.declare_local(&variable.name, variable.slot_idx as u8, self.last_position.0);
         }
+        self.set_value_instruction(&variable, start_reg)?;
+        self.free_registers(saved_reg_idx);
+        let end_reg = self.gen_from_expr(end)?;
+        self.set_value_instruction(&end_symbol, end_reg)?;
+        self.free_registers(saved_reg_idx);
+
+        // condition
+        let loop_start = self.label_here();
+        let (exit, next) = (self.new_label(), self.new_label());
+        let mark = self.register_index;
+        let var_reg = self.gen_variable_instruction(&variable)?;
+        let bound_reg = self.gen_variable_instruction(&end_symbol)?;
+        let cond_reg = self.get_next_register()?;
+        self.instructions.push(Instruction::Less {
+            dest: cond_reg,
+            r1: var_reg,
+            r2: bound_reg,
+        });
+
+        self.write_jump(
+            Instruction::JumpIfFalse {
+                condition_reg: cond_reg,
+                offset: 0,
+            },
+            exit,
+        );
+        self.note_position(Self::position_of(&token));
+        self.free_registers(mark);
+
+        let makes_closures = Self::declares_spells(&body);
+        self.loop_blocks.push(LoopBlock {
+            label: label.map(|label| label.lexeme.to_string()),
+            exit,
+            next,
+            attempts: 0,
+            closes_from: makes_closures.then_some(self.locals_floor),
+        });
 
-        for jump in flows {
-            self.patch_jump_to(jump, loop_idx)?;
+        self.gen_from_stmt(body)?;
+
+        // increment, flows land here so the mark still steps forward
+        self.place_label(next);
+        let int = variable.weave == Weave::Int;
+        let step = if int { Value::Int(1) } else { Value::Number(1.0) };
+        let step_reg = self.write_constant(step)?;
+        let var_reg = self.gen_variable_instruction(&variable)?;
+        let next_reg = self.get_next_register()?;
+        self.instructions.push(if int {
+            Instruction::AddInt {
+                dest: next_reg,
+                r1: var_reg,
+                r2: step_reg,
+            }
+        } else {
+            Instruction::Add {
+                dest: next_reg,
+                r1: var_reg,
+                r2: step_reg,
+            }
+        });
+        self.set_value_instruction(&variable, next_reg)?;
+        self.free_registers(mark);
+
+        self.write_jump(Instruction::Loop { offset: 0 }, loop_start);
+        self.note_position(Self::position_of(&token));
+        self.place_label(exit);
+        self.close_locals(opened);
+        self.loop_blocks.pop();
+
+        // the mark and the locals of a severed body are closed over on the way out
+        if makes_closures {
+            self.instructions
+                .push(Instruction::CloseUpvalues { from: saved_floor });
+        }
+
+        self.locals_floor = saved_floor;
+        self.register_index = saved_reg_idx;
+        Ok(cond_reg)
+    }
+
+    /// The body runs first, and the condition sends the circle back to it for as long as it doesn't hold
+    fn gen_circle_instructions(
+        &mut self,
+        label: Option<Token>,
+        body: WovenStmt,
+        condition: WovenExpr,
+    ) -> GenResult<u8> {
+        let start = self.label_here();
+        let (exit, next) = (self.new_label(), self.new_label());
+        let makes_closures = Self::declares_spells(&body);
+
+        self.loop_blocks.push(LoopBlock {
+            label: label.map(|label| label.lexeme.to_string()),
+            exit,
+            next,
+            attempts: 0,
+            closes_from: makes_closures.then_some(self.locals_floor),
+        });
+
+        self.gen_from_stmt(body)?;
+
+        // flows land on the condition, as the body is done for the round
+        self.place_label(next);
+        let mark = self.register_index;
+        let position = Self::position_of(&condition.token());
+        let cond_reg = self.gen_from_expr(condition)?;
+        // the condition may be read straight from a local, which is left as it is
+        let again_reg = self.get_next_register()?;
+        self.instructions.push(Instruction::Not {
+            dest: again_reg,
+            r1: cond_reg,
+        });
+        self.write_jump(
+            Instruction::JumpIfFalse {
+                condition_reg: again_reg,
+                offset: 0,
+            },
+            exit,
+        );
+        self.free_registers(mark);
+        self.write_jump(Instruction::Loop { offset: 0 }, start);
+        self.note_position(position);
+
+        self.place_label(exit);
+        self.loop_blocks.pop();
+
+        // severing skips the end of the body, its locals are closed over here instead
+        if makes_closures {
+            self.instructions.push(Instruction::CloseUpvalues {
+                from: self.locals_floor,
+            });
         }
 
         Ok(cond_reg)
     }
 
+    fn gen_flow_through_instructions(&mut self, flow: FlowThrough) -> GenResult<u8> {
+        let FlowThrough {
+            token,
+            label,
+            variable,
+            iterable,
+            iterable_symbol,
+            index_symbol,
+            body,
+        } = flow;
+
+        // the mark, the flowed value and the index are locals of the flow, released along with it
+        let saved_floor = self.locals_floor;
+        let saved_reg_idx = self.register_index;
+
+        // init: the value is evaluated only once, and walked from its first element
+        let opened = self.open_locals();
+        if variable.depth > 0 {
+            self.declare_local(&variable.name, variable.slot_idx as u8, token.line);
+        }
+        // a stream isn't counted through, each round draws its next value
+        let streamed = matches!(iterable.weave(), Weave::Stream(_));
+        let iterable_reg = self.gen_from_expr(iterable)?;
+        self.set_value_instruction(&iterable_symbol, iterable_reg)?;
+        self.free_registers(saved_reg_idx);
+        if !streamed {
+            let start_reg = self.write_constant(Value::Number(0.0))?;
+            self.set_value_instruction(&index_symbol, start_reg)?;
+            self.free_registers(saved_reg_idx);
+        }
+
+        // condition, then the mark takes the element of the round
+        let loop_start = self.label_here();
+        let (exit, next) = (self.new_label(), self.new_label());
+        let mark = self.register_index;
+        let flowed_reg = self.gen_variable_instruction(&iterable_symbol)?;
+        let cond_reg = if streamed {
+            let element_reg = self.get_next_register()?;
+            let more_reg = self.get_next_register()?;
+            self.instructions.push(Instruction::Draw {
+                dest: element_reg,
+                stream: flowed_reg,
+                more: more_reg,
+            });
+            self.write_jump(
+                Instruction::JumpIfFalse {
+                    condition_reg: more_reg,
+                    offset: 0,
+                },
+                exit,
+            );
+            self.set_value_instruction(&variable, element_reg)?;
+            more_reg
+        } else {
+            let index_reg = self.gen_variable_instruction(&index_symbol)?;
+            let length_reg = self.get_next_register()?;
+            self.instructions.push(Instruction::Length {
+                dest: length_reg,
+                r1: flowed_reg,
+            });
+            let cond_reg = self.get_next_register()?;
+            self.instructions.push(Instruction::Less {
+                dest: cond_reg,
+                r1: index_reg,
+                r2: length_reg,
+            });
+
+            self.write_jump(
+                Instruction::JumpIfFalse {
+                    condition_reg: cond_reg,
+                    offset: 0,
+                },
+                exit,
+            );
+            let element_reg = self.get_next_register()?;
+            self.instructions.push(Instruction::ExtractFromDeck {
+                dest: element_reg,
+                deck: flowed_reg,
+                index: index_reg,
+            });
+            self.set_value_instruction(&variable, element_reg)?;
+            cond_reg
+        };
+        let variable_reg = variable.slot_idx as u8;
+        self.note_position(Self::position_of(&token));
+        self.free_registers(mark);
+
+        let makes_closures = Self::declares_spells(&body);
+        self.loop_blocks.push(LoopBlock {
+            label: label.map(|label| label.lexeme.to_string()),
+            exit,
+            next,
+            attempts: 0,
+            closes_from: makes_closures.then_some(self.locals_floor),
+        });
+
+        self.gen_from_stmt(body)?;
+
+        // increment, flows land here so the walk still steps forward. Each round has a mark of
+        // its own, so the spells declared in it keep the element they saw
+        self.place_label(next);
+        if makes_closures {
+            self.instructions
+                .push(Instruction::CloseUpvalues { from: variable_reg });
+        }
+        if !streamed {
+            let step_reg = self.write_constant(Value::Number(1.0))?;
+            let index_reg = self.gen_variable_instruction(&index_symbol)?;
+            let next_reg = self.get_next_register()?;
+            self.instructions.push(Instruction::Add {
+                dest: next_reg,
+                r1: index_reg,
+                r2: step_reg,
+            });
+            self.set_value_instruction(&index_symbol, next_reg)?;
+            self.free_registers(mark);
+        }
+
+        self.write_jump(Instruction::Loop { offset: 0 }, loop_start);
+        self.note_position(Self::position_of(&token));
+        self.place_label(exit);
+        self.close_locals(opened);
+        self.loop_blocks.pop();
+
+        if makes_closures {
+            self.instructions
+                .push(Instruction::CloseUpvalues { from: saved_floor });
+        }
+
+        self.locals_floor = saved_floor;
+        self.register_index = saved_reg_idx;
+        Ok(cond_reg)
+    }
+
+    /// A `divert fate` chain nests as deep as it is long, its fates are generated one after
+    /// another, the end of the chain being the end of every one of them
     fn gen_fate_instructions(
         &mut self,
         condition: WovenExpr,
         then_branch: WovenStmt,
         else_branch: Option<Box<WovenStmt>>,
     ) -> GenResult<u8> {
-        let w_cond = self.gen_from_expr(condition)?;
+        let end = self.new_label();
+        let mut fate = (condition, then_branch, else_branch);
+        let mut first_cond = None;
+        loop {
+            let (condition, then_branch, else_branch) = fate;
+            let mark = self.register_index;
+            let w_cond = self.gen_from_expr(condition)?;
+            first_cond.get_or_insert(w_cond);
+
+            let then = self.new_label();
+            self.write_jump(
+                Instruction::JumpIfFalse {
+                    condition_reg: w_cond,
+                    offset: 0,
+                },
+                then,
+            );
+            self.free_registers(mark);
 
-        let then = self.write_jump(Instruction::JumpIfFalse {
-            condition_reg: w_cond,
-            offset: 0xffff,
-        });
+            // generate then block code
+            self.gen_from_stmt(then_branch)?;
 
-        // generate then block code
-        self.gen_from_stmt(then_branch)?;
+            let Some(else_) = else_branch else {
+                self.place_label(then);
+                break;
+            };
+            self.write_jump(Instruction::Jump { offset: 0 }, end);
+
+            // place it, since we got where it ends
+            self.place_label(then);
+
+            // generate else block code, the fate diverted to is taken on in place
+            match *else_ {
+                WovenStmt::Fate {
+                    condition,
+                    then_branch,
+                    else_branch,
+                    ..
+                } => fate = (condition, *then_branch, else_branch),
+                else_ => {
+                    self.gen_from_stmt(else_)?;
+                    break;
+                }
+            }
+        }
+        self.place_label(end);
 
-        if let Some(else_) = else_branch {
-            let else_idx = self.write_jump(Instruction::Jump { offset: 0xffff });
+        Ok(first_cond.unwrap_or_default())
+    }
 
-            // patch it up, since we got where it ends
-            self.patch_jump(then)?;
+    /// Holds the judged value against each arm in turn, the first one equal to it runs and the rest are jumped over
+    fn gen_judge_instructions(
+        &mut self,
+        token: Token,
+        subject: WovenExpr,
+        subject_symbol: Rc<Symbol>,
+        arms: Vec<(WovenExpr, WovenStmt)>,
+        divert: Option<Box<WovenStmt>>,
+    ) -> GenResult<u8> {
+        // the judged value is a local of the judge, released along with it
+        let saved_floor = self.locals_floor;
+        let saved_reg_idx = self.register_index;
 
-            // generate else block code
-            self.gen_from_stmt(*else_)?;
+        let subject_reg = self.gen_from_expr(subject)?;
+        self.set_value_instruction(&subject_symbol, subject_reg)?;
+        self.free_registers(saved_reg_idx);
+
+        let end = self.new_label();
+        for (value, body) in arms {
+            let mark = self.register_index;
+            let judged_reg = self.gen_variable_instruction(&subject_symbol)?;
+            let value_reg = self.gen_from_expr(value)?;
+            let cond_reg = self.get_next_register()?;
+            self.instructions.push(Instruction::Equal {
+                dest: cond_reg,
+                r1: judged_reg,
+                r2: value_reg,
+            });
+            let next_arm = self.new_label();
+            self.write_jump(
+                Instruction::JumpIfFalse {
+                    condition_reg: cond_reg,
+                    offset: 0,
+                },
+                next_arm,
+            );
+            self.note_position(Self::position_of(&token));
+            self.free_registers(mark);
 
-            self.patch_jump(else_idx)?;
-        } else {
-            self.patch_jump(then)?;
+            self.gen_from_stmt(body)?;
+            self.write_jump(Instruction::Jump { offset: 0 }, end);
+            self.place_label(next_arm);
         }
 
-        Ok(w_cond)
+        if let Some(divert) = divert {
+            self.gen_from_stmt(*divert)?;
+        }
+        self.place_label(end);
+
+        self.locals_floor = saved_floor;
+        self.register_index = saved_reg_idx;
+        Ok(subject_reg)
+    }
+
+    /// Both arms leave their value in the same register, only one of them runs
+    fn gen_conditional_instruction(
+        &mut self,
+        condition: WovenExpr,
+        then_branch: WovenExpr,
+        else_branch: WovenExpr,
+    ) -> GenResult<u8> {
+        let mark = self.register_index;
+        let cond_reg = self.gen_from_expr(condition)?;
+
+        // the condition is read by the jump before either arm writes the destination
+        self.free_registers(mark);
+        let dest = self.get_next_register()?;
+        let arms_mark = self.register_index;
+
+        let (else_label, end) = (self.new_label(), self.new_label());
+        self.write_jump(
+            Instruction::JumpIfFalse {
+                condition_reg: cond_reg,
+                offset: 0,
+            },
+            else_label,
+        );
+        self.gen_conditional_arm(then_branch, dest, arms_mark)?;
+
+        self.write_jump(Instruction::Jump { offset: 0 }, end);
+        self.place_label(else_label);
+        self.gen_conditional_arm(else_branch, dest, arms_mark)?;
+        self.place_label(end);
+
+        Ok(dest)
+    }
+
+    fn gen_conditional_arm(&mut self, arm: WovenExpr, dest: u8, mark: u8) -> GenResult<()> {
+        let reg = self.gen_from_expr(arm)?;
+        if reg != dest {
+            self.instructions.push(Instruction::Move {
+                dest,
+                source: reg as u16,
+            });
+        }
+        self.free_registers(mark);
+        Ok(())
     }
 
-    fn gen_assignment_instruction(&mut self, expr: WovenExpr, symbol: Symbol) -> GenResult<u8> {
+    fn gen_assignment_instruction(&mut self, expr: WovenExpr, symbol: Rc<Symbol>) -> GenResult<u8> {
         let reg = self.gen_from_expr(expr)?;
-        self.set_value_instruction(symbol, reg)?;
+        self.set_value_instruction(&symbol, reg)?;
         Ok(reg)
     }
 
     /// Checks the depth, sets as local if depth > 0 else as a global with a value if provided.
     fn gen_var_decl_instruction(
         &mut self,
+        name: Token,
         initializer: Option<WovenExpr>,
-        symbol: Symbol,
+        symbol: Rc<Symbol>,
     ) -> GenResult<u8> {
+        if symbol.depth > 0 {
+            self.declare_local(&name.lexeme, symbol.slot_idx as u8, name.line);
+        }
+        let mark = self.register_index;
         let src = match initializer {
             Some(init) => self.gen_from_expr(init)?,
             None => self.write_constant(Value::Emptiness)?,
         };
 
-        self.set_value_instruction(symbol, src)?;
+        self.set_value_instruction(&symbol, src)?;
+        self.note_position(Self::position_of(&name));
+        self.free_registers(mark);
 
         Ok(src)
     }
 
-    fn set_value_instruction(&mut self, symbol: Symbol, src_reg: u8) -> GenResult<()> {
+    fn set_value_instruction(&mut self, symbol: &Symbol, src_reg: u8) -> GenResult<()> {
         if symbol.depth > 0 {
-            // Calculate the target register for this variable
-            let target_reg = if self.in_spell {
-                // Check if this variable is an upvalue
-                if let Some(upv_reg) = self.upval_map.get(&(symbol.depth, symbol.slot_idx)) {
-                    // It's an upvalue, use its register directly
-                    *upv_reg as u8
-                } else {
-                    // Inside a spell, locals are offset by upvalue count
-                    (self.curr_upval_count + symbol.slot_idx) as u8
-                }
-            } else {
-                symbol.slot_idx as u8
-            };
+            // an upvalue lives outside of the frame, it is written through the closure
+            if let Some(&index) = self.upval_map.get(&(symbol.depth, symbol.slot_idx)) {
+                self.instructions
+                    .push(Instruction::SetUpvalue { index, src: src_reg });
+                return Ok(());
+            }
+            let target_reg = symbol.slot_idx as u8;
 
             // If src_reg != target_reg, we need to move the value
             if src_reg != target_reg {
@@ -1016,55 +2205,63 @@ impl CodeGen {
                     source: src_reg as u16,
                 });
             }
+            self.pin_local(target_reg);
         } else {
-            let c_ind = self.add_constant(Value::String(symbol.name.into()))?;
-            self.instructions.push(Instruction::SetGlobal {
-                src_reg,
-                const_index: c_ind,
-            });
+            let slot = self.global_slot(symbol.name.clone())?;
+            self.instructions.push(Instruction::SetGlobal { src_reg, slot });
         }
         Ok(())
     }
 
     fn gen_variable_instruction(&mut self, symbol: &Symbol) -> GenResult<u8> {
+        if let Some(value) = symbol.kind.borrow().get_seal_value() {
+            return self.write_constant(value);
+        }
         if symbol.depth > 0 {
             if self.in_spell {
+                // a local spell casting itself, its own slot isnt captured
+                if self.current_spell == Some((symbol.depth, symbol.slot_idx)) {
+                    let dest = self.get_next_register()?;
+                    self.instructions.push(Instruction::CurrentSpell { dest });
+                    return Ok(dest);
+                }
+
                 // Check if this variable is an upvalue using (depth, slot_idx) as key
                 // This will prevent collision between upvalues and locals with same slot_idx
-                if let Some(upv_reg) = self.upval_map.get(&(symbol.depth, symbol.slot_idx)) {
-                    // The value is an upvalue! jst return its register
-                    return Ok(*upv_reg as u8);
+                if let Some(&index) = self.upval_map.get(&(symbol.depth, symbol.slot_idx)) {
+                    let dest = self.get_next_register()?;
+                    self.instructions
+                        .push(Instruction::GetUpvalue { dest, index });
+                    return Ok(dest);
                 }
-
-                // Not an upvalue, so it's a local/parameter in current spell
-                // Locals are offset by the upvalue count
-                return Ok((self.curr_upval_count + symbol.slot_idx) as u8);
             }
             Ok(symbol.slot_idx as u8)
         } else {
             let dest = self.get_next_register()?;
-            let const_idx = self.add_constant(Value::String(symbol.name.clone().into()))?;
-            self.instructions.push(Instruction::GetGlobal {
-                dest: dest,
-                const_index: const_idx,
-            });
+            let slot = self.global_slot(symbol.name.clone())?;
+            self.instructions.push(Instruction::GetGlobal { dest, slot });
             Ok(dest)
         }
     }
 
-    fn gen_unary_instruction(&mut self, operand: WovenExpr, op: Token) -> GenResult<u8> {
+    fn gen_unary_instruction(&mut self, operand: WovenExpr, op: Token, weave: Weave) -> GenResult<u8> {
+        let mark = self.register_index;
         let register = self.gen_from_expr(operand)?;
+
+        self.free_registers(mark);
         let dest = self.get_next_register()?;
 
+        // only the operations the operand's strands were woven for are emitted
+        let tapestry = weave.get_tapestry();
         match op.token_type {
-            TokenType::Minus => {
+            TokenType::Minus if tapestry.has_strand(NEGATABLE_STRAND) => {
                 self.instructions.push(Instruction::Negate {
                     dest: dest,
                     r1: register,
                 });
                 Ok(dest)
             }
-            TokenType::Bang => {
+            TokenType::Bang if tapestry.has_strand(CONDITIONAL_STRAND) => {
                 self.instructions.push(Instruction::Not {
                     dest: dest,
                     r1: register,
@@ -1081,6 +2278,8 @@ impl CodeGen {
         }
     }
 
+    /// A chain of operators nests to the left as deep as it is long, so its first operand is found
+    /// by walking down the chain and the operators are generated back up one at a time
     fn gen_binary_instruction(
         &mut self,
         left: WovenExpr,
@@ -1088,25 +2287,57 @@ impl CodeGen {
         op: Token,
         weave: Weave,
     ) -> GenResult<u8> {
-        // generate left
-        let r1 = self.gen_from_expr(left.clone())?;
+        let mark = self.register_index;
 
-        //generate right
-        let r2 = self.gen_from_expr(right.clone())?;
+        let mut links = vec![(right, op, weave)]; // the outermost first
+        let mut first = left;
+        while let WovenExpr::Binary { left, right, operator, weave, .. } = first {
+            links.push((*right, operator, weave));
+            first = *left;
+        }
 
-        let reg = match weave {
-            num if num == Weave::Num => self.gen_num_op(r1, r2, op),
-            truth if truth == Weave::Truth => self.gen_bin_truth_op(r1, r2, op),
-            text if text == Weave::Text => self.gen_bin_text_op(r1, r2, op),
-            _ => return self.error("Unknown weave brotha, check it."),
-        }?;
-        return Ok(reg);
+        // generate left
+        let mut r1 = self.gen_from_expr(first)?;
+        let outermost = links.len() - 1;
+        for (i, (right, op, weave)) in links.into_iter().rev().enumerate() {
+            let position = Self::position_of(&op);
+
+            //generate right
+            let r2 = self.gen_from_expr(right)?;
+
+            // the operands are read before the result is written, so it can take their place
+            self.free_registers(mark);
+
+            r1 = match weave {
+                num if num == Weave::Num => self.gen_num_op(r1, r2, op),
+                Weave::Int => self.gen_int_op(r1, r2, op),
+                truth if truth == Weave::Truth => self.gen_bin_truth_op(r1, r2, op),
+                text if text == Weave::Text => self.gen_bin_text_op(r1, r2, op),
+                _ => return self.error("Unknown weave brotha, check it."),
+            }?;
+            // the position of the outermost operator is noted by the one generating the chain
+            if i < outermost {
+                self.note_position(position);
+            }
+        }
+        Ok(r1)
     }
 
-    fn gen_chant_stmt(&mut self, expr: WovenExpr) -> GenResult<u8> {
-        let expression = self.gen_from_expr(expr)?;
-        let inst = Instruction::Print { r1: expression };
-        self.instructions.push(inst);
+    /// The values are chanted one after another, only the last one of a `chant` ends the line
+    fn gen_chant_stmt(&mut self, exprs: Vec<WovenExpr>, newline: bool) -> GenResult<u8> {
+        let mark = self.register_index;
+        let last = exprs.len() - 1;
+        let mut expression = mark;
+        for (i, expr) in exprs.into_iter().enumerate() {
+            expression = self.gen_from_expr(expr)?;
+            let inst = if newline && i == last {
+                Instruction::Print { r1: expression }
+            } else {
+                Instruction::PrintRaw { r1: expression }
+            };
+            self.instructions.push(inst);
+            self.free_registers(mark);
+        }
         Ok(expression)
     }
 
@@ -1254,6 +2485,90 @@ impl CodeGen {
         }
         Ok(self.get_last_allocated_register())
     }
+
+    // ints get opcodes of their own, which keep them exact
+    fn gen_int_op(&mut self, left: u8, right: u8, op: Token) -> GenResult<u8> {
+        match op.token_type {
+            TokenType::Plus => {
+                self.gen_bin_op(left, right, |dest, r1, r2| Instruction::AddInt {
+                    dest,
+                    r1,
+                    r2,
+                })?;
+            }
+            TokenType::Minus => {
+                self.gen_bin_op(left, right, |dest, r1, r2| Instruction::SubtractInt {
+                    dest,
+                    r1,
+                    r2,
+                })?;
+            }
+            TokenType::Slash => {
+                self.gen_bin_op(left, right, |dest, r1, r2| Instruction::DivideInt {
+                    dest,
+                    r1,
+                    r2,
+                })?;
+            }
+            TokenType::Star => {
+                self.gen_bin_op(left, right, |dest, r1, r2| Instruction::MultiplyInt {
+                    dest,
+                    r1,
+                    r2,
+                })?;
+            }
+            TokenType::Percent => {
+                self.gen_bin_op(left, right, |dest, r1, r2| Instruction::ModInt {
+                    dest,
+                    r1,
+                    r2,
+                })?;
+            }
+            TokenType::And => {
+                self.gen_bin_op(left, right, |dest, r1, r2| Instruction::BitAnd {
+                    dest,
+                    r1,
+                    r2,
+                })?;
+            }
+            TokenType::Or => {
+                self.gen_bin_op(left, right, |dest, r1, r2| Instruction::BitOr {
+                    dest,
+                    r1,
+                    r2,
+                })?;
+            }
+            TokenType::Caret => {
+                self.gen_bin_op(left, right, |dest, r1, r2| Instruction::BitXor {
+                    dest,
+                    r1,
+                    r2,
+                })?;
+            }
+            TokenType::LessLess => {
+                self.gen_bin_op(left, right, |dest, r1, r2| Instruction::ShiftLeft {
+                    dest,
+                    r1,
+                    r2,
+                })?;
+            }
+            TokenType::GreaterGreater => {
+                self.gen_bin_op(left, right, |dest, r1, r2| Instruction::ShiftRight {
+                    dest,
+                    r1,
+                    r2,
+                })?;
+            }
+            _ => {
+                // This error msg should be shown to the user, if it does, compiler is bugged
+                return self.error(&format!(
+                    "Strand for '{}' operation hasnt been entangled with Eira realms!.\nThis error shouldn't be thrown, Report it to devs!",
+                    op.lexeme
+                ));
+            }
+        }
+        Ok(self.get_last_allocated_register())
+    }
 }
 
 // pub trait StrandBehaviour {
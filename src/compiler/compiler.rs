@@ -6,38 +6,65 @@ use crate::{
     compiler::{
         Stmt, WovenStmt,
         scanner::{Scanner, Token},
-        scroll_reader::ScrollReader, weave_analyser::WeaveAnalyzerContext,
+        diagnostics::{DEFAULT_ERROR_LIMIT, Diagnostic, EiraError, Severity},
+        scroll_reader::ScrollReader, weave_analyser::WeaveAnalyzerContext, weaves::Weave,
     },
-    print_ast, print_byte_code, print_woven_ast,
+    ast_to_json, print_ast, print_byte_code, print_woven_ast, woven_ast_to_json,
     project::config::Project,
     runtime::Instruction,
+    values::spell::DebugInfo,
 };
 
-type Result<T> = std::result::Result<T, CompileError>;
-
-pub struct CompileError {
-    pub msg: String,
-}
+type Result<T> = std::result::Result<T, EiraError>;
 
 pub struct CompilerOptions {
     pub print_tokens: bool,
     pub print_ast: Option<u8>,
     pub print_woven_ast: Option<u8>,
+    pub ast_json: bool, // print the ASTs as JSON instead of trees
     pub print_instructions: bool,
     pub print_bytecode: bool,
+    pub error_limit: usize, // errors to collect before giving up on the scroll
+    pub debug_info: bool,   // whether the columns and the names of the locals are kept for the debugger
+    pub deny_warnings: bool, // whether the warnings fail the compilation like errors
+}
+
+impl Default for CompilerOptions {
+    fn default() -> Self {
+        CompilerOptions {
+            print_tokens: false,
+            print_ast: None,
+            print_woven_ast: None,
+            ast_json: false,
+            print_instructions: false,
+            print_bytecode: false,
+            error_limit: DEFAULT_ERROR_LIMIT,
+            debug_info: true,
+            deny_warnings: false,
+        }
+    }
 }
 
 pub struct Compiler {
     pub source_path: String,
     pub options: CompilerOptions,
     pub project: Option<Project>,
-    // pub
+    pub native_spells: Vec<(String, Vec<Weave>, Weave)>,
+    pub host_globals: Vec<(String, Weave)>,
+    pub source: Option<String>, // compiled instead of reading the scroll at source_path
 }
 
+#[derive(Clone)]
 pub struct CompiledCode {
     pub bytecode: Vec<u8>,
     pub instructions: Vec<Instruction>,
     pub constants: Vec<Value>,
+    pub lines: Vec<(usize, usize)>,
+    pub register_count: usize,
+    pub debug: Option<DebugInfo>, // the columns and the named registers of the origin
+    pub globals: Vec<String>, // names of the globals, indexed by their slot
+    pub symbols: Vec<String>, // the interned names, indexed by their symbol ids
+    pub warnings: Vec<Diagnostic>, // found while compiling, they don't stop the scroll from running
 }
 
 pub enum CompileState {
@@ -50,9 +77,32 @@ impl Compiler {
             source_path,
             options,
             project,
+            native_spells: vec![],
+            host_globals: vec![],
+            source: None,
         }
     }
 
+    /// Compiles the given source rather than a scroll on the disk,
+    /// the name is only used to point at the problems in the source
+    pub fn from_source(name: &str, source: &str, options: CompilerOptions) -> Self {
+        Compiler {
+            source: Some(source.to_string()),
+            ..Compiler::new(name.to_string(), options, None)
+        }
+    }
+
+    /// Lets the scrolls cast a spell registered to the VM with `EiraVM::register_native`
+    pub fn declare_native(&mut self, name: &str, reagents: Vec<Weave>, release: Weave) {
+        self.native_spells
+            .push((name.to_string(), reagents, release));
+    }
+
+    /// Lets the scrolls read and write a mark the host gives a value to with `EiraVM::set_global`
+    pub fn declare_global(&mut self, name: &str, weave: Weave) {
+        self.host_globals.push((name.to_string(), weave));
+    }
+
     pub fn compile(&self) -> Result<CompiledCode> {
         let tokens = self.scan()?;
 
@@ -65,23 +115,40 @@ impl Compiler {
 
         let ast = self.parse(tokens)?;
 
-        if self.options.print_ast.is_some() {
-            println!("AST:");
-            print_ast(&ast, self.options.print_ast.unwrap());
+        if let Some(verbosity) = self.options.print_ast {
+            if self.options.ast_json {
+                println!("{}", ast_to_json(&ast));
+            } else {
+                println!("AST:");
+                print_ast(&ast, verbosity);
+            }
         }
 
-        let woven_ast = self.analyze_weaves(ast)?;
+        let (woven_ast, warnings) = self.analyze_weaves(ast)?;
 
-        if self.options.print_woven_ast.is_some() {
-            println!("Woven AST:");
-            print_woven_ast(&woven_ast, self.options.print_woven_ast.unwrap());
+        if let Some(verbosity) = self.options.print_woven_ast {
+            if self.options.ast_json {
+                println!("{}", woven_ast_to_json(&woven_ast));
+            } else {
+                println!("Woven AST:");
+                print_woven_ast(&woven_ast, verbosity);
+            }
         }
 
-        let instructions = self.gen_instructions(woven_ast)?;
+        let mut instructions = self.gen_instructions(woven_ast)?;
+        instructions.warnings = warnings;
 
         Ok(instructions)
     }
 
+    /// Parses and weaves the scroll without generating any code, returns the warnings found
+    pub fn check(&self) -> Result<Vec<Diagnostic>> {
+        let tokens = self.scan()?;
+        let ast = self.parse(tokens)?;
+        let (_, warnings) = self.analyze_weaves(ast)?;
+        Ok(warnings)
+    }
+
     pub fn compile_to_bytecode(&self) -> Result<CompiledCode> {
         let mut compiled_code = self.compile()?;
         compiled_code.bytecode = self.gen_bytecode(&compiled_code.instructions);
@@ -94,52 +161,55 @@ impl Compiler {
     }
 
     fn scan(&self) -> Result<Vec<Token>> {
+        if let Some(source) = &self.source {
+            return Ok(Scanner::init(source).tokenize());
+        }
+
         let scroll_reader = ScrollReader::new();    
 
         let content = scroll_reader.read_scroll(&PathBuf::from(&self.source_path));
 
         if content.is_err() {
-            return Err(CompileError {
-                msg: content.err().unwrap().msg,
-            });
+            return Err(EiraError::Scroll(content.err().unwrap().msg));
         }
 
         Ok(Scanner::init(&content.ok().unwrap()).tokenize())
     }
 
     fn parse(&self, tokens: Vec<Token>) -> Result<Vec<Stmt>> {
-        let ast = Parser::new(tokens, self.source_path.clone()).parse();
-        match ast {
-            Err(parse_error) => {
-                // println!("Parse Error: {:?}", parse_error.0);
-                return Err(CompileError {
-                    msg: format!("Parse Error: {}", parse_error.0),
-                });
-            }
-            Ok(ast) => Ok(ast),
-        }
+        Parser::new(tokens, self.source_path.clone())
+            .with_error_limit(self.options.error_limit)
+            .parse()
     }
 
-    fn analyze_weaves(&self, ast: Vec<Stmt>) -> Result<Vec<WovenStmt>> {
+    fn analyze_weaves(&self, ast: Vec<Stmt>) -> Result<(Vec<WovenStmt>, Vec<Diagnostic>)> {
         let mut context = WeaveAnalyzerContext::new(self.source_path.clone(), self.project.clone(), false);
+        context.error_limit = self.options.error_limit;
+        for (name, reagents, release) in &self.native_spells {
+            context.declare_native(name, reagents.clone(), release.clone());
+        }
+        for (name, weave) in &self.host_globals {
+            context.declare_global(name, weave.clone());
+        }
         let mut weave_analyzer = WeaveAnalyzer::new(&mut context);
-        match weave_analyzer.analyze(ast) {
-            Err(no_no) => {
-                let errstr = format!(
-                    "Weave Error: {}\nat '{}' in {}:{}:{}",
-                    no_no.msg,
-                    no_no.token.lexeme,
-                    self.source_path,
-                    no_no.token.line,
-                    no_no.token.column,
-                );
-                return Err(CompileError { msg: errstr });
-            }
-            Ok(woven_ast) => Ok(woven_ast),
+        let woven = weave_analyzer.analyze(ast)?;
+        let warnings = weave_analyzer.take_warnings();
+
+        if self.options.deny_warnings && !warnings.is_empty() {
+            let errors = warnings
+                .into_iter()
+                .map(|warning| Diagnostic {
+                    severity: Severity::Error,
+                    ..warning
+                })
+                .collect();
+            return Err(EiraError::Diagnostics(errors));
         }
+
+        Ok((woven, warnings))
     }
 
-    fn gen_bytecode(&self, instructions: &Vec<Instruction>) -> Vec<u8> {
+    fn gen_bytecode(&self, instructions: &[Instruction]) -> Vec<u8> {
         Assembler::convert_to_byte_code(instructions)
     }
 
@@ -149,18 +219,18 @@ impl Compiler {
             self.options.print_instructions,
             self.options.print_bytecode,
         );
-        match cg.summon_instructions() {
-            Err(gen_error) => {
-                // println!("CodeGen Error: {}", gen_error.msg);
-                return Err(CompileError {
-                    msg: format!("CodeGen Error: {}", gen_error.msg),
-                });
-            }
-            Ok(instructions) => Ok(CompiledCode {
-                bytecode: vec![],
-                instructions,
-                constants: cg.get_constants(),
-            }),
-        }
+        cg.debug_info = self.options.debug_info;
+        let instructions = cg.summon_instructions()?;
+        Ok(CompiledCode {
+            bytecode: vec![],
+            instructions,
+            constants: cg.get_constants(),
+            lines: cg.get_line_table(),
+            register_count: cg.get_register_count(),
+            debug: cg.get_debug_info(),
+            globals: cg.get_globals(),
+            symbols: cg.get_symbols(),
+            warnings: vec![],
+        })
     }
 }
@@ -1,20 +1,88 @@
-use std::path::PathBuf;
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+};
 
-use crate::compiler::scroll_reader::ScrollReader;
+use crate::compiler::{
+    scanner::{Span, Token},
+    scroll_reader::ScrollReader,
+    token_type::TokenType,
+};
 
+/// The number of errors after which the compilation gives up on the scroll
+pub const DEFAULT_ERROR_LIMIT: usize = 20;
+
+/// The error returned by the compilation of a scroll
+#[derive(Debug, Clone, PartialEq)]
+pub enum EiraError {
+    /// The scroll itself couldn't be read
+    Scroll(String),
+
+    /// Problems caught by the compilation phases, in the order they were found
+    Diagnostics(Vec<Diagnostic>),
+}
+
+impl EiraError {
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            EiraError::Scroll(_) => &[],
+            EiraError::Diagnostics(diagnostics) => diagnostics,
+        }
+    }
+
+    /// The message of the first problem found
+    pub fn message(&self) -> String {
+        match self {
+            EiraError::Scroll(msg) => msg.clone(),
+            EiraError::Diagnostics(diagnostics) => diagnostics
+                .first()
+                .map(|d| d.message.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl From<Diagnostic> for EiraError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        EiraError::Diagnostics(vec![diagnostic])
+    }
+}
+
+impl Display for EiraError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EiraError::Scroll(msg) => write!(f, "{}", msg),
+            EiraError::Diagnostics(diagnostics) => {
+                for (i, d) in diagnostics.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", d)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Diagnostic {
     pub severity: Severity,
     pub message: String,
-    pub location: SourceLocation,
+    pub location: Option<SourceLocation>,
     pub phase: CompilationPhase,
+    /// Told next to the underline, what is wrong with the very part pointed at
+    pub label: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Severity {
     Error,
     Warning,
     Info,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CompilationPhase {
     Scan,
     Parse,
@@ -22,11 +90,219 @@ pub enum CompilationPhase {
     CodeGen,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct SourceLocation {
     pub file: PathBuf,
     pub line: usize,
     pub column: usize,
     pub length: Option<usize>,
+    /// The whole of what is pointed at, like an expression spanning many tokens. Underlined where
+    /// it crosses the line, in place of the token
+    pub span: Option<Span>,
+}
+
+impl SourceLocation {
+    /// The location of the token, None for the tokens made up by the compiler
+    pub fn of_token(file: &str, token: &Token) -> Option<SourceLocation> {
+        if token.line == 0 {
+            return None;
+        }
+
+        Some(SourceLocation {
+            file: PathBuf::from(file),
+            line: token.line,
+            column: token.column,
+            // the lexeme of an error token is the scanner's complaint, not the source
+            length: if token.token_type == TokenType::Error {
+                None
+            } else {
+                Some(token.lexeme.chars().count())
+            },
+            span: None,
+        })
+    }
+
+    /// The location of the token, underlining the whole [span] it is a part of
+    pub fn of_span(file: &str, token: &Token, span: Span) -> Option<SourceLocation> {
+        let location = SourceLocation::of_token(file, token)?;
+        Some(SourceLocation {
+            span: (span != Span::default()).then_some(span),
+            ..location
+        })
+    }
+
+    /// The (start, length) of the span on the line of the location in [content], counted in chars.
+    /// None when there is no span, or it doesn't cross the line
+    pub fn span_on_line(&self, content: &str) -> Option<(usize, usize)> {
+        let span = self.span?;
+        let line_start: usize = content
+            .split_inclusive('\n')
+            .take(self.line.checked_sub(1)?)
+            .map(str::len)
+            .sum();
+        let line = content.get(line_start..)?.lines().next()?;
+        let line_end = line_start + line.len();
+
+        let start = span.start.clamp(line_start, line_end);
+        let end = span.end.clamp(line_start, line_end);
+        if start >= end {
+            return None;
+        }
+        Some((
+            content.get(line_start..start)?.chars().count(),
+            content.get(start..end)?.chars().count(),
+        ))
+    }
+}
+
+impl Diagnostic {
+    pub fn error(phase: CompilationPhase, message: &str, location: Option<SourceLocation>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.to_owned(),
+            location,
+            phase,
+            label: None,
+        }
+    }
+
+    pub fn warning(phase: CompilationPhase, message: &str, location: Option<SourceLocation>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message: message.to_owned(),
+            location,
+            phase,
+            label: None,
+        }
+    }
+
+    /// The diagnostic with the [label] told next to its underline
+    pub fn with_label(self, label: &str) -> Self {
+        Diagnostic {
+            label: Some(label.to_owned()),
+            ..self
+        }
+    }
+
+    /// The diagnostic as it is printed, its snippet taken from [source] rather than the scroll on
+    /// the disk. For the scrolls which were never written, or were changed since
+    pub fn render(&self, source: &str) -> String {
+        let mut rendered = String::new();
+        let _ = self.write_with(&mut rendered, Some(source));
+        rendered
+    }
+
+    /// Reads the scroll the diagnostic points into
+    fn scroll(&self) -> Option<String> {
+        let location = self.location.as_ref()?;
+        ScrollReader::new().read_scroll_quietly(&location.file)
+    }
+
+    fn write_with(&self, f: &mut impl fmt::Write, content: Option<&str>) -> fmt::Result {
+        let phase = match self.phase {
+            CompilationPhase::Scan => "Scan",
+            CompilationPhase::Parse => "Parse",
+            CompilationPhase::Weave => "Weave",
+            CompilationPhase::CodeGen => "CodeGen",
+        };
+        let severity = match self.severity {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+            Severity::Info => "Info",
+        };
+
+        write!(f, "{} {}: {}", phase, severity, self.message)?;
+
+        let Some(location) = &self.location else {
+            return match &self.label {
+                Some(label) => write!(f, "\n  = {}", label),
+                None => Ok(()),
+            };
+        };
+
+        write!(
+            f,
+            "\n  --> {}:{}:{}",
+            location.file.display(),
+            location.line,
+            location.column
+        )?;
+
+        let source = content.and_then(|content| content.lines().nth(location.line.checked_sub(1)?));
+        if let Some(source) = source {
+            let gutter = " ".repeat(location.line.to_string().len());
+            let (start, length) = content
+                .and_then(|content| location.span_on_line(content))
+                .unwrap_or_else(|| {
+                    // columns point at the end of the token, walk back to its start
+                    let length = location.length.unwrap_or(1).max(1);
+                    (location.column.saturating_sub(length), length)
+                });
+            write!(
+                f,
+                "\n{} |\n{} | {}\n{} | {}{}",
+                gutter,
+                location.line,
+                source,
+                gutter,
+                " ".repeat(start),
+                "^".repeat(length)
+            )?;
+            if let Some(label) = &self.label {
+                write!(f, " {}", label)?;
+            }
+        } else if let Some(label) = &self.label {
+            // without the line to underline, the label is told on its own
+            write!(f, "\n  = {}", label)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_with(f, self.scroll().as_deref())
+    }
+}
+
+/// The candidate closest in spelling to the name, when it is close enough to be a typo of it
+pub fn closest_name<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let allowed = (name.chars().count() / 2).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= allowed)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance, the edits needed to turn one text into the other. Two letters
+/// swapped count as a single edit, being the commonest of typos.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let replaced = previous[j - 1] + cost;
+            row[j] = replaced.min(previous[j] + 1).min(row[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, row);
+    }
+
+    previous[b.len()]
 }
 
 pub struct DiagnosticEngine {
@@ -14,6 +14,7 @@ use crate::{
 
 impl Parser {
     pub(super) fn spell_declaration(&mut self, attuned_to: Option<Token>) -> ParseResult<Stmt> {
+        let start = self.previous.span;
         self.consume(TokenType::Identifier, "Expected a variable name!");
         let name = self.previous.clone();
 
@@ -28,16 +29,34 @@ impl Parser {
 
                 // capture the weave of the mark!
                 self.consume(TokenType::Colon, "Expected ':' for weave definition!");
+                let gathered = self.match_token(TokenType::DotDotDot);
                 let weave = self.parse_weave("Expected a weave name after ';'")?;
 
+                // a default lets the caster leave the reagent out
+                let default = if self.match_token(TokenType::Equal) {
+                    if gathered {
+                        self.throw_error(
+                            "A gathered reagent can't have a default, it is left empty instead.",
+                        );
+                    }
+                    Some(self.expression()?)
+                } else {
+                    None
+                };
+
                 params.push(Reagent {
                     name: token,
                     weave: weave,
+                    default,
+                    gathered,
                 });
 
                 if !self.match_token(TokenType::Comma) {
                     break;
                 }
+                if gathered {
+                    self.throw_error("The gathered reagent has to be the last one of the spell.");
+                }
             }
         }
 
@@ -47,7 +66,7 @@ impl Parser {
 
         if self.match_token(TokenType::ColonColon) {
             weave_name = Some(self.parse_weave("Expected a weave bound to the spell!")?);
-            // weave_name = Some(self.previous.lexeme.clone());
+            // weave_name = Some(self.previous.lexeme.to_string());
         } else {
             weave_name = None;
         }
@@ -60,10 +79,12 @@ impl Parser {
             body: Box::new(working),
             return_weave: weave_name,
             attuned_to,
+            span: self.span_from(start),
         })
     }
 
     pub(super) fn variable_declaration(&mut self, mutable: bool) -> ParseResult<Stmt> {
+        let start = self.previous.span;
         self.consume(TokenType::Identifier, "Expected a variable name!");
         let name = self.previous.clone();
         let initializer: Option<Expr>;
@@ -88,10 +109,33 @@ impl Parser {
             mutable: mutable,
             initializer: initializer,
             weave: weave,
+            span: self.span_from(start),
+        })
+    }
+
+    pub(super) fn seal_declaration(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        self.consume(TokenType::Identifier, "Expected a name for the seal!");
+        let name = self.previous.clone();
+
+        let mut weave: Option<ParsedWeave> = None;
+        if self.match_token(TokenType::Colon) {
+            weave = Some(self.parse_weave("Expected a weave name to bind with the seal!")?);
+        }
+
+        self.consume(TokenType::Equal, "A seal must be given its value with '='!");
+        let initializer = self.expression()?;
+        self.consume(TokenType::SemiColon, MSG_MISSED_SEMICOLON);
+        Ok(Stmt::Seal {
+            name,
+            initializer,
+            weave,
+            span: self.span_from(start),
         })
     }
 
     pub(super) fn sign_declaration(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
         self.consume(TokenType::Identifier, "Expected a name for the sign.");
         let name = self.previous.clone();
         self.consume(TokenType::BraceLeft, "Expected '{' after the sign name.");
@@ -109,9 +153,15 @@ impl Parser {
 
                     let parsed_weave =
                         self.parse_weave("Expected a weave name to bind with the sign's mark!")?;
+                    let default = if self.match_token(TokenType::Equal) {
+                        Some(self.expression()?)
+                    } else {
+                        None
+                    };
                     marks.push(Mark {
                         name: mark_name,
                         parsed_weave: parsed_weave,
+                        default,
                     });
 
                     if self.match_token(TokenType::Comma) {
@@ -128,10 +178,40 @@ impl Parser {
         self.consume(TokenType::BraceRight, "Expected '}' after sign marks.");
         // self.consume(TokenType::SemiColon, MSG_MISSED_SEMICOLON);
 
-        Ok(Stmt::Sign { name, marks })
+        Ok(Stmt::Sign {
+            name,
+            marks,
+            span: self.span_from(start),
+        })
+    }
+
+    pub(super) fn glyph_declaration(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        self.consume(TokenType::Identifier, "Expected a name for the glyph.");
+        let name = self.previous.clone();
+        self.consume(TokenType::BraceLeft, "Expected '{' after the glyph name.");
+
+        let mut variants: Vec<Token> = vec![];
+
+        while self.match_token(TokenType::Identifier) {
+            variants.push(self.previous.clone());
+
+            if !self.match_token(TokenType::Comma) {
+                break; // no comma means the variant list has ended
+            }
+        }
+
+        self.consume(TokenType::BraceRight, "Expected '}' after glyph variants.");
+
+        Ok(Stmt::Glyph {
+            name,
+            variants,
+            span: self.span_from(start),
+        })
     }
 
     pub(super) fn attune_declaration(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
         self.consume(
             TokenType::Identifier,
             "Expected a name for the sign to attune to.",
@@ -157,10 +237,79 @@ impl Parser {
             "Expected '}' after the attunement block.",
         );
 
-        Ok(Stmt::Attune { sign, spells })
+        Ok(Stmt::Attune {
+            sign,
+            spells,
+            span: self.span_from(start),
+        })
+    }
+
+    pub(super) fn tome_declaration(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        self.consume(TokenType::Identifier, "Expected a name for the tome.");
+        let name = self.previous.clone();
+
+        let origin = if self.match_token(TokenType::Refers) {
+            self.consume(
+                TokenType::Identifier,
+                "Expected the name of the tome to refer after 'refers'.",
+            );
+            Some(self.previous.clone())
+        } else {
+            None
+        };
+
+        self.consume(TokenType::BraceLeft, "Expected '{' after the tome name.");
+
+        let mut marks: Vec<Mark> = vec![];
+        let mut spells: Vec<Stmt> = vec![];
+
+        while !self.check(TokenType::BraceRight) && !self.reached_end() {
+            if self.match_token(TokenType::Spell) {
+                spells.push(self.spell_declaration(Some(name.clone()))?);
+            } else if self.match_token(TokenType::Identifier) {
+                let mark_name = self.previous.clone();
+                self.consume(
+                    TokenType::Colon,
+                    "Expected a weave definition for the tome's mark.",
+                );
+
+                let parsed_weave =
+                    self.parse_weave("Expected a weave name to bind with the tome's mark!")?;
+                let default = if self.match_token(TokenType::Equal) {
+                    Some(self.expression()?)
+                } else {
+                    None
+                };
+                marks.push(Mark {
+                    name: mark_name,
+                    parsed_weave,
+                    default,
+                });
+
+                // marks are separated by commas, just like in signs
+                if !self.match_token(TokenType::Comma) && !self.check(TokenType::Spell) {
+                    break;
+                }
+            } else {
+                self.throw_error("Only marks and spell declarations are allowed inside a tome!");
+                break;
+            }
+        }
+
+        self.consume(TokenType::BraceRight, "Expected '}' after the tome's body.");
+
+        Ok(Stmt::Tome {
+            name,
+            origin,
+            marks,
+            spells,
+            span: self.span_from(start),
+        })
     }
 
     pub(super) fn tether_declaration(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
         let token = self.previous.clone();
 
         let mut is_path = false;
@@ -199,6 +348,7 @@ impl Parser {
             path: path,
             bind_to: bind_to,
             is_path: is_path,
+            span: self.span_from(start),
         })
     }
 }
@@ -6,27 +6,65 @@ use crate::{
         Expr,
         mark::EtchedMark,
         parser::types::{ParseError, ParseResult, Precedence},
+        scanner::number_value,
         token_type::TokenType,
     },
 };
 
 impl Parser {
     pub(super) fn grouping(&mut self, _can_assign: bool) -> ParseResult<Expr> {
+        let start = self.previous.span;
+        // the reagents of a cast can be named again inside the parens
+        let then_arms = std::mem::take(&mut self.then_arms);
         let exp = self.expression();
+        self.then_arms = then_arms;
         self.consume(
             TokenType::ParenRight,
             "Close the bracket!\nError: Expected ')' after expression.",
         );
         Ok(Expr::Grouping {
             expression: Box::new(exp?),
+            span: self.span_from(start),
         })
     }
 
     pub(super) fn number(&mut self, _can_assign: bool) -> ParseResult<Expr> {
-        let val: f64 = self.previous.lexeme.parse().unwrap();
+        let val = number_value(&self.previous.lexeme);
         Ok(Expr::Literal {
             value: Value::Number(val),
             token: self.previous.clone(),
+            span: self.previous.span,
+        })
+    }
+
+    /// `heed` reads the next line given to the scroll
+    pub(super) fn heed(&mut self, _can_assign: bool) -> ParseResult<Expr> {
+        Ok(Expr::Heed {
+            token: self.previous.clone(),
+            span: self.previous.span,
+        })
+    }
+
+    /// `conjure cast worker with 1` starts the cast alongside the scroll, handing out its conjuring
+    pub(super) fn conjure(&mut self, _can_assign: bool) -> ParseResult<Expr> {
+        let token = self.previous.clone();
+        self.consume(TokenType::Cast, "Only a cast can be conjured, like 'conjure cast worker with 1'.");
+        let cast = self.cast(false)?;
+        Ok(Expr::Conjure {
+            span: self.span_from(token.span),
+            token,
+            cast: Box::new(cast),
+        })
+    }
+
+    /// `claim conjuring` waits for the conjured spell to release, and takes what it released
+    pub(super) fn claim(&mut self, _can_assign: bool) -> ParseResult<Expr> {
+        let token = self.previous.clone();
+        let conjuring = self.parse_precedence(Precedence::Unary)?;
+        Ok(Expr::Claim {
+            span: token.span.to(conjuring.span()),
+            token,
+            conjuring: Box::new(conjuring),
         })
     }
 
@@ -35,75 +73,81 @@ impl Parser {
             TokenType::True => Ok(Expr::Literal {
                 value: Value::Bool(true),
                 token: self.previous.clone(),
+                span: self.previous.span,
             }),
             TokenType::False => Ok(Expr::Literal {
                 value: Value::Bool(false),
                 token: self.previous.clone(),
+                span: self.previous.span,
             }),
             _ => Err(ParseError("Error: UNKNOWN.... LITERAL?!".to_owned())),
         }
     }
 
+    /// The value within the '@(' just read, up to its ')'
+    fn interpolation(&mut self) -> ParseResult<Expr> {
+        let token = self.previous.clone();
+        let value = self.expression()?;
+
+        self.consume(
+            TokenType::InterpolateEnd,
+            "Expected ')' after interpolated expression.",
+        );
+
+        Ok(Expr::Interpolation {
+            value: Box::new(value),
+            span: self.span_from(token.span),
+            token,
+        })
+    }
+
     pub(super) fn string(&mut self, _can_assign: bool) -> ParseResult<Expr> {
         let string = if self.previous.token_type == TokenType::String {
-            self.previous.lexeme.clone()
+            self.previous.lexeme.to_string()
         } else {
             "".to_owned()
         };
 
+        let start = self.previous.span;
         let mut expr = Expr::Literal {
             value: Value::String(Rc::new(string)),
             token: self.previous.clone(),
+            span: start,
         };
 
         // Handle case where string starts with interpolation
         if self.previous.token_type == TokenType::InterpolateStart {
-            let inner_expr = self.expression()?;
             let plus = Token {
-                column: self.previous.column,
-                line: self.previous.line,
                 token_type: TokenType::Plus,
-                lexeme: self.previous.lexeme.clone(),
+                ..self.previous.clone()
             };
-
-            self.consume(
-                TokenType::InterpolateEnd,
-                "Expected ')' after interpolated expression.",
-            );
-
             expr = Expr::Binary {
                 left: Box::new(expr),
-                right: Box::new(inner_expr),
+                right: Box::new(self.interpolation()?),
                 operator: plus,
+                span: self.span_from(start),
             };
         }
 
         loop {
             while self.match_token(TokenType::InterpolateStart) {
-                let inner_expr = self.expression()?;
                 let plus = Token {
-                    column: self.previous.column,
-                    line: self.previous.line,
                     token_type: TokenType::Plus,
-                    lexeme: self.previous.lexeme.clone(),
+                    ..self.previous.clone()
                 };
-
-                self.consume(
-                    TokenType::InterpolateEnd,
-                    "Expected ')' after interpolated expression.",
-                );
-
                 expr = Expr::Binary {
                     left: Box::new(expr),
-                    right: Box::new(inner_expr),
+                    right: Box::new(self.interpolation()?),
                     operator: plus,
+                    span: self.span_from(start),
                 };
             }
 
             if self.match_token(TokenType::String) {
                 let next_str = Expr::Literal {
-                    value: Value::String(Rc::new(self.previous.lexeme.clone())),
+                    value: Value::String(Rc::new(self.previous.lexeme.to_string())),
                     token: self.previous.clone(),
+                    span: self.previous.span,
                 };
 
                 expr = Expr::Binary {
@@ -114,7 +158,9 @@ impl Parser {
                         line: self.previous.line,
                         token_type: TokenType::Plus,
                         lexeme: self.previous.lexeme.clone(),
+                        span: self.previous.span,
                     },
+                    span: self.span_from(start),
                 };
             } else {
                 break;
@@ -129,6 +175,7 @@ impl Parser {
 
         match op.token_type {
             TokenType::Minus | TokenType::Bang => Ok(Expr::Unary {
+                span: op.span.to(exp.span()),
                 operand: Box::new(exp),
                 operator: op,
             }),
@@ -152,7 +199,13 @@ impl Parser {
             | TokenType::LessEqual
             | TokenType::Greater
             | TokenType::GreaterEqual
-            | TokenType::Percent => Ok(Expr::Binary {
+            | TokenType::Percent
+            | TokenType::And
+            | TokenType::Or
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => Ok(Expr::Binary {
+                span: lhs.span().to(rhs.span()),
                 left: Box::new(lhs),
                 right: Box::new(rhs),
                 operator: op,
@@ -163,18 +216,31 @@ impl Parser {
 
     pub(super) fn cast(&mut self, _can_assign: bool) -> ParseResult<Expr> {
         //self.consume(TokenType::Identifier, "Expected a spell name to cast.");
+        let start = self.previous.span;
         let spell = self.expression()?;
 
         let token = self.previous.clone();
 
         let mut reagents: Vec<Expr> = vec![];
+        let mut named: Vec<(Token, Expr)> = vec![];
 
         if self.match_token(TokenType::With) {
             // self.consume(TokenType::With, "Expected 'with' after spell name.");
 
-            // Parse the reagent expressions
+            // Parse the reagent expressions, `name: value` gives a reagent by its name
             loop {
-                reagents.push(self.expression()?);
+                let reagent = self.expression()?;
+                if self.then_arms == 0 && self.match_token(TokenType::Colon) {
+                    match reagent {
+                        Expr::Variable { name, .. } => named.push((name, self.expression()?)),
+                        _ => self.throw_error("A reagent can only be named by a plain name."),
+                    }
+                } else if !named.is_empty() {
+                    self.throw_error("The reagents given by their names have to come last.");
+                } else {
+                    reagents.push(reagent);
+                }
+
                 if self.match_token(TokenType::Comma) {
                     continue;
                 } else {
@@ -186,46 +252,29 @@ impl Parser {
 
         Ok(Expr::Cast {
             reagents,
+            named,
             callee: Box::new(spell),
             token: token,
+            span: self.span_from(start),
         })
     }
 
     pub(super) fn draw(&mut self, _can_assign: bool) -> ParseResult<Expr> {
+        let start = self.previous.span;
         self.consume(TokenType::Identifier, "Expected a Sign name to draw!");
         let sign_name = self.previous.clone();
 
+        let mut reagents: Vec<Expr> = vec![];
         let mut marks: Vec<EtchedMark> = vec![];
 
         if self.match_token(TokenType::With) {
             self.consume(TokenType::BraceLeft, "Expected '{' after 'with'");
 
-            // Parse the marks expressions
-            loop {
-                if self.match_token(TokenType::Identifier) {
-                    // self.consume(TokenType::Identifier, "Expected a mark name!");
-                    let param = self.previous.clone();
-
-                    self.consume(TokenType::Colon, "Expected a ':' after the mark name.");
-
-                    let mrk = EtchedMark {
-                        expr: self.expression()?,
-                        name: param,
-                    };
-
-                    marks.push(mrk);
-
-                    if self.match_token(TokenType::Comma) {
-                        continue;
-                    } else {
-                        // End of mark list when next token isn't a comma!
-                        break;
-                    }
-                } else {
-                    // simply break out of the loop, any errors will be caught by following code (hopefully)
-                    break;
-                }
-            }
+            // the marks can be named inside the braces, even in a then arm
+            let then_arms = std::mem::take(&mut self.then_arms);
+            let listed = self.draw_marks(&mut reagents, &mut marks);
+            self.then_arms = then_arms;
+            listed?;
 
             self.consume(
                 TokenType::BraceRight,
@@ -234,15 +283,44 @@ impl Parser {
         }
 
         Ok(Expr::Draw {
+            reagents,
             marks,
             callee: sign_name,
+            span: self.span_from(start),
         })
     }
 
+    /// The marks are given in order, or by their names as `mark: value` after those
+    fn draw_marks(&mut self, reagents: &mut Vec<Expr>, marks: &mut Vec<EtchedMark>) -> ParseResult<()> {
+        while !self.check(TokenType::BraceRight) {
+            let reagent = self.expression()?;
+            if self.match_token(TokenType::Colon) {
+                match reagent {
+                    Expr::Variable { name, .. } => marks.push(EtchedMark {
+                        expr: self.expression()?,
+                        name,
+                    }),
+                    _ => self.throw_error("A mark can only be named by a plain name."),
+                }
+            } else if !marks.is_empty() {
+                self.throw_error("The marks given by their names have to come last.");
+            } else {
+                reagents.push(reagent);
+            }
+
+            if !self.match_token(TokenType::Comma) {
+                // End of mark list when next token isn't a comma!
+                break;
+            }
+        }
+        Ok(())
+    }
+
     pub(super) fn access(&mut self, lhs: Expr, _can_assign: bool) -> ParseResult<Expr> {
         // nothing much to do here than just getting the property name.
         self.consume(TokenType::Identifier, "Expected a property name after '.'!");
         Ok(Expr::Access {
+            span: self.span_from(lhs.span()),
             material: Box::new(lhs),
             property: self.previous.clone(),
         })
@@ -253,17 +331,23 @@ impl Parser {
 
         // if a '=' is found after the variable name, it should be a assignment
         if self.match_token(TokenType::Equal) {
+            let value = self.expression()?;
             return Ok(Expr::Assignment {
+                span: var_name.span.to(value.span()),
                 name: var_name,
-                value: Box::new(self.expression()?),
+                value: Box::new(value),
             });
         }
 
         // else, it should be a variable access
-        Ok(Expr::Variable { name: var_name })
+        Ok(Expr::Variable {
+            span: var_name.span,
+            name: var_name,
+        })
     }
 
     pub(super) fn deck(&mut self, _can_assign: bool) -> ParseResult<Expr> {
+        let start = self.previous.span;
         let mut elements: Vec<Expr> = vec![];
 
         // Handle empty deck case []
@@ -272,6 +356,7 @@ impl Parser {
             return Ok(Expr::Deck {
                 elements,
                 token: self.previous.clone(),
+                span: self.span_from(start),
             });
         }
 
@@ -290,17 +375,34 @@ impl Parser {
         Ok(Expr::Deck {
             elements,
             token: self.previous.clone(),
+            span: self.span_from(start),
         })
     }
 
     pub(super) fn extract(&mut self, lhs: Expr, _can_assign: bool) -> ParseResult<Expr> {
         let index_expr = self.expression()?;
+        // `xs[start..end]` takes the elements from start up to, but not including, end
+        if self.match_token(TokenType::DotDot) {
+            let end = self.expression()?;
+            self.consume(
+                TokenType::SquareRight,
+                "Expected ']' after the end of the slice.",
+            );
+            return Ok(Expr::Slice {
+                span: self.span_from(lhs.span()),
+                target: Box::new(lhs),
+                start: Box::new(index_expr),
+                end: Box::new(end),
+                token: self.previous.clone(),
+            });
+        }
         self.consume(
             TokenType::SquareRight,
             "Expected ']' after deck access expression.",
         );
 
         Ok(Expr::Extract {
+            span: self.span_from(lhs.span()),
             deck: Box::new(lhs),
             index: Box::new(index_expr),
             token: self.previous.clone(),
@@ -310,11 +412,13 @@ impl Parser {
     pub(super) fn blank(&mut self, _can_assign: bool) -> ParseResult<Expr> {
         Ok(Expr::Blank {
             token: self.previous.clone(),
+            span: self.previous.span,
         })
     }
 
     pub(super) fn manifests(&mut self, lhs: Expr, _can_assign: bool) -> ParseResult<Expr> {
         Ok(Expr::Manifests {
+            span: self.span_from(lhs.span()),
             value: Box::new(lhs),
             token: self.previous.clone(),
         })
@@ -326,13 +430,33 @@ impl Parser {
             "Expected a property name after '?.'!",
         );
         Ok(Expr::SafeAccess {
+            span: self.span_from(lhs.span()),
             material: Box::new(lhs),
             property: self.previous.clone(),
         })
     }
 
+    pub(super) fn conditional(&mut self, lhs: Expr, _can_assign: bool) -> ParseResult<Expr> {
+        let token = self.previous.clone();
+        self.then_arms += 1;
+        let then_branch = self.expression();
+        self.then_arms -= 1;
+        let then_branch = then_branch?;
+        self.consume(TokenType::Colon, "Expected ':' between the arms of the conditional!");
+        // the else arm binds to the right, so `a ? b : c ? d : e` reads as `a ? b : (c ? d : e)`
+        let else_branch = self.parse_precedence(Precedence::Conditional)?;
+        Ok(Expr::Conditional {
+            span: lhs.span().to(else_branch.span()),
+            condition: Box::new(lhs),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+            token,
+        })
+    }
+
     pub(super) fn assert_safe(&mut self, lhs: Expr, _can_assign: bool) -> ParseResult<Expr> {
         Ok(Expr::AssertSafe {
+            span: self.span_from(lhs.span()),
             operand: Box::new(lhs),
             operator: self.previous.clone(),
         })
@@ -1,7 +1,10 @@
+use std::rc::Rc;
+
 use crate::compiler::{
     Expr, Stmt,
+    diagnostics::{CompilationPhase, DEFAULT_ERROR_LIMIT, Diagnostic, EiraError, SourceLocation},
     parser::types::{ParseError, ParseResult, ParseRule, ParsedWeave, Precedence},
-    scanner::Token,
+    scanner::{Span, Token, number_value},
     token_type::TokenType,
 };
 
@@ -9,6 +12,14 @@ pub(super) const MSG_BIND_VALUE_NOT_INITIALIZED: &str = "bind values must be ini
 pub(super) const MSG_MISSED_SEMICOLON: &str =
     "Expected a ';' after the expression. Forgot to add it?";
 
+/// How deep the expressions, blocks and weaves can nest. The later phases walk the tree
+/// recursively, so a deeper one is refused here rather than overflowing their stack.
+pub const MAX_NESTING: usize = 64;
+
+/// How long a chain of operators or of `divert fate`s can be. The phases walk along the chains
+/// without nesting, only dropping the tree goes down them, so they can be far longer.
+pub const MAX_CHAIN: usize = 4096;
+
 pub struct Parser {
     // Token list
     pub(super) tokens: Vec<Token>,
@@ -23,16 +34,24 @@ pub struct Parser {
 
     // error and unwinding
     pub(super) panic: bool,
-    pub(super) error: bool,
+    pub(super) diagnostics: Vec<Diagnostic>,
+    pub(super) error_limit: usize,
+
+    // the then arms of the conditionals being parsed, a `:` in them ends the arm rather than naming a reagent
+    pub(super) then_arms: usize,
+
+    // how deep the tree being parsed nests, up to MAX_NESTING
+    pub(super) depth: usize,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>, current_file: String) -> Self {
         let temp_token = Token {
-            lexeme: "tempo tokan!".to_string(),
+            lexeme: Rc::from("tempo tokan!"),
             line: 0,
             token_type: TokenType::Error, // temp
             column: 0,
+            span: Span::default(),
         };
 
         let mut parser = Parser {
@@ -42,27 +61,44 @@ impl Parser {
             previous: temp_token.clone(),
             current: temp_token,
             panic: false,
-            error: false,
+            diagnostics: vec![],
+            error_limit: DEFAULT_ERROR_LIMIT,
+            then_arms: 0,
+            depth: 0,
         };
 
         // parser.advance();
-        parser.current = parser.tokens[0].clone();
+        parser.current = parser.tokens.first().cloned().unwrap_or_else(Self::eof);
 
         parser
     }
 
-    pub fn parse(mut self) -> ParseResult<Vec<Stmt>> {
+    /// Stops parsing once [limit] errors have been caught
+    pub fn with_error_limit(mut self, limit: usize) -> Self {
+        self.error_limit = limit.max(1);
+        self
+    }
+
+    pub fn parse(self) -> Result<Vec<Stmt>, EiraError> {
+        let (stmts, diagnostics) = self.parse_partial();
+        if !diagnostics.is_empty() {
+            return Err(EiraError::Diagnostics(diagnostics));
+        }
+        Ok(stmts)
+    }
+
+    /// Parses the statements it can, the broken ones are left out and told about in the
+    /// diagnostics. The editors still find their way around the rest of a scroll being written
+    pub fn parse_partial(mut self) -> (Vec<Stmt>, Vec<Diagnostic>) {
         let mut stmts: Vec<Stmt> = vec![];
-        while !self.reached_end() {
+        while !self.reached_end() && self.diagnostics.len() < self.error_limit {
             if let Some(stmt) = self.declaration() {
                 stmts.push(stmt);
             }
         }
 
-        if self.error {
-            return Err(ParseError("Parsing failed due to errors.".to_owned()));
-        }
-        Ok(stmts)
+        self.diagnostics.truncate(self.error_limit);
+        (stmts, self.diagnostics)
     }
 
     pub(super) fn advance(&mut self) {
@@ -73,6 +109,13 @@ impl Parser {
         loop {
             self.current_pos += 1;
             if self.current_pos >= self.tokens.len() {
+                // the tokens handed in without an Eof end as if they had one
+                self.current = Token {
+                    line: self.previous.line,
+                    column: self.previous.column,
+                    span: Span { start: self.previous.span.end, end: self.previous.span.end },
+                    ..Self::eof()
+                };
                 return;
             }
             self.current = self.tokens[self.current_pos].clone();
@@ -81,9 +124,45 @@ impl Parser {
                 break;
             }
 
-            // error token reporting!
-            self.throw_error_at_current(&self.current.lexeme.clone());
+            // error token reporting! the lexeme of an error token is its message
+            let message = self.current.lexeme.clone();
+            self.throw_error_at_current(&message);
+        }
+    }
+
+    fn eof() -> Token {
+        Token {
+            token_type: TokenType::Eof,
+            lexeme: Rc::from(""),
+            line: 0,
+            column: 0,
+            span: Span::default(),
+        }
+    }
+
+    /// The span from [start] to the end of the token parsed last
+    pub(super) fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous.span)
+    }
+
+    /// Runs [parse] a level deeper, complaining instead once the tree nests past MAX_NESTING
+    pub(super) fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        let depth = self.depth;
+        let result = self.descend().and_then(|_| parse(self));
+        self.depth = depth;
+        result
+    }
+
+    fn descend(&mut self) -> ParseResult<()> {
+        if self.depth >= MAX_NESTING {
+            self.throw_error_at_current(&format!(
+                "The scroll nests deeper than {} levels here. Try splitting it up into spells or marks.",
+                MAX_NESTING
+            ));
+            return Err(ParseError("".to_owned()));
         }
+        self.depth += 1;
+        Ok(())
     }
 
     pub(super) fn reached_end(&mut self) -> bool {
@@ -112,16 +191,23 @@ impl Parser {
     }
 
     pub(super) fn error_at(&mut self, msg: &str, pos: Token) {
+        // the errors following the first one are mostly its echoes, until we sync
         if self.panic {
-            // return;
-        } else {
-            self.panic = true;
+            return;
         }
-        println!(
-            "Woah! Caught an incorrect magic at: {}:{}:{}\nError: {}\n",
-            self.current_file, pos.line, pos.column, msg
-        );
-        self.error = true;
+        self.panic = true;
+
+        // error tokens are the scanner's complaints
+        let phase = if pos.token_type == TokenType::Error {
+            CompilationPhase::Scan
+        } else {
+            CompilationPhase::Parse
+        };
+        self.diagnostics.push(Diagnostic::error(
+            phase,
+            msg,
+            SourceLocation::of_token(&self.current_file, &pos),
+        ));
     }
 
     pub(super) fn throw_error_at_current(&mut self, msg: &str) {
@@ -139,22 +225,30 @@ impl Parser {
         let mut capacity: Option<usize> = None;
 
         if self.match_token(TokenType::Less) {
-            inner = Some(Box::new(self.parse_weave(
-                "Expected a weave name to bind with the weave after the '<'!",
-            )?));
+            inner = Some(Box::new(self.nested(|parser| {
+                parser.parse_weave("Expected a weave name to bind with the weave after the '<'!")
+            })?));
 
             if self.match_token(TokenType::Comma) {
                 self.consume(
                     TokenType::Number,
                     "Expected a capacity for the weave after ','!",
                 );
-                capacity = Some(self.previous.lexeme.parse::<usize>().unwrap());
+                if self.previous.token_type == TokenType::Number {
+                    capacity = Some(number_value(&self.previous.lexeme) as usize);
+                }
             }
 
-            self.consume(
-                TokenType::Greater,
-                "Expected closing '>' after inner weave.",
-            );
+            // a `>>` closing two weaves at once was scanned as a shift, its first half closes this one
+            if self.check(TokenType::GreaterGreater) {
+                self.current.token_type = TokenType::Greater;
+                self.current.lexeme = Rc::from(">");
+            } else {
+                self.consume(
+                    TokenType::Greater,
+                    "Expected closing '>' after inner weave.",
+                );
+            }
         }
         Ok(ParsedWeave {
             base: weave,
@@ -178,10 +272,20 @@ impl Parser {
                 TokenType::Bind => return,
                 TokenType::Seal => return,
                 TokenType::While => return,
+                TokenType::Cycle => return,
+                TokenType::Circle => return,
+                TokenType::Judge => return,
+                TokenType::Attempt => return,
+                TokenType::Invoke => return,
+                TokenType::Expect => return,
                 TokenType::Chant => return,
+                TokenType::ChantRaw => return,
                 TokenType::Release => return,
                 TokenType::Fate => return,
                 TokenType::Sign => return,
+                TokenType::Glyph => return,
+                // the block being parsed closes here, its statements after the broken one are done
+                TokenType::BraceRight if self.depth > 0 => return,
                 _ => {}
             }
 
@@ -197,21 +301,28 @@ impl Parser {
             res = self.variable_declaration(true);
         } else if self.match_token(TokenType::Bind) {
             res = self.variable_declaration(false);
+        } else if self.match_token(TokenType::Seal) {
+            res = self.seal_declaration();
         } else if self.match_token(TokenType::Spell) {
             res = self.spell_declaration(None);
         } else if self.match_token(TokenType::Sign) {
             res = self.sign_declaration();
         } else if self.match_token(TokenType::Attune) {
             res = self.attune_declaration();
+        } else if self.match_token(TokenType::Tome) {
+            res = self.tome_declaration();
+        } else if self.match_token(TokenType::Glyph) {
+            res = self.glyph_declaration();
         } else if self.match_token(TokenType::Tether) {
             res = self.tether_declaration();
         } else {
             res = self.statement();
         }
 
+        // a declaration which complained is dropped, even if it managed to finish
         match res {
-            Ok(res) => Some(res),
-            Err(_) => {
+            Ok(res) if !self.panic => Some(res),
+            _ => {
                 self.sync();
                 None
             }
@@ -222,19 +333,35 @@ impl Parser {
 
     pub(super) fn statement(&mut self) -> ParseResult<Stmt> {
         if self.match_token(TokenType::Chant) {
-            self.chant_statment()
+            self.chant_statment(true)
+        } else if self.match_token(TokenType::ChantRaw) {
+            self.chant_statment(false)
         } else if self.match_token(TokenType::BraceLeft) {
             self.block()
         } else if self.match_token(TokenType::Fate) {
             self.fate_statement()
         } else if self.match_token(TokenType::While) {
             self.while_statement()
+        } else if self.match_token(TokenType::Cycle) {
+            self.cycle_statement()
+        } else if self.match_token(TokenType::Circle) {
+            self.circle_statement()
+        } else if self.match_token(TokenType::Judge) {
+            self.judge_statement()
         } else if self.match_token(TokenType::Sever) {
             self.sever_statement()
         } else if self.match_token(TokenType::Flow) {
             self.flow_statement()
+        } else if self.match_token(TokenType::Attempt) {
+            self.attempt_statement()
+        } else if self.match_token(TokenType::Invoke) {
+            self.invoke_statement()
+        } else if self.match_token(TokenType::Expect) {
+            self.expect_statement()
         } else if self.match_token(TokenType::Release) {
             self.release_statement()
+        } else if self.match_token(TokenType::Offer) {
+            self.offer_statement()
         } else if self.match_token(TokenType::Vanish) {
             self.vanish_statement()
         } else {
@@ -250,6 +377,10 @@ impl Parser {
     // ----------------------- Core -------------------------------//
 
     pub(super) fn parse_precedence(&mut self, precedence: Precedence) -> ParseResult<Expr> {
+        self.nested(|parser| parser.parse_operations(precedence))
+    }
+
+    fn parse_operations(&mut self, precedence: Precedence) -> ParseResult<Expr> {
         self.advance();
         let rule = self.get_rule(self.previous.token_type).prefix;
 
@@ -261,6 +392,7 @@ impl Parser {
             Some(prefix_rule) => {
                 let can_assign = precedence.power() <= Precedence::Assign.power();
                 let mut lhs = prefix_rule(self, can_assign)?;
+                let mut chained = 0;
 
                 while precedence.power()
                     <= self.get_rule(self.current.token_type).precedence.power()
@@ -278,37 +410,51 @@ impl Parser {
                         break;
                     };
 
+                    // the operators chain to the left without nesting, `a + b + c` is parsed in this loop
+                    chained += 1;
+                    if chained > MAX_CHAIN {
+                        self.throw_error(&format!(
+                            "The expression chains more than {} operators. Try splitting it up into marks.",
+                            MAX_CHAIN
+                        ));
+                        return Err(ParseError("".to_owned()));
+                    }
                     lhs = infix_rule(self, lhs, can_assign)?;
                 }
 
                 if can_assign && self.match_token(TokenType::Equal) {
                     let equals = self.previous.clone();
                     let value = self.expression()?;
+                    let span = lhs.span().to(value.span());
 
                     match lhs {
-                        Expr::Variable { name } => {
+                        Expr::Variable { name, .. } => {
                             return Ok(Expr::Assignment {
                                 name,
                                 value: Box::new(value),
+                                span,
                             });
                         }
                         Expr::Extract {
                             deck,
                             index,
                             token: _,
+                            ..
                         } => {
                             return Ok(Expr::DeckSet {
                                 deck,
                                 index,
                                 value: Box::new(value),
                                 token: equals,
+                                span,
                             });
                         }
-                        Expr::Access { material, property } => {
+                        Expr::Access { material, property, .. } => {
                             return Ok(Expr::FieldSet {
                                 material,
                                 property,
                                 value: Box::new(value),
+                                span,
                             });
                         }
                         _ => {}
@@ -326,6 +472,11 @@ impl Parser {
 
     pub(super) fn get_rule(&self, token_type: TokenType) -> ParseRule {
         match token_type {
+            TokenType::And => ParseRule {
+                prefix: None,
+                infix: Some(Self::binary),
+                precedence: Precedence::BitAnd,
+            },
             TokenType::Bang => ParseRule {
                 prefix: Some(Self::unary),
                 infix: Some(Self::assert_safe),
@@ -341,6 +492,11 @@ impl Parser {
                 infix: None,
                 precedence: Precedence::None,
             },
+            TokenType::Caret => ParseRule {
+                prefix: None,
+                infix: Some(Self::binary),
+                precedence: Precedence::BitXor,
+            },
             TokenType::Cast => ParseRule {
                 prefix: Some(Self::cast),
                 infix: None,
@@ -351,11 +507,31 @@ impl Parser {
                 infix: Some(Self::access),
                 precedence: Precedence::Call,
             },
+            TokenType::Heed => ParseRule {
+                prefix: Some(Self::heed),
+                infix: None,
+                precedence: Precedence::None,
+            },
+            TokenType::Conjure => ParseRule {
+                prefix: Some(Self::conjure),
+                infix: None,
+                precedence: Precedence::None,
+            },
+            TokenType::Claim => ParseRule {
+                prefix: Some(Self::claim),
+                infix: None,
+                precedence: Precedence::None,
+            },
             TokenType::Ego => ParseRule {
                 prefix: Some(Self::variable),
                 infix: None,
                 precedence: Precedence::None,
             },
+            TokenType::Origin => ParseRule {
+                prefix: Some(Self::variable),
+                infix: None,
+                precedence: Precedence::None,
+            },
             TokenType::EqualEqual => ParseRule {
                 prefix: None,
                 infix: Some(Self::binary),
@@ -376,6 +552,11 @@ impl Parser {
                 infix: Some(Self::binary),
                 precedence: Precedence::Compare,
             },
+            TokenType::GreaterGreater => ParseRule {
+                prefix: None,
+                infix: Some(Self::binary),
+                precedence: Precedence::Shift,
+            },
             TokenType::Identifier => ParseRule {
                 prefix: Some(Self::variable),
                 infix: None,
@@ -396,6 +577,11 @@ impl Parser {
                 infix: Some(Self::binary),
                 precedence: Precedence::Compare,
             },
+            TokenType::LessLess => ParseRule {
+                prefix: None,
+                infix: Some(Self::binary),
+                precedence: Precedence::Shift,
+            },
             TokenType::Manifests => ParseRule {
                 prefix: None,
                 infix: Some(Self::manifests),
@@ -411,6 +597,11 @@ impl Parser {
                 infix: None,
                 precedence: Precedence::None,
             },
+            TokenType::Or => ParseRule {
+                prefix: None,
+                infix: Some(Self::binary),
+                precedence: Precedence::BitOr,
+            },
             TokenType::ParenLeft => ParseRule {
                 prefix: Some(Self::grouping),
                 infix: None,
@@ -426,6 +617,11 @@ impl Parser {
                 infix: Some(Self::binary),
                 precedence: Precedence::Term,
             },
+            TokenType::QuestionMark => ParseRule {
+                prefix: None,
+                infix: Some(Self::conditional),
+                precedence: Precedence::Conditional,
+            },
             TokenType::QuestionDot => ParseRule {
                 prefix: None,
                 infix: Some(Self::safe_access),
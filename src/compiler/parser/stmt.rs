@@ -1,29 +1,39 @@
-use crate::{Parser, compiler::{Stmt, parser::{parser::MSG_MISSED_SEMICOLON, types::ParseResult}, token_type::TokenType}};
+use crate::{Parser, Token, compiler::{Expr, Stmt, parser::{parser::{MAX_CHAIN, MSG_MISSED_SEMICOLON}, types::{ParseError, ParseResult}}, token_type::TokenType}};
 
 impl Parser {
       pub(super) fn block(&mut self) -> ParseResult<Stmt> {
-        let mut stmts: Vec<Stmt> = vec![];
-        while !self.check(TokenType::BraceRight) && !self.reached_end() {
-            if let Some(stmt) = self.declaration() {
-                stmts.push(stmt);
-            } else {
-                break;
+        let start = self.previous.span;
+        self.nested(|parser| {
+            let mut stmts: Vec<Stmt> = vec![];
+            // a broken statement is dropped and the rest of the block still parsed, so its errors are told as well
+            while !parser.check(TokenType::BraceRight)
+                && !parser.reached_end()
+                && parser.diagnostics.len() < parser.error_limit
+            {
+                if let Some(stmt) = parser.declaration() {
+                    stmts.push(stmt);
+                }
             }
-        }
 
-        self.consume(
-            TokenType::BraceRight,
-            "Expected '}' at the end of a block. Forgot about it?",
-        );
+            parser.consume(
+                TokenType::BraceRight,
+                "Expected '}' at the end of a block. Forgot about it?",
+            );
 
-        Ok(Stmt::Block { statements: stmts })
+            Ok(Stmt::Block {
+                statements: stmts,
+                span: parser.span_from(start),
+            })
+        })
     }
 
     pub(super) fn release_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
         if self.match_token(TokenType::SemiColon) {
             return Ok(Stmt::Release {
                 token: self.previous.clone(),
                 expr: None,
+                span: self.span_from(start),
             });
         }
 
@@ -35,71 +45,337 @@ impl Parser {
         Ok(Stmt::Release {
             token,
             expr: Some(expr),
+            span: self.span_from(start),
+        })
+    }
+
+    /// `offer value;` hands the value to the flow drawing from the spell, which waits for the next draw
+    pub(super) fn offer_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        let token = self.previous.clone();
+        let value = self.expression()?;
+        self.consume(TokenType::SemiColon, MSG_MISSED_SEMICOLON);
+        Ok(Stmt::Offer {
+            token,
+            value,
+            span: self.span_from(start),
         })
     }
 
     pub(super) fn expression_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.current.span;
         let e = self.expression()?;
         self.consume(TokenType::SemiColon, MSG_MISSED_SEMICOLON);
-        Ok(Stmt::ExprStmt { expr: e })
+        Ok(Stmt::ExprStmt {
+            expr: e,
+            span: self.span_from(start),
+        })
     }
 
-    pub(super) fn chant_statment(&mut self) -> ParseResult<Stmt> {
-        let exp = self.expression()?;
+    /// `chant a, b;` chants the values one after another, [newline] ends the line after them
+    pub(super) fn chant_statment(&mut self, newline: bool) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        let mut expressions = vec![self.expression()?];
+        while self.match_token(TokenType::Comma) {
+            expressions.push(self.expression()?);
+        }
         self.consume(TokenType::SemiColon, MSG_MISSED_SEMICOLON);
-        Ok(Stmt::Chant { expression: exp })
+        Ok(Stmt::Chant {
+            expressions,
+            newline,
+            span: self.span_from(start),
+        })
     }
 
     pub(super) fn while_statement(&mut self) -> ParseResult<Stmt> {
-        let condition = self.expression()?;
+        let start = self.previous.span;
+        // `while outer: cond { ... }` names the loop, the name is read as the condition until the `:` shows up
+        let mut label = None;
+        let mut condition = self.expression()?;
+        if self.match_token(TokenType::Colon) {
+            match condition {
+                Expr::Variable { name, .. } => label = Some(name),
+                _ => self.throw_error("A loop can only be named by a plain name."),
+            }
+            condition = self.expression()?;
+        }
         self.consume(TokenType::BraceLeft, "Expected '{' after loop condition.");
         let body = self.block()?;
         Ok(Stmt::While {
+            label,
             condition: condition,
             body: Box::new(body),
+            span: self.span_from(start),
         })
     }
 
-    pub(super) fn fate_statement(&mut self) -> ParseResult<Stmt> {
-        let condition = self.expression()?;
-        self.consume(TokenType::BraceLeft, "Expected '{' at start of fate block.");
-        let then_branch = self.block()?;
-
-        let else_branch = if self.match_token(TokenType::Divert) {
-            if self.match_token(TokenType::Fate) {
-                Some(Box::new(self.fate_statement()?))
-            } else {
-            self.consume(
-                TokenType::BraceLeft,
-                "Expected '{' at start of fate-else block.",
-            );
-            Some(Box::new(self.block()?))
-        }
+    /// `circle { ... } until cond;` runs the body once before checking the condition, and again
+    /// for as long as it doesn't hold
+    pub(super) fn circle_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        let label = if self.match_token(TokenType::Identifier) {
+            let label = self.previous.clone();
+            self.consume(TokenType::Colon, "Expected ':' after the name of the circle.");
+            Some(label)
         } else {
             None
         };
-        Ok(Stmt::Fate {
-            condition: condition,
-            then_branch: Box::new(then_branch),
-            else_branch: else_branch,
+
+        self.consume(TokenType::BraceLeft, "Expected '{' after 'circle'.");
+        let body = self.block()?;
+
+        self.consume(TokenType::Until, "Expected 'until' after the body of the circle.");
+        let condition = self.expression()?;
+        self.consume(TokenType::SemiColon, MSG_MISSED_SEMICOLON);
+
+        Ok(Stmt::Circle {
+            label,
+            body: Box::new(body),
+            condition,
+            span: self.span_from(start),
+        })
+    }
+
+    /// The `name:` naming a cycle or a flow, read once the name is behind. The mark follows it
+    fn loop_label(&mut self, keyword: &str) -> Option<Token> {
+        let name = self.previous.clone();
+        if !self.match_token(TokenType::Colon) {
+            return None;
+        }
+        self.consume(
+            TokenType::Identifier,
+            &format!("Expected a mark name after the name of the {}.", keyword),
+        );
+        Some(name)
+    }
+
+    pub(super) fn cycle_statement(&mut self) -> ParseResult<Stmt> {
+        let token = self.previous.clone();
+
+        self.consume(TokenType::Identifier, "Expected a mark name after 'cycle'.");
+        let label = self.loop_label("cycle");
+        let variable = self.previous.clone();
+
+        self.consume(TokenType::From, "Expected 'from' after the cycle mark.");
+        let start = self.expression()?;
+
+        self.consume(TokenType::To, "Expected 'to' after the start of the cycle range.");
+        let end = self.expression()?;
+
+        self.consume(TokenType::BraceLeft, "Expected '{' after cycle range.");
+        let body = self.block()?;
+
+        Ok(Stmt::Cycle {
+            span: self.span_from(token.span),
+            token,
+            label,
+            variable,
+            start,
+            end,
+            body: Box::new(body),
+        })
+    }
+
+    /// A fate along with its chain of `divert fate`s, parsed one after another rather than one
+    /// within the other
+    pub(super) fn fate_statement(&mut self) -> ParseResult<Stmt> {
+        let mut fates = vec![]; // the start, condition and then branch of every fate of the chain
+        let else_branch = loop {
+            if fates.len() >= MAX_CHAIN {
+                self.throw_error(&format!("The fate diverts more than {} times. Try judging instead.", MAX_CHAIN));
+                return Err(ParseError("".to_owned()));
+            }
+            let start = self.previous.span;
+            let condition = self.expression()?;
+            self.consume(TokenType::BraceLeft, "Expected '{' at start of fate block.");
+            let then_branch = self.block()?;
+            fates.push((start, condition, then_branch));
+
+            if !self.match_token(TokenType::Divert) {
+                break None;
+            }
+            if !self.match_token(TokenType::Fate) {
+                self.consume(
+                    TokenType::BraceLeft,
+                    "Expected '{' at start of fate-else block.",
+                );
+                break Some(Box::new(self.block()?));
+            }
+        };
+
+        // every fate spans to the end of the chain, the diverted ones nest within the one before
+        let mut else_branch = else_branch;
+        for (start, condition, then_branch) in fates.into_iter().rev() {
+            else_branch = Some(Box::new(Stmt::Fate {
+                condition: condition,
+                then_branch: Box::new(then_branch),
+                else_branch: else_branch,
+                span: self.span_from(start),
+            }));
+        }
+        Ok(*else_branch.unwrap())
+    }
+
+    /// `judge x { 1 -> { ... } divert -> { ... } }` runs the first arm holding the judged value,
+    /// the divert runs when none of them does
+    pub(super) fn judge_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        let token = self.previous.clone();
+        let subject = self.expression()?;
+        self.consume(TokenType::BraceLeft, "Expected '{' after the judged value.");
+
+        let mut arms = vec![];
+        let mut divert = None;
+        while !self.check(TokenType::BraceRight) && !self.reached_end() && !self.panic {
+            if divert.is_some() {
+                self.throw_error_at_current("The divert has to be the last arm of a judge.");
+                break;
+            }
+
+            if self.match_token(TokenType::Divert) {
+                self.consume(TokenType::Arrow, "Expected '->' after 'divert'.");
+                self.consume(TokenType::BraceLeft, "Expected '{' at the start of the divert arm.");
+                divert = Some(Box::new(self.block()?));
+                continue;
+            }
+
+            let value = self.expression()?;
+            self.consume(TokenType::Arrow, "Expected '->' after the value of the arm.");
+            self.consume(TokenType::BraceLeft, "Expected '{' at the start of the arm.");
+            arms.push((value, self.block()?));
+        }
+
+        self.consume(
+            TokenType::BraceRight,
+            "Expected '}' after the arms of the judge.",
+        );
+
+        Ok(Stmt::Judge {
+            token,
+            subject,
+            arms,
+            divert,
+            span: self.span_from(start),
         })
     }
 
+    /// `sever outer;` leaves the loop named outer, along with the ones inside it
     pub(super) fn sever_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        let label = self
+            .match_token(TokenType::Identifier)
+            .then(|| self.previous.clone());
         self.consume(TokenType::SemiColon, MSG_MISSED_SEMICOLON);
         Ok(Stmt::Sever {
             token: self.previous.clone(),
+            label,
+            span: self.span_from(start),
         })
     }
 
     pub(super) fn flow_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        // `flow x through xs { ... }` walks the elements, a bare `flow;` skips to the next round
+        // and `flow outer;` to the next round of the loop named outer
+        let token = self.previous.clone();
+        let mut label = None;
+        if self.match_token(TokenType::Identifier) {
+            if !self.check(TokenType::SemiColon) {
+                return self.flow_through_statement(token);
+            }
+            label = Some(self.previous.clone());
+        }
         self.consume(TokenType::SemiColon, MSG_MISSED_SEMICOLON);
         Ok(Stmt::Flow {
             token: self.previous.clone(),
+            label,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Continues `flow`, once the name after it is read
+    fn flow_through_statement(&mut self, token: Token) -> ParseResult<Stmt> {
+        let start = token.span;
+        let label = self.loop_label("flow");
+        let variable = self.previous.clone();
+
+        self.consume(TokenType::Through, "Expected 'through' after the flow mark.");
+        let iterable = self.expression()?;
+
+        self.consume(TokenType::BraceLeft, "Expected '{' after the flowed value.");
+        let body = self.block()?;
+
+        Ok(Stmt::FlowThrough {
+            token,
+            label,
+            variable,
+            iterable,
+            body: Box::new(body),
+            span: self.span_from(start),
+        })
+    }
+
+    /// `attempt { ... } mend (e) { ... }` runs the mend with the error, if the attempt breaks down
+    pub(super) fn attempt_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        let token = self.previous.clone();
+
+        self.consume(TokenType::BraceLeft, "Expected '{' after 'attempt'.");
+        let body = self.block()?;
+
+        self.consume(TokenType::Mend, "An attempt must be followed by a 'mend' block!");
+        self.consume(TokenType::ParenLeft, "Expected '(' after 'mend'.");
+        self.consume(TokenType::Identifier, "Expected a name for the error in the mend.");
+        let error = self.previous.clone();
+        self.consume(TokenType::ParenRight, "Expected ')' after the name of the error.");
+
+        self.consume(TokenType::BraceLeft, "Expected '{' at start of the mend block.");
+        let mend = self.block()?;
+
+        Ok(Stmt::Attempt {
+            token,
+            body: Box::new(body),
+            error,
+            mend: Box::new(mend),
+            span: self.span_from(start),
+        })
+    }
+
+    /// `invoke doom "message";` breaks the VM down with the value, unless an attempt mends it
+    pub(super) fn invoke_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        self.consume(TokenType::Doom, "Only a doom can be invoked, like 'invoke doom \"message\";'");
+        let token = self.previous.clone();
+        let value = self.expression()?;
+        self.consume(TokenType::SemiColon, MSG_MISSED_SEMICOLON);
+        Ok(Stmt::Doom {
+            token,
+            value,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `expect cond, "message";` breaks the VM down with the message when the condition fails
+    pub(super) fn expect_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
+        let token = self.previous.clone();
+        let condition = self.expression()?;
+        let message = if self.match_token(TokenType::Comma) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.consume(TokenType::SemiColon, MSG_MISSED_SEMICOLON);
+        Ok(Stmt::Expect {
+            token,
+            condition,
+            message,
+            span: self.span_from(start),
         })
     }
 
     pub(super) fn vanish_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.previous.span;
         let tkn = self.previous.clone();
         let expr = self.expression()?;
         
@@ -108,6 +384,7 @@ impl Parser {
         Ok(Stmt::Vanish {
             token: tkn,
             target: expr,
+            span: self.span_from(start),
         })
     }
 }
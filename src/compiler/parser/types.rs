@@ -1,3 +1,5 @@
+use serde::Serialize;
+
 use crate::{Parser, Token, compiler::Expr};
 
 pub struct ParseRule {
@@ -15,7 +17,7 @@ pub type ParseFun = fn(&mut Parser, bool) -> ParseResult<Expr>;
 
 pub type InfixParseFun = fn(&mut Parser, Expr, bool) -> ParseResult<Expr>;
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub struct ParsedWeave {
     pub base: Token,
     pub inner: Option<Box<ParsedWeave>>,
@@ -25,10 +27,15 @@ pub struct ParsedWeave {
 pub enum Precedence {
     None,
     Assign,
+    Conditional,
     Or,
     And,
     Equality,
     Compare,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
     Term,
     Factor,
     Unary,
@@ -40,11 +47,16 @@ impl Precedence {
     pub fn next(&self) -> Precedence {
         match self {
             Precedence::None => Precedence::Assign,
-            Precedence::Assign => Precedence::Or,
+            Precedence::Assign => Precedence::Conditional,
+            Precedence::Conditional => Precedence::Or,
             Precedence::Or => Precedence::And,
             Precedence::And => Precedence::Equality,
             Precedence::Equality => Precedence::Compare,
-            Precedence::Compare => Precedence::Term,
+            Precedence::Compare => Precedence::BitOr,
+            Precedence::BitOr => Precedence::BitXor,
+            Precedence::BitXor => Precedence::BitAnd,
+            Precedence::BitAnd => Precedence::Shift,
+            Precedence::Shift => Precedence::Term,
             Precedence::Term => Precedence::Factor,
             Precedence::Factor => Precedence::Unary,
             Precedence::Unary => Precedence::Call,
@@ -57,15 +69,20 @@ impl Precedence {
         match self {
             Precedence::None => 0,
             Precedence::Assign => 1,
-            Precedence::Or => 2,
-            Precedence::And => 3,
-            Precedence::Equality => 4,
-            Precedence::Compare => 5,
-            Precedence::Term => 6,
-            Precedence::Factor => 7,
-            Precedence::Unary => 8,
-            Precedence::Call => 9,
-            Precedence::Primary => 10,
+            Precedence::Conditional => 2,
+            Precedence::Or => 3,
+            Precedence::And => 4,
+            Precedence::Equality => 5,
+            Precedence::Compare => 6,
+            Precedence::BitOr => 7,
+            Precedence::BitXor => 8,
+            Precedence::BitAnd => 9,
+            Precedence::Shift => 10,
+            Precedence::Term => 11,
+            Precedence::Factor => 12,
+            Precedence::Unary => 13,
+            Precedence::Call => 14,
+            Precedence::Primary => 15,
         }
     }
 }
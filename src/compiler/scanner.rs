@@ -1,22 +1,32 @@
-use std::{collections::VecDeque, fmt::Display};
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::Display,
+    rc::Rc,
+};
+
+use serde::Serialize;
 
 use crate::compiler::token_type::TokenType;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Token {
     pub token_type: TokenType,
-    pub lexeme: String,
+    /// Shared with every other token of the same text, so a token is cheap to clone
+    pub lexeme: Rc<str>,
     pub line: usize,
     pub column: usize,
+    /// Where the token was scanned from, the quotes of a text included
+    pub span: Span,
 }
 
 impl Token {
     pub fn dummy() -> Self {
         Token {
             token_type: TokenType::Error,
-            lexeme: "".to_string(),
+            lexeme: Rc::from(""),
             line: 0,
             column: 0,
+            span: Span::default(),
         }
     }
 }
@@ -31,6 +41,44 @@ impl Display for Token {
     }
 }
 
+/// A stretch of the source, in bytes from its start
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The span from the start of this one to the end of [other]. The default span of the tokens
+    /// made up by the compiler is left out
+    pub fn to(self, other: Span) -> Span {
+        if self == Span::default() {
+            return other;
+        }
+        if other == Span::default() {
+            return self;
+        }
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+/// What a stretch of the source is, for the editors to highlight it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TokenClass {
+    Keyword,
+    Identifier,
+    Number,
+    String, // the texts, with the `@(` and `)` around their interpolations
+    Operator,
+    Punctuation, // the brackets, `;`, `,`, `:` and `.`
+    Comment,
+    Whitespace,
+    Error, // what the scanner couldn't make sense of
+}
+
 #[derive(PartialEq, Eq, Clone, Copy)]
 enum ScanMode {
     Normal,
@@ -47,6 +95,9 @@ pub struct Scanner<'a> {
 
     mode: ScanMode,
     token_buffer: VecDeque<Token>,
+    lexemes: HashSet<Rc<str>>,
+
+    classes: Option<Vec<(Span, TokenClass)>>, // kept by `classify`, the whitespace and comments included
 }
 
 impl<'a> Scanner<'a> {
@@ -59,6 +110,59 @@ impl<'a> Scanner<'a> {
             column: 0,
             mode: ScanMode::Normal,
             token_buffer: VecDeque::new(),
+            lexemes: HashSet::new(),
+            classes: None,
+        }
+    }
+
+    /// Splits the whole source into the classes of its stretches, the whitespace and the comments
+    /// left out of the tokens included. The spans follow each other from the start of the source
+    /// to its end.
+    pub fn classify(source: &str) -> Vec<(Span, TokenClass)> {
+        let mut scanner = Scanner::init(source);
+        scanner.classes = Some(vec![]);
+        scanner.scan_all();
+
+        // the scanner gives up on what it can't get past
+        scanner.note_class(source.len(), TokenClass::Error);
+        scanner.classes.unwrap_or_default()
+    }
+
+    /// Notes what follows the last noted stretch up to [end] as [class], joining it to the
+    /// stretch before it when that is of the same class
+    fn note_class(&mut self, end: usize, class: TokenClass) {
+        let Some(classes) = &mut self.classes else {
+            return;
+        };
+        let start = classes.last().map_or(0, |(span, _)| span.end);
+        if start >= end {
+            return;
+        }
+        match classes.last_mut() {
+            Some((span, last)) if *last == class => span.end = end,
+            _ => classes.push((Span { start, end }, class)),
+        }
+    }
+
+    /// The class of the stretch scanned into [token]
+    fn class_of(&self, token: &Token, start: usize) -> TokenClass {
+        match token.token_type {
+            TokenType::String | TokenType::InterpolateStart | TokenType::InterpolateEnd => TokenClass::String,
+            TokenType::Number => TokenClass::Number,
+            TokenType::Identifier | TokenType::Underscore => TokenClass::Identifier,
+            TokenType::Error => TokenClass::Error,
+            TokenType::ParenLeft
+            | TokenType::ParenRight
+            | TokenType::BraceLeft
+            | TokenType::BraceRight
+            | TokenType::SquareLeft
+            | TokenType::SquareRight
+            | TokenType::SemiColon
+            | TokenType::Comma
+            | TokenType::Colon
+            | TokenType::Dot => TokenClass::Punctuation,
+            _ if KEYWORDS.contains(&&self.source[start..self.current]) => TokenClass::Keyword,
+            _ => TokenClass::Operator,
         }
     }
 
@@ -69,30 +173,44 @@ impl<'a> Scanner<'a> {
         ch
     }
 
+    /// The shared copy of [text], so that the names repeated across a script are allocated once
+    fn intern(&mut self, text: &str) -> Rc<str> {
+        if let Some(lexeme) = self.lexemes.get(text) {
+            return lexeme.clone();
+        }
+        let lexeme: Rc<str> = Rc::from(text);
+        self.lexemes.insert(lexeme.clone());
+        lexeme
+    }
+
     fn make_token(&mut self, token_type: TokenType) -> Token {
+        let source = self.source;
         Token {
-            lexeme: self.source[self.start..self.current].to_string(),
+            lexeme: self.intern(&source[self.start..self.current]),
             token_type,
             line: self.line,
             column: self.column,
+            span: Span { start: self.start, end: self.current },
         }
     }
 
     fn make_token_with_lexeme(&self, token_type: TokenType, lexeme: String) -> Token {
         Token {
-            lexeme,
+            lexeme: Rc::from(lexeme),
             token_type,
             line: self.line,
             column: self.column,
+            span: Span { start: self.start, end: self.current },
         }
     }
 
     fn error_token(&self, msg: &'a str) -> Token {
         Token {
-            lexeme: msg.to_string(),
+            lexeme: Rc::from(msg),
             line: self.line,
             token_type: TokenType::Error,
             column: self.column,
+            span: Span { start: self.start, end: self.current },
         }
     }
 
@@ -129,27 +247,74 @@ impl<'a> Scanner<'a> {
         self.make_token(identifier_type(identifier))
     }
 
+    /// Scans `42`, `4.2`, `1_000`, `0xFF` or `0b1010`. The lexeme is kept as written, and
+    /// [number_value] reads it
     fn number(&mut self) -> Token {
-        while let Some(c) = self.peek() {
-            if is_number(c) {
-                self.advance();
-            } else {
-                break;
+        let prefixed = &self.source[self.start..self.current] == "0";
+        let radix = match self.peek() {
+            Some('x' | 'X') if prefixed => 16,
+            Some('b' | 'B') if prefixed => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            let prefix = self.advance();
+            if !self.peek().is_some_and(|c| c.is_digit(radix)) {
+                return self.malformed_number(format!(
+                    "Expected the digits of the number after '0{}'.",
+                    prefix
+                ));
             }
         }
-        if self.peek() == Some('.') && self.peek_next().map_or(false, is_number) {
+
+        if let Some(error) = self.digits(radix) {
+            return error;
+        }
+        if radix == 10 && self.peek() == Some('.') && self.peek_next().is_some_and(is_number) {
             self.advance();
+            if let Some(error) = self.digits(radix) {
+                return error;
+            }
+        }
 
-            while let Some(c) = self.peek() {
-                if is_number(c) {
-                    self.advance();
-                } else {
-                    break;
+        self.make_token(TokenType::Number)
+    }
+
+    /// Eats the digits of the radix, two of them may be separated by a `_`
+    fn digits(&mut self, radix: u32) -> Option<Token> {
+        while let Some(c) = self.peek() {
+            if c.is_digit(radix) {
+                self.advance();
+            } else if c == '_' {
+                let after_digit = self.source[..self.current]
+                    .chars()
+                    .last()
+                    .is_some_and(|p| p.is_digit(radix));
+                let before_digit = self.peek_next().is_some_and(|n| n.is_digit(radix));
+                if !after_digit || !before_digit {
+                    return Some(self.malformed_number(
+                        "An '_' in a number has to sit between two digits.".to_string(),
+                    ));
                 }
+                self.advance();
+            } else if radix != 10 && c.is_ascii_alphanumeric() {
+                let name = if radix == 16 { "hex" } else { "binary" };
+                return Some(self.malformed_number(format!("'{}' is not a {} digit.", c, name)));
+            } else {
+                break;
             }
         }
+        None
+    }
 
-        self.make_token(TokenType::Number)
+    /// Skips the rest of a malformed number, so it is complained about only once
+    fn malformed_number(&mut self, msg: String) -> Token {
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.advance();
+        }
+        self.make_token_with_lexeme(TokenType::Error, msg)
     }
 
     fn scan_string_mode(&mut self, quote: char) -> Token {
@@ -213,6 +378,7 @@ impl<'a> Scanner<'a> {
                         }
 
                         let ident_start = self.current;
+                        self.note_class(ident_start, TokenClass::String);
                         while let Some(ch) = self.peek() {
                             if is_alpha(ch) || is_number(ch) {
                                 self.advance();
@@ -220,6 +386,7 @@ impl<'a> Scanner<'a> {
                                 break;
                             }
                         }
+                        self.note_class(self.current, TokenClass::Identifier);
 
                         let ident = self.source[ident_start..self.current].to_string();
 
@@ -259,7 +426,9 @@ impl<'a> Scanner<'a> {
     }
 
     fn scan_interpolation_mode(&mut self, quote: char, mut paren_depth: usize) -> Token {
-        self.eat_whitespace();
+        if let Some(error) = self.eat_whitespace() {
+            return error;
+        }
         self.start = self.current;
 
         if self.reached_end() {
@@ -301,14 +470,25 @@ impl<'a> Scanner<'a> {
             ':' if self.match_char(':') => self.make_token(TokenType::ColonColon),
             ':' => self.make_token(TokenType::Colon),
 
+            '.' if self.match_char('.') => {
+                if self.match_char('.') {
+                    self.make_token(TokenType::DotDotDot)
+                } else {
+                    self.make_token(TokenType::DotDot)
+                }
+            }
             '.' => self.make_token(TokenType::Dot),
             ',' => self.make_token(TokenType::Comma),
 
             '+' => self.make_token(TokenType::Plus),
+            '-' if self.match_char('>') => self.make_token(TokenType::Arrow),
             '-' => self.make_token(TokenType::Minus),
             '/' => self.make_token(TokenType::Slash),
             '*' => self.make_token(TokenType::Star),
             '%' => self.make_token(TokenType::Percent),
+            '&' => self.make_token(TokenType::And),
+            '|' => self.make_token(TokenType::Or),
+            '^' => self.make_token(TokenType::Caret),
 
             '!' if self.match_char('=') => self.make_token(TokenType::BangEqual),
             '!' => self.make_token(TokenType::Bang),
@@ -317,9 +497,11 @@ impl<'a> Scanner<'a> {
             '=' => self.make_token(TokenType::Equal),
 
             '>' if self.match_char('=') => self.make_token(TokenType::GreaterEqual),
+            '>' if self.match_char('>') => self.make_token(TokenType::GreaterGreater),
             '>' => self.make_token(TokenType::Greater),
 
             '<' if self.match_char('=') => self.make_token(TokenType::LessEqual),
+            '<' if self.match_char('<') => self.make_token(TokenType::LessLess),
             '<' => self.make_token(TokenType::Less),
 
             '~' => self.make_token(TokenType::Tilde),
@@ -348,33 +530,88 @@ impl<'a> Scanner<'a> {
         true
     }
 
-    fn eat_whitespace(&mut self) {
+    /// Skips the whitespace and the comments, an unterminated block comment is given back as an error
+    fn eat_whitespace(&mut self) -> Option<Token> {
         loop {
             match self.peek() {
                 Some(' ' | '\r' | '\t') => {
                     self.advance();
+                    self.note_class(self.current, TokenClass::Whitespace);
                 }
                 Some('\n') => {
                     self.advance();
                     self.line += 1;
                     self.column = 0;
+                    self.note_class(self.current, TokenClass::Whitespace);
                 }
                 Some('/') => {
                     if self.peek_next() == Some('/') {
                         while self.peek() != Some('\n') && !self.reached_end() {
                             self.advance();
                         }
+                        self.note_class(self.current, TokenClass::Comment);
+                    } else if self.peek_next() == Some('*') {
+                        let error = self.block_comment();
+                        self.note_class(self.current, TokenClass::Comment);
+                        if error.is_some() {
+                            return error;
+                        }
                     } else {
-                        return;
+                        return None;
                     }
                 }
-                _ => break,
+                _ => return None,
             }
         }
     }
 
+    /// Skips a `/* ... */` comment, the ones nested inside it included
+    fn block_comment(&mut self) -> Option<Token> {
+        let (line, column) = (self.line, self.column + 1);
+        let opened = Span { start: self.current, end: self.current + 2 };
+        self.advance();
+        self.advance();
+
+        let mut depth = 1;
+        while depth > 0 {
+            match self.peek() {
+                None => {
+                    return Some(Token {
+                        token_type: TokenType::Error,
+                        lexeme: Rc::from(format!(
+                            "The block comment opened on line {} was never closed.",
+                            line
+                        )),
+                        line,
+                        column,
+                        span: opened,
+                    });
+                }
+                Some('/') if self.peek_next() == Some('*') => {
+                    self.advance();
+                    depth += 1;
+                }
+                Some('*') if self.peek_next() == Some('/') => {
+                    self.advance();
+                    depth -= 1;
+                }
+                Some('\n') => {
+                    self.advance();
+                    self.line += 1;
+                    self.column = 0;
+                    continue;
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+        None
+    }
+
     fn scan_normal_token(&mut self) -> Token {
-        self.eat_whitespace();
+        if let Some(error) = self.eat_whitespace() {
+            return error;
+        }
         self.start = self.current;
         if self.reached_end() {
             return self.make_token(TokenType::Eof);
@@ -400,14 +637,25 @@ impl<'a> Scanner<'a> {
             ':' if self.match_char(':') => self.make_token(TokenType::ColonColon),
             ':' => self.make_token(TokenType::Colon),
 
+            '.' if self.match_char('.') => {
+                if self.match_char('.') {
+                    self.make_token(TokenType::DotDotDot)
+                } else {
+                    self.make_token(TokenType::DotDot)
+                }
+            }
             '.' => self.make_token(TokenType::Dot),
             ',' => self.make_token(TokenType::Comma),
 
             '+' => self.make_token(TokenType::Plus),
+            '-' if self.match_char('>') => self.make_token(TokenType::Arrow),
             '-' => self.make_token(TokenType::Minus),
             '/' => self.make_token(TokenType::Slash),
             '*' => self.make_token(TokenType::Star),
             '%' => self.make_token(TokenType::Percent),
+            '&' => self.make_token(TokenType::And),
+            '|' => self.make_token(TokenType::Or),
+            '^' => self.make_token(TokenType::Caret),
 
             '!' if self.match_char('=') => self.make_token(TokenType::BangEqual),
             '!' => self.make_token(TokenType::Bang),
@@ -416,9 +664,11 @@ impl<'a> Scanner<'a> {
             '=' => self.make_token(TokenType::Equal),
 
             '>' if self.match_char('=') => self.make_token(TokenType::GreaterEqual),
+            '>' if self.match_char('>') => self.make_token(TokenType::GreaterGreater),
             '>' => self.make_token(TokenType::Greater),
 
             '<' if self.match_char('=') => self.make_token(TokenType::LessEqual),
+            '<' if self.match_char('<') => self.make_token(TokenType::LessLess),
             '<' => self.make_token(TokenType::Less),
 
             '~' => self.make_token(TokenType::Tilde),
@@ -453,12 +703,24 @@ impl<'a> Scanner<'a> {
     }
 
     pub fn tokenize(mut self) -> Vec<Token> {
+        self.scan_all()
+    }
+
+    fn scan_all(&mut self) -> Vec<Token> {
         let mut tokens: Vec<Token> = Vec::new();
         loop {
             let previous_current = self.current;
             let previous_mode = self.mode;
 
             let token = self.scan_token();
+            if let Some(classes) = &self.classes {
+                // the whitespace and the comments before the token are already noted
+                let start = classes.last().map_or(0, |(span, _)| span.end);
+                if start < self.current {
+                    let class = self.class_of(&token, start);
+                    self.note_class(self.current, class);
+                }
+            }
             if token.token_type == TokenType::Eof {
                 tokens.push(token);
                 break;
@@ -485,25 +747,74 @@ fn is_alpha(c: char) -> bool {
     c.is_ascii_alphabetic() || c == '_'
 }
 
+/// The value of a number literal the scanner let through, its `_` left out.
+/// NaN for a lexeme the scanner wouldn't have let through.
+pub fn number_value(lexeme: &str) -> f64 {
+    let digits = lexeme.replace('_', "");
+    let radix = match digits.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0b" | "0B") => 2,
+        _ => return digits.parse().unwrap_or(f64::NAN),
+    };
+    // folded into a float, so a long literal grows past u64 instead of overflowing
+    digits[2..].chars().try_fold(0.0, |value, c| {
+        Some(value * radix as f64 + c.to_digit(radix)? as f64)
+    }).unwrap_or(f64::NAN)
+}
+
+/// The value of a whole number literal as an int, None for fractions and the ones past an i64
+pub fn int_value(lexeme: &str) -> Option<i64> {
+    let digits = lexeme.replace('_', "");
+    match digits.get(..2) {
+        Some("0x" | "0X") => i64::from_str_radix(&digits[2..], 16).ok(),
+        Some("0b" | "0B") => i64::from_str_radix(&digits[2..], 2).ok(),
+        _ => digits.parse().ok(),
+    }
+}
+
 fn is_number(c: char) -> bool {
     c.is_ascii_digit()
 }
 
+/// The words eira keeps for itself, matched by `identifier_type`
+pub const KEYWORDS: [&str; 44] = [
+    "attempt", "attune", "bind", "cast", "chant", "chant_raw", "circle", "claim", "conjure",
+    "cycle", "doom", "ego", "expect", "divert", "false", "fate", "flow", "forge", "from",
+    "glyph", "heed", "invoke", "judge", "manifests", "mark", "mend", "offer", "origin",
+    "refers", "release", "seal", "secret", "sign", "spell", "sever", "tether", "through", "to",
+    "tome", "true", "until", "while", "with", "vanish",
+];
+
 fn identifier_type(ident: &str) -> TokenType {
     match ident {
         "_" => TokenType::Underscore,
+        "attempt" => TokenType::Attempt,
         "attune" => TokenType::Attune,
         "bind" => TokenType::Bind,
         "cast" => TokenType::Cast,
         "chant" => TokenType::Chant,
+        "chant_raw" => TokenType::ChantRaw,
+        "circle" => TokenType::Circle,
+        "claim" => TokenType::Claim,
+        "conjure" => TokenType::Conjure,
+        "cycle" => TokenType::Cycle,
+        "doom" => TokenType::Doom,
         "ego" => TokenType::Ego,
+        "expect" => TokenType::Expect,
         "divert" => TokenType::Divert,
         "false" => TokenType::False,
         "fate" => TokenType::Fate,
         "flow" => TokenType::Flow,
         "forge" => TokenType::Forge,
+        "from" => TokenType::From,
+        "glyph" => TokenType::Glyph,
+        "heed" => TokenType::Heed,
+        "invoke" => TokenType::Invoke,
+        "judge" => TokenType::Judge,
         "manifests" => TokenType::Manifests,
         "mark" => TokenType::Mark,
+        "mend" => TokenType::Mend,
+        "offer" => TokenType::Offer,
         "origin" => TokenType::Origin,
         "refers" => TokenType::Refers,
         "release" => TokenType::Release,
@@ -513,8 +824,11 @@ fn identifier_type(ident: &str) -> TokenType {
         "spell" => TokenType::Spell,
         "sever" => TokenType::Sever,
         "tether" => TokenType::Tether,
+        "through" => TokenType::Through,
+        "to" => TokenType::To,
         "tome" => TokenType::Tome,
         "true" => TokenType::True,
+        "until" => TokenType::Until,
         "while" => TokenType::While,
         "with" => TokenType::With,
         "vanish" => TokenType::Vanish,
@@ -13,14 +13,15 @@ impl ScrollReader {
         }
     }
 
+    /// Reads the scroll without complaining if it can't be read
+    pub fn read_scroll_quietly(&self, path: &PathBuf) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
     pub fn read_scroll(&self, path: &PathBuf) -> Result<String, ScrollReadError> {
         let f = std::fs::read_to_string(path);
 
         if f.is_err() {
-            println!(
-                "The eira was cursed while reading the scroll '{}'.",
-                path.display()
-            );
             let msg: String;
             match &f.err().unwrap().kind() {
                 std::io::ErrorKind::NotFound => {
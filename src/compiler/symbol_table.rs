@@ -1,13 +1,21 @@
-use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt::Display,
+    rc::Rc,
+};
+
+use serde::{Serialize, Serializer, ser::SerializeStruct};
 
 use crate::{
-    compiler::weaves::Weave,
-    values::{sign::SignInfo, spell::SpellInfo},
+    compiler::{scanner::Token, weaves::Weave},
+    values::{glyph::GlyphInfo, sign::SignInfo, spell::SpellInfo, value::Value},
 };
 
+/// The symbols are shared with the woven AST, a symbol defined again is replaced rather than changed
 #[derive(Debug)]
 pub struct SymbolTable {
-    scopes: Vec<HashMap<String, Symbol>>,
+    scopes: Vec<HashMap<String, Rc<Symbol>>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -15,6 +23,9 @@ pub enum SymbolKind {
     Variable { mutable: bool },
     Spell(SpellInfo),
     Sign(SignInfo),
+    Glyph(GlyphInfo),
+    /// a compile-time constant, its value is inlined wherever it is read
+    Seal(Value),
 }
 
 impl SymbolKind {
@@ -31,6 +42,20 @@ impl SymbolKind {
             _ => None,
         }
     }
+
+    pub fn get_glyph_info(&self) -> Option<GlyphInfo> {
+        match self {
+            Self::Glyph(i) => Some(i.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn get_seal_value(&self) -> Option<Value> {
+        match self {
+            Self::Seal(v) => Some(v.clone()),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -41,11 +66,31 @@ pub struct Symbol {
     pub kind: RefCell<SymbolKind>,
     pub slot_idx: usize,
     pub parent: Option<Rc<Symbol>>,
+    pub reads: Rc<Cell<usize>>, // shared by the clones, so a read through any of them is counted
+}
+
+impl Symbol {
+    /// Counts a read of the symbol, the ones never read are warned about
+    pub fn read(&self) {
+        self.reads.set(self.reads.get() + 1);
+    }
+}
+
+// the AST dumps show where a name was resolved to, not everything the analyzer knows of it
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut symbol = serializer.serialize_struct("Symbol", 4)?;
+        symbol.serialize_field("name", &self.name)?;
+        symbol.serialize_field("weave", &self.weave)?;
+        symbol.serialize_field("depth", &self.depth)?;
+        symbol.serialize_field("slot", &self.slot_idx)?;
+        symbol.end()
+    }
 }
 
 impl SymbolTable {
     pub fn new() -> Self {
-        let mut scopes: Vec<HashMap<String, Symbol>> = vec![];
+        let mut scopes: Vec<HashMap<String, Rc<Symbol>>> = vec![];
         scopes.push(HashMap::new());
         SymbolTable { scopes: scopes }
     }
@@ -58,7 +103,7 @@ impl SymbolTable {
         self.scopes.pop();
     }
 
-    pub fn modify_symbol(&mut self, symbol: Symbol) {
+    pub fn modify_symbol(&mut self, symbol: Rc<Symbol>) {
         self.scopes[symbol.depth].insert(symbol.name.clone(), symbol);
     }
 
@@ -69,7 +114,7 @@ impl SymbolTable {
         mutable: bool,
         slot_idx: usize,
         parent: Option<Rc<Symbol>>,
-    ) -> Option<Symbol> {
+    ) -> Option<Rc<Symbol>> {
         self.add_symbol(
             name,
             weave,
@@ -86,7 +131,7 @@ impl SymbolTable {
         info: SpellInfo,
         slot_idx: usize,
         parent: Option<Rc<Symbol>>,
-    ) -> Option<Symbol> {
+    ) -> Option<Rc<Symbol>> {
         let kind = SymbolKind::Spell(info);
         self.add_symbol(name, weave, kind, parent, slot_idx)
     }
@@ -98,11 +143,32 @@ impl SymbolTable {
         info: SignInfo,
         parent: Option<Rc<Symbol>>,
         slot_idx: usize,
-    ) -> Option<Symbol> {
+    ) -> Option<Rc<Symbol>> {
         let kind = SymbolKind::Sign(info);
         self.add_symbol(name, weave, kind, parent, slot_idx)
     }
 
+    pub fn define_glyph(
+        &mut self,
+        name: String,
+        info: GlyphInfo,
+        slot_idx: usize,
+    ) -> Option<Rc<Symbol>> {
+        let kind = SymbolKind::Glyph(info);
+        self.add_symbol(name.clone(), Weave::Glyph(name), kind, None, slot_idx)
+    }
+
+    pub fn define_seal(
+        &mut self,
+        name: String,
+        weave: Weave,
+        value: Value,
+        slot_idx: usize,
+    ) -> Option<Rc<Symbol>> {
+        let kind = SymbolKind::Seal(value);
+        self.add_symbol(name, weave, kind, None, slot_idx)
+    }
+
     pub fn add_symbol(
         &mut self,
         name: String,
@@ -110,18 +176,23 @@ impl SymbolTable {
         kind: SymbolKind,
         parent: Option<Rc<Symbol>>,
         slot_idx: usize,
-    ) -> Option<Symbol> {
+    ) -> Option<Rc<Symbol>> {
         let depth = self.scopes.len() - 1;
 
         if let Some(scope) = self.scopes.last_mut() {
-            let symbol = Symbol {
+            // a symbol defined again (like a spell, once its body is known) keeps its reads
+            let reads = scope
+                .get(&name)
+                .map_or_else(|| Rc::new(Cell::new(0)), |s| s.reads.clone());
+            let symbol = Rc::new(Symbol {
                 name: name.clone(),
                 weave: weave,
                 depth: depth,
                 parent: parent,
                 kind: RefCell::new(kind),
                 slot_idx: slot_idx,
-            };
+                reads,
+            });
             scope.insert(name, symbol.clone());
             return Some(symbol);
         } else {
@@ -131,7 +202,7 @@ impl SymbolTable {
         }
     }
 
-    pub fn resolve(&self, name: &String) -> Option<&Symbol> {
+    pub fn resolve(&self, name: &str) -> Option<&Rc<Symbol>> {
         for scope in self.scopes.iter().rev() {
             if let Some(var) = scope.get(name) {
                 return Some(var);
@@ -140,7 +211,12 @@ impl SymbolTable {
         None
     }
 
-    pub fn resolve_in_current_scope(&self, name: &String) -> Option<&Symbol> {
+    /// Every symbol reachable from the current scope
+    pub fn visible_symbols(&self) -> impl Iterator<Item = &Rc<Symbol>> {
+        self.scopes.iter().flat_map(|scope| scope.values())
+    }
+
+    pub fn resolve_in_current_scope(&self, name: &str) -> Option<&Rc<Symbol>> {
         self.scopes.last()?.get(name)
     }
 
@@ -152,13 +228,53 @@ impl SymbolTable {
         self.scopes.len() - 1
     }
 
-    pub fn get_exports(&self) -> &HashMap<String, Symbol> {
+    pub fn get_exports(&self) -> &HashMap<String, Rc<Symbol>> {
         self.scopes
             .first()
             .unwrap()
     }
 }
 
+/// Where the names of a scroll are declared and read, kept by the analyzer for the editors
+#[derive(Debug, Clone, Default)]
+pub struct SymbolIndex {
+    pub declarations: Vec<IndexedSymbol>,
+    pub references: Vec<(Token, usize)>, // a name read by the scroll, with its declaration
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexedSymbol {
+    pub name: Token,
+    pub symbol: Symbol,
+    pub lines: (usize, usize), // (first, last) source line of its scope
+}
+
+impl SymbolIndex {
+    /// The declaration of the name written at the 1 based (line, column)
+    pub fn declaration_at(&self, line: usize, column: usize) -> Option<&IndexedSymbol> {
+        let covers = |token: &Token| {
+            // the scanner leaves the columns at the end of the tokens
+            let start = (token.column + 1).saturating_sub(token.lexeme.chars().count());
+            token.line == line && (start..=token.column).contains(&column)
+        };
+
+        if let Some(declaration) = self.declarations.iter().find(|d| covers(&d.name)) {
+            return Some(declaration);
+        }
+        self.references
+            .iter()
+            .find(|(token, _)| covers(token))
+            .map(|(_, i)| &self.declarations[*i])
+    }
+
+    /// The symbols in scope on [line]
+    pub fn visible_on(&self, line: usize) -> impl Iterator<Item = &IndexedSymbol> {
+        self.declarations
+            .iter()
+            .filter(move |d| (d.lines.0..=d.lines.1).contains(&line))
+    }
+}
+
 impl Display for SymbolTable {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut result = String::new();
@@ -1,5 +1,7 @@
+use serde::Serialize;
+
 #[repr(usize)]
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 pub enum TokenType {
     // Keywords
     Tether, // imports
@@ -16,18 +18,31 @@ pub enum TokenType {
     Fate,
     Divert, // If/Else like divert if its not a fate
     While,
+    Cycle,  // ranged loop
+    Circle, // do-while, as in `circle { ... } until cond;`
+    Judge,  // switch/match
     True,
     False,
     Release, // return
+    Offer,   // yield, from a flowing spell
+    Conjure, // spawn, casts a spell alongside the scroll
+    Claim,   // join, waits for a conjured spell to release
     // Maybe, // yeah maybe
     Refers, // inheritance
     Origin, // super
     Ego,  // this/self (ego as in sense of self)
     Chant,  // print
+    ChantRaw, // print, without ending the line
+    Heed,   // read a line of input
     Alias,  // type aliasing
     Glyph,  // enum
     Sever,  // break
     Flow,   // continue
+    Attempt, // try
+    Mend,   // catch
+    Invoke,
+    Doom,   // throw, as in `invoke doom`
+    Expect, // assert
     Weave,  // types (more like set of behaviours)d
     Deck,   // array type
     Vanish, // sets a Maybe<T> to empty
@@ -35,6 +50,10 @@ pub enum TokenType {
 
     // Connector words
     With, // used in casting
+    From,
+    To, // used in cycle ranges
+    Through, // flow x through xs
+    Until,   // ends a circle
 
     // Symbols
     SemiColon, // ;
@@ -46,16 +65,21 @@ pub enum TokenType {
     EqualEqual, // =
     Greater,
     GreaterEqual, // >
+    GreaterGreater, // >> shifting bits right
     Less,
     LessEqual, // <
+    LessLess, // << shifting bits left
     Bang,
     BangEqual, // !
     Minus,
+    Arrow, // -> of a judge arm
     Plus,
     Star,
     Slash,
     Percent, // arithematic
     Dot,     // .
+    DotDot,  // .. of a slice
+    DotDotDot, // ... of a gathered reagent
     Comma,   // ,
     ParenLeft,
     ParenRight,
@@ -67,6 +91,7 @@ pub enum TokenType {
     AndAnd, // &
     Or,
     OrOr,  // |
+    Caret, // ^
     Tilde, // ~
     Underscore, // _
 
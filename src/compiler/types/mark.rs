@@ -1,27 +1,30 @@
+use serde::Serialize;
+
 use crate::{
     Token,
     compiler::{Expr, WovenExpr, parser::types::ParsedWeave, weaves::Weave},
 };
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Mark {
     pub name: Token,
     pub parsed_weave: ParsedWeave,
+    pub default: Option<Expr>, // taken when the sign is drawn without the mark
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct WovenMark {
     pub name: Token,
     pub weave: Weave,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct EtchedMark {
     pub name: Token,
     pub expr: Expr,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct WovenEtchedMark {
     pub name: Token,
     pub expr: WovenExpr,
@@ -1,19 +1,34 @@
-use crate::compiler::{parser::types::ParsedWeave, scanner::Token, weaves::Weave};
+use serde::Serialize;
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::{
+    compiler::{Expr, parser::types::ParsedWeave, scanner::Token, weaves::Weave},
+    values::Value,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Reagent {
     pub name: Token,
     pub weave: ParsedWeave,
+    pub default: Option<Expr>,
+    pub gathered: bool, // `...W`, takes the trailing reagents of a cast as a deck
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct WovenReagent {
-    // pub name: Token,
+    pub name: Option<String>, // the reagents of the native spells go unnamed
     pub weave: Weave,
+    // filled in at the call site when the reagent is left out
+    pub default: Option<Value>,
+    pub gathered: bool, // held as a `Deck<W>`, the casts hand it their trailing reagents
 }
 
 impl WovenReagent {
     pub fn new(weave: Weave) -> Self {
-        Self { weave }
+        Self {
+            name: None,
+            weave,
+            default: None,
+            gathered: false,
+        }
     }
 }
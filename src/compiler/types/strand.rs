@@ -32,5 +32,14 @@ pub const CALLABLE_STRAND: u64 = 1 << 10;
 /// Presence of this strand can cause the value to be empty
 pub const MAYBE_STRAND: u64 = 1 << 11;
 
+/// Able to be negated with an unary '-'
+pub const NEGATABLE_STRAND: u64 = 1 << 12;
+
+/// Able to be worked bit by bit, &, |, ^, << and >>
+pub const BITWISE_STRAND: u64 = 1 << 13;
+
+/// Able to be turned into a text, as it is joined to one with a '+'
+pub const CHANTABLE_STRAND: u64 = 1 << 14;
+
 // Emptiness
 pub const NO_STRAND: u64 = 0;
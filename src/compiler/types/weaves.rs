@@ -1,6 +1,8 @@
+use serde::{Serialize, Serializer, ser::SerializeStruct};
+
 use crate::compiler::{
     strand::{
-        ADDITIVE_STRAND, CALLABLE_STRAND, CONCATINABLE_STRAND, CONDITIONAL_STRAND, DIVISIVE_STRAND, EQUATABLE_STRAND, INDEXIVE_STRAND, ITERABLE_STRAND, MAYBE_STRAND, MULTIPLICATIVE_STRAND, NO_STRAND, ORDINAL_STRAND, SUBTRACTIVE_STRAND
+        ADDITIVE_STRAND, BITWISE_STRAND, CALLABLE_STRAND, CHANTABLE_STRAND, CONCATINABLE_STRAND, CONDITIONAL_STRAND, DIVISIVE_STRAND, EQUATABLE_STRAND, INDEXIVE_STRAND, ITERABLE_STRAND, MAYBE_STRAND, MULTIPLICATIVE_STRAND, NEGATABLE_STRAND, NO_STRAND, ORDINAL_STRAND, SUBTRACTIVE_STRAND
     },
     tapestry::Tapestry,
 };
@@ -8,20 +10,27 @@ use crate::compiler::{
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum Weave {
     Num,
+    Int, // whole numbers, kept exact where a Num would drift
     Text,
     Truth,
     Spell {
+        // the weaves of the reagents, None when only the release is known, like in a written `Spell<W>`
+        reagents: Option<Vec<Weave>>,
         release: Box<Weave>,
     },
     Sign(String /* name */),
+    Glyph(String /* name */),
     Deck(Box<Weave>, Option<usize>),
     Maybe(Box<Weave>),
+    Stream(Box<Weave>), // the values offered by a flowing spell, drawn as they are flowed through
+    Conjuring(Box<Weave>), // a spell running alongside the scroll, claimed for the weave it releases
+    Error, // what a mend is given when its attempt breaks down
     Empty,
 }
 
 impl Weave {
     pub fn can_sub_weave(&self) -> bool {
-        matches!(self, Weave::Spell { .. } | Weave::Deck(_, _) | Weave::Maybe(_))
+        matches!(self, Weave::Spell { .. } | Weave::Deck(_, _) | Weave::Maybe(_) | Weave::Stream(_) | Weave::Conjuring(_))
     }
 
     pub fn get_tapestry(&self) -> Tapestry {
@@ -29,17 +38,45 @@ impl Weave {
             Weave::Num => Tapestry::new(
                 ADDITIVE_STRAND
                     | SUBTRACTIVE_STRAND
+                    | NEGATABLE_STRAND
+                    | ORDINAL_STRAND
+                    | MULTIPLICATIVE_STRAND
+                    | DIVISIVE_STRAND
+                    | EQUATABLE_STRAND
+                    | CHANTABLE_STRAND,
+            ),
+            Weave::Int => Tapestry::new(
+                ADDITIVE_STRAND
+                    | SUBTRACTIVE_STRAND
+                    | NEGATABLE_STRAND
                     | ORDINAL_STRAND
                     | MULTIPLICATIVE_STRAND
                     | DIVISIVE_STRAND
-                    | EQUATABLE_STRAND,
+                    | EQUATABLE_STRAND
+                    | BITWISE_STRAND
+                    | CHANTABLE_STRAND,
             ),
-            Weave::Text => Tapestry::new(CONCATINABLE_STRAND | INDEXIVE_STRAND | EQUATABLE_STRAND),
-            Weave::Truth => Tapestry::new(CONDITIONAL_STRAND | EQUATABLE_STRAND),
+            Weave::Text => Tapestry::new(
+                CONCATINABLE_STRAND
+                    | INDEXIVE_STRAND
+                    | ITERABLE_STRAND
+                    | EQUATABLE_STRAND
+                    | ORDINAL_STRAND
+                    | CHANTABLE_STRAND,
+            ),
+            Weave::Truth => Tapestry::new(CONDITIONAL_STRAND | EQUATABLE_STRAND | CHANTABLE_STRAND),
             Weave::Empty => Tapestry::new(NO_STRAND),
+            Weave::Error => Tapestry::new(CHANTABLE_STRAND), // chanted as its message
             Weave::Spell { .. } => Tapestry::new(CALLABLE_STRAND),
             Weave::Sign(_) => Tapestry::new(NO_STRAND),
+            Weave::Glyph(_) => Tapestry::new(EQUATABLE_STRAND | CHANTABLE_STRAND),
             Weave::Deck(_, _) => Tapestry::new(INDEXIVE_STRAND | ITERABLE_STRAND),
+            Weave::Stream(_) => Tapestry::new(ITERABLE_STRAND | EQUATABLE_STRAND),
+            Weave::Conjuring(_) => Tapestry::new(EQUATABLE_STRAND),
+            // a Maybe<Truth> is a condition too, its emptiness is falsey
+            Weave::Maybe(inner) if **inner == Weave::Truth => {
+                Tapestry::new(MAYBE_STRAND | EQUATABLE_STRAND | CONDITIONAL_STRAND)
+            }
             Weave::Maybe(_) => Tapestry::new(MAYBE_STRAND | EQUATABLE_STRAND)
         }
     }
@@ -47,11 +84,14 @@ impl Weave {
     pub fn get_name(&self) -> String {
         match self {
             Weave::Num => "Num".to_string(),
+            Weave::Int => "Int".to_string(),
             Weave::Text => "Text".to_string(),
             Weave::Truth => "Truth".to_string(),
             Weave::Empty => "Empty".to_string(),
-            Weave::Spell { .. } => "Spell".to_string(),
+            Weave::Error => "Error".to_string(),
+            Weave::Spell { release, .. } => format!("Spell<{}>", release.get_name()),
             Weave::Sign(name) => format!("Sign<{}>", name),
+            Weave::Glyph(name) => format!("Glyph<{}>", name),
             Weave::Deck(inner, length) => {
                 let str = if length.is_some() {
                     &format!(", {}", length.unwrap())
@@ -61,6 +101,8 @@ impl Weave {
                 format!("Deck<{}{}>", inner.get_name(), str)
             },
             Weave::Maybe(base) => format!("Maybe<{}>", base.get_name()),
+            Weave::Stream(base) => format!("Stream<{}>", base.get_name()),
+            Weave::Conjuring(base) => format!("Conjuring<{}>", base.get_name()),
         }
     }
 }
@@ -92,6 +134,16 @@ impl Weave {
 //     Weave::iter
 // }
 
+// the AST dumps show a weave by its name, along with the strands it is woven from
+impl Serialize for Weave {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut weave = serializer.serialize_struct("Weave", 2)?;
+        weave.serialize_field("name", &self.get_name())?;
+        weave.serialize_field("tapestry", &self.get_tapestry().0)?;
+        weave.end()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct WeaverError(pub String);
 
@@ -102,10 +154,8 @@ impl Weaver {
     pub fn weave_spell(base: Weave, inner: Weave) -> WeaverResult<Weave> {
         match base {
             Weave::Spell { .. } => {
-                // let mut new_reagents = reagents.clone();
-                // new_reagents.push(inner);
                 Ok(Weave::Spell {
-                    // reagents: new_reagents,
+                    reagents: None,
                     release: Box::new(inner),
                 })
             }
@@ -126,6 +176,26 @@ impl Weaver {
         }
     }
 
+    pub fn weave_stream(base: Weave, inner: Weave) -> WeaverResult<Weave> {
+        match base {
+            Weave::Stream(_) => Ok(Weave::Stream(Box::new(inner))),
+            _ => Err(WeaverError(format!(
+                "The weave '{}' cannot contain any sub weaves!",
+                base.get_name()
+            ))),
+        }
+    }
+
+    pub fn weave_conjuring(base: Weave, inner: Weave) -> WeaverResult<Weave> {
+        match base {
+            Weave::Conjuring(_) => Ok(Weave::Conjuring(Box::new(inner))),
+            _ => Err(WeaverError(format!(
+                "The weave '{}' cannot contain any sub weaves!",
+                base.get_name()
+            ))),
+        }
+    }
+
     pub fn weave_maybe(base: Weave, inner: Weave) -> WeaverResult<Weave> {
         match base {
             Weave::Maybe(_) => Ok(Weave::Maybe(Box::new(inner))),
@@ -12,22 +12,28 @@ use crate::{
     compiler::{
         Expr, Stmt, WovenExpr, WovenStmt,
         compiler::CompileState,
-        mark::{WovenEtchedMark, WovenMark},
+        diagnostics::{
+            CompilationPhase, DEFAULT_ERROR_LIMIT, Diagnostic, EiraError, SourceLocation,
+            closest_name,
+        },
+        mark::{EtchedMark, WovenEtchedMark, WovenMark},
         parser::types::ParsedWeave,
-        reagents::WovenReagent,
-        scanner::Token,
+        reagents::{Reagent, WovenReagent},
+        scanner::{Span, Token, int_value},
         scroll_reader::ScrollReader,
         strand::{
-            ADDITIVE_STRAND, CALLABLE_STRAND, CONCATINABLE_STRAND, CONDITIONAL_STRAND,
-            DIVISIVE_STRAND, EQUATABLE_STRAND, INDEXIVE_STRAND, ITERABLE_STRAND, MAYBE_STRAND,
-            MULTIPLICATIVE_STRAND, NO_STRAND, ORDINAL_STRAND, SUBTRACTIVE_STRAND,
+            ADDITIVE_STRAND, BITWISE_STRAND, CALLABLE_STRAND, CHANTABLE_STRAND, CONCATINABLE_STRAND,
+            CONDITIONAL_STRAND, DIVISIVE_STRAND, EQUATABLE_STRAND, INDEXIVE_STRAND,
+            ITERABLE_STRAND, MAYBE_STRAND, MULTIPLICATIVE_STRAND, NEGATABLE_STRAND, NO_STRAND,
+            ORDINAL_STRAND, SUBTRACTIVE_STRAND,
         },
-        symbol_table::{Symbol, SymbolKind, SymbolTable},
+        symbol_table::{IndexedSymbol, Symbol, SymbolIndex, SymbolKind, SymbolTable},
         token_type::TokenType,
         weaves::{Weave, Weaver},
     },
     project::config::Project,
     values::{
+        glyph::GlyphInfo,
         native_spell::NativeSpell,
         sign::{SignInfo, SignSchema},
         spell::{SpellInfo, UpValue},
@@ -38,6 +44,8 @@ use crate::{
 pub struct WeaveError {
     pub msg: String,
     pub token: Token,
+    pub span: Option<Span>, // the whole of what went wrong, the token is a part of it
+    pub mismatch: Option<Box<WeaveMismatch>>, // boxed, the error is carried in every result of the weaving
 }
 
 impl WeaveError {
@@ -45,12 +53,50 @@ impl WeaveError {
         WeaveError {
             msg: msg.to_owned(),
             token: token,
+            span: None,
+            mismatch: None,
+        }
+    }
+}
+
+/// The weave wanted somewhere, and the one given in its place
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeaveMismatch {
+    pub expected: String, // a weave, or the strands it should have
+    pub found: String,
+}
+
+impl WeaveMismatch {
+    pub fn of(expected: &Weave, found: &Weave) -> Self {
+        WeaveMismatch {
+            expected: format!("'{}'", expected.get_name()),
+            found: format!("'{}'", found.get_name()),
+        }
+    }
+
+    /// A weave without the [strand] given where it is needed
+    pub fn lacking(strand: &str, found: &Weave) -> Self {
+        WeaveMismatch {
+            expected: format!("a weave with the '{}' strand", strand),
+            found: format!("'{}'", found.get_name()),
         }
     }
 }
 
+impl std::fmt::Display for WeaveMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
 pub type WeaveResult<T> = Result<T, WeaveError>;
 
+// the weaves woven into eira itself, as matched by `get_weave_from_name`
+pub const BUILTIN_WEAVES: [&str; 12] = [
+    "Num", "Int", "Text", "Truth", "Empty", "Error", "Sign", "Spell", "Deck", "Maybe", "Stream",
+    "Conjuring",
+];
+
 #[derive(PartialEq, Clone)]
 enum Realm {
     Genesis, // script level scope
@@ -62,6 +108,10 @@ pub struct WeaveAnalyzerContext {
     pub project: Option<Project>,
     pub tethered_scrolls: HashMap<String, CompileState>,
     pub import_mode: bool,
+    pub native_spells: HashMap<String, SpellInfo>, // signatures of spells provided by the host
+    pub host_globals: Vec<(String, Weave)>, // marks given their values by the host
+    pub error_limit: usize,
+    pub index: Option<SymbolIndex>, // the names declared and read by the scroll, kept when asked for
 }
 
 impl WeaveAnalyzerContext {
@@ -71,21 +121,83 @@ impl WeaveAnalyzerContext {
             project,
             import_mode,
             tethered_scrolls: HashMap::new(),
+            native_spells: HashMap::new(),
+            host_globals: vec![],
+            error_limit: DEFAULT_ERROR_LIMIT,
+            index: None,
         }
     }
+
+    /// Declares the signature of a spell that the host registers to the VM
+    pub fn declare_native(&mut self, name: &str, reagents: Vec<Weave>, release: Weave) {
+        let info = SpellInfo {
+            name: name.to_string(),
+            reagents: reagents
+                .into_iter()
+                .map(WovenReagent::new)
+                .collect(),
+            release_weave: release,
+            upvalues: vec![],
+        };
+        self.native_spells.insert(name.to_string(), info);
+    }
+
+    /// Declares a mark of the scroll that the host gives a value to with `EiraVM::set_global`
+    pub fn declare_global(&mut self, name: &str, weave: Weave) {
+        self.host_globals.push((name.to_string(), weave));
+    }
 }
 
 pub struct WeaveAnalyzer<'a> {
     context: &'a mut WeaveAnalyzerContext,
 
     symbol_table: SymbolTable,
-    loop_depth: usize,
+    loops: Vec<Option<String>>, // the names of the loops around the statement, innermost last
     current_realm: Realm,     // track the realm (scope type) the analyzer is in!
     spell_stack: Vec<String>, // track the current spell name
 
     current_upvalues: Vec<UpValue>, // upvalue for currently resolving spell
     spell_base_depth: usize,        // depth where current spell body starts (parameters live here)
-    spell_slot_counter: usize,      // continuous slot counter within current spell
+    enclosing_spells: Vec<(usize, Vec<UpValue>)>, // (base depth, upvalues) of the spells around the current one
+    slot_counter: usize,            // the next free slot of the running frame (the origin's or a spell's)
+    declarations: Vec<Declaration>, // the marks and spells of the open scopes, to warn about the unused ones
+    unassigned: Vec<(String, usize)>, // (name, depth) of the marks not yet given a value on every path
+    hoisted: HashSet<(String, usize)>, // (name, depth) of the spells known by their signature, before their declaration is met
+    furthest_line: usize, // the furthest line a name was met on, the scopes in the index end there
+
+    diagnostics: Vec<Diagnostic>,
+    warnings: Vec<Diagnostic>, // problems that don't stop the scroll from being compiled
+}
+
+/// A mark or a spell declared by the scroll, checked for its reads once its scope ends
+struct Declaration {
+    name: Token,
+    symbol: Symbol,
+}
+
+/// The state of the analyzer before a statement, to recover from its errors
+struct Checkpoint {
+    depth: usize,
+    realm: Realm,
+    spell_stack_len: usize,
+    loops: Vec<Option<String>>,
+    current_upvalues: Vec<UpValue>,
+    spell_base_depth: usize,
+    enclosing_spells: Vec<(usize, Vec<UpValue>)>,
+    slot_counter: usize,
+    declarations: usize,
+    unassigned: Vec<(String, usize)>,
+}
+
+/// The cycle statement as parsed, its bounds and body still to be woven
+struct Cycle {
+    token: Token,
+    label: Option<Token>,
+    variable: Token,
+    start: Expr,
+    end: Expr,
+    body: Stmt,
+    span: Span,
 }
 
 impl<'a> WeaveAnalyzer<'a> {
@@ -94,12 +206,19 @@ impl<'a> WeaveAnalyzer<'a> {
         WeaveAnalyzer {
             context,
             symbol_table: st,
-            loop_depth: 0,
+            loops: vec![],
             current_realm: Realm::Genesis,
             spell_stack: vec![],
             current_upvalues: vec![],
             spell_base_depth: 0,
-            spell_slot_counter: 0,
+            enclosing_spells: vec![],
+            slot_counter: 0,
+            declarations: vec![],
+            unassigned: vec![],
+            hoisted: HashSet::new(),
+            furthest_line: 0,
+            diagnostics: vec![],
+            warnings: vec![],
         }
     }
 
@@ -111,20 +230,244 @@ impl<'a> WeaveAnalyzer<'a> {
         Err(WeaveError::new(msg, token))
     }
 
-    pub fn analyze(&mut self, ast: Vec<Stmt>) -> WeaveResult<Vec<WovenStmt>> {
-        self.analyze_statements(ast)
+    /// An error at the token, underlining the whole expression or statement [span] it is in
+    fn error_over<T>(&self, msg: &str, token: Token, span: Span) -> Result<T, WeaveError> {
+        Err(WeaveError {
+            span: Some(span),
+            ..WeaveError::new(msg, token)
+        })
+    }
+
+    /// An error where a weave was given in place of another, underlining the [span] of what was given
+    fn mismatch<T>(&self, msg: &str, token: Token, span: Span, mismatch: WeaveMismatch) -> WeaveResult<T> {
+        Err(WeaveError {
+            span: Some(span),
+            mismatch: Some(Box::new(mismatch)),
+            ..WeaveError::new(msg, token)
+        })
+    }
+
+    /// Points at the name the mage probably meant, when one is spelt closely enough
+    fn did_you_mean<'n>(&self, name: &str, candidates: impl IntoIterator<Item = &'n str>) -> String {
+        closest_name(name, candidates)
+            .map_or(String::new(), |close| format!(" Did you mean '{}'?", close))
+    }
+
+    fn warn(&mut self, msg: &str, token: &Token) {
+        let location = SourceLocation::of_token(&self.context.source_path, token);
+        self.warnings
+            .push(Diagnostic::warning(CompilationPhase::Weave, msg, location));
+    }
+
+    /// The warnings found by the analysis, in the order they were found
+    pub fn take_warnings(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    pub fn analyze(&mut self, ast: Vec<Stmt>) -> Result<Vec<WovenStmt>, EiraError> {
+        // the marks of the host are there before the scroll, like a prelude to it
+        for (name, weave) in &self.context.host_globals {
+            self.symbol_table.define_variable(name.clone(), weave.clone(), true, 0, None);
+        }
+        let woven = self.analyze_statements(ast);
+
+        // the declarations of a tethered scroll are there for the scrolls tethering it
+        if !self.context.import_mode {
+            self.warn_unused(0);
+        }
+
+        if !self.diagnostics.is_empty() {
+            return Err(EiraError::Diagnostics(std::mem::take(&mut self.diagnostics)));
+        }
+
+        woven.map_err(|e| self.to_diagnostic(e).into())
+    }
+
+    /// Defines the spells of the statements by their signatures ahead of them, so they can be cast
+    /// before their declaration and cast each other. A signature which can't be woven yet (like
+    /// one holding a sign declared later) is left to the declaration, which reports it
+    fn hoist_spells(&mut self, ast: &[Stmt]) {
+        for stmt in ast {
+            let Stmt::Spell {
+                name,
+                reagents,
+                return_weave,
+                attuned_to: None,
+                ..
+            } = stmt
+            else {
+                continue;
+            };
+            if self.symbol_table.resolve_in_current_scope(&name.lexeme).is_some() {
+                continue;
+            }
+
+            let warnings = self.warnings.len();
+            let signature = self.hoisted_signature(name, reagents, return_weave);
+            self.warnings.truncate(warnings); // the declaration warns about them again
+
+            if let Ok((reagents, release)) = signature {
+                let weave = Weave::Spell {
+                    reagents: Some(reagents.iter().map(|r| r.weave.clone()).collect()),
+                    release: Box::new(release.clone()),
+                };
+                let info = SpellInfo {
+                    name: name.lexeme.to_string(),
+                    reagents,
+                    release_weave: release,
+                    upvalues: vec![],
+                };
+                let slot = self.next_local_slot();
+                self.symbol_table
+                    .define_spell(name.lexeme.to_string(), weave, info, slot, None);
+                let depth = self.symbol_table.get_depth();
+                self.hoisted.insert((name.lexeme.to_string(), depth));
+            }
+        }
+    }
+
+    /// The reagents and the release weave of a spell, woven without its body
+    fn hoisted_signature(
+        &mut self,
+        name: &Token,
+        reagents: &[Reagent],
+        return_weave: &Option<ParsedWeave>,
+    ) -> WeaveResult<(Vec<WovenReagent>, Weave)> {
+        let release = match return_weave {
+            Some(rw) => self.analyze_parsed_weave(rw.clone())?,
+            None => Weave::Empty,
+        };
+        let mut woven = vec![];
+        for r in reagents {
+            let weave = self.reagent_weave(r)?;
+            let default = match &r.default {
+                Some(expr) => Some(self.analyze_reagent_default(expr.clone(), &weave, &r.name)?),
+                None if !r.gathered && woven.iter().any(|w: &WovenReagent| w.default.is_some()) => {
+                    return self.error("A reagent is missing its default", name.clone());
+                }
+                None => None,
+            };
+            woven.push(WovenReagent {
+                name: Some(r.name.lexeme.to_string()),
+                weave,
+                default,
+                gathered: r.gathered,
+            });
+        }
+        Ok((woven, release))
+    }
+
+    /// The slot of a spell being declared. A hoisted spell takes the one given to its signature
+    fn spell_slot(&mut self, name: &Token, attuned: bool) -> WeaveResult<usize> {
+        let hoisted = (name.lexeme.to_string(), self.symbol_table.get_depth());
+        if !attuned
+            && self.hoisted.remove(&hoisted)
+            && let Some(symbol) = self.symbol_table.resolve_in_current_scope(&name.lexeme)
+        {
+            return Ok(symbol.slot_idx);
+        }
+
+        // allow spell shadowing from outer scopes, an attuned spell can share the name of a hoisted one
+        let existing = self.symbol_table.resolve_in_current_scope(&name.lexeme);
+        if existing.is_some() && !self.hoisted.contains(&hoisted) {
+            return self.error(
+                &format!(
+                    "The spell '{}' already exists in the current scope!",
+                    name.lexeme
+                ),
+                name.clone(),
+            );
+        }
+        Ok(self.next_local_slot())
+    }
+
+    fn to_diagnostic(&self, error: WeaveError) -> Diagnostic {
+        let diagnostic = Diagnostic::error(
+            CompilationPhase::Weave,
+            &error.msg,
+            match error.span {
+                Some(span) => SourceLocation::of_span(&self.context.source_path, &error.token, span),
+                None => SourceLocation::of_token(&self.context.source_path, &error.token),
+            },
+        );
+        match error.mismatch {
+            Some(mismatch) => diagnostic.with_label(&mismatch.to_string()),
+            None => diagnostic,
+        }
     }
 
+    /// Analyzes the statements one by one. A statement with an error is reported and
+    /// skipped, so that the rest of the scroll still gets checked
     fn analyze_statements(&mut self, stmts: Vec<Stmt>) -> WeaveResult<Vec<WovenStmt>> {
+        self.hoist_spells(&stmts);
         let mut w_stmts: Vec<WovenStmt> = Vec::new();
+        let mut ended_by: Option<Token> = None; // the statement leaving the block, if one did
+        let mut unreachable = false;
         for stmt in stmts {
-            let woven = self.analyze_statement(stmt)?;
-            w_stmts.push(woven);
+            if self.diagnostics.len() >= self.context.error_limit {
+                break;
+            }
+
+            if let Some(token) = &ended_by
+                && !unreachable
+            {
+                self.warn("The statements after this one are never reached.", token);
+                unreachable = true;
+            }
+            if let Stmt::Release { token, .. }
+            | Stmt::Sever { token, .. }
+            | Stmt::Flow { token, .. }
+            | Stmt::Doom { token, .. } = &stmt
+            {
+                ended_by.get_or_insert_with(|| token.clone());
+            }
+
+            let checkpoint = self.checkpoint();
+            match self.analyze_statement(stmt) {
+                Ok(woven) => w_stmts.push(woven),
+                Err(e) => {
+                    self.restore(checkpoint);
+                    let diagnostic = self.to_diagnostic(e);
+                    self.diagnostics.push(diagnostic);
+                }
+            }
         }
 
         Ok(w_stmts)
     }
 
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            depth: self.symbol_table.get_depth(),
+            realm: self.current_realm.clone(),
+            spell_stack_len: self.spell_stack.len(),
+            loops: self.loops.clone(),
+            current_upvalues: self.current_upvalues.clone(),
+            spell_base_depth: self.spell_base_depth,
+            enclosing_spells: self.enclosing_spells.clone(),
+            slot_counter: self.slot_counter,
+            declarations: self.declarations.len(),
+            unassigned: self.unassigned.clone(),
+        }
+    }
+
+    /// Rolls back the scopes and spell state left open by a failed statement
+    fn restore(&mut self, checkpoint: Checkpoint) {
+        while self.symbol_table.get_depth() > checkpoint.depth {
+            self.end_index_scope();
+            self.symbol_table.end_scope();
+        }
+        self.current_realm = checkpoint.realm;
+        self.spell_stack.truncate(checkpoint.spell_stack_len);
+        self.loops = checkpoint.loops;
+        self.current_upvalues = checkpoint.current_upvalues;
+        self.spell_base_depth = checkpoint.spell_base_depth;
+        self.enclosing_spells = checkpoint.enclosing_spells;
+        self.slot_counter = checkpoint.slot_counter;
+        self.declarations.truncate(checkpoint.declarations);
+        self.unassigned = checkpoint.unassigned;
+    }
+
     fn analyze_statement(&mut self, stmt: Stmt) -> WeaveResult<WovenStmt> {
         let is_tether_top_level = self.context.import_mode
             && self.symbol_table.get_depth() == 0
@@ -135,6 +478,9 @@ impl<'a> WeaveAnalyzer<'a> {
         match woven {
             WovenStmt::Attune { .. }
             | WovenStmt::Sign { .. }
+            | WovenStmt::Glyph { .. }
+            | WovenStmt::Seal { .. }
+            | WovenStmt::Tome { .. }
             | WovenStmt::Spell { .. }
             | WovenStmt::VarDeclaration { .. } => {
                 // this is fine.... (should be.. atleast!)
@@ -154,59 +500,42 @@ impl<'a> WeaveAnalyzer<'a> {
 
     fn analyze_statement_inner(&mut self, stmt: Stmt) -> WeaveResult<WovenStmt> {
         match stmt {
-            Stmt::Block { statements } => {
-                self.symbol_table.new_scope();
+            Stmt::Block { statements, span } => {
+                let slots = self.begin_scope();
                 let w_block = self.analyze_statements(statements)?;
-                self.symbol_table.end_scope();
+                self.end_scope(slots);
                 return Ok(WovenStmt::Block {
                     statements: w_block,
+                    span,
                 });
             }
-            Stmt::Chant { expression } => {
-                let w_expr = self.analyze_expression(expression, None)?;
-                Ok(WovenStmt::Chant { expression: w_expr })
-            }
-            Stmt::ExprStmt { expr } => {
-                let w_expr = self.analyze_expression(expr, None)?;
-                Ok(WovenStmt::ExprStmt { expr: w_expr })
-            }
-            Stmt::Fate {
-                condition,
-                then_branch,
-                else_branch,
+            Stmt::Chant {
+                expressions,
+                newline,
+                span,
             } => {
-                let w_condition = self.analyze_expression(condition, None)?;
-
-                if !w_condition
-                    .weave()
-                    .get_tapestry()
-                    .has_strand(CONDITIONAL_STRAND)
-                {
-                    return self.error(
-                        "The condition provided to determine the fate does not contain the 'Conditional' strand.",
-                        w_condition.token(),
-                    );
+                let mut w_expressions = Vec::with_capacity(expressions.len());
+                for expression in expressions {
+                    w_expressions.push(self.analyze_expression(expression, None)?);
                 }
-                // scoping n stuff will be added by the block!
-                let w_then = self.analyze_statement(*then_branch)?;
-
-                // self.symbol_table.end_scope();
-
-                let w_else: Option<Box<WovenStmt>> = match else_branch {
-                    Some(e_b) => Some(Box::new(self.analyze_statement(*e_b)?)),
-                    None => None,
-                };
-                Ok(WovenStmt::Fate {
-                    condition: w_condition,
-                    then_branch: Box::new(w_then),
-                    else_branch: w_else,
+                Ok(WovenStmt::Chant {
+                    expressions: w_expressions,
+                    newline,
+                    span,
                 })
             }
+            Stmt::ExprStmt { expr, span } => {
+                let w_expr = self.analyze_expression(expr, None)?;
+                self.warn_ignored_release(&w_expr);
+                Ok(WovenStmt::ExprStmt { expr: w_expr, span })
+            }
+            Stmt::Fate { .. } => self.analyze_fate(stmt),
             Stmt::VarDeclaration {
                 name,
                 mutable,
                 initializer,
                 weave,
+                span,
             } => {
                 // allow variable shadowing from outer scopes
                 if let Some(_symbol) = self.symbol_table.resolve_in_current_scope(&name.lexeme) {
@@ -242,13 +571,11 @@ impl<'a> WeaveAnalyzer<'a> {
 
                 match &w_initializer {
                     Some(val) => {
-                        // Try to get weave from symbol first (for composite weaves like SpellWeave<TextWeave>)
-                        expr_weave = if let Some(symbol) = val.symbol() {
-                            parent = Some(Rc::new(symbol.clone()));
-                            Ok(val.weave())
-                        } else {
-                            Ok(val.weave())
-                        };
+                        // a variable naming a spell keeps pointing at it, so casting the variable knows its reagents
+                        if let WovenExpr::Variable { symbol, .. } = val {
+                            parent = Some(symbol.clone());
+                        }
+                        expr_weave = Ok(val.weave());
                     }
                     None => {
                         if !mutable {
@@ -270,15 +597,7 @@ impl<'a> WeaveAnalyzer<'a> {
                     }
                 }
 
-                let slot = if matches!(self.current_realm, Realm::Spell) {
-                    // Inside a spell, use continuous slot counter
-                    let current_slot = self.spell_slot_counter;
-                    self.spell_slot_counter += 1;
-                    current_slot
-                } else {
-                    // Outside spells, use scope-local slot assignment
-                    self.symbol_table.get_current_scope_size()
-                };
+                let slot = self.next_local_slot();
 
                 // use the explicit weave if defined/available
                 let weave_for_symbol = if specified_weave.is_some() {
@@ -287,19 +606,49 @@ impl<'a> WeaveAnalyzer<'a> {
                     expr_weave?
                 };
 
+                self.warn_shadowing(&name);
+
                 let s = self
                     .symbol_table
-                    .define_variable(name.lexeme.clone(), weave_for_symbol, mutable, slot, parent)
+                    .define_variable(name.lexeme.to_string(), weave_for_symbol, mutable, slot, parent)
                     .unwrap();
+                self.declare(&name, &s);
+                if w_initializer.is_none() && !matches!(s.weave, Weave::Maybe(_)) {
+                    self.unassigned.push((s.name.clone(), s.depth));
+                }
+
+                // checked once the variable is defined, so the rest of the scroll still knows its weave
+                if let (Some(_), Some(val)) = (&weave, &w_initializer) {
+                    let provided = val.weave();
+                    if !self.can_assign(&s.weave, &provided) {
+                        return self.mismatch(
+                            &format!(
+                                "The variable '{}' was woven as '{}' but was given '{}'",
+                                name.lexeme,
+                                s.weave.get_name(),
+                                provided.get_name()
+                            ),
+                            name,
+                            val.span(),
+                            WeaveMismatch::of(&s.weave, &provided),
+                        );
+                    }
+                }
 
                 Ok(WovenStmt::VarDeclaration {
                     name: name,
                     mutable: mutable,
                     initializer: w_initializer,
                     symbol: s,
+                    span,
                 })
             }
-            Stmt::While { condition, body } => {
+            Stmt::While {
+                label,
+                condition,
+                body,
+                span,
+            } => {
                 let w_condition = self.analyze_expression(condition, None)?;
 
                 if !w_condition
@@ -307,81 +656,110 @@ impl<'a> WeaveAnalyzer<'a> {
                     .get_tapestry()
                     .has_strand(CONDITIONAL_STRAND)
                 {
-                    return self.error(
+                    return self.error_over(
                         "The condition provided to determine the fate of loop does not contain the 'Conditional' strand.",
                         w_condition.token(),
+                        w_condition.span(),
                     );
                 }
 
                 // enter loop scope (for sever, flow purposes)
-                self.loop_depth += 1;
+                self.enter_loop(&label)?;
 
+                // the body might not run at all, so what it assigns isn't surely assigned after it
+                let before = self.unassigned.clone();
                 let w_body = self.analyze_statement(*body)?;
+                self.unassigned = before;
 
                 // loop scope exit
-                self.loop_depth -= 1;
+                self.loops.pop();
 
                 Ok(WovenStmt::While {
+                    label,
                     condition: w_condition,
                     body: Box::new(w_body),
+                    span,
                 })
             }
-            Stmt::Sever { token } => {
-                if self.loop_depth == 0 {
-                    return self.error("'sever' cannot be used outside a loop circle!", token);
-                }
-                Ok(WovenStmt::Sever { token })
+            Stmt::Cycle {
+                token,
+                label,
+                variable,
+                start,
+                end,
+                body,
+                span,
+            } => self.analyze_cycle(Cycle {
+                token,
+                label,
+                variable,
+                start,
+                end,
+                body: *body,
+                span,
+            }),
+            Stmt::Circle {
+                label,
+                body,
+                condition,
+                span,
+            } => self.analyze_circle(label, *body, condition, span),
+            Stmt::FlowThrough {
+                token,
+                label,
+                variable,
+                iterable,
+                body,
+                span,
+            } => self.analyze_flow_through(token, label, variable, iterable, *body, span),
+            Stmt::Judge {
+                token,
+                subject,
+                arms,
+                divert,
+                span,
+            } => self.analyze_judge(token, subject, arms, divert, span),
+            Stmt::Sever { token, label, span } => {
+                self.check_loop_named(&token, &label, "sever")?;
+                Ok(WovenStmt::Sever { token, label, span })
             }
-            Stmt::Flow { token } => {
-                if self.loop_depth == 0 {
-                    return self.error("'flow' cannot be used outside a loop circle!", token);
-                }
-                Ok(WovenStmt::Flow { token })
+            Stmt::Flow { token, label, span } => {
+                self.check_loop_named(&token, &label, "flow")?;
+                Ok(WovenStmt::Flow { token, label, span })
+            }
+            Stmt::Attempt {
+                token,
+                body,
+                error,
+                mend,
+                span,
+            } => self.analyze_attempt(token, *body, error, *mend, span),
+            Stmt::Doom { token, value, span } => {
+                // any value can be invoked, its chant is the message of the error
+                let value = self.analyze_expression(value, None)?;
+                Ok(WovenStmt::Doom { token, value, span })
             }
-            Stmt::Release { token, expr } => {
-                // Ensure 'release' is only used within a spell realm
-                if self.current_realm == Realm::Genesis {
+            Stmt::Expect {
+                token,
+                condition,
+                message,
+                span,
+            } => self.analyze_expect(token, condition, message, span),
+            Stmt::Release { token, expr, span } => {
+                let (curr_spell_name, spell_entry) = self.current_spell(&token, "release")?;
+                let expected_weave = spell_entry.release_weave.clone();
+
+                // a stream ends with a bare release, its values are offered
+                if matches!(expected_weave, Weave::Stream(_)) && expr.is_some() {
                     return self.error(
-                        "Values cannot be released from the 'Genesis' realm!\n\
-                        Error: Usage of 'release' outside the spell scope.",
+                        &format!(
+                            "The flowing spell '{}' offers its values, it can only release without one to end its stream",
+                            curr_spell_name
+                        ),
                         token,
                     );
                 }
 
-                let curr_spell_name = match self.spell_stack.last() {
-                    Some(name) => name.clone(),
-                    None => {
-                        return self.error("Release used outside of any spell scope.", token);
-                    }
-                };
-
-                // Ensure spell exists and check if already released
-                let spell_entry = match self.symbol_table.resolve(&curr_spell_name) {
-                    Some(v) => match v.kind.borrow().clone() {
-                        SymbolKind::Spell(info) => info,
-                        _ => {
-                            return self.error(
-                                &format!(
-                                    "No Spell found in the realm with the name '{}'",
-                                    curr_spell_name
-                                ),
-                                token,
-                            );
-                        }
-                    },
-                    None => {
-                        return self.error(
-                            &format!(
-                                "No Spell found in the realm with the name '{}'",
-                                curr_spell_name
-                            ),
-                            token,
-                        );
-                    }
-                };
-
-                let expected_weave = spell_entry.release_weave.clone();
-
                 if let Some(e) = expr {
                     let w_expr = self.analyze_expression(e, Some(&expected_weave))?;
 
@@ -393,9 +771,8 @@ impl<'a> WeaveAnalyzer<'a> {
                         w_expr.weave()
                     };
 
-                    // Exact tapestry check (spells should return the exact weave)
-                    if expected_weave != w_expr.weave() {
-                        return self.error(
+                    if !self.can_assign(&expected_weave, &w_expr.weave()) {
+                        return self.mismatch(
                             &format!(
                                 "The spell '{}' was expected to release '{}' but '{}' was released",
                                 curr_spell_name,
@@ -403,17 +780,20 @@ impl<'a> WeaveAnalyzer<'a> {
                                 actual_weave.get_name()
                             ),
                             token,
+                            w_expr.span(),
+                            WeaveMismatch::of(&expected_weave, &actual_weave),
                         );
                     }
 
                     Ok(WovenStmt::Release {
                         token: token,
                         expr: Some(w_expr),
+                        span,
                     })
                 } else {
                     // release; with no expression implies Emptiness.
                     // If the spell expects a non-empty weave, this is an error.
-                    if expected_weave != Weave::Empty {
+                    if expected_weave != Weave::Empty && !matches!(expected_weave, Weave::Stream(_)) {
                         return self.error(
                             &format!(
                                 "The spell '{}' expects a value of weave '{}' to be released, but no value was provided.",
@@ -426,30 +806,22 @@ impl<'a> WeaveAnalyzer<'a> {
                     Ok(WovenStmt::Release {
                         token: token,
                         expr: None,
+                        span,
                     })
                 }
             }
+            Stmt::Offer { token, value, span } => self.analyze_offer(token, value, span),
             Stmt::Spell {
                 name,
                 reagents,
                 body,
                 return_weave,
                 attuned_to,
+                span,
             } => {
-                // allow spell shadowing from outer scopes
-                let existing = self.symbol_table.resolve_in_current_scope(&name.lexeme);
-                if existing.is_some() {
-                    return self.error(
-                        &format!(
-                            "The spell '{}' already exists in the current scope!",
-                            name.lexeme
-                        ),
-                        name,
-                    );
-                }
-
                 let mut w_reagents: Vec<WovenReagent> = vec![];
-                let slot = self.symbol_table.get_current_scope_size();
+                // a spell declared in a spell is a local of it, taking a slot of its frame
+                let slot = self.spell_slot(&name, attuned_to.is_some())?;
 
                 // get the ret type (weave ofcourse)
                 let ret_weave = match return_weave {
@@ -458,16 +830,17 @@ impl<'a> WeaveAnalyzer<'a> {
                 };
 
                 // define the spell
-                // Create SpellWeave<ReturnWeave> for the spell's symbol
-                let spell_weave = Weave::Spell {
-                    // reagents: Vec::new(),
+                // Create SpellWeave<ReturnWeave> for the spell's symbol, its reagents are filled in once woven
+                let mut spell_weave = Weave::Spell {
+                    reagents: None,
                     release: Box::new(ret_weave.clone()),
                 };
 
+                let attuned = attuned_to.is_some();
                 let spell_name = if attuned_to.is_some() {
                     format!("{}:{}", attuned_to.as_ref().unwrap().lexeme, name.lexeme)
                 } else {
-                    name.lexeme.clone()
+                    name.lexeme.to_string()
                 };
 
                 // mark the symbol definition
@@ -495,10 +868,12 @@ impl<'a> WeaveAnalyzer<'a> {
                 let saved_spell_base_depth = self.spell_base_depth;
                 self.spell_base_depth = self.symbol_table.get_depth() - 1;
 
-                // Reset spell slot counter for parameters
-                self.spell_slot_counter = 0;
+                // Reset spell slot counter for parameters, the enclosing spell's is picked up again after the body
+                let enclosing_slot_counter = self.slot_counter;
+                self.slot_counter = 0;
 
-                let upvals_saved = std::mem::take(&mut self.current_upvalues);
+                let enclosing_upvalues = std::mem::take(&mut self.current_upvalues);
+                self.enclosing_spells.push((saved_spell_base_depth, enclosing_upvalues));
 
                 if let Some(sign) = attuned_to {
                     let sign_lexeme = &sign.lexeme;
@@ -516,16 +891,18 @@ impl<'a> WeaveAnalyzer<'a> {
                             );
                         };
 
-                        let mut kind = s.kind.borrow_mut();
+                        // the sign is attuned in a copy, the woven statements keep the symbol they were given
+                        let s = (**s).clone();
 
-                        match &mut *kind {
+                        match &mut *s.kind.borrow_mut() {
                             SymbolKind::Sign(si) => {
-                                if si.attunements.contains_key(&name.lexeme) {
+                                // an attunement inherited from a referred tome can be overridden
+                                if si.attunements.get(&**name_lexeme) == Some(&method_name) {
                                     return self.error(&format!("The sign '{}' is already attuned to a spell named '{}', Try renaming the spell.",sign_lexeme, name_lexeme),
                                     sign.clone(),);
                                 }
 
-                                si.attunements.insert(name_lexeme.clone(), method_name);
+                                si.attunements.insert(name_lexeme.to_string(), method_name);
                             }
                             _ => {
                                 return self.error(
@@ -537,40 +914,64 @@ impl<'a> WeaveAnalyzer<'a> {
                                 );
                             }
                         }
+                        self.symbol_table.modify_symbol(Rc::new(s));
                     }
 
                     self.symbol_table.define_variable(
                         "ego".to_string(),
-                        Weave::Sign(sign_lexeme.clone()),
+                        Weave::Sign(sign_lexeme.to_string()),
                         false,
-                        self.spell_slot_counter,
+                        self.slot_counter,
                         None,
                     );
-                    self.spell_slot_counter += 1;
+                    self.slot_counter += 1;
 
-                    w_reagents.push(WovenReagent {
-                        weave: Weave::Sign(sign_lexeme.clone()),
-                    });
+                    w_reagents.push(WovenReagent::new(Weave::Sign(sign_lexeme.to_string())));
                 }
 
                 for r in reagents {
-                    let weave = self.analyze_parsed_weave(r.weave)?;
-                    self.symbol_table.define_variable(
-                        r.name.lexeme.clone(),
+                    let weave = self.reagent_weave(&r)?;
+                    let default = match r.default {
+                        Some(expr) => Some(self.analyze_reagent_default(expr, &weave, &r.name)?),
+                        // only the trailing reagents can be left out, the gathered one is left empty
+                        None if !r.gathered && w_reagents.iter().any(|w| w.default.is_some()) => {
+                            return self.error(
+                                &format!(
+                                    "The reagent '{}' needs a default, since a reagent before it has one",
+                                    r.name.lexeme
+                                ),
+                                r.name,
+                            );
+                        }
+                        None => None,
+                    };
+                    let reagent = self.symbol_table.define_variable(
+                        r.name.lexeme.to_string(),
                         weave.clone(),
                         false,
-                        self.spell_slot_counter, // Use continuous slot counter, (lexical scoping doesnt work right here!)
+                        self.slot_counter, // Use continuous slot counter, (lexical scoping doesnt work right here!)
                         None,
                     );
-                    self.spell_slot_counter += 1; // Increment for next parameter
+                    if let Some(reagent) = reagent {
+                        self.index_declaration(&r.name, &reagent);
+                    }
+                    self.slot_counter += 1; // Increment for next parameter
                     w_reagents.push(WovenReagent {
-                        // name: r.name.clone(),
-                        weave: weave,
+                        name: Some(r.name.lexeme.to_string()),
+                        weave,
+                        default,
+                        gathered: r.gathered,
                     });
                 }
 
-                stub_symbol.kind = RefCell::new(SymbolKind::Spell(SpellInfo {
-                    name: stub_symbol.name.clone(),
+                spell_weave = Weave::Spell {
+                    reagents: Some(w_reagents.iter().map(|r| r.weave.clone()).collect()),
+                    release: Box::new(ret_weave.clone()),
+                };
+                let stub = Rc::make_mut(&mut stub_symbol);
+                stub.weave = spell_weave.clone();
+                stub.kind = RefCell::new(SymbolKind::Spell(SpellInfo {
+                    name: stub.name.clone(),
                     reagents: w_reagents.clone(),
                     release_weave: ret_weave.clone(),
                     upvalues: vec![],
@@ -578,48 +979,42 @@ impl<'a> WeaveAnalyzer<'a> {
 
                 self.symbol_table.modify_symbol(stub_symbol);
 
+                // the locals of the body take the slots after the reagents
                 let prev_realm = self.current_realm.clone();
-                let prev_slot_counter = self.spell_slot_counter;
-
-                self.spell_slot_counter = 0;
 
                 self.current_realm = Realm::Spell;
-                self.spell_stack.push(name.lexeme.clone());
+                self.spell_stack.push(spell_name.clone());
 
                 // analyze the body of the spell
+                // the spell may be cast anywhere, the marks around it are not followed inside
+                // the loops around the spell can't be severed or flowed from its body
+                let outer_unassigned = std::mem::take(&mut self.unassigned);
+                let outer_loops = std::mem::take(&mut self.loops);
+                let releases = always_releases(&body);
                 let woven_body = self.analyze_statement(*body)?;
+                self.unassigned = outer_unassigned;
+                self.loops = outer_loops;
+                self.check_releases(releases, &ret_weave, &name);
 
                 self.spell_stack.pop();
 
-                // Reset spell slot counter when exiting spell
-                // self.spell_slot_counter = 0;
-
-                self.spell_slot_counter = prev_slot_counter;
+                self.slot_counter = enclosing_slot_counter;
 
                 self.current_realm = prev_realm;
 
-                let captured_vals = std::mem::replace(&mut self.current_upvalues, upvals_saved);
+                let (_, enclosing_upvalues) = self.enclosing_spells.pop().unwrap();
+                let captured_vals = std::mem::replace(&mut self.current_upvalues, enclosing_upvalues);
                 let Some(s) = self.symbol_table.resolve(&spell_name) else {
                     return self.error(
                         &format!("Could not find '{}' across the realms of eira!", spell_name),
                         name,
                     );
                 };
-                let _ = {
-                    let mut kind = s.kind.borrow_mut();
-
-                    let spell_info = match &mut *kind {
-                        SymbolKind::Spell(i) => i,
-                        _ => {
-                            return self
-                                .error(&format!("The symbol '{}' is not a spell", s.name), name);
-                        }
-                    };
-                    spell_info.upvalues = captured_vals.clone();
-
-                    spell_info.clone()
-                };
+                if !matches!(*s.kind.borrow(), SymbolKind::Spell(_)) {
+                    return self.error(&format!("The symbol '{}' is not a spell", s.name), name);
+                }
 
+                self.end_index_scope();
                 self.symbol_table.end_scope();
 
                 // Restore base_depth
@@ -641,15 +1036,20 @@ impl<'a> WeaveAnalyzer<'a> {
                         None,
                     )
                     .unwrap();
+                // the attuned spells are cast through their materials
+                if !attuned {
+                    self.declare(&name, &symbol);
+                }
 
                 Ok(WovenStmt::Spell {
                     name: name,
                     reagents: w_reagents,
                     body: Box::new(woven_body),
                     spell_symbol: symbol,
+                    span,
                 })
             }
-            Stmt::Sign { name, marks } => {
+            Stmt::Sign { name, marks, span } => {
                 if let Some(_) = self.symbol_table.resolve_in_current_scope(&name.lexeme) {
                     return self.error(
                         "A variable has been declared with same name as the sign.",
@@ -658,17 +1058,21 @@ impl<'a> WeaveAnalyzer<'a> {
                 }
 
                 let mut sign_info = SignInfo {
-                    schema: SignSchema::new(name.lexeme.clone()),
+                    schema: SignSchema::new(name.lexeme.to_string()),
                     marks: HashMap::new(),
+                    defaults: HashMap::new(),
                     attunements: HashMap::new(),
+                    tome: false,
+                    origin: None,
                 };
 
+                let slot = self.next_local_slot();
                 let symbol = self.symbol_table.define_sign(
-                    name.lexeme.clone(),
-                    Weave::Sign(name.lexeme.clone()),
+                    name.lexeme.to_string(),
+                    Weave::Sign(name.lexeme.to_string()),
                     sign_info.clone(),
                     None,
-                    self.symbol_table.get_current_scope_size(),
+                    slot,
                 );
 
                 let symbol = match symbol {
@@ -683,96 +1087,353 @@ impl<'a> WeaveAnalyzer<'a> {
                 let mut w_marks: Vec<WovenMark> = vec![];
 
                 for m in marks {
-                    if names.contains(&m.name.lexeme) {
+                    if names.iter().any(|n| **n == *m.name.lexeme) {
                         return self.error(
                             "A different mark with same name exists in the sign!",
                             m.name,
                         );
                     }
-                    names.push(m.name.lexeme.clone());
+                    names.push(m.name.lexeme.to_string());
                     let mark_weave = self.analyze_parsed_weave(m.parsed_weave)?;
+                    if let Some(default) = m.default {
+                        let value = self.mark_default(&m.name, default, &mark_weave)?;
+                        sign_info.defaults.insert(m.name.lexeme.to_string(), value);
+                    }
                     w_marks.push(WovenMark {
                         name: m.name.clone(),
                         weave: mark_weave.clone(),
                     });
 
-                    sign_info.marks.insert(m.name.lexeme.clone(), mark_weave);
-                    sign_info.schema.add_field(m.name.lexeme);
+                    sign_info.marks.insert(m.name.lexeme.to_string(), mark_weave);
+                    sign_info.schema.add_field(m.name.lexeme.to_string());
                 }
 
-                let new_symbol = Symbol {
-                    name: symbol.name,
-                    weave: symbol.weave,
+                let new_symbol = Rc::new(Symbol {
+                    name: symbol.name.clone(),
+                    weave: symbol.weave.clone(),
                     depth: symbol.depth,
                     kind: RefCell::new(SymbolKind::Sign(sign_info)),
                     slot_idx: symbol.slot_idx,
                     parent: None,
-                };
+                    reads: symbol.reads.clone(),
+                });
 
                 self.symbol_table.modify_symbol(new_symbol.clone());
+                self.index_declaration(&name, &new_symbol);
 
                 Ok(WovenStmt::Sign {
                     name,
                     marks: w_marks,
                     sign_symbol: new_symbol,
                     // schema
+                    span,
                 })
             }
-            Stmt::Vanish { target, token } => {
-                let w_target = self.analyze_expression(target, None)?;
+            Stmt::Glyph { name, variants, span } => {
+                if self.symbol_table.resolve_in_current_scope(&name.lexeme).is_some() {
+                    return self.error(
+                        "A variable has been declared with same name as the glyph.",
+                        name,
+                    );
+                }
 
-                match w_target.weave() {
-                    Weave::Maybe(_) => {}
-                    _ => {
+                if variants.is_empty() {
+                    return self.error("A glyph must carve atleast one variant!", name);
+                }
+
+                let mut info = GlyphInfo {
+                    name: name.lexeme.to_string(),
+                    variants: vec![],
+                };
+
+                for v in variants {
+                    if info.variants.iter().any(|n| **n == *v.lexeme) {
                         return self.error(
-                            "The weave of the target expression does not support vanishing (not a Maybe<T> weave).",
-                            token,
+                            &format!("The variant '{}' is already carved in the glyph!", v.lexeme),
+                            v,
                         );
                     }
+                    info.variants.push(v.lexeme.to_string());
                 }
 
-                let empty_literal = WovenExpr::Literal {
-                    value: Value::Emptiness,
-                    token: token.clone(),
-                    weave: Weave::Empty,
+                let Some(symbol) = self.symbol_table.define_glyph(
+                    name.lexeme.to_string(),
+                    info,
+                    self.symbol_table.get_current_scope_size(),
+                ) else {
+                    // this shouldnt be thrown
+                    return self.error("", name);
                 };
+                self.index_declaration(&name, &symbol);
 
-                // aka desugared
-                let sugar_less = match w_target {
-                    WovenExpr::Access {
-                        material,
-                        property,
-                        field_name_idx,
-                        weave,
-                    } => WovenExpr::FieldSet {
-                        material,
-                        property,
-                        value: Box::new(empty_literal),
-                        field_name_idx,
-                        weave,
-                    },
-                    // WovenExpr::Assignment { name, value, weave, symbol } => {},
-                    WovenExpr::Variable {
+                Ok(WovenStmt::Glyph {
+                    name,
+                    glyph_symbol: symbol,
+                    span,
+                })
+            }
+            Stmt::Seal {
+                name,
+                initializer,
+                weave,
+                span,
+            } => {
+                if self.symbol_table.resolve_in_current_scope(&name.lexeme).is_some() {
+                    return self.error(
+                        &format!("The seal '{}' already exists in the current scope!", name.lexeme),
+                        name,
+                    );
+                }
+
+                let specified_weave = match weave {
+                    Some(w) => Some(self.analyze_parsed_weave(w)?),
+                    None => None,
+                };
+
+                let w_initializer = self.analyze_expression(initializer, specified_weave.as_ref())?;
+                let provided = w_initializer.weave();
+
+                let Some(value) = Self::literal_value(&w_initializer) else {
+                    return self.error(
+                        &format!("The seal '{}' must be given a literal value or another seal", name.lexeme),
+                        name,
+                    );
+                };
+
+                let seal_weave = match specified_weave {
+                    Some(s_w) if !self.can_assign(&s_w, &provided) => {
+                        return self.mismatch(
+                            &format!(
+                                "The seal '{}' was woven as '{}' but was given '{}'",
+                                name.lexeme,
+                                s_w.get_name(),
+                                provided.get_name()
+                            ),
+                            name,
+                            w_initializer.span(),
+                            WeaveMismatch::of(&s_w, &provided),
+                        );
+                    }
+                    Some(s_w) => s_w,
+                    None => provided,
+                };
+
+                let Some(symbol) = self.symbol_table.define_seal(
+                    name.lexeme.to_string(),
+                    seal_weave,
+                    value,
+                    self.symbol_table.get_current_scope_size(),
+                ) else {
+                    // this shouldnt be thrown
+                    return self.error("", name);
+                };
+                self.index_declaration(&name, &symbol);
+
+                Ok(WovenStmt::Seal {
+                    name,
+                    seal_symbol: symbol,
+                    span,
+                })
+            }
+            Stmt::Tome {
+                name,
+                origin,
+                marks,
+                spells,
+                span,
+            } => {
+                // tome attunements are resolved by their global names at runtime
+                if self.symbol_table.get_depth() != 0 || self.current_realm != Realm::Genesis {
+                    return self.error(
+                        "Tomes can only be inscribed at the top level of a scroll!",
+                        name,
+                    );
+                }
+
+                if self.symbol_table.resolve_in_current_scope(&name.lexeme).is_some() {
+                    return self.error(
+                        &format!(
+                            "The name '{}' is already taken in the current scope!",
+                            name.lexeme
+                        ),
+                        name,
+                    );
+                }
+
+                let mut tome_info = SignInfo {
+                    schema: SignSchema::new(name.lexeme.to_string()),
+                    marks: HashMap::new(),
+                    defaults: HashMap::new(),
+                    attunements: HashMap::new(),
+                    tome: true,
+                    origin: None,
+                };
+
+                if let Some(origin) = origin {
+                    let Some(origin_info) = self
+                        .symbol_table
+                        .resolve(&origin.lexeme)
+                        .and_then(|s| s.kind.borrow().get_sign_info())
+                    else {
+                        return self.error(
+                            &format!(
+                                "No tome found across the eira realms with the name '{}'",
+                                origin.lexeme
+                            ),
+                            origin,
+                        );
+                    };
+
+                    if !origin_info.tome {
+                        return self.error(
+                            &format!(
+                                "'{}' is not a tome. A tome can only refer another tome!",
+                                origin.lexeme
+                            ),
+                            origin,
+                        );
+                    }
+
+                    // the inherited marks come first, so the field indices stay the same for the
+                    // referred tome's spells
+                    for field in &origin_info.schema.field_names {
+                        tome_info.schema.add_field(field.clone());
+                    }
+                    tome_info.marks = origin_info.marks;
+                    tome_info.defaults = origin_info.defaults;
+                    tome_info.attunements = origin_info.attunements;
+                    tome_info.origin = Some(origin.lexeme.to_string());
+                }
+
+                let mut w_marks: Vec<WovenMark> = vec![];
+
+                for m in marks {
+                    if tome_info.marks.contains_key(&*m.name.lexeme) {
+                        return self.error(
+                            &format!(
+                                "The mark '{}' is already inscribed in the tome (or the tome it refers)!",
+                                m.name.lexeme
+                            ),
+                            m.name,
+                        );
+                    }
+
+                    let mark_weave = self.analyze_parsed_weave(m.parsed_weave)?;
+                    if let Some(default) = m.default {
+                        let value = self.mark_default(&m.name, default, &mark_weave)?;
+                        tome_info.defaults.insert(m.name.lexeme.to_string(), value);
+                    }
+                    w_marks.push(WovenMark {
+                        name: m.name.clone(),
+                        weave: mark_weave.clone(),
+                    });
+
+                    tome_info.marks.insert(m.name.lexeme.to_string(), mark_weave);
+                    tome_info.schema.add_field(m.name.lexeme.to_string());
+                }
+
+                let slot = self.next_local_slot();
+                let tome_symbol = self.symbol_table.define_sign(
+                    name.lexeme.to_string(),
+                    Weave::Sign(name.lexeme.to_string()),
+                    tome_info,
+                    None,
+                    slot,
+                );
+                if let Some(tome_symbol) = tome_symbol {
+                    self.index_declaration(&name, &tome_symbol);
+                }
+
+                let mut w_spells: Vec<WovenStmt> = vec![];
+                for spell in spells {
+                    w_spells.push(self.analyze_statement(spell)?);
+                }
+
+                // pick up the attunements registered by the spells
+                let tome_symbol = self.symbol_table.resolve(&name.lexeme).unwrap().clone();
+                let tome_info = tome_symbol.kind.borrow().get_sign_info().unwrap();
+
+                let mut inherited: Vec<(String, String)> = tome_info
+                    .attunements
+                    .iter()
+                    .filter_map(|(spell, global)| {
+                        let own = format!("{}:{}", name.lexeme, spell);
+                        (*global != own).then(|| (own, global.clone()))
+                    })
+                    .collect();
+                inherited.sort();
+
+                Ok(WovenStmt::Tome {
+                    name,
+                    marks: w_marks,
+                    spells: w_spells,
+                    tome_symbol,
+                    inherited,
+                    span,
+                })
+            }
+            Stmt::Vanish { target, token, span } => {
+                let w_target = self.analyze_expression(target, None)?;
+
+                match w_target.weave() {
+                    Weave::Maybe(_) => {}
+                    _ => {
+                        return self.error(
+                            "The weave of the target expression does not support vanishing (not a Maybe<T> weave).",
+                            token,
+                        );
+                    }
+                }
+
+                let empty_literal = WovenExpr::Literal {
+                    value: Value::Emptiness,
+                    token: token.clone(),
+                    weave: Weave::Empty,
+                    span,
+                };
+
+                // aka desugared
+                let sugar_less = match w_target {
+                    WovenExpr::Access {
+                        material,
+                        property,
+                        field_name_idx,
+                        weave,
+                        span,
+                    } => WovenExpr::FieldSet {
+                        material,
+                        property,
+                        value: Box::new(empty_literal),
+                        field_name_idx,
+                        weave,
+                        span,
+                    },
+                    // WovenExpr::Assignment { name, value, weave, symbol } => {},
+                    WovenExpr::Variable {
                         name,
                         weave,
                         symbol,
+                        span,
                     } => WovenExpr::Assignment {
                         name,
                         value: Box::new(empty_literal),
                         weave,
                         symbol,
+                        span,
                     },
                     WovenExpr::Extract {
                         deck,
                         index,
                         token,
                         weave,
+                        span,
                     } => WovenExpr::DeckSet {
                         deck,
                         index,
                         value: Box::new(empty_literal),
                         token,
                         weave,
+                        span,
                     },
 
                     _ => {
@@ -780,11 +1441,11 @@ impl<'a> WeaveAnalyzer<'a> {
                     }
                 };
 
-                return Ok(WovenStmt::ExprStmt { expr: sugar_less });
+                return Ok(WovenStmt::ExprStmt { expr: sugar_less, span });
             }
-            Stmt::Attune { sign, spells } => {
+            Stmt::Attune { sign, spells, span } => {
                 // verify that the symbol exists and it is a sign
-                let Some(sign_symbol) = self.symbol_table.resolve(&sign.lexeme) else {
+                let Some(sign_symbol) = self.symbol_table.resolve(&sign.lexeme).cloned() else {
                     return self.error(
                         &format!(
                             "No sign found across the eira realms with the name '{}'",
@@ -793,6 +1454,7 @@ impl<'a> WeaveAnalyzer<'a> {
                         sign,
                     );
                 };
+                self.index_reference(&sign, &sign_symbol);
 
                 match *sign_symbol.kind.borrow() {
                     SymbolKind::Sign(_) => {}
@@ -819,6 +1481,7 @@ impl<'a> WeaveAnalyzer<'a> {
                 Ok(WovenStmt::Attune {
                     sign: sign,
                     spells: w_spells,
+                    span,
                 })
             }
             Stmt::Tether {
@@ -826,6 +1489,7 @@ impl<'a> WeaveAnalyzer<'a> {
                 path,
                 bind_to,
                 is_path,
+                span,
             } => {
                 if self.symbol_table.get_depth() != 0 {
                     return self.error("Tethering can only be done in the global scope!", token);
@@ -846,7 +1510,7 @@ impl<'a> WeaveAnalyzer<'a> {
                             .to_str()
                             .unwrap(), // dont judge me by this line!
                         MAIN_SEPARATOR_STR,
-                        path.first().unwrap().lexeme.as_str()
+                        path.first().unwrap().lexeme.as_ref()
                     );
 
                     // unwrap cus error is infallibe (never gonna give you- I mean happen)
@@ -877,7 +1541,7 @@ impl<'a> WeaveAnalyzer<'a> {
                         );
                     }
 
-                    let contents = if path[0].lexeme == "eira" {
+                    let contents = if &*path[0].lexeme == "eira" {
                         // core library/archive/project, whatever you wanna call it
                         let Some(core_scroll) = self.get_core_scroll(&path[1].lexeme) else {
                             return self.error(
@@ -892,10 +1556,10 @@ impl<'a> WeaveAnalyzer<'a> {
                         core_scroll
                     } else if let Some(proj) = self.context.project.as_ref() {
                         // case of local tethering (imports)
-                        if proj.name == path[0].lexeme {
+                        if *proj.name == *path[0].lexeme {
                             let mut file_path: PathBuf = PathBuf::from_str("./").unwrap();
                             for p in path[1..].iter() {
-                                file_path.push(p.lexeme.clone());
+                                file_path.push(p.lexeme.to_string());
                                 if !file_path.exists() {
                                     return self.error(
                                         &format!(
@@ -928,7 +1592,7 @@ impl<'a> WeaveAnalyzer<'a> {
 
                 let path = path
                     .iter()
-                    .map(|t| t.lexeme.clone())
+                    .map(|t| t.lexeme.to_string())
                     .collect::<Vec<String>>()
                     .join(".");
 
@@ -940,6 +1604,7 @@ impl<'a> WeaveAnalyzer<'a> {
                                 statements: vec![],
                                 bind_to,
                                 path,
+                                span,
                             });
                         }
                         CompileState::Compiling => {
@@ -961,8 +1626,10 @@ impl<'a> WeaveAnalyzer<'a> {
                 let ast = match Parser::new(tokens, path.clone()).parse() {
                     Ok(a) => a,
                     Err(e) => {
-                        return self
-                            .error(&format!("Failed to parse tethered content: {}", e.0), token);
+                        return self.error(
+                            &format!("Failed to parse tethered content: {}", e.message()),
+                            token,
+                        );
                     }
                 };
 
@@ -972,7 +1639,7 @@ impl<'a> WeaveAnalyzer<'a> {
                     Ok(w) => w,
                     Err(e) => {
                         return self.error(
-                            &format!("Failed to analyze tethered content: {}", e.msg),
+                            &format!("Failed to analyze tethered content: {}", e.message()),
                             token,
                         );
                     }
@@ -1010,6 +1677,7 @@ impl<'a> WeaveAnalyzer<'a> {
                     statements: w_ast,
                     path,
                     bind_to,
+                    span,
                 })
             }
         }
@@ -1021,97 +1689,16 @@ impl<'a> WeaveAnalyzer<'a> {
         expected_weave: Option<&Weave>,
     ) -> WeaveResult<WovenExpr> {
         match expr {
-            Expr::Binary {
-                left,
-                right,
-                operator,
-            } => {
-                let w_left = self.analyze_expression(*left, None)?;
-                let w_right = self.analyze_expression(*right, None)?;
-
-                if operator.token_type == TokenType::Plus {
-                    let left_has_additive =
-                        w_left.weave().get_tapestry().has_strand(ADDITIVE_STRAND);
-                    let left_has_concat = w_left
-                        .weave()
-                        .get_tapestry()
-                        .has_strand(CONCATINABLE_STRAND);
-                    let right_has_additive =
-                        w_right.weave().get_tapestry().has_strand(ADDITIVE_STRAND);
-                    let right_has_concat = w_right
-                        .weave()
-                        .get_tapestry()
-                        .has_strand(CONCATINABLE_STRAND);
-
-                    // Both must support the same type of operation
-                    if (left_has_additive && right_has_additive)
-                        || (left_has_concat && right_has_concat)
-                    {
-                        // Valid operation
-                    } else {
-                        return self.error(
-                            "Cannot perform '+' operation: operands must both contain either 'Additive' or 'Concatinable' strand.",
-                            operator,
-                        );
-                    }
-                } else {
-                    if let Some(req_strand) = self.strand_from_op(operator.token_type) {
-                        if !w_left.weave().get_tapestry().has_strand(req_strand) {
-                            return self.error(
-                                &format!(
-                                    "The weave of one of the operands is not composed of {} strand.",
-                                    self.strand_string_from_bits(req_strand)
-                                ),
-                                operator,
-                            );
-                        }
-
-                        if !w_right.weave().get_tapestry().has_strand(req_strand) {
-                            return self.error(
-                                &format!(
-                                    "The weave of one of the operands is not composed of {} strand.",
-                                    self.strand_string_from_bits(req_strand)
-                                ),
-                                operator,
-                            );
-                        }
-                    } else {
-                        return self.error(
-                            &format!("Unknown operation '{}'", operator.lexeme),
-                            operator,
-                        );
-                    }
+            Expr::Binary { .. } => self.analyze_binary(expr, expected_weave),
+            Expr::Grouping { expression, .. } => self.analyze_expression(*expression, expected_weave),
+            Expr::Interpolation { value, token, .. } => {
+                let w_value = self.analyze_expression(*value, None)?;
+                match w_value.weave() {
+                    Weave::Text => Ok(w_value),
+                    _ => Ok(Self::chanted_text(w_value, &token)),
                 }
-
-                let result_weave = match operator.token_type {
-                    TokenType::Greater
-                    | TokenType::Less
-                    | TokenType::EqualEqual
-                    | TokenType::LessEqual
-                    | TokenType::GreaterEqual
-                    | TokenType::BangEqual => Weave::Truth,
-                    TokenType::Plus => {
-                        // hard coded for now. Should be dynamic later
-                        if w_left.weave().get_tapestry().has_strand(ADDITIVE_STRAND)
-                            && w_right.weave().get_tapestry().has_strand(ADDITIVE_STRAND)
-                        {
-                            Weave::Num
-                        } else {
-                            Weave::Text
-                        }
-                    }
-                    _ => w_left.weave(), // Assumes left-hand side's type
-                };
-
-                Ok(WovenExpr::Binary {
-                    left: Box::new(w_left),
-                    right: Box::new(w_right),
-                    operator: operator,
-                    weave: result_weave,
-                })
             }
-            Expr::Grouping { expression } => self.analyze_expression(*expression, None),
-            Expr::Literal { value, token } => {
+            Expr::Literal { value, token, span } => {
                 let weave = match value {
                     Value::Number(_) => Weave::Num,
                     Value::Emptiness => Weave::Empty,
@@ -1121,19 +1708,13 @@ impl<'a> WeaveAnalyzer<'a> {
                         return self.error("Couldnt find a weave for the value", token.clone());
                     }
                 };
-                return Ok(WovenExpr::Literal {
-                    value: value,
-                    token: token,
-                    weave,
-                });
+                Ok(Self::literal_for(value, token, weave, expected_weave, span))
             }
-            Expr::Unary { operand, operator } => {
-                if operator.token_type != TokenType::Minus && operator.token_type != TokenType::Bang
-                {
-                    return self.error("Unknown Unary Operation", operator);
-                }
-                if let Some(strand) = self.strand_from_op(operator.token_type) {
-                    let expr = self.analyze_expression(*operand, None)?;
+            Expr::Unary { operand, operator, span } => {
+                if let Some(strand) = self.unary_strand_from_op(operator.token_type) {
+                    // `-1` is an int wherever `1` would be
+                    let hint = expected_weave.filter(|_| operator.token_type == TokenType::Minus);
+                    let expr = self.analyze_expression(*operand, hint)?;
                     if !expr.weave().get_tapestry().has_strand(strand) {
                         return self.error(
                             &format!(
@@ -1144,38 +1725,71 @@ impl<'a> WeaveAnalyzer<'a> {
                             operator,
                         );
                     }
-                    let weave = expr.weave();
+                    // '!' always gives a Truth, even for a Maybe<Truth>
+                    let weave = match operator.token_type {
+                        TokenType::Bang => Weave::Truth,
+                        _ => expr.weave(),
+                    };
                     Ok(WovenExpr::Unary {
                         operand: Box::new(expr),
                         operator: operator,
                         weave: weave,
+                        span,
                     })
                 } else {
-                    return self.error("Unknown Operation", operator);
+                    self.error("Unknown Unary Operation", operator)
                 }
             }
-            Expr::Variable { name } => {
+            Expr::Variable { name, span } => {
+                if name.token_type == TokenType::Origin {
+                    return self.error(
+                        "'origin' can only be used to cast the spells of the referred tome!",
+                        name,
+                    );
+                }
+
                 if let Some(symbol) = self.symbol_table.resolve(&name.lexeme).cloned() {
                     //The symbol(variable) has been found
+                    if symbol.kind.borrow().get_glyph_info().is_some() {
+                        return self.error(
+                            &format!(
+                                "The glyph '{}' cannot be used by itself, pick one of its variants with '.'",
+                                name.lexeme
+                            ),
+                            name,
+                        );
+                    }
+
                     self.resolve_n_add_upvalue(&symbol)?;
 
+                    symbol.read();
+                    self.index_reference(&name, &symbol);
+                    self.check_assigned(&symbol, &name)?;
+
                     let weave = &symbol.weave;
                     let woven = WovenExpr::Variable {
                         name: name,
                         weave: weave.clone(),
                         symbol: symbol,
+                        span,
                     };
 
                     Ok(woven)
                 } else {
+                    let names = self.symbol_table.visible_symbols().map(|s| s.name.as_str());
                     return self.error(
-                        &format!("'{}' was undefined in the eira-verse!", name.lexeme),
+                        &format!(
+                            "'{}' was undefined in the eira-verse!{}",
+                            name.lexeme,
+                            self.did_you_mean(&name.lexeme, names)
+                        ),
                         name,
                     );
                 }
             }
-            Expr::Assignment { name, value } => {
+            Expr::Assignment { name, value, span } => {
                 if let Some(resolved) = self.symbol_table.resolve(&name.lexeme).cloned() {
+                    self.index_reference(&name, &resolved);
                     match *resolved.kind.borrow() {
                         SymbolKind::Variable { mutable } => {
                             if !mutable {
@@ -1185,57 +1799,126 @@ impl<'a> WeaveAnalyzer<'a> {
                         );
                             }
                         }
+                        SymbolKind::Seal(_) => {
+                            return self.error(
+                                &format!("Tried to reassign the seal '{}'. Seals cannot be reassigned!", name.lexeme),
+                                name,
+                            );
+                        }
                         _ => return self.error("The value isnt a variable!", name),
                     };
 
-                    let woven_expr = self.analyze_expression(*value, None)?;
+                    let woven_expr = self.analyze_expression(*value, Some(&resolved.weave))?;
                     let weave = woven_expr.weave();
 
-                    // Assignment requires an exact match of the tapestry!
-                    if resolved.weave == woven_expr.weave() {
+                    if self.can_assign(&resolved.weave, &woven_expr.weave()) {
+                        self.resolve_n_add_upvalue(&resolved)?;
+                        self.unassigned
+                            .retain(|(n, depth)| *n != resolved.name || *depth != resolved.depth);
                         return Ok(WovenExpr::Assignment {
                             name: name,
                             value: Box::new(woven_expr),
                             weave: weave,
                             symbol: resolved,
+                            span,
                         });
                     }
 
-                    return self.error(
-                        "The assignee and the value to be assigned are of different Weaves!\nAssignment failed.",
+                    return self.mismatch(
+                        &format!(
+                            "The mark '{}' is woven as '{}' and can't be given '{}'!\nAssignment failed.",
+                            name.lexeme,
+                            resolved.weave.get_name(),
+                            weave.get_name()
+                        ),
                         name,
+                        woven_expr.span(),
+                        WeaveMismatch::of(&resolved.weave, &weave),
                     );
                 } else {
+                    let names = self.symbol_table.visible_symbols().map(|s| s.name.as_str());
                     return self.error(
-                        "The mark was no where to be found from this realm!\nVariable resolution failed.",
+                        &format!(
+                            "The mark was no where to be found from this realm!{}\nVariable resolution failed.",
+                            self.did_you_mean(&name.lexeme, names)
+                        ),
                         name,
                     );
                 }
             }
             Expr::Cast {
                 reagents,
+                named,
                 callee,
                 token,
+                span,
             } => {
-                if let Expr::Access { material, property } = *callee.clone() {
-                    let w_material = self.analyze_expression(*material, None)?;
+                if let Expr::Access { material, property, .. } = *callee {
+                    // 'origin' casts the referred tome's spell on ego, skipping the runtime dispatch
+                    let from_origin = matches!(
+                        &*material,
+                        Expr::Variable { name, .. } if name.token_type == TokenType::Origin
+                    );
+
+                    let w_material = if let Expr::Variable { name, span: origin_span } = &*material
+                        && from_origin
+                    {
+                        let name = name.clone();
+                        let Some(ego) = self.symbol_table.resolve("ego").cloned()
+                        else {
+                            return self.error(
+                                "'origin' can only be used inside the spells of a tome!",
+                                name,
+                            );
+                        };
+                        self.resolve_n_add_upvalue(&ego)?;
+
+                        WovenExpr::Variable {
+                            name,
+                            weave: ego.weave.clone(),
+                            symbol: ego,
+                            span: *origin_span,
+                        }
+                    } else {
+                        self.analyze_expression(*material, None)?
+                    };
 
                     if let Weave::Sign(ref sign_name) = w_material.weave() {
                         let Some(sign_symbol) = self.symbol_table.resolve(sign_name).cloned()
                         else {
-                            return self.error(
+                            return self.error_over(
                                 &format!("The sign '{}' was not found!", sign_name),
                                 w_material.token(),
+                                w_material.span(),
                             );
                         };
 
-                        let sign_info = sign_symbol.kind.borrow().get_sign_info().unwrap();
+                        let mut sign_info = sign_symbol.kind.borrow().get_sign_info().unwrap();
+
+                        if from_origin {
+                            let origin_info = match &sign_info.origin {
+                                Some(origin) => self
+                                    .symbol_table
+                                    .resolve(origin)
+                                    .and_then(|s| s.kind.borrow().get_sign_info()),
+                                None => None,
+                            };
+
+                            let Some(origin_info) = origin_info else {
+                                return self.error_over(
+                                    &format!("The tome '{}' does not refer any tome!", sign_name),
+                                    w_material.token(),
+                                    w_material.span(),
+                                );
+                            };
+                            sign_info = origin_info;
+                        }
 
-                        let Some(method_name) = sign_info.attunements.get(&property.lexeme) else {
+                        let Some(method_name) = sign_info.attunements.get(&*property.lexeme) else {
                             return self.error(
                                 &format!(
                                     "The sign '{}' is not attuned to a spell '{}'",
-                                    sign_name, property.lexeme
+                                    sign_info.schema.name, property.lexeme
                                 ),
                                 property,
                             );
@@ -1256,7 +1939,7 @@ impl<'a> WeaveAnalyzer<'a> {
                         let spell_info = method_symbol.kind.borrow().get_spell_info().unwrap();
 
                         if let Some(expected) = expected_weave {
-                            if *expected != spell_info.release_weave {
+                            if !self.can_assign(expected, &spell_info.release_weave) {
                                 return self.error(
                                     &format!(
                                         "The release weave of spell '{}' does not match the expected weave '{}'",
@@ -1268,25 +1951,24 @@ impl<'a> WeaveAnalyzer<'a> {
                             }
                         }
 
+                        // one of the spell's reagents is ego, the stub of a spell whose reagents failed has none
                         let mut final_reagents = vec![w_material];
-                        for (i, r) in reagents.iter().enumerate() {
-                            let w_r = self.analyze_expression(
-                                r.clone(),
-                                Some(&spell_info.reagents.get(i).unwrap().weave),
-                            )?;
-                            final_reagents.push(w_r);
-                        }
-
-                        if final_reagents.len() != spell_info.reagents.len() {
-                            return self.error(
-                                &format!(
-                                    "The spell '{}' expected {} reagent(s), but you provided {} of them!",
-                                    method_name,
-                                    spell_info.reagents.len()-1, // one is ego
-                                    final_reagents.len()-1
-                                ),
-                                property,
-                            );
+                        final_reagents.extend(self.analyze_reagents(
+                            method_name,
+                            (reagents, named),
+                            Some(spell_info.reagents.get(1..).unwrap_or_default()),
+                            &property,
+                            span,
+                        )?);
+
+                        if sign_info.tome && !from_origin {
+                            return Ok(WovenExpr::Invoke {
+                                callee: property,
+                                reagents: final_reagents,
+                                spell_symbol: method_symbol.clone(),
+                                weave: spell_info.release_weave.clone(),
+                                span,
+                            });
                         }
 
                         return Ok(WovenExpr::Cast {
@@ -1294,11 +1976,13 @@ impl<'a> WeaveAnalyzer<'a> {
                             reagents: final_reagents,
                             spell_symbol: method_symbol.clone(),
                             weave: spell_info.release_weave.clone(),
+                            span,
                         });
                     } else {
-                        return self.error(
+                        return self.error_over(
                             "for now... just be satisfied with spell casting only on signs!",
                             w_material.token(),
+                            w_material.span(),
                         );
                     }
                 }
@@ -1312,7 +1996,10 @@ impl<'a> WeaveAnalyzer<'a> {
                         if nat.is_ok() {
                             Some(nat.unwrap())
                         } else {
-                            None
+                            self.context
+                                .native_spells
+                                .get(&*token.lexeme)
+                                .map(|info| NativeSpell::Host(info.clone()))
                         }
                     }
                     Ok(_) => None,
@@ -1323,6 +2010,13 @@ impl<'a> WeaveAnalyzer<'a> {
 
                     let native_info = NativeSpell::get_spell_info(native_spell.clone()).unwrap();
 
+                    if let Some((name, _)) = named.first() {
+                        return self.error(
+                            "The reagents of a native spell go unnamed, they can't be given by name.",
+                            name.clone(),
+                        );
+                    }
+
                     if native_info.reagents.len() != reagents.len() {
                         return self.error(
                             &format!(
@@ -1336,7 +2030,7 @@ impl<'a> WeaveAnalyzer<'a> {
                     }
 
                     if let Some(expected) = expected_weave {
-                        if *expected != native_info.release_weave {
+                        if !self.can_assign(expected, &native_info.release_weave) {
                             return self.error(
                                     &format!(
                                         "The release weave of spell '{}' does not match the expected weave '{}'",
@@ -1349,12 +2043,17 @@ impl<'a> WeaveAnalyzer<'a> {
                     }
 
                     let mut w_reagents: Vec<WovenExpr> = vec![];
-                    for (i, reagent) in reagents.iter().enumerate() {
+                    for (i, reagent) in reagents.into_iter().enumerate() {
                         let expected = native_info.reagents.get(i).unwrap();
-                        let w_expr =
-                            self.analyze_expression(reagent.clone(), Some(&expected.weave))?;
-                        if w_expr.weave() != expected.weave {
-                            return self.error(
+                        // a generic reagent cant guide the weave of the expression
+                        let hint = if Self::is_generic_weave(&expected.weave) {
+                            None
+                        } else {
+                            Some(&expected.weave)
+                        };
+                        let w_expr = self.analyze_expression(reagent, hint)?;
+                        if !Self::fits_native_reagent(&expected.weave, &w_expr.weave()) {
+                            return self.mismatch(
                                 &format!(
                                     "The reagent #{} was expected to be {}, but got {}",
                                     i + 1,
@@ -1362,9 +2061,11 @@ impl<'a> WeaveAnalyzer<'a> {
                                     w_expr.weave().get_name()
                                 ),
                                 token,
+                                w_expr.span(),
+                                WeaveMismatch::of(&expected.weave, &w_expr.weave()),
                             );
                         }
-                        w_reagents.push(w_expr.clone());
+                        w_reagents.push(w_expr);
                     }
 
                     return Ok(WovenExpr::NativeCast {
@@ -1372,6 +2073,7 @@ impl<'a> WeaveAnalyzer<'a> {
                         callee: token,
                         weave: native_info.release_weave,
                         native_spell,
+                        span,
                     });
                 }
 
@@ -1385,55 +2087,42 @@ impl<'a> WeaveAnalyzer<'a> {
                 }
 
                 // atp its usually a variable expr. If its not, well... good luck ig
-                let spell_info = match w_callee {
-                    WovenExpr::Variable { symbol, .. } => {
-                        if let SymbolKind::Spell(si) = &*symbol.kind.borrow() {
-                            si.clone()
-                        } else {
-                            let mut spell_info: Option<SpellInfo> = None;
-                            let mut s = symbol.clone();
-                            while let Some(p) = s.parent {
-                                if let SymbolKind::Spell(si) = &*p.kind.borrow() {
-                                    spell_info = Some(si.clone());
-                                    break;
-                                }
-                                s = Rc::unwrap_or_clone(p);
-                            }
-
-                            match spell_info {
-                                Some(si) => si,
+                let WovenExpr::Variable { symbol, weave: callee_weave, .. } = w_callee else {
+                    // this should be unreachable.. if im not wrong
+                    return self.error("Eira can only cast a spell from a variable!", token);
+                };
 
-                                // if not found, try checking Native Spells
-                                None => {
-                                    return self.error("Only spells can be casted!", token);
-                                }
-                            }
+                let spell_info = if let SymbolKind::Spell(si) = &*symbol.kind.borrow() {
+                    Some(si.clone())
+                } else {
+                    let mut spell_info: Option<SpellInfo> = None;
+                    let mut parent = symbol.parent.clone();
+                    while let Some(p) = parent {
+                        if let SymbolKind::Spell(si) = &*p.kind.borrow() {
+                            spell_info = Some(si.clone());
+                            break;
                         }
+                        parent = p.parent.clone();
                     }
-                    _ => {
-                        // this should be unreachable.. if im not wrong
-                        return self.error("Eira can only cast a spell from a variable!", token);
-                    }
+                    spell_info
                 };
 
-                if reagents.len() != spell_info.reagents.len() {
-                    return self.error(
-                        &format!(
-                            "The spell '{}' expected {} reagent(s), but you provided {} of them!",
-                            spell_info.name,
-                            spell_info.reagents.len(),
-                            reagents.len()
-                        ),
-                        token,
-                    );
-                }
+                // a spell handed over as a reagent or released from a cast only carries its weave
+                let (release_weave, signature) = match (spell_info, callee_weave) {
+                    (Some(si), _) => (si.release_weave, Some(si.reagents)),
+                    (None, Weave::Spell { reagents, release }) => (
+                        *release,
+                        reagents.map(|r| r.into_iter().map(WovenReagent::new).collect()),
+                    ),
+                    (None, _) => return self.error("Only spells can be casted!", token),
+                };
 
                 if let Some(expected) = expected_weave {
-                    if *expected != spell_info.release_weave {
+                    if !self.can_assign(expected, &release_weave) {
                         return self.error(
                                     &format!(
                                         "The release weave of spell '{}' does not match the expected weave '{}'",
-                                        spell_info.name,
+                                        symbol.name,
                                         expected.get_name()
                                     ),
                                     token,
@@ -1441,36 +2130,56 @@ impl<'a> WeaveAnalyzer<'a> {
                     }
                 }
 
-                let mut final_reagents: Vec<WovenExpr> = vec![];
-
-                for r in reagents {
-                    final_reagents.push(self.analyze_expression(r, None)?);
-                }
+                let final_reagents = self.analyze_reagents(
+                    &symbol.name,
+                    (reagents, named),
+                    signature.as_deref(),
+                    &token,
+                    span,
+                )?;
 
                 Ok(WovenExpr::Cast {
                     callee: token.clone(),
                     reagents: final_reagents,
                     spell_symbol: self.symbol_table.resolve(&token.lexeme).unwrap().clone(),
-                    weave: spell_info.release_weave,
+                    weave: release_weave,
+                    span,
                 })
             }
-            Expr::Draw { marks, callee } => {
+            Expr::Draw {
+                reagents,
+                marks,
+                callee,
+                span,
+            } => {
                 let var_name = &callee.lexeme;
 
-                let Some(_) = self.symbol_table.resolve_in_current_scope(var_name) else {
+                if let Some(s) = self.symbol_table.resolve_in_current_scope(var_name)
+                    && s.kind.borrow().get_sign_info().is_none()
+                {
                     return self.error(
                         "A variable with the same name as the sign exists in the current scope!",
                         callee,
                     );
-                };
+                }
 
                 let Some(symbol) = self.symbol_table.resolve(&callee.lexeme).cloned() else {
+                    let signs = self
+                        .symbol_table
+                        .visible_symbols()
+                        .filter(|s| s.kind.borrow().get_sign_info().is_some())
+                        .map(|s| s.name.as_str());
                     return self.error(
-                        &format!("The sign '{}' was not found!", callee.lexeme),
+                        &format!(
+                            "The sign '{}' was not found!{}",
+                            callee.lexeme,
+                            self.did_you_mean(&callee.lexeme, signs)
+                        ),
                         callee,
                     );
                 };
 
+                self.index_reference(&callee, &symbol);
                 let sign_info = {
                     let Some(info) = symbol.kind.borrow().get_sign_info() else {
                         return self.error(&format!("'{}' is not a sign!", symbol.name), callee);
@@ -1479,45 +2188,61 @@ impl<'a> WeaveAnalyzer<'a> {
                     info.clone()
                 };
 
-                // Will have to change for optional fields
-                if sign_info.marks.len() != marks.len() {
+                let field_names = &sign_info.schema.field_names;
+                if reagents.len() > field_names.len() {
                     return self.error(
                         &format!(
-                            "The sign '{}' expected {} marks, but you provided{} {} of them!",
+                            "The sign '{}' inscribes {} marks, but {} were given in order!",
                             callee.lexeme,
-                            sign_info.marks.len(),
-                            if marks.len() < sign_info.marks.len() {
-                                " only"
-                            } else {
-                                ""
-                            },
-                            marks.len()
+                            field_names.len(),
+                            reagents.len()
                         ),
                         callee,
                     );
                 }
 
+                // the marks given in order take the names of the marks as the sign inscribes them
+                let in_order: Vec<EtchedMark> = reagents
+                    .into_iter()
+                    .zip(field_names)
+                    .map(|(expr, field)| EtchedMark {
+                        name: Token {
+                            lexeme: Rc::from(field.as_str()),
+                            ..callee.clone()
+                        },
+                        expr,
+                    })
+                    .collect();
+
                 let mut w_marks: Vec<WovenEtchedMark> = vec![];
-                for mark in marks {
-                    if let Some(field) = sign_info.marks.get(&mark.name.lexeme) {
+                for mark in in_order.into_iter().chain(marks) {
+                    if w_marks.iter().any(|m| m.name.lexeme == mark.name.lexeme) {
+                        return self.error(
+                            &format!("The mark '{}' was given more than once!", mark.name.lexeme),
+                            mark.name,
+                        );
+                    }
+
+                    if let Some(field) = sign_info.marks.get(&*mark.name.lexeme) {
                         // set blank as a way to set empty value
                         let mark_val = match mark.expr {
-                            Expr::Blank { token } => WovenExpr::Literal {
+                            Expr::Blank { token, span } => WovenExpr::Literal {
                                 value: Value::Emptiness,
                                 token: token,
                                 weave: Weave::Empty,
+                                span,
                             },
-                            _ => self.analyze_expression(mark.expr, None)?,
+                            _ => self.analyze_expression(mark.expr, Some(field))?,
                         };
 
                         let mark_weave = mark_val.weave();
                         if self.can_assign(field, &mark_weave) {
                             w_marks.push(WovenEtchedMark {
                                 name: mark.name.clone(),
-                                expr: mark_val.clone(),
+                                expr: mark_val,
                             })
                         } else {
-                            return self.error(
+                            return self.mismatch(
                                 &format!(
                                     "The mark '{}' was expected to have weave '{}' but got '{}'",
                                     mark.name.lexeme,
@@ -1525,6 +2250,8 @@ impl<'a> WeaveAnalyzer<'a> {
                                     mark_weave.get_name()
                                 ),
                                 mark.name,
+                                mark_val.span(),
+                                WeaveMismatch::of(field, &mark_weave),
                             );
                         }
                     } else {
@@ -1538,16 +2265,99 @@ impl<'a> WeaveAnalyzer<'a> {
                     }
                 }
 
+                // the marks left out take their defaults
+                let mut missing: Vec<&str> = vec![];
+                for field in field_names {
+                    if w_marks.iter().any(|m| *m.name.lexeme == **field) {
+                        continue;
+                    }
+                    let Some(value) = sign_info.defaults.get(field) else {
+                        missing.push(field);
+                        continue;
+                    };
+                    let token = Token {
+                        lexeme: Rc::from(field.as_str()),
+                        ..callee.clone()
+                    };
+                    w_marks.push(WovenEtchedMark {
+                        name: token.clone(),
+                        expr: WovenExpr::Literal {
+                            value: value.clone(),
+                            token,
+                            weave: sign_info.marks[field].clone(),
+                            span,
+                        },
+                    });
+                }
+
+                if !missing.is_empty() {
+                    return self.error(
+                        &format!(
+                            "The sign '{}' was drawn without the marks '{}', they have no defaults to take!",
+                            callee.lexeme,
+                            missing.join("', '")
+                        ),
+                        callee,
+                    );
+                }
+
                 Ok(WovenExpr::Draw {
                     marks: w_marks,
                     callee: callee.clone(),
                     weave: Weave::Sign(sign_info.schema.name.clone()),
                     sign_symbol: symbol.clone(),
+                    span,
                 })
             }
-            Expr::Access { material, property } => {
+            Expr::Access { material, property, span } => {
+                if let Expr::Variable { name, .. } = &*material {
+                    let glyph = self
+                        .symbol_table
+                        .resolve(&name.lexeme)
+                        .filter(|s| s.kind.borrow().get_glyph_info().is_some())
+                        .cloned();
+
+                    if let Some(glyph) = glyph {
+                        self.index_reference(name, &glyph);
+                        let info = glyph.kind.borrow().get_glyph_info().unwrap();
+                        let Some(variant) = info.get_variant(&property.lexeme) else {
+                            return self.error(
+                                &format!(
+                                    "The variant '{}' is not carved in the glyph '{}'",
+                                    property.lexeme, info.name
+                                ),
+                                property,
+                            );
+                        };
+
+                        return Ok(WovenExpr::Literal {
+                            value: Value::Glyph(Rc::new(variant)),
+                            token: property,
+                            weave: Weave::Glyph(info.name),
+                            span,
+                        });
+                    }
+                }
+
                 let w_material = self.analyze_expression(*material, None)?;
-                // it should be a variable expression
+
+                // an error carries nothing but its message
+                if w_material.weave() == Weave::Error {
+                    if &*property.lexeme != "message" {
+                        return self.error(
+                            &format!("An error only carries its 'message', not '{}'", property.lexeme),
+                            property,
+                        );
+                    }
+                    return Ok(WovenExpr::Access {
+                        material: Box::new(w_material),
+                        property,
+                        field_name_idx: 0,
+                        weave: Weave::Text,
+                        span,
+                    });
+                }
+
                 let sign_name = match w_material.weave() {
                     Weave::Sign(s) => s,
                     _ => {
@@ -1556,48 +2366,17 @@ impl<'a> WeaveAnalyzer<'a> {
                     }
                 };
 
-                let Some(sign_symbol) = self.symbol_table.resolve(&sign_name) else {
-                    return self.error(
-                        &format!(
-                            "The sign '{}' was not found across the eira realms!",
-                            sign_name
-                        ),
-                        property,
-                    );
-                };
-
-                let Some(sign_info) = sign_symbol.kind.borrow().get_sign_info() else {
-                    return self.error(&format!("'{}' is not a sign!", sign_symbol.name), property);
-                };
-
-                let Some(mark) = sign_info.schema.get_field_index(property.lexeme.clone()) else {
-                    return self.error(
-                        &format!(
-                            "The mark '{}' is not defined for '{}'",
-                            property.lexeme, sign_name
-                        ),
-                        property,
-                    );
-                };
-
-                let Some(property_weave) = sign_info.marks.get(&property.lexeme) else {
-                    return self.error(
-                        &format!(
-                            "Eira couldn't find the weave for property '{}'",
-                            property.lexeme
-                        ),
-                        property,
-                    );
-                };
+                let (mark, property_weave) = self.sign_mark(&sign_name, &property)?;
 
                 Ok(WovenExpr::Access {
                     material: Box::new(w_material),
                     property,
-                    field_name_idx: mark as u16,
-                    weave: property_weave.clone(),
+                    field_name_idx: mark,
+                    weave: property_weave,
+                    span,
                 })
             }
-            Expr::Deck { elements, token } => {
+            Expr::Deck { elements, token, span } => {
                 let mut w_elements = vec![];
 
                 let mut expected_capacity: Option<usize> = None;
@@ -1607,15 +2386,8 @@ impl<'a> WeaveAnalyzer<'a> {
                             expected_capacity = *c;
                             Some(*inner.clone())
                         }
-                        _ => {
-                            return self.error(
-                                &format!(
-                                    "Hows this possible? a {} weave passed on to a deck!",
-                                    w.get_name()
-                                ),
-                                token,
-                            );
-                        }
+                        // whoever expected another weave reports the mismatch
+                        _ => None,
                     },
                     None => None,
                 };
@@ -1637,16 +2409,17 @@ impl<'a> WeaveAnalyzer<'a> {
                     }
                 }
 
-                for element in &elements {
-                    let w_element = self.analyze_expression(element.clone(), None)?;
+                for element in elements {
+                    let w_element = self.analyze_expression(element, prev_elem_weave.as_ref())?;
                     let elem_weave = w_element.weave();
-                    if let Some(prev_weave) = prev_elem_weave {
-                        if elem_weave != prev_weave {
+                    match &prev_elem_weave {
+                        Some(prev_weave) if !self.can_assign(prev_weave, &elem_weave) => {
                             return self
                                 .error("All elements of a deck must be of the same weave!", token);
                         }
+                        Some(_) => {}
+                        None => prev_elem_weave = Some(elem_weave),
                     }
-                    prev_elem_weave = Some(elem_weave);
                     w_elements.push(w_element);
                 }
 
@@ -1658,12 +2431,15 @@ impl<'a> WeaveAnalyzer<'a> {
                 Ok(WovenExpr::Deck {
                     elements: w_elements,
                     weave: weave,
+                    span,
                 })
             }
-            Expr::Extract { deck, index, token } => {
+            Expr::Extract { deck, index, token, span } => {
                 let w_deck = self.analyze_expression(*deck, None)?;
                 let elem_weave = match w_deck.weave() {
                     Weave::Deck(weave, _) => *weave,
+                    // a letter of a text is a text of its own
+                    Weave::Text => Weave::Text,
                     _ => {
                         return self.error(
                             &format!(
@@ -1680,7 +2456,7 @@ impl<'a> WeaveAnalyzer<'a> {
 
                 let index_weave = w_index.weave();
 
-                if index_weave != Weave::Num {
+                if !matches!(index_weave, Weave::Num | Weave::Int) {
                     return self.error(
                         "The index expression of a deck set operation must be of NumWeave!",
                         token.clone(),
@@ -1692,130 +2468,73 @@ impl<'a> WeaveAnalyzer<'a> {
                     index: Box::new(w_index),
                     weave: elem_weave,
                     token,
+                    span,
                 })
             }
+            Expr::Slice {
+                target,
+                start,
+                end,
+                token,
+                span,
+            } => self.analyze_slice(*target, *start, *end, token, span),
             Expr::DeckSet {
                 deck,
                 index,
                 value,
                 token,
-            } => {
-                let w_deck = self.analyze_expression(*deck, None)?;
-                let w_index = self.analyze_expression(*index, Some(&Weave::Num))?;
-                let w_value = self.analyze_expression(*value, None)?;
-
-                let index_weave = w_index.weave();
-
-                if index_weave != Weave::Num {
-                    return self.error(
-                        "The index expression of a deck set operation must be of NumWeave!",
-                        token.clone(),
-                    );
-                }
-
-                Ok(WovenExpr::DeckSet {
-                    deck: Box::new(w_deck),
-                    index: Box::new(w_index),
-                    value: Box::new(w_value.clone()),
-                    weave: w_value.weave(),
-                    token,
-                })
-            }
+                span,
+            } => self.analyze_deck_set(*deck, *index, *value, token, span),
             Expr::FieldSet {
                 material,
                 property,
                 value,
+                span,
             } => {
-                let w_material_token = match self.analyze_expression(*material, None)? {
-                    WovenExpr::Variable { name, .. } => name,
-                    _ => {
-                        return self.error(
-                            "Only variables can be accessed with '.' operator!",
-                            property,
-                        );
-                    }
-                };
-
-                let Some(symbol) = self.symbol_table.resolve(&w_material_token.lexeme).cloned()
-                else {
-                    return self.error(
-                        &format!(
-                            "The mark '{}' was not found across the eira realms!",
-                            w_material_token.lexeme
-                        ),
-                        w_material_token,
-                    );
-                };
-
-                let sign_name = match symbol.weave {
-                    Weave::Sign(ref name) => name,
-                    _ => {
-                        return self.error(
-                            "The mark 'n' is not a material of a sign!",
-                            w_material_token,
-                        );
-                    }
-                };
-
-                let Some(sign_symbol) = self.symbol_table.resolve(sign_name) else {
+                // any material can be set through, `a.b.c = 1` sets the mark of the material in `a.b`
+                let w_material = self.analyze_expression(*material, None)?;
+                let Weave::Sign(sign_name) = w_material.weave() else {
                     return self.error(
                         &format!(
-                            "The sign '{}' was not found across the eira realms!",
-                            sign_name
+                            "Only the marks of a sign can be set with '.', '{}' is a '{}'!",
+                            w_material.token().lexeme,
+                            w_material.weave().get_name()
                         ),
-                        w_material_token,
+                        property,
                     );
                 };
 
-                let Some(sign_info) = sign_symbol.kind.borrow().get_sign_info() else {
-                    return self.error(
-                        &format!("'{}' is not a sign!", sign_symbol.name),
-                        w_material_token,
-                    );
-                };
+                let (mark, property_weave) = self.sign_mark(&sign_name, &property)?;
 
-                let Some(mark) = sign_info.schema.get_field_index(property.lexeme.clone()) else {
-                    return self.error(
+                let w_value = self.analyze_expression(*value, Some(&property_weave))?;
+                if !self.can_assign(&property_weave, &w_value.weave()) {
+                    return self.error_over(
                         &format!(
-                            "The mark '{}' is not defined for '{}'",
-                            property.lexeme, sign_name
+                            "The mark '{}' is woven as '{}' but was given a '{}'",
+                            property.lexeme,
+                            property_weave.get_name(),
+                            w_value.weave().get_name()
                         ),
                         property,
+                        w_value.span(),
                     );
-                };
-
-                let Some(property_weave) = sign_info.marks.get(&property.lexeme) else {
-                    return self.error(
-                        &format!(
-                            "Eira couldn't find the weave for property '{}'",
-                            property.lexeme
-                        ),
-                        w_material_token,
-                    );
-                };
-
-                let w_material_expr = WovenExpr::Variable {
-                    name: w_material_token,
-                    weave: symbol.weave.clone(),
-                    symbol: symbol,
-                };
-
-                let w_value = self.analyze_expression(*value, None)?;
+                }
                 Ok(WovenExpr::FieldSet {
-                    material: Box::new(w_material_expr),
+                    material: Box::new(w_material),
                     property,
                     value: Box::new(w_value),
-                    field_name_idx: mark as u16,
-                    weave: property_weave.clone(),
+                    field_name_idx: mark,
+                    weave: property_weave,
+                    span,
                 })
             }
-            Expr::Blank { token } => {
+            Expr::Blank { token, .. } => {
                 return self.error(
                     "Invalid '_' usage. '_' is used to assign a Empty value to Maybe<T> weaves!",
                     token,
                 );
             }
-            Expr::Manifests { value, token } => {
+            Expr::Manifests { value, token, span } => {
                 let w_value = self.analyze_expression(*value, None)?;
 
                 // if !matches!(w_value.weave(), Weave::Maybe(_)) {
@@ -1829,9 +2548,10 @@ impl<'a> WeaveAnalyzer<'a> {
                     value: Box::new(w_value),
                     token,
                     weave: Weave::Truth,
+                    span,
                 })
             }
-            Expr::SafeAccess { material, property } => {
+            Expr::SafeAccess { material, property, span } => {
                 let w_material = self.analyze_expression(*material, None)?;
 
                 if !matches!(w_material.weave(), Weave::Maybe(_)) {
@@ -1860,48 +2580,17 @@ impl<'a> WeaveAnalyzer<'a> {
                     }
                 };
 
-                let Some(sign_symbol) = self.symbol_table.resolve(&sign_name) else {
-                    return self.error(
-                        &format!(
-                            "The sign '{}' was not found across the eira realms!",
-                            sign_name
-                        ),
-                        property,
-                    );
-                };
-
-                let Some(sign_info) = sign_symbol.kind.borrow().get_sign_info() else {
-                    return self.error(&format!("'{}' is not a sign!", sign_symbol.name), property);
-                };
-
-                let Some(mark) = sign_info.schema.get_field_index(property.lexeme.clone()) else {
-                    return self.error(
-                        &format!(
-                            "The mark '{}' is not defined for '{}'",
-                            property.lexeme, sign_name
-                        ),
-                        property,
-                    );
-                };
-
-                let Some(property_weave) = sign_info.marks.get(&property.lexeme) else {
-                    return self.error(
-                        &format!(
-                            "Eira couldn't find the weave for property '{}'",
-                            property.lexeme
-                        ),
-                        property,
-                    );
-                };
+                let (mark, property_weave) = self.sign_mark(&sign_name, &property)?;
 
                 Ok(WovenExpr::SafeAccess {
                     material: Box::new(w_material),
                     property,
-                    field_name_idx: mark as u16,
-                    weave: Weave::Maybe(Box::new(property_weave.clone())),
+                    field_name_idx: mark,
+                    weave: Weave::Maybe(Box::new(property_weave)),
+                    span,
                 })
             }
-            Expr::AssertSafe { operand, operator } => {
+            Expr::AssertSafe { operand, operator, span } => {
                 let w_operand = self.analyze_expression(*operand, None)?;
 
                 let weave = match w_operand.weave() {
@@ -1918,6 +2607,97 @@ impl<'a> WeaveAnalyzer<'a> {
                     operand: Box::new(w_operand),
                     operator,
                     weave: weave,
+                    span,
+                })
+            }
+            Expr::Heed { token, span } => Ok(WovenExpr::Heed { token, span }),
+            Expr::Conjure { token, cast, span } => {
+                let w_cast = self.analyze_expression(*cast, None)?;
+                let release = match &w_cast {
+                    WovenExpr::Cast { weave, .. } | WovenExpr::Invoke { weave, .. } => weave.clone(),
+                    _ => {
+                        return self.error(
+                            "Only the spells of the scroll can be conjured, a native spell is cast right away.",
+                            token,
+                        );
+                    }
+                };
+                if matches!(release, Weave::Stream(_)) {
+                    return self.error(
+                        "A flowing spell can't be conjured, it runs as its stream is drawn from.",
+                        token,
+                    );
+                }
+                Ok(WovenExpr::Conjure {
+                    token,
+                    cast: Box::new(w_cast),
+                    weave: Weave::Conjuring(Box::new(release)),
+                    span,
+                })
+            }
+            Expr::Claim { token, conjuring, span } => {
+                let w_conjuring = self.analyze_expression(*conjuring, None)?;
+                let Weave::Conjuring(release) = w_conjuring.weave() else {
+                    return self.error(
+                        &format!(
+                            "Only a conjuring can be claimed, but got a '{}' weave.",
+                            w_conjuring.weave().get_name()
+                        ),
+                        token,
+                    );
+                };
+                Ok(WovenExpr::Claim {
+                    token,
+                    conjuring: Box::new(w_conjuring),
+                    weave: *release,
+                    span,
+                })
+            }
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                token,
+                span,
+            } => {
+                let w_condition = self.analyze_expression(*condition, None)?;
+                if !w_condition
+                    .weave()
+                    .get_tapestry()
+                    .has_strand(CONDITIONAL_STRAND)
+                {
+                    return self.error(
+                        "The condition of the conditional expression does not contain the 'Conditional' strand.",
+                        token,
+                    );
+                }
+
+                let (w_then, w_else) = self.analyze_operands(*then_branch, *else_branch, expected_weave)?;
+
+                // both arms land in the same place, so one of them has to hold the other
+                let (then_weave, else_weave) = (w_then.weave(), w_else.weave());
+                let weave = if self.can_assign(&then_weave, &else_weave) {
+                    then_weave
+                } else if self.can_assign(&else_weave, &then_weave) {
+                    else_weave
+                } else {
+                    return self.error(
+                        &format!(
+                            "The arms of the conditional expression are woven differently, '{}' and '{}'.",
+                            then_weave.get_name(),
+                            else_weave.get_name()
+                        ),
+                        token,
+                    );
+                };
+
+                Ok(WovenExpr::Conditional {
+                    condition: Box::new(w_condition),
+                    then_branch: Box::new(w_then),
+                    else_branch: Box::new(w_else),
+                    token,
+                    weave,
+                    span,
                 })
             }
         }
@@ -1925,15 +2705,33 @@ impl<'a> WeaveAnalyzer<'a> {
 
     fn analyze_parsed_weave(&mut self, parsed_weave: ParsedWeave) -> WeaveResult<Weave> {
         let Some(base_weave) = self.get_weave_from_name(&parsed_weave.base.lexeme) else {
+            let woven_names = self
+                .symbol_table
+                .visible_symbols()
+                .filter(|s| {
+                    let kind = s.kind.borrow();
+                    kind.get_sign_info().is_some() || kind.get_glyph_info().is_some()
+                })
+                .map(|s| s.name.as_str());
+            let suggestion = self.did_you_mean(
+                &parsed_weave.base.lexeme,
+                BUILTIN_WEAVES.iter().copied().chain(woven_names),
+            );
             return self.error(
                 &format!(
-                    "Couldn't find {} weave across the realms of eira!",
-                    parsed_weave.base.lexeme
+                    "Couldn't find {} weave across the realms of eira!{}",
+                    parsed_weave.base.lexeme, suggestion
                 ),
                 parsed_weave.base,
             );
         };
 
+        if matches!(base_weave, Weave::Sign(_) | Weave::Glyph(_))
+            && let Some(symbol) = self.symbol_table.resolve(&parsed_weave.base.lexeme).cloned()
+        {
+            self.index_reference(&parsed_weave.base, &symbol);
+        }
+
         if !base_weave.can_sub_weave() && parsed_weave.inner.is_some() {
             return self.error(
                 &format!(
@@ -1980,6 +2778,32 @@ impl<'a> WeaveAnalyzer<'a> {
                 }
                 res.unwrap()
             }
+            Weave::Conjuring(_) => {
+                let res = Weaver::weave_conjuring(base_weave, inner_weave);
+                if res.is_err() {
+                    return self.error(
+                        &format!(
+                            "Couldnt weave {} to {}",
+                            parsed_weave.base.lexeme, inner_parsed_weave.base.lexeme
+                        ),
+                        inner_parsed_weave.base,
+                    );
+                }
+                res.unwrap()
+            }
+            Weave::Stream(_) => {
+                let res = Weaver::weave_stream(base_weave, inner_weave);
+                if res.is_err() {
+                    return self.error(
+                        &format!(
+                            "Couldnt weave {} to {}",
+                            parsed_weave.base.lexeme, inner_parsed_weave.base.lexeme
+                        ),
+                        inner_parsed_weave.base,
+                    );
+                }
+                res.unwrap()
+            }
             Weave::Maybe(_) => {
                 let res = Weaver::weave_maybe(base_weave, inner_weave);
                 if res.is_err() {
@@ -2014,46 +2838,1666 @@ impl<'a> WeaveAnalyzer<'a> {
         }
     }
 
-    fn can_assign(&self, expected: &Weave, provided: &Weave) -> bool {
-        if expected == provided {
-            return true;
+    /// Returns the slot for the next local being defined in the current scope.
+    /// The slots are numbered across the whole frame, so nested and sibling blocks never share a
+    /// slot with a local that is still alive.
+    fn analyze_expect(
+        &mut self,
+        token: Token,
+        condition: Expr,
+        message: Option<Expr>,
+        span: Span,
+    ) -> WeaveResult<WovenStmt> {
+        let w_condition = self.analyze_expression(condition, None)?;
+
+        if !w_condition
+            .weave()
+            .get_tapestry()
+            .has_strand(CONDITIONAL_STRAND)
+        {
+            return self.error_over(
+                &format!(
+                    "Only conditions can be expected, but got a '{}' weave.",
+                    w_condition.weave().get_name()
+                ),
+                w_condition.token(),
+                w_condition.span(),
+            );
         }
 
-        match (expected, provided) {
-            (Weave::Maybe(ai), b) => {
-                if *b == Weave::Empty {
-                    return true;
-                }
-                ai.as_ref() == provided
-            }
-            // (_, Weave::Maybe(bi)) => self.match_weave(expected, bi),
-            _ => false,
-        }
-    }
+        // like a doom, the message can be any value
+        let w_message = match message {
+            Some(message) => self.analyze_expression(message, None)?,
+            None => WovenExpr::Literal {
+                value: Value::from("The expectation was not met".to_string()),
+                token: token.clone(),
+                weave: Weave::Text,
+                span,
+            },
+        };
+
+        Ok(WovenStmt::Expect {
+            token,
+            condition: w_condition,
+            message: w_message,
+            span,
+        })
+    }
+
+    /// Weaves an attempt, its mend sees the error under the given name
+    /// Weaves `flow x through xs { ... }`, the mark taking the weave of the elements walked
+    fn analyze_cycle(&mut self, cycle: Cycle) -> WeaveResult<WovenStmt> {
+        let Cycle {
+            token,
+            label,
+            variable,
+            start,
+            end,
+            body,
+            span,
+        } = cycle;
+        let (w_start, w_end) = self.analyze_operands(start, end, None)?;
+
+        // the range walks from start towards end one step at a time, so both bounds
+        // must be comparable and steppable. A range isn't a weave of its own to hold the
+        // ITERABLE strand, the numbers it walks are only ordinal & additive. The iterables
+        // (decks, texts and streams) are walked by the flow instead, which checks the strand
+        for bound in [&w_start, &w_end] {
+            let tapestry = bound.weave().get_tapestry();
+            if !tapestry.has_strand(ORDINAL_STRAND) || !tapestry.has_strand(ADDITIVE_STRAND) {
+                return self.error_over(
+                    &format!(
+                        "The bounds of a cycle must be ordinal & additive, but got a '{}' weave.",
+                        bound.weave().get_name()
+                    ),
+                    bound.token(),
+                    bound.span(),
+                );
+            }
+        }
+
+        if w_start.weave() != w_end.weave() {
+            return self.error_over(
+                &format!(
+                    "The cycle bounds should share the same weave! Got '{}' and '{}'.",
+                    w_start.weave().get_name(),
+                    w_end.weave().get_name()
+                ),
+                w_end.token(),
+                w_end.span(),
+            );
+        }
+
+        // the cycle mark and the end bound live in a scope around the body, so that the body
+        // locals take the slots after them
+        let slots = self.begin_scope();
+
+        let variable_slot = self.next_local_slot();
+        let variable_symbol = self
+            .symbol_table
+            .define_variable(
+                variable.lexeme.to_string(),
+                w_start.weave(),
+                true,
+                variable_slot,
+                None,
+            )
+            .unwrap();
+        self.index_declaration(&variable, &variable_symbol);
+
+        // the name can never be written by the user, so it cant be resolved from the body
+        let end_slot = self.next_local_slot();
+        let end_symbol = self
+            .symbol_table
+            .define_variable("<cycle end>".to_owned(), w_end.weave(), false, end_slot, None)
+            .unwrap();
+
+        self.enter_loop(&label)?;
+
+        let before = self.unassigned.clone();
+        let w_body = self.analyze_body(body)?;
+        self.unassigned = before;
+
+        self.loops.pop();
+        self.end_scope(slots);
+
+        Ok(WovenStmt::Cycle {
+            token,
+            label,
+            variable: variable_symbol,
+            start: w_start,
+            end: w_end,
+            end_symbol,
+            body: Box::new(w_body),
+            span,
+        })
+    }
+
+    /// The body of a circle runs at least once, so the marks it gives a value are surely given one
+    /// for its condition and after it, unless a sever or a flow skips the rest of the body
+    fn analyze_circle(
+        &mut self,
+        label: Option<Token>,
+        body: Stmt,
+        condition: Expr,
+        span: Span,
+    ) -> WeaveResult<WovenStmt> {
+        self.enter_loop(&label)?;
+        let before = self.unassigned.clone();
+        let cut_short = cuts_short(&body);
+        let w_body = self.analyze_statement(body)?;
+        if cut_short {
+            self.unassigned = before;
+        }
+        self.loops.pop();
+
+        let w_condition = self.analyze_expression(condition, None)?;
+        if !w_condition
+            .weave()
+            .get_tapestry()
+            .has_strand(CONDITIONAL_STRAND)
+        {
+            return self.error_over(
+                "The condition ending the circle does not contain the 'Conditional' strand.",
+                w_condition.token(),
+                w_condition.span(),
+            );
+        }
+
+        Ok(WovenStmt::Circle {
+            label,
+            body: Box::new(w_body),
+            condition: w_condition,
+            span,
+        })
+    }
+
+    /// The name and the info of the spell whose body is being analyzed, the [word] used outside of one is refused
+    fn current_spell(&mut self, token: &Token, word: &str) -> WeaveResult<(String, SpellInfo)> {
+        // Ensure the word is only used within a spell realm
+        if self.current_realm == Realm::Genesis {
+            return self.error(
+                &format!(
+                    "Only a spell can {} values, not the 'Genesis' realm!\n\
+                    Error: Usage of '{}' outside the spell scope.",
+                    word, word
+                ),
+                token.clone(),
+            );
+        }
+
+        let Some(curr_spell_name) = self.spell_stack.last().cloned() else {
+            return self.error(&format!("'{}' used outside of any spell scope.", word), token.clone());
+        };
+
+        // Ensure the spell exists
+        let spell_entry = match self.symbol_table.resolve(&curr_spell_name) {
+            Some(v) => match v.kind.borrow().clone() {
+                SymbolKind::Spell(info) => Some(info),
+                _ => None,
+            },
+            None => None,
+        };
+        let Some(spell_entry) = spell_entry else {
+            return self.error(
+                &format!(
+                    "No Spell found in the realm with the name '{}'",
+                    curr_spell_name
+                ),
+                token.clone(),
+            );
+        };
+        Ok((curr_spell_name, spell_entry))
+    }
+
+    /// Only a spell releasing a stream offers, each value of the weave the stream flows with
+    fn analyze_offer(&mut self, token: Token, value: Expr, span: Span) -> WeaveResult<WovenStmt> {
+        let (curr_spell_name, spell_entry) = self.current_spell(&token, "offer")?;
+        let Weave::Stream(element) = spell_entry.release_weave else {
+            return self.error(
+                &format!(
+                    "The spell '{}' isn't flowing, only a spell releasing a 'Stream' can offer values",
+                    curr_spell_name
+                ),
+                token,
+            );
+        };
+
+        let w_value = self.analyze_expression(value, Some(&element))?;
+        if !self.can_assign(&element, &w_value.weave()) {
+            return self.error(
+                &format!(
+                    "The spell '{}' was expected to offer '{}' but '{}' was offered",
+                    curr_spell_name,
+                    element.get_name(),
+                    w_value.weave().get_name()
+                ),
+                token,
+            );
+        }
+        Ok(WovenStmt::Offer { token, value: w_value, span })
+    }
+
+    fn analyze_flow_through(
+        &mut self,
+        token: Token,
+        label: Option<Token>,
+        variable: Token,
+        iterable: Expr,
+        body: Stmt,
+        span: Span,
+    ) -> WeaveResult<WovenStmt> {
+        let w_iterable = self.analyze_expression(iterable, None)?;
+        let weave = w_iterable.weave();
+        if !weave.get_tapestry().has_strand(ITERABLE_STRAND) {
+            return self.error_over(
+                &format!(
+                    "Only the weaves with the 'ITERABLE' strand can be flowed through, but got a '{}' weave.",
+                    weave.get_name()
+                ),
+                w_iterable.token(),
+                w_iterable.span(),
+            );
+        }
+        let element_weave = match &weave {
+            Weave::Deck(element, _) | Weave::Stream(element) => *element.clone(),
+            // a text is walked letter by letter, each a text of its own
+            _ => weave.clone(),
+        };
+
+        // like the cycle, the mark and the hidden locals share a scope with the body
+        let slots = self.begin_scope();
+
+        let variable_slot = self.next_local_slot();
+        let variable_symbol = self
+            .symbol_table
+            .define_variable(
+                variable.lexeme.to_string(),
+                element_weave,
+                false,
+                variable_slot,
+                None,
+            )
+            .unwrap();
+        self.index_declaration(&variable, &variable_symbol);
+
+        // the names can never be written by the user, so they cant be resolved from the body
+        let iterable_slot = self.next_local_slot();
+        let iterable_symbol = self
+            .symbol_table
+            .define_variable(
+                "<flowed value>".to_owned(),
+                weave,
+                false,
+                iterable_slot,
+                None,
+            )
+            .unwrap();
+        let index_slot = self.next_local_slot();
+        let index_symbol = self
+            .symbol_table
+            .define_variable(
+                "<flow index>".to_owned(),
+                Weave::Num,
+                true,
+                index_slot,
+                None,
+            )
+            .unwrap();
+
+        self.enter_loop(&label)?;
+
+        let before = self.unassigned.clone();
+        let w_body = self.analyze_body(body)?;
+        self.unassigned = before;
+
+        self.loops.pop();
+        self.end_scope(slots);
+
+        Ok(WovenStmt::FlowThrough {
+            token,
+            label,
+            variable: variable_symbol,
+            iterable: w_iterable,
+            iterable_symbol,
+            index_symbol,
+            body: Box::new(w_body),
+            span,
+        })
+    }
+
+    /// Weaves `xs[start..end]`, a slice of a deck is a deck of the same elements and a slice of
+    /// a text is a text
+    fn analyze_slice(
+        &mut self,
+        target: Expr,
+        start: Expr,
+        end: Expr,
+        token: Token,
+        span: Span,
+    ) -> WeaveResult<WovenExpr> {
+        let w_target = self.analyze_expression(target, None)?;
+        let weave = match w_target.weave() {
+            Weave::Deck(element, _) => Weave::Deck(element, None),
+            Weave::Text => Weave::Text,
+            other => {
+                return self.error(
+                    &format!(
+                        "Only a 'Deck' or a 'Text' can be sliced, but got a '{}'!",
+                        other.get_name()
+                    ),
+                    token,
+                );
+            }
+        };
+
+        let w_start = self.analyze_expression(start, Some(&Weave::Num))?;
+        let w_end = self.analyze_expression(end, Some(&Weave::Num))?;
+        for bound in [&w_start, &w_end] {
+            if !matches!(bound.weave(), Weave::Num | Weave::Int) {
+                return self.error_over(
+                    &format!(
+                        "The bounds of a slice must be of Num or Int weave, but got a '{}'!",
+                        bound.weave().get_name()
+                    ),
+                    bound.token(),
+                    bound.span(),
+                );
+            }
+        }
+
+        Ok(WovenExpr::Slice {
+            target: Box::new(w_target),
+            start: Box::new(w_start),
+            end: Box::new(w_end),
+            token,
+            weave,
+            span,
+        })
+    }
+
+    /// Weaves `deck[index] = value`, the value has to fit the element weave of the deck
+    fn analyze_deck_set(
+        &mut self,
+        deck: Expr,
+        index: Expr,
+        value: Expr,
+        token: Token,
+        span: Span,
+    ) -> WeaveResult<WovenExpr> {
+        let w_deck = self.analyze_expression(deck, None)?;
+        let elem_weave = match w_deck.weave() {
+            Weave::Deck(weave, _) => *weave,
+            _ => {
+                return self.error(
+                    &format!(
+                        "'{}' was expected to be a 'Deck' but its a '{}'!",
+                        w_deck.token().lexeme,
+                        w_deck.weave().get_name(),
+                    ),
+                    token,
+                );
+            }
+        };
+        let w_index = self.analyze_expression(index, Some(&Weave::Num))?;
+
+        if !matches!(w_index.weave(), Weave::Num | Weave::Int) {
+            return self.error(
+                "The index expression of a deck set operation must be of NumWeave!",
+                token,
+            );
+        }
+
+        // the deck of an empty literal knows nothing of its elements yet
+        let expected = (elem_weave != Weave::Empty).then_some(&elem_weave);
+        let w_value = self.analyze_expression(value, expected)?;
+        if let Some(expected) = expected
+            && !self.can_assign(expected, &w_value.weave())
+        {
+            return self.error(
+                &format!(
+                    "The deck '{}' holds '{}' but was given '{}'",
+                    w_deck.token().lexeme,
+                    expected.get_name(),
+                    w_value.weave().get_name()
+                ),
+                token,
+            );
+        }
+
+        Ok(WovenExpr::DeckSet {
+            deck: Box::new(w_deck),
+            index: Box::new(w_index),
+            weave: w_value.weave(),
+            value: Box::new(w_value),
+            token,
+            span,
+        })
+    }
+
+    fn analyze_attempt(
+        &mut self,
+        token: Token,
+        body: Stmt,
+        error: Token,
+        mend: Stmt,
+        span: Span,
+    ) -> WeaveResult<WovenStmt> {
+        // the error lives in a scope around the mend. Its slot is taken before
+        // the body's, which the closures made by the body may still hold when it is mended
+        let slots = self.begin_scope();
+        let error_slot = self.next_local_slot();
+
+        // the body can break down anywhere, so the mend can't count on anything it assigned
+        let before = self.unassigned.clone();
+        let body_diverges = diverges(&body);
+        let w_body = self.analyze_statement(body)?;
+        let after_body = std::mem::replace(&mut self.unassigned, before);
+
+        let error_symbol = self
+            .symbol_table
+            .define_variable(error.lexeme.to_string(), Weave::Error, false, error_slot, None)
+            .unwrap();
+        self.index_declaration(&error, &error_symbol);
+
+        let mend_diverges = diverges(&mend);
+        let w_mend = self.analyze_body(mend)?;
+        self.join_flows(after_body, body_diverges, mend_diverges);
+
+        self.end_scope(slots);
+
+        Ok(WovenStmt::Attempt {
+            token,
+            body: Box::new(w_body),
+            error_symbol,
+            mend: Box::new(w_mend),
+            span,
+        })
+    }
+
+    fn analyze_judge(
+        &mut self,
+        token: Token,
+        subject: Expr,
+        arms: Vec<(Expr, Stmt)>,
+        divert: Option<Box<Stmt>>,
+        span: Span,
+    ) -> WeaveResult<WovenStmt> {
+        let w_subject = self.analyze_expression(subject, None)?;
+        let weave = w_subject.weave();
+        if !weave.get_tapestry().has_strand(EQUATABLE_STRAND) {
+            return self.error_over(
+                &format!(
+                    "The judged '{}' does not contain the 'Equatable' strand, its arms can't be held against it.",
+                    weave.get_name()
+                ),
+                w_subject.token(),
+                w_subject.span(),
+            );
+        }
+
+        // the judged value is evaluated once, into a local of its own which the arms are held against
+        let slots = self.begin_scope();
+        let subject_slot = self.next_local_slot();
+        let subject_symbol = self
+            .symbol_table
+            .define_variable(
+                "<judged>".to_owned(),
+                weave.clone(),
+                false,
+                subject_slot,
+                None,
+            )
+            .unwrap();
+
+        // without a divert, the flow can also pass by every arm
+        let before = self.unassigned.clone();
+        let mut reached = before.clone();
+        let mut all_diverge = divert.is_some();
+
+        let mut seen: Vec<Value> = vec![];
+        let mut w_arms = vec![];
+        for (value, body) in arms {
+            let w_value = self.analyze_expression(value, Some(&weave))?;
+            let value_weave = w_value.weave();
+            if !self.can_assign(&weave, &value_weave) {
+                return self.error_over(
+                    &format!(
+                        "The arm holds a '{}' while the judged value is a '{}'.",
+                        value_weave.get_name(),
+                        weave.get_name()
+                    ),
+                    w_value.token(),
+                    w_value.span(),
+                );
+            }
+            if let Some(literal) = Self::literal_value(&w_value) {
+                if seen.iter().any(|v| v.equals(&literal)) {
+                    self.warn(
+                        "The arm is never reached, an earlier one holds the same value.",
+                        &w_value.token(),
+                    );
+                } else {
+                    seen.push(literal);
+                }
+            }
+
+            self.unassigned = before.clone();
+            let body_diverges = diverges(&body);
+            let w_body = self.analyze_statement(body)?;
+            let after = std::mem::replace(&mut self.unassigned, reached);
+            self.join_flows(after, body_diverges, all_diverge);
+            reached = std::mem::take(&mut self.unassigned);
+            all_diverge &= body_diverges;
+            w_arms.push((w_value, w_body));
+        }
+
+        let w_divert = match divert {
+            Some(divert) => {
+                self.unassigned = before;
+                let divert_diverges = diverges(&divert);
+                let w_divert = self.analyze_statement(*divert)?;
+                let after = std::mem::replace(&mut self.unassigned, reached);
+                self.join_flows(after, divert_diverges, all_diverge);
+                Some(Box::new(w_divert))
+            }
+            None => {
+                self.unassigned = reached;
+                None
+            }
+        };
+
+        self.end_scope(slots);
+
+        Ok(WovenStmt::Judge {
+            token,
+            subject: w_subject,
+            subject_symbol,
+            arms: w_arms,
+            divert: w_divert,
+            span,
+        })
+    }
+
+    /// Enters the body of a loop, a name already given to a loop around it would be ambiguous
+    fn enter_loop(&mut self, label: &Option<Token>) -> WeaveResult<()> {
+        if let Some(label) = label
+            && self.loops.contains(&Some(label.lexeme.to_string()))
+        {
+            return self.error(
+                &format!(
+                    "The name '{}' is already given to a loop around this one.",
+                    label.lexeme
+                ),
+                label.clone(),
+            );
+        }
+        self.loops.push(label.as_ref().map(|label| label.lexeme.to_string()));
+        Ok(())
+    }
+
+    /// A sever or a flow has to be inside a loop, and can only name one of the loops around it
+    fn check_loop_named(
+        &self,
+        token: &Token,
+        label: &Option<Token>,
+        keyword: &str,
+    ) -> WeaveResult<()> {
+        if self.loops.is_empty() {
+            return self.error(
+                &format!("'{}' cannot be used outside a loop circle!", keyword),
+                token.clone(),
+            );
+        }
+        match label {
+            Some(label) if !self.loops.contains(&Some(label.lexeme.to_string())) => {
+                let names = self.loops.iter().flatten().map(String::as_str);
+                self.error(
+                    &format!(
+                        "There is no loop named '{}' around the '{}'.{}",
+                        label.lexeme,
+                        keyword,
+                        self.did_you_mean(&label.lexeme, names)
+                    ),
+                    label.clone(),
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn next_local_slot(&mut self) -> usize {
+        // the globals are reached by their name, they dont take a slot of the frame
+        if self.symbol_table.get_depth() == 0 {
+            return self.symbol_table.get_current_scope_size();
+        }
+        let slot = self.slot_counter;
+        self.slot_counter += 1;
+        slot
+    }
+
+    /// Opens a scope, returning the slots to go back to once it ends
+    fn begin_scope(&mut self) -> usize {
+        self.symbol_table.new_scope();
+        self.slot_counter
+    }
+
+    /// Analyzes the body of a loop or a mend in a scope of its own, so its marks can shadow the
+    /// ones the statement declares around it
+    fn analyze_body(&mut self, body: Stmt) -> WeaveResult<WovenStmt> {
+        let Stmt::Block { statements, span } = body else {
+            return self.analyze_statement(body);
+        };
+        let slots = self.begin_scope();
+        let statements = self.analyze_statements(statements)?;
+        self.end_scope(slots);
+        Ok(WovenStmt::Block { statements, span })
+    }
+
+    /// Closes the scope, its locals are gone so their slots are free for the next ones
+    fn end_scope(&mut self, slots: usize) {
+        let depth = self.symbol_table.get_depth();
+        self.unassigned.retain(|(_, d)| *d != depth);
+        self.warn_unused(depth);
+        self.end_index_scope();
+        self.symbol_table.end_scope();
+        self.slot_counter = slots;
+    }
+
+    /// Ends the scope of the names declared in the current scope in the index, at the furthest
+    /// line a name was met on so far
+    fn end_index_scope(&mut self) {
+        let depth = self.symbol_table.get_depth();
+        let Some(index) = &mut self.context.index else {
+            return;
+        };
+        for declaration in &mut index.declarations {
+            if declaration.symbol.depth == depth && declaration.lines.1 == usize::MAX {
+                declaration.lines.1 = self.furthest_line.max(declaration.lines.0);
+            }
+        }
+    }
+
+    /// Reports a spell with a release weave whose body can end without releasing. The spell
+    /// stays declared, so its casts are still woven.
+    fn check_releases(&mut self, releases: bool, weave: &Weave, name: &Token) {
+        // a flowing spell ends its stream by falling off the end as well
+        if releases || *weave == Weave::Empty || matches!(weave, Weave::Stream(_)) {
+            return;
+        }
+        let message = format!(
+            "Not every path through the spell '{}' releases the '{}' it promises!",
+            name.lexeme,
+            weave.get_name()
+        );
+        if let Err(e) = self.error::<()>(&message, name.clone()) {
+            let diagnostic = self.to_diagnostic(e);
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    /// Refuses reading a mark which isn't given a value on every path leading to the read
+    fn check_assigned(&self, symbol: &Symbol, name: &Token) -> WeaveResult<()> {
+        let unassigned = self
+            .unassigned
+            .iter()
+            .any(|(n, depth)| *n == symbol.name && *depth == symbol.depth);
+        if unassigned {
+            return self.error(
+                &format!(
+                    "The mark '{}' is read before it is surely given a value!",
+                    name.lexeme
+                ),
+                name.clone(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Merges the marks left unassigned by two paths of the flow, the current one and the other.
+    /// A path which never reaches the end (releasing or severing) doesn't count.
+    fn join_flows(&mut self, other: Vec<(String, usize)>, other_diverges: bool, diverges: bool) {
+        if other_diverges {
+            return;
+        }
+        if diverges {
+            self.unassigned = other;
+            return;
+        }
+        for mark in other {
+            if !self.unassigned.contains(&mark) {
+                self.unassigned.push(mark);
+            }
+        }
+    }
+
+    /// Keeps the mark or spell until its scope ends, to see whether it was ever read
+    /// Notes [name] declaring [symbol] in the index of the scroll, when one is kept
+    fn index_declaration(&mut self, name: &Token, symbol: &Symbol) {
+        self.furthest_line = self.furthest_line.max(name.line);
+        let Some(index) = &mut self.context.index else {
+            return;
+        };
+        index.declarations.push(IndexedSymbol {
+            name: name.clone(),
+            symbol: symbol.clone(),
+            lines: (name.line, usize::MAX),
+        });
+    }
+
+    /// Notes [name] reading [symbol] in the index of the scroll, when one is kept
+    fn index_reference(&mut self, name: &Token, symbol: &Symbol) {
+        self.furthest_line = self.furthest_line.max(name.line);
+        let Some(index) = &mut self.context.index else {
+            return;
+        };
+        // a symbol defined again (or modified) still shares its reads with the declared one
+        if let Some(i) = index
+            .declarations
+            .iter()
+            .rposition(|d| Rc::ptr_eq(&d.symbol.reads, &symbol.reads))
+        {
+            index.references.push((name.clone(), i));
+        }
+    }
+
+    fn declare(&mut self, name: &Token, symbol: &Symbol) {
+        self.index_declaration(name, symbol);
+        self.declarations.push(Declaration {
+            name: name.clone(),
+            symbol: symbol.clone(),
+        });
+    }
+
+    /// Warns about the marks and spells of the ending scope which were never read
+    fn warn_unused(&mut self, depth: usize) {
+        let mut unused = vec![];
+        while self
+            .declarations
+            .last()
+            .is_some_and(|d| d.symbol.depth == depth)
+        {
+            let declaration = self.declarations.pop().unwrap();
+            // a leading '_' tells it is left unused on purpose
+            if declaration.symbol.reads.get() == 0 && !declaration.name.lexeme.starts_with('_') {
+                unused.push(declaration);
+            }
+        }
+
+        for declaration in unused.into_iter().rev() {
+            let name = &declaration.name.lexeme;
+            let msg = match *declaration.symbol.kind.borrow() {
+                SymbolKind::Variable { mutable: true } => format!("The mark '{}' is never read.", name),
+                SymbolKind::Variable { mutable: false } => format!("The bind '{}' is never read.", name),
+                _ => format!("The spell '{}' is never cast.", name),
+            };
+            self.warn(&msg, &declaration.name);
+        }
+    }
+
+    /// Warns when the value released by a cast is left unused by its statement
+    fn warn_ignored_release(&mut self, expr: &WovenExpr) {
+        let cast = matches!(expr, WovenExpr::Cast { .. } | WovenExpr::NativeCast { .. });
+        if cast && expr.weave() != Weave::Empty {
+            self.warn(
+                &format!(
+                    "The '{}' released by the spell is ignored.",
+                    expr.weave().get_name()
+                ),
+                &expr.token(),
+            );
+        }
+    }
+
+    /// Warns when a mark hides a local of the blocks or the spell around it
+    fn warn_shadowing(&mut self, name: &Token) {
+        if let Some(outer) = self.symbol_table.resolve(&name.lexeme)
+            && outer.depth > 0
+            && matches!(*outer.kind.borrow(), SymbolKind::Variable { .. })
+        {
+            self.warn(
+                &format!("The mark '{}' shadows another one from an outer realm.", name.lexeme),
+                name,
+            );
+        }
+    }
+
+    /// Whether the tome refers (directly or through its origins) to the given ancestor tome.
+    fn tome_refers(&self, tome: &str, ancestor: &str) -> bool {
+        let mut current = tome.to_owned();
+        loop {
+            let Some(info) = self
+                .symbol_table
+                .resolve(&current)
+                .and_then(|s| s.kind.borrow().get_sign_info())
+            else {
+                return false;
+            };
+
+            match info.origin {
+                Some(origin) if origin == ancestor => return true,
+                Some(origin) => current = origin,
+                None => return false,
+            }
+        }
+    }
+
+    /// The Empty weave inside a native spell's reagent stands for any weave.
+    fn is_generic_weave(weave: &Weave) -> bool {
+        match weave {
+            Weave::Empty => true,
+            Weave::Deck(inner, _) | Weave::Maybe(inner) => Self::is_generic_weave(inner),
+            _ => false,
+        }
+    }
+
+    fn fits_native_reagent(expected: &Weave, provided: &Weave) -> bool {
+        match (expected, provided) {
+            (Weave::Empty, _) => true,
+            (Weave::Deck(e, None), Weave::Deck(p, _)) => Self::fits_native_reagent(e, p),
+            // a text is measured and walked like a deck of its letters
+            (Weave::Deck(e, None), Weave::Text) => **e == Weave::Empty,
+            (Weave::Maybe(e), Weave::Maybe(p)) => Self::fits_native_reagent(e, p),
+            _ => expected == provided,
+        }
+    }
+
+    fn can_assign(&self, expected: &Weave, provided: &Weave) -> bool {
+        if expected == provided {
+            return true;
+        }
+
+        match (expected, provided) {
+            (Weave::Maybe(ai), b) => {
+                if *b == Weave::Empty {
+                    return true;
+                }
+                ai.as_ref() == provided
+            }
+            // (_, Weave::Maybe(bi)) => self.match_weave(expected, bi),
+            (Weave::Sign(ancestor), Weave::Sign(tome)) => self.tome_refers(tome, ancestor),
+            // a written `Spell<W>` holds any spell releasing W, whatever its reagents
+            (
+                Weave::Spell { reagents: None, release: expected },
+                Weave::Spell { release: provided, .. },
+            ) => expected == provided,
+            _ => false,
+        }
+    }
+
+    /// Weaves the reagents of a cast, checking them against the [signature] of the spell when it's known.
+    /// The reagents left out are filled in with their defaults, and the ones past the fixed reagents
+    /// are gathered into a deck. The named ones are put in their places first.
+    fn analyze_reagents(
+        &mut self,
+        spell_name: &str,
+        (reagents, named): (Vec<Expr>, Vec<(Token, Expr)>),
+        signature: Option<&[WovenReagent]>,
+        token: &Token,
+        span: Span, // of the cast, the reagents left to their defaults take it
+    ) -> WeaveResult<Vec<WovenExpr>> {
+        let reagents = self.place_named_reagents(spell_name, reagents, named, signature, token, span)?;
+        let gathered = signature.and_then(|s| s.last()).filter(|r| r.gathered).cloned();
+        // the reagents given one by one, the rest go to the gathered one
+        let fixed = match (&gathered, signature) {
+            (Some(_), Some(signature)) => signature.len() - 1,
+            _ => usize::MAX,
+        };
+
+        if let Some(signature) = signature {
+            let required = signature
+                .iter()
+                .filter(|r| r.default.is_none() && !r.gathered)
+                .count();
+            let too_many = gathered.is_none() && reagents.len() > signature.len();
+            if reagents.len() < required || too_many {
+                let expected = if gathered.is_some() {
+                    format!("at least {}", required)
+                } else if required == signature.len() {
+                    required.to_string()
+                } else {
+                    format!("{} to {}", required, signature.len())
+                };
+                return self.error(
+                    &format!(
+                        "The spell '{}' expected {} reagents, but you provided {} of them!",
+                        spell_name,
+                        expected,
+                        reagents.len()
+                    ),
+                    token.clone(),
+                );
+            }
+        }
+
+        let mut reagents = reagents.into_iter();
+        let mut woven = Vec::with_capacity(reagents.len());
+        for (i, reagent) in reagents.by_ref().take(fixed).enumerate() {
+            let expected = signature.and_then(|s| s.get(i)).map(|r| &r.weave);
+            let w_reagent = self.analyze_expression(reagent, expected)?;
+            self.check_reagent(expected, &w_reagent, i, token)?;
+            woven.push(w_reagent);
+        }
+
+        for reagent in signature.unwrap_or_default().iter().take(fixed).skip(woven.len()) {
+            woven.push(WovenExpr::Literal {
+                value: reagent.default.clone().unwrap(),
+                token: token.clone(),
+                weave: reagent.weave.clone(),
+                span,
+            });
+        }
+
+        if let Some(gathered) = gathered {
+            let Weave::Deck(inner, _) = &gathered.weave else {
+                return self.error("A gathered reagent is held in a deck", token.clone());
+            };
+            let mut elements = vec![];
+            for reagent in reagents {
+                let w_reagent = self.analyze_expression(reagent, Some(inner))?;
+                self.check_reagent(Some(inner), &w_reagent, fixed + elements.len(), token)?;
+                elements.push(w_reagent);
+            }
+            woven.push(WovenExpr::Deck {
+                elements,
+                weave: gathered.weave,
+                span,
+            });
+        }
+        Ok(woven)
+    }
+
+    /// Puts the reagents given by their names in the places of the spell's reagents of those names.
+    /// The reagents skipped over on the way take their defaults.
+    fn place_named_reagents(
+        &self,
+        spell_name: &str,
+        reagents: Vec<Expr>,
+        named: Vec<(Token, Expr)>,
+        signature: Option<&[WovenReagent]>,
+        token: &Token,
+        span: Span,
+    ) -> WeaveResult<Vec<Expr>> {
+        if named.is_empty() {
+            return Ok(reagents);
+        }
+        let Some(signature) = signature else {
+            return self.error(
+                &format!(
+                    "The reagents of '{}' aren't known by their names, they have to be given in order.",
+                    spell_name
+                ),
+                named[0].0.clone(),
+            );
+        };
+
+        let mut placed: Vec<Option<Expr>> = reagents.into_iter().map(Some).collect();
+        for (name, value) in named {
+            let Some(at) = signature
+                .iter()
+                .position(|r| r.name.as_deref() == Some(&*name.lexeme))
+            else {
+                let names = signature.iter().filter_map(|r| r.name.as_deref());
+                return self.error(
+                    &format!(
+                        "The spell '{}' has no reagent named '{}'.{}",
+                        spell_name,
+                        name.lexeme,
+                        self.did_you_mean(&name.lexeme, names)
+                    ),
+                    name,
+                );
+            };
+            if signature[at].gathered {
+                return self.error(
+                    &format!(
+                        "The gathered reagent '{}' can't be named, its reagents are given in order.",
+                        name.lexeme
+                    ),
+                    name,
+                );
+            }
+            if placed.len() <= at {
+                placed.resize(at + 1, None);
+            }
+            if placed[at].is_some() {
+                return self.error(
+                    &format!("The reagent '{}' was already given.", name.lexeme),
+                    name,
+                );
+            }
+            placed[at] = Some(value);
+        }
+
+        // a named reagent comes after the ones given in order, so every place is within the signature
+        let mut reagents = Vec::with_capacity(placed.len());
+        for (slot, reagent) in placed.into_iter().zip(signature) {
+            match (slot, &reagent.default) {
+                (Some(value), _) => reagents.push(value),
+                (None, Some(default)) => reagents.push(Expr::Literal {
+                    value: default.clone(),
+                    token: token.clone(),
+                    span,
+                }),
+                (None, None) => {
+                    return self.error(
+                        &format!(
+                            "The reagent '{}' of the spell '{}' was left out, while a later one was given.",
+                            reagent.name.as_deref().unwrap_or_default(),
+                            spell_name
+                        ),
+                        token.clone(),
+                    );
+                }
+            }
+        }
+        Ok(reagents)
+    }
+
+    /// Checks the [i]th reagent of a cast against the weave the spell expects there
+    fn check_reagent(
+        &self,
+        expected: Option<&Weave>,
+        reagent: &WovenExpr,
+        i: usize,
+        token: &Token,
+    ) -> WeaveResult<()> {
+        if let Some(expected) = expected
+            && !self.can_assign(expected, &reagent.weave())
+        {
+            return self.mismatch(
+                &format!(
+                    "The reagent #{} was expected to be {}, but got {}",
+                    i + 1,
+                    expected.get_name(),
+                    reagent.weave().get_name()
+                ),
+                token.clone(),
+                reagent.span(),
+                WeaveMismatch::of(expected, &reagent.weave()),
+            );
+        }
+        Ok(())
+    }
+
+    /// The weave a spell holds its reagent in, a gathered reagent holds a deck of them
+    fn reagent_weave(&mut self, reagent: &Reagent) -> WeaveResult<Weave> {
+        let weave = self.analyze_parsed_weave(reagent.weave.clone())?;
+        if reagent.gathered {
+            return Ok(Weave::Deck(Box::new(weave), None));
+        }
+        Ok(weave)
+    }
+
+    /// The value a reagent takes when it's left out, it has to be known before the spell is ever cast
+    fn analyze_reagent_default(
+        &mut self,
+        default: Expr,
+        weave: &Weave,
+        name: &Token,
+    ) -> WeaveResult<Value> {
+        let w_default = self.analyze_expression(default, Some(weave))?;
+        let provided = w_default.weave();
+        let Some(value) = Self::literal_value(&w_default) else {
+            return self.error("The default of a reagent has to be a literal value", name.clone());
+        };
+
+        if !self.can_assign(weave, &provided) {
+            return self.error(
+                &format!(
+                    "The default of the reagent '{}' was expected to be {}, but got {}",
+                    name.lexeme,
+                    weave.get_name(),
+                    provided.get_name()
+                ),
+                name.clone(),
+            );
+        }
+        Ok(value)
+    }
+
+    fn is_arithmetic(operator: &Token) -> bool {
+        matches!(
+            operator.token_type,
+            TokenType::Plus
+                | TokenType::Minus
+                | TokenType::Star
+                | TokenType::Slash
+                | TokenType::Percent
+        )
+    }
+
+    /// The weave the operands of an operation are woven towards. Only ints hold bits, and an int
+    /// wanted from arithmetic is wanted from its operands too.
+    fn operand_hint<'w>(operator: &Token, expected: Option<&'w Weave>) -> Option<&'w Weave> {
+        static INT: Weave = Weave::Int;
+        if matches!(
+            operator.token_type,
+            TokenType::And
+                | TokenType::Or
+                | TokenType::Caret
+                | TokenType::LessLess
+                | TokenType::GreaterGreater
+        ) {
+            return Some(&INT);
+        }
+        expected.filter(|w| Self::is_arithmetic(operator) && Self::wants_int(w))
+    }
+
+    /// Ints and Nums are never mixed in arithmetic, one side is converted with as_int or as_num instead
+    fn check_int_mix(
+        &self,
+        left: &WovenExpr,
+        right: &WovenExpr,
+        operator: &Token,
+    ) -> WeaveResult<()> {
+        let span = right.span();
+        let (left, right) = (left.weave(), right.weave());
+        if !Self::is_arithmetic(operator) || (left == Weave::Int) == (right == Weave::Int) {
+            return Ok(());
+        }
+        self.mismatch(
+            &format!(
+                "Cannot mix '{}' with '{}' in '{}', convert one of them with 'as_int' or 'as_num'.",
+                left.get_name(),
+                right.get_name(),
+                operator.lexeme
+            ),
+            operator.clone(),
+            span,
+            WeaveMismatch::of(&left, &right),
+        )
+    }
+
+    /// Whether a whole number literal is read as an int where the weave is expected
+    fn wants_int(weave: &Weave) -> bool {
+        match weave {
+            Weave::Int => true,
+            Weave::Maybe(inner) => **inner == Weave::Int,
+            _ => false,
+        }
+    }
+
+    /// [expr] turned into a text, the way it is chanted
+    fn chanted_text(expr: WovenExpr, token: &Token) -> WovenExpr {
+        WovenExpr::ToText {
+            token: token.clone(),
+            span: expr.span(),
+            value: Box::new(expr),
+        }
+    }
+
+    /// Only the weaves with a plain chant are joined to a text, the rest go through `to_text` first
+    fn check_chantable(&self, weave: &Weave, operator: &Token) -> WeaveResult<()> {
+        if weave.get_tapestry().has_strand(CHANTABLE_STRAND) {
+            return Ok(());
+        }
+        self.error(
+            &format!(
+                "A '{}' can't be joined to a Text with '+', turn it into one with 'to_text' first.",
+                weave.get_name()
+            ),
+            operator.clone(),
+        )
+    }
+
+    /// Takes a whole Num literal as an Int literal, a negated one too. Anything else is left as it is.
+    fn as_int_literal(expr: WovenExpr) -> WovenExpr {
+        match expr {
+            WovenExpr::Literal {
+                value: Value::Number(n),
+                token,
+                weave,
+                span,
+            } => match int_value(&token.lexeme) {
+                Some(i) => WovenExpr::Literal {
+                    value: Value::Int(i),
+                    token,
+                    weave: Weave::Int,
+                    span,
+                },
+                None => WovenExpr::Literal {
+                    value: Value::Number(n),
+                    token,
+                    weave,
+                    span,
+                },
+            },
+            WovenExpr::Unary {
+                operand,
+                operator,
+                weave,
+                span,
+            } if operator.token_type == TokenType::Minus => {
+                let operand = Self::as_int_literal(*operand);
+                let weave = if operand.weave() == Weave::Int {
+                    Weave::Int
+                } else {
+                    weave
+                };
+                WovenExpr::Unary {
+                    operand: Box::new(operand),
+                    operator,
+                    weave,
+                    span,
+                }
+            }
+            other => other,
+        }
+    }
+
+    /// A literal as it is woven, taken as an Int where an Int is expected
+    fn literal_for(
+        value: Value,
+        token: Token,
+        weave: Weave,
+        expected: Option<&Weave>,
+        span: Span,
+    ) -> WovenExpr {
+        let literal = WovenExpr::Literal {
+            value,
+            token,
+            weave,
+            span,
+        };
+        match expected {
+            Some(expected) if Self::wants_int(expected) => Self::as_int_literal(literal),
+            _ => literal,
+        }
+    }
+
+    /// Weaves the two sides of an operation, a whole Num literal on one side is an Int when the other is
+    /// A fate with its chain of `divert fate`s, which nests as deep as the chain is long. The
+    /// branches are woven one after another rather than one within the other, and nested back
+    /// once they all are
+    fn analyze_fate(&mut self, stmt: Stmt) -> WeaveResult<WovenStmt> {
+        let mut links = vec![]; // the woven condition and branch of every fate, with its flows
+        let mut stmt = stmt;
+        let last_else = loop {
+            let Stmt::Fate {
+                condition,
+                then_branch,
+                else_branch,
+                span,
+            } = stmt
+            else {
+                break Some(stmt);
+            };
+
+            let w_condition = self.analyze_expression(condition, None)?;
+            if !w_condition
+                .weave()
+                .get_tapestry()
+                .has_strand(CONDITIONAL_STRAND)
+            {
+                return self.error_over(
+                    "The condition provided to determine the fate does not contain the 'Conditional' strand.",
+                    w_condition.token(),
+                    w_condition.span(),
+                );
+            }
+            // scoping n stuff will be added by the block!
+            let before = self.unassigned.clone();
+            let then_diverges = diverges(&then_branch);
+            let w_then = self.analyze_statement(*then_branch)?;
+            let after_then = std::mem::replace(&mut self.unassigned, before);
+
+            let else_diverges = else_branch.as_deref().is_some_and(diverges);
+            links.push((w_condition, w_then, span, after_then, then_diverges, else_diverges));
+            match else_branch {
+                Some(e_b) => stmt = *e_b,
+                None => break None,
+            }
+        };
+
+        let mut w_else: Option<Box<WovenStmt>> = match last_else {
+            Some(e_b) => Some(Box::new(self.analyze_statement(e_b)?)),
+            None => None,
+        };
+        // the innermost fate joins its flows first, like it would if it were woven within the others
+        for (w_condition, w_then, span, after_then, then_diverges, else_diverges) in links.into_iter().rev() {
+            self.join_flows(after_then, then_diverges, else_diverges);
+            w_else = Some(Box::new(WovenStmt::Fate {
+                condition: w_condition,
+                then_branch: Box::new(w_then),
+                else_branch: w_else,
+                span,
+            }));
+        }
+        Ok(*w_else.unwrap())
+    }
+
+    /// A chain of operators like `a + b + c` nests to the left as deep as it is long. It is walked
+    /// down to its first operand and woven back up one operator at a time, so that a long chain
+    /// doesn't run out of the stack the way a deep nesting would
+    fn analyze_binary(&mut self, expr: Expr, expected_weave: Option<&Weave>) -> WeaveResult<WovenExpr> {
+        let mut links = vec![]; // the operators with their right operands, the outermost first
+        let mut hint = expected_weave.cloned();
+        let mut expr = expr;
+        while let Expr::Binary { left, right, operator, span } = expr {
+            hint = Self::operand_hint(&operator, hint.as_ref()).cloned();
+            links.push((*right, operator, span, hint.clone()));
+            expr = *left;
+        }
+
+        let mut w_left = self.analyze_expression(expr, hint.as_ref())?;
+        for (right, operator, span, hint) in links.into_iter().rev() {
+            let w_right = self.analyze_expression(right, hint.as_ref())?;
+            let (left, right) = Self::match_int_literals(w_left, w_right);
+            w_left = self.weave_binary(left, right, operator, span)?;
+        }
+        Ok(w_left)
+    }
+
+    /// Checks the [operator] can join the woven operands, and weaves the result
+    fn weave_binary(
+        &mut self,
+        w_left: WovenExpr,
+        w_right: WovenExpr,
+        operator: Token,
+        span: Span,
+    ) -> WeaveResult<WovenExpr> {
+        // a value joined to a text with a '+' has to be chantable, the interpolated ones are
+        // chanted into texts already
+        let (w_left, w_right) = match (w_left.weave(), w_right.weave()) {
+            (Weave::Text, Weave::Text) => (w_left, w_right),
+            (Weave::Text, right) if operator.token_type == TokenType::Plus => {
+                self.check_chantable(&right, &operator)?;
+                (w_left, Self::chanted_text(w_right, &operator))
+            }
+            (left, Weave::Text) if operator.token_type == TokenType::Plus => {
+                self.check_chantable(&left, &operator)?;
+                (Self::chanted_text(w_left, &operator), w_right)
+            }
+            _ => (w_left, w_right),
+        };
+
+        if operator.token_type == TokenType::Plus {
+            let left_has_additive =
+                w_left.weave().get_tapestry().has_strand(ADDITIVE_STRAND);
+            let left_has_concat = w_left
+                .weave()
+                .get_tapestry()
+                .has_strand(CONCATINABLE_STRAND);
+            let right_has_additive =
+                w_right.weave().get_tapestry().has_strand(ADDITIVE_STRAND);
+            let right_has_concat = w_right
+                .weave()
+                .get_tapestry()
+                .has_strand(CONCATINABLE_STRAND);
+
+            // Both must support the same type of operation
+            if (left_has_additive && right_has_additive)
+                || (left_has_concat && right_has_concat)
+            {
+                // Valid operation
+            } else {
+                // the right side is blamed for not fitting the left one, unless the left can't be added at all
+                let (blamed, mismatch) = if left_has_additive || left_has_concat {
+                    (w_right.span(), WeaveMismatch::of(&w_left.weave(), &w_right.weave()))
+                } else {
+                    (w_left.span(), WeaveMismatch::lacking("ADDITIVE", &w_left.weave()))
+                };
+                return self.mismatch(
+                    &format!(
+                        "Cannot perform '+' on '{}' and '{}', it adds two numbers or joins a value to a text.",
+                        w_left.weave().get_name(),
+                        w_right.weave().get_name()
+                    ),
+                    operator,
+                    blamed,
+                    mismatch,
+                );
+            }
+        } else {
+            if let Some(req_strand) = self.strand_from_op(operator.token_type) {
+                let strand = self.strand_string_from_bits(req_strand);
+                for (side, operand) in [("left", &w_left), ("right", &w_right)] {
+                    let weave = operand.weave();
+                    if !weave.get_tapestry().has_strand(req_strand) {
+                        return self.mismatch(
+                            &format!(
+                                "The weave '{}' on the {} of '{}' is not composed of {} strand.",
+                                weave.get_name(),
+                                side,
+                                operator.lexeme,
+                                strand
+                            ),
+                            operator,
+                            operand.span(),
+                            WeaveMismatch::lacking(strand, &weave),
+                        );
+                    }
+                }
+            } else {
+                return self.error(
+                    &format!("Unknown operation '{}'", operator.lexeme),
+                    operator,
+                );
+            }
+        }
+
+        self.check_int_mix(&w_left, &w_right, &operator)?;
+
+        // glyphs only compare against variants of the very same glyph
+        if (matches!(w_left.weave(), Weave::Glyph(_))
+            || matches!(w_right.weave(), Weave::Glyph(_)))
+            && w_left.weave() != w_right.weave()
+        {
+            return self.mismatch(
+                &format!(
+                    "Cannot compare '{}' with '{}'.",
+                    w_left.weave().get_name(),
+                    w_right.weave().get_name()
+                ),
+                operator,
+                w_right.span(),
+                WeaveMismatch::of(&w_left.weave(), &w_right.weave()),
+            );
+        }
+
+        // numbers are ordered by their value and texts by their letters, but never one against the other
+        if self.strand_from_op(operator.token_type) == Some(ORDINAL_STRAND)
+            && w_left.weave() != w_right.weave()
+        {
+            return self.mismatch(
+                &format!(
+                    "Cannot order '{}' against '{}'.",
+                    w_left.weave().get_name(),
+                    w_right.weave().get_name()
+                ),
+                operator,
+                w_right.span(),
+                WeaveMismatch::of(&w_left.weave(), &w_right.weave()),
+            );
+        }
+
+        // values of weaves which can't hold one another are never equal (two maybes are, once both are empty)
+        if matches!(operator.token_type, TokenType::EqualEqual | TokenType::BangEqual) {
+            let (left, right) = (w_left.weave(), w_right.weave());
+            let both_maybe = matches!((&left, &right), (Weave::Maybe(_), Weave::Maybe(_)));
+            if !both_maybe && !self.can_assign(&left, &right) && !self.can_assign(&right, &left) {
+                let outcome = operator.token_type == TokenType::BangEqual;
+                self.warn(
+                    &format!(
+                        "Comparing '{}' with '{}' is always {}, their values are never equal.",
+                        left.get_name(),
+                        right.get_name(),
+                        outcome
+                    ),
+                    &operator,
+                );
+            }
+        }
+
+        // the vm breaks down on it, unless it is never reached
+        if matches!(operator.token_type, TokenType::Slash | TokenType::Percent)
+            && matches!(&w_right, WovenExpr::Literal { value, .. } if value.extract_number() == Some(0.0))
+        {
+            self.warn("Dividing by zero breaks the VM down when it's reached.", &operator);
+        }
+
+        let result_weave = match operator.token_type {
+            TokenType::Greater
+            | TokenType::Less
+            | TokenType::EqualEqual
+            | TokenType::LessEqual
+            | TokenType::GreaterEqual
+            | TokenType::BangEqual => Weave::Truth,
+            TokenType::Plus => {
+                // hard coded for now. Should be dynamic later
+                if w_left.weave().get_tapestry().has_strand(ADDITIVE_STRAND)
+                    && w_right.weave().get_tapestry().has_strand(ADDITIVE_STRAND)
+                {
+                    w_left.weave()
+                } else {
+                    Weave::Text
+                }
+            }
+            _ => w_left.weave(), // Assumes left-hand side's type
+        };
+
+        Ok(WovenExpr::Binary {
+            left: Box::new(w_left),
+            right: Box::new(w_right),
+            operator: operator,
+            weave: result_weave,
+            span,
+        })
+    }
+
+    fn analyze_operands(
+        &mut self,
+        left: Expr,
+        right: Expr,
+        hint: Option<&Weave>,
+    ) -> WeaveResult<(WovenExpr, WovenExpr)> {
+        let left = self.analyze_expression(left, hint)?;
+        let right = self.analyze_expression(right, hint)?;
+        Ok(Self::match_int_literals(left, right))
+    }
+
+    /// A whole Num literal next to an Int is an Int as well, so `i + 1` and `i < 10` stay ints
+    fn match_int_literals(left: WovenExpr, right: WovenExpr) -> (WovenExpr, WovenExpr) {
+        match (left.weave(), right.weave()) {
+            (Weave::Int, Weave::Num) => (left, Self::as_int_literal(right)),
+            (Weave::Num, Weave::Int) => (Self::as_int_literal(left), right),
+            _ => (left, right),
+        }
+    }
+
+    /// The value of an expression known while weaving: a literal, a negated number or a seal
+    /// The index and the weave of the mark [property] of the sign [sign_name]
+    fn sign_mark(&self, sign_name: &str, property: &Token) -> WeaveResult<(u16, Weave)> {
+        let Some(sign_symbol) = self.symbol_table.resolve(sign_name) else {
+            return self.error(
+                &format!("The sign '{}' was not found across the eira realms!", sign_name),
+                property.clone(),
+            );
+        };
+
+        let Some(sign_info) = sign_symbol.kind.borrow().get_sign_info() else {
+            return self.error(&format!("'{}' is not a sign!", sign_symbol.name), property.clone());
+        };
+
+        let (Some(mark), Some(weave)) = (
+            sign_info.schema.get_field_index(property.lexeme.to_string()),
+            sign_info.marks.get(&*property.lexeme),
+        ) else {
+            return self.error(
+                &format!("The mark '{}' is not defined for '{}'", property.lexeme, sign_name),
+                property.clone(),
+            );
+        };
+
+        Ok((mark as u16, weave.clone()))
+    }
+
+    /// The value a mark takes when its sign is drawn without it, a literal value or a seal (just
+    /// like the seals themselves)
+    fn mark_default(&mut self, name: &Token, default: Expr, weave: &Weave) -> WeaveResult<Value> {
+        let w_default = self.analyze_expression(default, Some(weave))?;
+        let given = w_default.weave();
+        if !self.can_assign(weave, &given) {
+            return self.error(
+                &format!(
+                    "The mark '{}' is woven as '{}' but its default is a '{}'",
+                    name.lexeme,
+                    weave.get_name(),
+                    given.get_name()
+                ),
+                name.clone(),
+            );
+        }
+
+        let Some(value) = Self::literal_value(&w_default) else {
+            return self.error(
+                &format!("The default of the mark '{}' must be a literal value or a seal", name.lexeme),
+                name.clone(),
+            );
+        };
+        Ok(value)
+    }
+
+    fn literal_value(expr: &WovenExpr) -> Option<Value> {
+        match expr {
+            WovenExpr::Literal { value, .. } => Some(value.clone()),
+            WovenExpr::Grouping { expression, .. } => Self::literal_value(expression),
+            WovenExpr::Variable { symbol, .. } => symbol.kind.borrow().get_seal_value(),
+            WovenExpr::Unary { operand, operator, .. } if operator.token_type == TokenType::Minus => {
+                match Self::literal_value(operand)? {
+                    Value::Number(n) => Some(Value::Number(-n)),
+                    Value::Int(i) => i.checked_neg().map(Value::Int),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
 
     /// Resolve and add an upvalue for a symbol
     fn resolve_n_add_upvalue(&mut self, symbol: &Symbol) -> WeaveResult<()> {
         // Only capture as upvalue if variable is from the spell's defining scope or outer
-        // Parameters and locals have depth greater than the spell base depth
-        if self.current_realm == Realm::Spell && symbol.depth <= self.spell_base_depth {
-            // check if new. use both index and depth to avoid duplicates
-            let slot = symbol.slot_idx;
-            let is_new = !self
-                .current_upvalues
-                .iter()
-                .any(|it| it.index == slot && it.depth == symbol.depth);
+        // Parameters and locals have depth greater than the spell base depth, globals (depth 0) aren't captured
+        // the spell casting itself is loaded from the running frame instead, and seals are inlined
+        let is_current_spell = matches!(*symbol.kind.borrow(), SymbolKind::Spell(_))
+            && self.spell_stack.last() == Some(&symbol.name);
+        if matches!(*symbol.kind.borrow(), SymbolKind::Seal(_)) {
+            return Ok(());
+        }
 
-            if is_new {
-                self.current_upvalues.push(UpValue {
-                    index: slot,
-                    closed: Value::Emptiness.into(),
-                    depth: symbol.depth,
-                });
+        if self.current_realm == Realm::Spell
+            && symbol.depth > 0
+            && symbol.depth <= self.spell_base_depth
+            && !is_current_spell
+        {
+            Self::add_upvalue(&mut self.current_upvalues, symbol);
+
+            // the spells in between capture it as well, to hand it down
+            for (base_depth, upvalues) in self.enclosing_spells.iter_mut().rev() {
+                if symbol.depth > *base_depth {
+                    break;
+                }
+                Self::add_upvalue(upvalues, symbol);
             }
         }
         Ok(())
     }
 
+    fn add_upvalue(upvalues: &mut Vec<UpValue>, symbol: &Symbol) {
+        // check if new. use both index and depth to avoid duplicates
+        let is_new = !upvalues
+            .iter()
+            .any(|it| it.index == symbol.slot_idx && it.depth == symbol.depth);
+
+        if is_new {
+            upvalues.push(UpValue {
+                index: symbol.slot_idx,
+                depth: symbol.depth,
+            });
+        }
+    }
+
     fn strand_from_op(&self, op: TokenType) -> Option<u64> {
         match op {
             TokenType::Plus => Some(ADDITIVE_STRAND | CONCATINABLE_STRAND),
@@ -2061,12 +4505,25 @@ impl<'a> WeaveAnalyzer<'a> {
             TokenType::Star => Some(MULTIPLICATIVE_STRAND),
             TokenType::Slash => Some(DIVISIVE_STRAND),
             TokenType::Percent => Some(DIVISIVE_STRAND),
-            TokenType::Bang => Some(CONDITIONAL_STRAND),
             TokenType::Greater
             | TokenType::Less
             | TokenType::GreaterEqual
             | TokenType::LessEqual => Some(ORDINAL_STRAND),
             TokenType::EqualEqual | TokenType::BangEqual => Some(EQUATABLE_STRAND),
+            TokenType::And
+            | TokenType::Or
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => Some(BITWISE_STRAND),
+            _ => None,
+        }
+    }
+
+    /// The strand an operand needs for an unary operation, '-' negates and '!' inverts a truth
+    fn unary_strand_from_op(&self, op: TokenType) -> Option<u64> {
+        match op {
+            TokenType::Minus => Some(NEGATABLE_STRAND),
+            TokenType::Bang => Some(CONDITIONAL_STRAND),
             _ => None,
         }
     }
@@ -2086,6 +4543,9 @@ impl<'a> WeaveAnalyzer<'a> {
             EQUATABLE_STRAND => "EQUATABLE",
             CALLABLE_STRAND => "CALLABLE",
             MAYBE_STRAND => "MAYBE",
+            NEGATABLE_STRAND => "NEGATABLE",
+            BITWISE_STRAND => "BITWISE",
+            CHANTABLE_STRAND => "CHANTABLE",
             NO_STRAND => "NONE",
             _ => "UNKNOWN",
         }
@@ -2094,24 +4554,30 @@ impl<'a> WeaveAnalyzer<'a> {
     fn get_weave_from_name(&mut self, name: &str) -> Option<Weave> {
         match name {
             "Num" => Some(Weave::Num),
+            "Int" => Some(Weave::Int),
             "Text" => Some(Weave::Text),
             "Truth" => Some(Weave::Truth),
             "Empty" => Some(Weave::Empty),
+            "Error" => Some(Weave::Error),
             "Sign" => Some(Weave::Sign(String::new())),
             "Spell" => Some(Weave::Spell {
-                // reagents: vec![],
+                reagents: None,
                 release: Box::new(Weave::Empty),
             }),
             "Deck" => Some(Weave::Deck(Box::new(Weave::Empty), None)),
             "Maybe" => Some(Weave::Maybe(Box::new(Weave::Empty))),
+            "Stream" => Some(Weave::Stream(Box::new(Weave::Empty))),
+            "Conjuring" => Some(Weave::Conjuring(Box::new(Weave::Empty))),
             _ => {
                 // match user defined types!
-                let Some(symbol) = self.symbol_table.resolve(&name.to_string()) else {
+                let Some(symbol) = self.symbol_table.resolve(name) else {
                     return None;
                 };
 
                 if symbol.kind.borrow().get_sign_info().is_some() {
                     Some(Weave::Sign(name.to_owned()))
+                } else if symbol.kind.borrow().get_glyph_info().is_some() {
+                    Some(Weave::Glyph(name.to_owned()))
                 } else {
                     None
                 }
@@ -2119,3 +4585,67 @@ impl<'a> WeaveAnalyzer<'a> {
         }
     }
 }
+
+/// Whether the statement never lets the flow reach the one after it
+fn diverges(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Release { .. } | Stmt::Sever { .. } | Stmt::Flow { .. } | Stmt::Doom { .. } => true,
+        Stmt::Block { statements, .. } => statements.iter().any(diverges),
+        Stmt::Judge {
+            arms,
+            divert: Some(divert),
+            ..
+        } => arms.iter().all(|(_, body)| diverges(body)) && diverges(divert),
+        Stmt::Fate {
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => diverges(then_branch) && diverges(else_branch),
+        Stmt::Circle { body, .. } => diverges(body) && !cuts_short(body),
+        _ => false,
+    }
+}
+
+/// Whether every path through the statement ends in a release, or dooms the scroll
+fn always_releases(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Release { .. } | Stmt::Doom { .. } => true,
+        Stmt::Block { statements, .. } => statements.iter().any(always_releases),
+        Stmt::Judge {
+            arms,
+            divert: Some(divert),
+            ..
+        } => arms.iter().all(|(_, body)| always_releases(body)) && always_releases(divert),
+        Stmt::Fate {
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => always_releases(then_branch) && always_releases(else_branch),
+        Stmt::Attempt { body, mend, .. } => always_releases(body) && always_releases(mend),
+        Stmt::Circle { body, .. } => always_releases(body) && !cuts_short(body),
+        _ => false,
+    }
+}
+
+/// Whether a sever or a flow in the statement may skip the rest of it
+fn cuts_short(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Sever { .. } | Stmt::Flow { .. } => true,
+        Stmt::Block { statements, .. } => statements.iter().any(cuts_short),
+        Stmt::Fate {
+            then_branch,
+            else_branch,
+            ..
+        } => cuts_short(then_branch) || else_branch.as_deref().is_some_and(cuts_short),
+        Stmt::Judge { arms, divert, .. } => {
+            arms.iter().any(|(_, body)| cuts_short(body))
+                || divert.as_deref().is_some_and(cuts_short)
+        }
+        Stmt::While { body, .. }
+        | Stmt::Cycle { body, .. }
+        | Stmt::FlowThrough { body, .. }
+        | Stmt::Circle { body, .. } => cuts_short(body),
+        Stmt::Attempt { body, mend, .. } => cuts_short(body) || cuts_short(mend),
+        _ => false,
+    }
+}
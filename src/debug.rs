@@ -1,6 +1,9 @@
+use std::{collections::HashMap, fmt::Write};
+
 use crate::{
+    compiler::compiler::CompiledCode,
     runtime::{Instruction, OpCode},
-    values::Value,
+    values::{Value, value::stringify_value},
 };
 
 pub fn print_instructions(
@@ -48,3 +51,205 @@ pub fn print_byte_code(code: &Vec<u8>) {
     }
     println!("\n==END==\n");
 }
+
+/// Lists the bytecode as text, an instruction a line with its operands decoded. The constants
+/// the instructions load are noted next to them, the jumps land on labels, and the spells among
+/// the constants are listed after the code, each under its own name.
+pub fn disassemble(bytecode: &[u8], constants: &[Value]) -> String {
+    let mut out = String::new();
+    disassemble_spell(&mut out, "<origin>", bytecode, constants, &[]);
+    out
+}
+
+/// Lists the compiled scroll like [disassemble], noting the line and column each instruction
+/// comes from when the scroll was compiled with debug info
+pub fn disassemble_code(code: &CompiledCode) -> String {
+    let positions = code.debug.as_ref().map_or(&[][..], |debug| &debug.positions);
+    let mut out = String::new();
+    disassemble_spell(&mut out, "<origin>", &code.bytecode, &code.constants, positions);
+    out
+}
+
+fn disassemble_spell(
+    out: &mut String,
+    name: &str,
+    bytecode: &[u8],
+    constants: &[Value],
+    positions: &[(usize, usize, usize)],
+) {
+    let _ = writeln!(out, "== {} ==", name);
+
+    let mut decoded = vec![];
+    let mut pos = 0;
+    while pos < bytecode.len() {
+        let Some(inst) = Instruction::decode(&bytecode[pos..]) else {
+            let _ = writeln!(out, "{:04}  <malformed byte {:02X}>", pos, bytecode[pos]);
+            break;
+        };
+        decoded.push((pos, inst));
+        pos += inst.len();
+    }
+
+    // the labels are numbered in the order they appear
+    let mut targets: Vec<usize> = decoded
+        .iter()
+        .filter_map(|(pos, inst)| jump_target(*pos, inst))
+        .collect();
+    targets.sort_unstable();
+    targets.dedup();
+
+    for (pos, inst) in decoded {
+        if let Ok(label) = targets.binary_search(&pos) {
+            let _ = writeln!(out, "L{}:", label);
+        }
+        if let Ok(i) = positions.binary_search_by(|(offset, _, _)| offset.cmp(&pos)) {
+            let (_, line, column) = positions[i];
+            let _ = writeln!(out, "      ; {}:{}", line, column);
+        }
+
+        let mut operands: Vec<String> = inst.operands().iter().map(u32::to_string).collect();
+        // a jump landing where no instruction starts keeps its raw offset
+        let label = jump_target(pos, &inst).and_then(|target| targets.binary_search(&target).ok());
+        if let (Some(label), Some(offset)) = (label, operands.last_mut()) {
+            *offset = format!("L{}", label);
+        }
+
+        let mut line = format!("{:04}  {:?}", pos, inst.opcode()).to_uppercase();
+        for operand in operands {
+            line.push(' ');
+            line.push_str(&operand);
+        }
+
+        match inst.loaded_constant().and_then(|idx| constants.get(idx)) {
+            Some(constant) => {
+                let _ = writeln!(out, "{:<32}; {}", line, describe_constant(constant));
+            }
+            None => {
+                let _ = writeln!(out, "{}", line);
+            }
+        }
+    }
+    if let Ok(label) = targets.binary_search(&bytecode.len()) {
+        let _ = writeln!(out, "L{}:", label);
+    }
+
+    for constant in constants {
+        let spell = match constant {
+            Value::Spell(spell) => spell,
+            Value::Closure(closure) => &closure.spell,
+            _ => continue,
+        };
+        let name = spell.name.as_deref().unwrap_or("<unnamed>");
+        let positions = spell.debug.as_ref().map_or(&[][..], |debug| &debug.positions);
+        out.push('\n');
+        disassemble_spell(out, name, &spell.bytecode, &spell.constants, positions);
+    }
+}
+
+/// Where the jump at [pos] lands, as a bytecode offset
+fn jump_target(pos: usize, inst: &Instruction) -> Option<usize> {
+    let end = pos + inst.len();
+    match *inst {
+        Instruction::Jump { offset }
+        | Instruction::JumpIfFalse { offset, .. }
+        | Instruction::Attempt { offset, .. } => Some(end + offset as usize),
+        Instruction::Loop { offset } => end.checked_sub(offset as usize),
+        Instruction::JumpLong { offset }
+        | Instruction::JumpIfFalseLong { offset, .. }
+        | Instruction::AttemptLong { offset, .. } => Some(end + offset as usize),
+        Instruction::LoopLong { offset } => end.checked_sub(offset as usize),
+        _ => None,
+    }
+}
+
+fn describe_constant(constant: &Value) -> String {
+    match constant {
+        Value::String(text) => format!("{:?}", text.as_str()),
+        Value::Spell(spell) => format!("Spell '{}'", spell.name.as_deref().unwrap_or("<unnamed>")),
+        Value::Closure(closure) => {
+            format!("Spell '{}'", closure.spell.name.as_deref().unwrap_or("<unnamed>"))
+        }
+        other => stringify_value(other),
+    }
+}
+
+/// Assembles a listing written like the ones of [disassemble] back into bytecode, for the first
+/// spell in it. The offsets in front of the instructions and the notes after a ';' are skipped,
+/// and a jump can take its offset as a number or land on a label. Handy to write the bytecode
+/// of the VM tests by hand.
+pub fn assemble(listing: &str) -> Result<Vec<u8>, String> {
+    // (line, opcode, offset right after the instruction, operands as written)
+    let mut lines: Vec<(usize, OpCode, usize, Vec<&str>)> = vec![];
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut pos = 0;
+
+    for (n, line) in listing.lines().enumerate() {
+        let line_no = n + 1;
+        let line = line.split(';').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        // the listings of the nested spells follow under their own header
+        if line.starts_with("==") {
+            if !lines.is_empty() || !labels.is_empty() {
+                break;
+            }
+            continue;
+        }
+        if let Some(label) = line.strip_suffix(':') {
+            if labels.insert(label.trim(), pos).is_some() {
+                return Err(format!("Line {}: The label '{}' is placed twice", line_no, label));
+            }
+            continue;
+        }
+
+        let mut words = line.split_whitespace().peekable();
+        words.next_if(|word| word.chars().all(|c| c.is_ascii_digit()));
+        let Some(name) = words.next() else {
+            return Err(format!("Line {}: An instruction was expected", line_no));
+        };
+        let Some(op) = OpCode::from_name(name) else {
+            return Err(format!("Line {}: Unknown instruction '{}'", line_no, name));
+        };
+        pos += op.inst_len();
+        lines.push((line_no, op, pos, words.collect()));
+    }
+
+    let mut code = Vec::with_capacity(pos);
+    for (line_no, op, end, words) in lines {
+        let is_jump = matches!(op, OpCode::Jump | OpCode::JumpIfFalse | OpCode::Attempt | OpCode::Loop);
+        let is_long_jump = matches!(
+            op,
+            OpCode::JumpLong | OpCode::JumpIfFalseLong | OpCode::AttemptLong | OpCode::LoopLong
+        );
+        let mut operands = Vec::with_capacity(words.len());
+
+        for (i, word) in words.iter().enumerate() {
+            if let Ok(operand) = word.parse::<u32>() {
+                operands.push(operand);
+                continue;
+            }
+            let Some(&target) = labels.get(word) else {
+                return Err(format!("Line {}: '{}' is neither a number nor a label", line_no, word));
+            };
+            if !(is_jump || is_long_jump) || i + 1 != words.len() {
+                return Err(format!("Line {}: Only the offset of a jump can be a label", line_no));
+            }
+            // loops jump backwards, the rest forwards
+            let offset = match op {
+                OpCode::Loop | OpCode::LoopLong => end.checked_sub(target),
+                _ => target.checked_sub(end),
+            };
+            let reach = if is_long_jump { u32::MAX as usize } else { u16::MAX as usize };
+            let Some(offset) = offset.filter(|o| *o <= reach) else {
+                return Err(format!("Line {}: The label '{}' can't be reached from here", line_no, word));
+            };
+            operands.push(offset as u32);
+        }
+
+        let inst = Instruction::from_operands(op, &operands)
+            .map_err(|e| format!("Line {}: {}", line_no, e))?;
+        code.extend(inst.get_byte_code());
+    }
+    Ok(code)
+}
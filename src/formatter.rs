@@ -0,0 +1,452 @@
+use crate::{
+    Parser, Scanner,
+    compiler::{diagnostics::EiraError, token_type::TokenType},
+};
+
+const INDENT: &str = "    ";
+
+/// Lays the scroll out in the canonical way: four spaces for every block, spaces around the
+/// operators and a line for every statement. The comments and the texts are kept as written,
+/// and only a scroll which parses gets formatted.
+pub fn format_source(source: &str) -> Result<String, EiraError> {
+    format_named_source(source, "<scroll>")
+}
+
+/// Formats the source like [format_source], the errors pointing at the scroll by its name
+pub fn format_named_source(source: &str, name: &str) -> Result<String, EiraError> {
+    Parser::new(Scanner::init(source).tokenize(), name.to_string()).parse()?;
+    Ok(Layout::new().lay_out(&pieces(source)))
+}
+
+/// The bits the scroll is laid out from. The scanner leaves the comments behind and splits the
+/// texts into their interpolations, so those two are cut out of the source as they are.
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Token(TokenType, String),
+    Text(String), // with its quotes and interpolations
+    Comment(String),
+    BlockComment(String), // kept as written, it can sit in the middle of a line
+    Newlines(usize),
+}
+
+fn pieces(source: &str) -> Vec<Piece> {
+    let mut pieces = vec![];
+    let mut code = String::new(); // the code since the last whitespace, comment or text
+
+    let mut i = 0;
+    while i < source.len() {
+        let rest = &source[i..];
+        let c = rest.chars().next().unwrap();
+        let quoted = c == '"' || c == '\'';
+
+        let comment = rest.starts_with("//") || rest.starts_with("/*");
+        if c.is_whitespace() || quoted || comment {
+            scan_code(&mut code, &mut pieces);
+        }
+
+        let len = if c == '\n' {
+            match pieces.last_mut() {
+                Some(Piece::Newlines(count)) => *count += 1,
+                _ => pieces.push(Piece::Newlines(1)),
+            }
+            1
+        } else if c.is_whitespace() {
+            c.len_utf8()
+        } else if rest.starts_with("//") {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            pieces.push(Piece::Comment(rest[..end].trim_end().to_string()));
+            end
+        } else if rest.starts_with("/*") {
+            let end = block_comment_len(rest);
+            pieces.push(Piece::BlockComment(rest[..end].to_string()));
+            end
+        } else if quoted {
+            let end = rest[1..].find(c).map_or(rest.len(), |end| end + 2);
+            pieces.push(Piece::Text(rest[..end].to_string()));
+            end
+        } else {
+            code.push(c);
+            c.len_utf8()
+        };
+        i += len;
+    }
+    scan_code(&mut code, &mut pieces);
+
+    pieces
+}
+
+/// The length of the block comment [rest] starts with, the comments nested in it included
+fn block_comment_len(rest: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < rest.len() {
+        if rest[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if rest[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += rest[i..].chars().next().unwrap().len_utf8();
+        }
+    }
+    rest.len()
+}
+
+fn scan_code(code: &mut String, pieces: &mut Vec<Piece>) {
+    if code.is_empty() {
+        return;
+    }
+    for token in Scanner::init(code).tokenize() {
+        if token.token_type != TokenType::Eof {
+            pieces.push(Piece::Token(token.token_type, token.lexeme.to_string()));
+        }
+    }
+    code.clear();
+}
+
+/// How the insides of a pair of braces are laid out
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Braces {
+    Block,  // a statement on each line
+    Marks,  // the body of a sign or a tome, a mark or a spell on each line
+    Arms,   // the arms of a judge, an arm on each line
+    Inline, // the variants of a glyph, or the marks of a drawn material
+}
+
+struct Layout {
+    out: String,
+    indent: usize,
+    braces: Vec<Braces>,
+    nesting: usize, // the parens and squares left open, the lines don't break inside them
+    conditionals: usize, // the `?` still waiting for their `:`
+    angles: usize,  // the `<` of a weave left open
+    reagents_at: Option<usize>, // the nesting of the cast whose reagents are being written
+    in_weave: bool,
+    statement: Option<TokenType>, // the first token of the current statement
+    previous: Option<TokenType>,
+    tight: bool,        // whether the previous token sticks to the next one
+    pending_line: bool, // the next token goes on a line of its own
+    blank: bool,        // and a blank line is left before it
+    same_line: bool,    // no newline was met since the previous token
+}
+
+impl Layout {
+    fn new() -> Self {
+        Layout {
+            out: String::new(),
+            indent: 0,
+            braces: vec![],
+            nesting: 0,
+            conditionals: 0,
+            angles: 0,
+            reagents_at: None,
+            in_weave: false,
+            statement: None,
+            previous: None,
+            tight: true,
+            pending_line: false,
+            blank: false,
+            same_line: true,
+        }
+    }
+
+    fn lay_out(mut self, pieces: &[Piece]) -> String {
+        let mut i = 0;
+        while i < pieces.len() {
+            match &pieces[i] {
+                Piece::Newlines(count) => {
+                    self.same_line = false;
+                    let block_opened = self.previous == Some(TokenType::BraceLeft);
+                    if *count > 1 && self.pending_line && self.statement.is_none() && !block_opened
+                    {
+                        self.blank = true;
+                    }
+                }
+                Piece::Comment(comment) => {
+                    if self.same_line && !self.out.is_empty() {
+                        self.out.push(' ');
+                    } else {
+                        self.start_line();
+                    }
+                    self.out.push_str(comment);
+                    self.pending_line = true;
+                }
+                Piece::BlockComment(comment) => {
+                    let own_line = !self.same_line || self.out.is_empty();
+                    let ended_line = self.pending_line;
+                    if own_line {
+                        self.start_line();
+                    } else if !self.out.ends_with(' ') {
+                        self.out.push(' ');
+                    }
+                    self.out.push_str(comment);
+                    // a comment on a line of its own keeps it, the code carries on after it otherwise
+                    self.pending_line = own_line || ended_line;
+                    self.tight = false;
+                    self.same_line = true;
+                }
+                Piece::Text(text) => self.write(TokenType::String, text, false),
+                Piece::Token(TokenType::BraceLeft, _) => {
+                    let braces = self.braces_kind();
+                    // an empty block stays on its line
+                    if braces != Braces::Inline
+                        && next_token(pieces, i + 1) == Some(TokenType::BraceRight)
+                    {
+                        self.write(TokenType::BraceLeft, "{", false);
+                        self.write(TokenType::BraceRight, "}", true);
+                        self.close_statement();
+                        i = pieces[i + 1..]
+                            .iter()
+                            .position(|p| matches!(p, Piece::Token(TokenType::BraceRight, _)))
+                            .map_or(i, |at| i + 1 + at);
+                    } else {
+                        self.write(TokenType::BraceLeft, "{", false);
+                        self.braces.push(braces);
+                        if braces != Braces::Inline {
+                            self.indent += 1;
+                            self.close_statement();
+                        }
+                    }
+                }
+                Piece::Token(TokenType::BraceRight, _) => {
+                    let braces = self.braces.pop().unwrap_or(Braces::Block);
+                    if braces == Braces::Inline {
+                        let empty = self.previous == Some(TokenType::BraceLeft);
+                        self.write(TokenType::BraceRight, "}", empty);
+                        // a glyph ends with its variants, without a `;`
+                        if self.statement == Some(TokenType::Glyph) {
+                            self.close_statement();
+                        }
+                    } else {
+                        self.indent = self.indent.saturating_sub(1);
+                        self.pending_line = true;
+                        self.blank = false;
+                        self.statement = None;
+                        self.write(TokenType::BraceRight, "}", false);
+                        self.close_statement();
+                    }
+                }
+                Piece::Token(token_type, lexeme) => {
+                    self.token(*token_type, lexeme, next_token(pieces, i + 1));
+                }
+            }
+            i += 1;
+        }
+
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+
+    fn braces_kind(&self) -> Braces {
+        match (self.previous, self.statement) {
+            (Some(TokenType::With), _) | (_, Some(TokenType::Glyph)) => Braces::Inline,
+            (_, Some(TokenType::Sign | TokenType::Tome)) => Braces::Marks,
+            (_, Some(TokenType::Judge)) => Braces::Arms,
+            _ => Braces::Block,
+        }
+    }
+
+    fn token(&mut self, token_type: TokenType, lexeme: &str, next: Option<TokenType>) {
+        // the braces laid out on one line don't need a trailing comma
+        if token_type == TokenType::Comma
+            && next == Some(TokenType::BraceRight)
+            && self.braces.last() == Some(&Braces::Inline)
+        {
+            return;
+        }
+
+        let operand = self.previous.is_some_and(is_operand) && !self.tight;
+        let weave_angle = self.in_weave
+            && (token_type == TokenType::Less
+                || (matches!(token_type, TokenType::Greater | TokenType::GreaterGreater)
+                    && self.angles > 0));
+
+        let tight_before = match token_type {
+            TokenType::ParenRight
+            | TokenType::SquareRight
+            | TokenType::Comma
+            | TokenType::SemiColon
+            | TokenType::Dot
+            | TokenType::DotDot
+            | TokenType::QuestionDot
+            | TokenType::ColonColon => true,
+            TokenType::Colon => self.conditionals == 0,
+            TokenType::Bang => operand, // asserting a maybe, not a negation
+            TokenType::ParenLeft => self.previous == Some(TokenType::Identifier),
+            TokenType::SquareLeft => operand,
+            TokenType::Less | TokenType::Greater | TokenType::GreaterGreater => weave_angle,
+            _ => false,
+        };
+        let tight_after = match token_type {
+            TokenType::ParenLeft
+            | TokenType::SquareLeft
+            | TokenType::Dot
+            | TokenType::DotDot
+            | TokenType::DotDotDot
+            | TokenType::QuestionDot
+            | TokenType::Tilde => true,
+            TokenType::Minus | TokenType::Bang => !operand,
+            TokenType::Less => weave_angle,
+            _ => false,
+        };
+
+        // `} divert {`, `} mend e {` and `} until cond;` carry on the line of the brace, the divert
+        // arm of a judge doesn't
+        if matches!(
+            token_type,
+            TokenType::Divert | TokenType::Mend | TokenType::Until
+        ) && self.out.ends_with('}')
+            && self.braces.last() != Some(&Braces::Arms)
+        {
+            self.pending_line = false;
+            self.blank = false;
+        }
+
+        self.write(token_type, lexeme, tight_before);
+        self.tight = tight_after;
+
+        match token_type {
+            TokenType::ParenLeft | TokenType::SquareLeft => self.nesting += 1,
+            TokenType::ParenRight | TokenType::SquareRight => {
+                self.nesting = self.nesting.saturating_sub(1);
+                if self.reagents_at.is_some_and(|at| at > self.nesting) {
+                    self.reagents_at = None;
+                }
+            }
+            // the marks of a drawn material are written in braces
+            TokenType::With if next != Some(TokenType::BraceLeft) => {
+                self.reagents_at = Some(self.nesting)
+            }
+            TokenType::SemiColon if self.nesting == 0 => self.close_statement(),
+            TokenType::Comma if self.nesting == 0 && self.braces.last() == Some(&Braces::Marks) => {
+                self.close_statement()
+            }
+            TokenType::QuestionMark => self.conditionals += 1,
+            TokenType::Colon if self.conditionals > 0 => self.conditionals -= 1,
+            // a reagent given by its name
+            TokenType::Colon if self.reagents_at == Some(self.nesting) => {}
+            // the name of a loop isn't followed by a weave
+            TokenType::Colon
+                if self.nesting == 0
+                    && matches!(
+                        self.statement,
+                        Some(
+                            TokenType::While
+                                | TokenType::Cycle
+                                | TokenType::Flow
+                                | TokenType::Circle
+                        )
+                    ) => {}
+            TokenType::Colon if self.braces.last() != Some(&Braces::Inline) || self.nesting > 0 => {
+                self.in_weave = true
+            }
+            TokenType::ColonColon => self.in_weave = true,
+            _ => {}
+        }
+
+        // the weave ends with its name, unless the name is followed by the inner weave
+        let weave_opened = matches!(
+            token_type,
+            TokenType::Colon | TokenType::ColonColon | TokenType::DotDotDot
+        );
+        if self.in_weave && !weave_opened {
+            match token_type {
+                TokenType::Less => self.angles += 1,
+                TokenType::Greater => self.angles = self.angles.saturating_sub(1),
+                // two weaves closed at once
+                TokenType::GreaterGreater => self.angles = self.angles.saturating_sub(2),
+                TokenType::Identifier | TokenType::Comma | TokenType::Number => {}
+                _ => self.angles = 0,
+            }
+            let continues = self.angles > 0 || next == Some(TokenType::Less);
+            let in_weave_token = matches!(
+                token_type,
+                TokenType::Identifier
+                    | TokenType::Less
+                    | TokenType::Greater
+                    | TokenType::GreaterGreater
+                    | TokenType::Comma
+                    | TokenType::Number
+            );
+            if !continues || !in_weave_token {
+                self.in_weave = false;
+                self.angles = 0;
+            }
+        }
+    }
+
+    /// Writes the token on the current line, or on a new one if it was asked for
+    fn write(&mut self, token_type: TokenType, lexeme: &str, tight_before: bool) {
+        if self.pending_line {
+            self.start_line();
+        } else if !self.out.is_empty() && !self.out.ends_with('\n') && !tight_before && !self.tight
+        {
+            self.out.push(' ');
+        }
+        self.out.push_str(lexeme);
+
+        if self.statement.is_none() {
+            self.statement = Some(token_type);
+        }
+        self.previous = Some(token_type);
+        self.tight = false;
+        self.same_line = true;
+    }
+
+    fn start_line(&mut self) {
+        if !self.out.is_empty() {
+            self.out.push('\n');
+            if self.blank {
+                self.out.push('\n');
+            }
+        }
+        // a statement broken by a comment carries on a level deeper
+        let indent = self.indent + usize::from(self.statement.is_some());
+        self.out.push_str(&INDENT.repeat(indent));
+        self.pending_line = false;
+        self.blank = false;
+    }
+
+    fn close_statement(&mut self) {
+        self.pending_line = true;
+        self.statement = None;
+        self.reagents_at = None;
+        self.in_weave = false;
+        self.conditionals = 0;
+    }
+}
+
+fn next_token(pieces: &[Piece], from: usize) -> Option<TokenType> {
+    pieces[from..].iter().find_map(|piece| match piece {
+        Piece::Token(token_type, _) => Some(*token_type),
+        Piece::Text(_) => Some(TokenType::String),
+        // not skipped over, the comment needs its place
+        Piece::Comment(_) | Piece::BlockComment(_) => Some(TokenType::Error),
+        Piece::Newlines(_) => None,
+    })
+}
+
+/// Whether the token ends a value, which makes a `-` or `!` after it binary or postfix
+fn is_operand(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Identifier
+            | TokenType::Number
+            | TokenType::String
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Ego
+            | TokenType::Origin
+            | TokenType::Underscore
+            | TokenType::ParenRight
+            | TokenType::SquareRight
+            | TokenType::BraceRight
+            | TokenType::Bang
+    )
+}
@@ -1,8 +1,14 @@
 pub mod assembler;
 pub mod ast_printer;
+pub mod bytecode_file;
 pub mod compiler;
 pub mod debug;
+pub mod formatter;
+pub mod lsp;
+pub mod optimizer;
+pub mod program;
 pub mod runtime;
+pub mod test_runner;
 pub mod values;
 pub mod project;
 
@@ -14,7 +20,13 @@ pub use compiler::weave_analyser::WeaveAnalyzer;
 pub use values::spell::{ClosureObject, SpellObject};
 pub use values::value::{Value, ValueType};
 
-pub use runtime::vm::EiraVM;
+pub use runtime::debugger::{DebugCommand, DebugFrontend, Debugger};
+pub use runtime::collector::GcStats;
+pub use runtime::vm::{EiraVM, ErrorKind, RuntimeError, StepHook, VmState};
 
-pub use ast_printer::{print_ast, print_woven_ast};
-pub use debug::{print_byte_code, print_instructions};
+pub use program::{Program, SharedProgram, compile};
+
+pub use ast_printer::{ast_to_json, print_ast, print_woven_ast, woven_ast_to_json};
+pub use formatter::{format_named_source, format_source};
+pub use test_runner::{TestResult, find_tests, results_to_json, run_test};
+pub use debug::{assemble, disassemble, disassemble_code, print_byte_code, print_instructions};
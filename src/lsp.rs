@@ -0,0 +1,408 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use serde_json::{Value as Json, json};
+
+use crate::{
+    Parser, Scanner, WeaveAnalyzer,
+    compiler::{
+        diagnostics::{Diagnostic, Severity},
+        scanner::{KEYWORDS, Token},
+        symbol_table::{IndexedSymbol, SymbolIndex, SymbolKind},
+        weave_analyser::{BUILTIN_WEAVES, WeaveAnalyzerContext},
+    },
+};
+
+// the kinds of the completion items, as numbered by the protocol
+const KIND_FUNCTION: u8 = 3;
+const KIND_VARIABLE: u8 = 6;
+const KIND_ENUM: u8 = 13;
+const KIND_KEYWORD: u8 = 14;
+const KIND_CONSTANT: u8 = 21;
+const KIND_STRUCT: u8 = 22;
+const KIND_TYPE_PARAMETER: u8 = 25;
+
+/// Speaks the language server protocol to the editors: the problems of a scroll are told as it
+/// is written, the names are followed to their declarations and completed with the ones in scope.
+///
+/// The scrolls are parsed and woven again on every change. The statements which don't parse
+/// are left out of the weaving, so the names in the rest of the scroll are still known.
+pub struct LanguageServer {
+    indices: HashMap<String, SymbolIndex>, // the names of the open scrolls, by their uri
+    texts: HashMap<String, String>,        // the text of the open scrolls, to count their positions in
+    exited: bool,
+}
+
+impl Default for LanguageServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanguageServer {
+    pub fn new() -> Self {
+        LanguageServer {
+            indices: HashMap::new(),
+            texts: HashMap::new(),
+            exited: false,
+        }
+    }
+
+    /// Whether the client told the server to exit
+    pub fn exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Handles a message of the client, returns the messages to send back: the response of a
+    /// request and the diagnostics published for the changed scrolls
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    // the characters of a line are counted in UTF-16 units, the one encoding every client knows
+                    "positionEncoding": "utf-16",
+                    "textDocumentSync": 1, // the whole text is sent on every change
+                    "definitionProvider": true,
+                    "completionProvider": {},
+                },
+                "serverInfo": { "name": "eira-lsp" },
+            }),
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                return self.update(document["uri"].as_str(), document["text"].as_str());
+            }
+            "textDocument/didChange" => {
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                return self.update(params["textDocument"]["uri"].as_str(), text);
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.indices.remove(uri);
+                self.texts.remove(uri);
+                return vec![publish(uri, vec![])];
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/completion" => self.completion(params),
+            "shutdown" => Json::Null,
+            "exit" => {
+                self.exited = true;
+                return vec![];
+            }
+            _ => {
+                // the notifications the server has no use for are let go
+                let Some(id) = id else {
+                    return vec![];
+                };
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": -32601, "message": format!("'{}' is not known to eira", method) },
+                })];
+            }
+        };
+
+        match id {
+            Some(id) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            None => vec![],
+        }
+    }
+
+    /// Weaves the changed scroll again, and publishes the problems found in it
+    fn update(&mut self, uri: Option<&str>, text: Option<&str>) -> Vec<Json> {
+        let (Some(uri), Some(text)) = (uri, text) else {
+            return vec![];
+        };
+
+        let (diagnostics, index) = analyze(&path_of(uri), text);
+        self.indices.insert(uri.to_string(), index);
+        self.texts.insert(uri.to_string(), text.to_string());
+
+        vec![publish(uri, diagnostics.iter().map(|d| to_lsp_diagnostic(d, text)).collect())]
+    }
+
+    /// The location of the declaration of the name under the cursor
+    fn definition(&self, params: &Json) -> Json {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let (Some(index), Some(text)) = (self.indices.get(uri), self.texts.get(uri)) else {
+            return Json::Null;
+        };
+        let (line, character) = position(&params["position"], text);
+
+        // the cursor can also sit right after the name
+        let declaration = index
+            .declaration_at(line + 1, character + 1)
+            .or_else(|| index.declaration_at(line + 1, character));
+
+        match declaration {
+            Some(declaration) => json!({ "uri": uri, "range": range_of(&declaration.name, text) }),
+            None => Json::Null,
+        }
+    }
+
+    /// The keywords, the weaves and the names in scope on the line of the cursor
+    fn completion(&self, params: &Json) -> Json {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        // the line is all that's needed, its characters aren't counted
+        let line = params["position"]["line"].as_u64().unwrap_or_default() as usize;
+
+        let mut items: Vec<Json> = KEYWORDS
+            .iter()
+            .map(|keyword| json!({ "label": keyword, "kind": KIND_KEYWORD }))
+            .chain(
+                BUILTIN_WEAVES
+                    .iter()
+                    .map(|weave| json!({ "label": weave, "kind": KIND_TYPE_PARAMETER })),
+            )
+            .collect();
+
+        let Some(index) = self.indices.get(uri) else {
+            return Json::Array(items);
+        };
+
+        // the innermost of the shadowing names is the one in scope
+        let mut names: HashMap<&str, &IndexedSymbol> = HashMap::new();
+        for symbol in index.visible_on(line + 1) {
+            if names.get(&*symbol.name.lexeme).is_none_or(|s| s.symbol.depth <= symbol.symbol.depth) {
+                names.insert(&symbol.name.lexeme, symbol);
+            }
+        }
+
+        let mut names: Vec<(&str, &IndexedSymbol)> = names.into_iter().collect();
+        names.sort_by_key(|(name, _)| *name);
+        items.extend(names.into_iter().map(|(name, symbol)| {
+            json!({
+                "label": name,
+                "kind": completion_kind(&symbol.symbol.kind.borrow()),
+                "detail": symbol.symbol.weave.get_name(),
+            })
+        }));
+
+        Json::Array(items)
+    }
+}
+
+/// Parses and weaves the scroll, returns the problems found and the names it declares and reads
+fn analyze(path: &str, text: &str) -> (Vec<Diagnostic>, SymbolIndex) {
+    let tokens = Scanner::init(text).tokenize();
+    let (ast, parse_problems) = Parser::new(tokens, path.to_string()).parse_partial();
+
+    let mut context = WeaveAnalyzerContext::new(path.to_string(), None, false);
+    context.index = Some(SymbolIndex::default());
+    let (woven, warnings) = {
+        let mut analyzer = WeaveAnalyzer::new(&mut context);
+        let woven = analyzer.analyze(ast);
+        (woven, analyzer.take_warnings())
+    };
+    let index = context.index.take().unwrap_or_default();
+
+    // the weaving misses the statements which didn't parse, its problems would only be echoes
+    if !parse_problems.is_empty() {
+        return (parse_problems, index);
+    }
+
+    let mut diagnostics = match woven {
+        Ok(_) => vec![],
+        Err(e) => e.diagnostics().to_vec(),
+    };
+    diagnostics.extend(warnings);
+    (diagnostics, index)
+}
+
+/// Serves the client talking over [input] and [output] (the standard streams of the server)
+/// until it exits
+pub fn serve(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut server = LanguageServer::new();
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            // a message which can't be made sense of is told to the client, the ones after it still can
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                let error = json!({ "code": -32700, "message": e.to_string() });
+                write_message(&mut output, &json!({ "jsonrpc": "2.0", "id": null, "error": error }))?;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        for reply in server.handle(&message) {
+            write_message(&mut output, &reply)?;
+        }
+        if server.exited() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a message of the client, a `Content-Length` header and the JSON it counts.
+/// None once the client hangs up, an error of [io::ErrorKind::InvalidData] when the message is malformed
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Json>> {
+    let mut length: Option<usize> = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        // the body of a message without its length runs into the header of the next one
+        if let Some((_, value)) = header.split_once("Content-Length:") {
+            length = value.trim().parse().ok();
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "A message came without its 'Content-Length'"));
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn publish(uri: &str, diagnostics: Vec<Json>) -> Json {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+/// The diagnostic as the protocol has it, underlining its span in [text] when it has one
+fn to_lsp_diagnostic(diagnostic: &Diagnostic, text: &str) -> Json {
+    let underline = match &diagnostic.location {
+        Some(location) if let Some(span) = location.span => {
+            json!({ "start": position_of(text, span.start), "end": position_of(text, span.end) })
+        }
+        Some(location) => {
+            // the columns point at the end of the token, like the lines they are 1 based
+            let length = location.length.unwrap_or(1).max(1);
+            let start = location.column.saturating_sub(length);
+            let line = location.line.saturating_sub(1);
+            range(line, utf16_column(text, line, start), utf16_column(text, line, start + length))
+        }
+        None => range(0, 0, 0),
+    };
+    let severity = match diagnostic.severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Info => 3,
+    };
+    // the editors have no place next to the underline, the label goes under the message
+    let message = match &diagnostic.label {
+        Some(label) => format!("{}\n{}", diagnostic.message, label),
+        None => diagnostic.message.clone(),
+    };
+
+    json!({
+        "range": underline,
+        "severity": severity,
+        "source": "eira",
+        "message": message,
+    })
+}
+
+/// The range of the token in [text]
+fn range_of(token: &Token, text: &str) -> Json {
+    let length = token.lexeme.chars().count();
+    let line = token.line.saturating_sub(1);
+    let start = token.column.saturating_sub(length);
+    range(line, utf16_column(text, line, start), utf16_column(text, line, token.column))
+}
+
+fn range(line: usize, start: usize, end: usize) -> Json {
+    json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": line, "character": end },
+    })
+}
+
+/// The position of the protocol [offset] bytes into [text]
+fn position_of(text: &str, offset: usize) -> Json {
+    let before = text.get(..offset).unwrap_or(text);
+    let line = before.matches('\n').count();
+    let character = before.rsplit('\n').next().unwrap_or_default().encode_utf16().count();
+    json!({ "line": line, "character": character })
+}
+
+/// The 0 based (line, character) of a position of the protocol in [text], its character counted
+/// in letters like the columns of the tokens
+fn position(position: &Json, text: &str) -> (usize, usize) {
+    let line = position["line"].as_u64().unwrap_or_default() as usize;
+    let units = position["character"].as_u64().unwrap_or_default() as usize;
+
+    let mut counted = 0;
+    let mut letters = 0;
+    for letter in text.lines().nth(line).unwrap_or_default().chars() {
+        if counted >= units {
+            return (line, letters);
+        }
+        counted += letter.len_utf16();
+        letters += 1;
+    }
+    // past the end of the line, every unit is a letter of its own
+    (line, letters + units.saturating_sub(counted))
+}
+
+/// The UTF-16 units the first [letters] of the [line] of [text] take, as the protocol counts
+/// the characters. The columns of the tokens are counted in letters
+fn utf16_column(text: &str, line: usize, letters: usize) -> usize {
+    let line = text.lines().nth(line).unwrap_or_default();
+    let units: usize = line.chars().take(letters).map(char::len_utf16).sum();
+    units + letters.saturating_sub(line.chars().count())
+}
+
+fn completion_kind(kind: &SymbolKind) -> u8 {
+    match kind {
+        SymbolKind::Variable { .. } => KIND_VARIABLE,
+        SymbolKind::Spell(_) => KIND_FUNCTION,
+        SymbolKind::Sign(_) => KIND_STRUCT,
+        SymbolKind::Glyph(_) => KIND_ENUM,
+        SymbolKind::Seal(_) => KIND_CONSTANT,
+    }
+}
+
+/// The path of the scroll a `file://` uri points at, the tethers are found next to it
+fn path_of(uri: &str) -> String {
+    let Some(path) = uri.strip_prefix("file://") else {
+        return uri.to_string();
+    };
+
+    let mut bytes = vec![];
+    let mut rest = path.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        let escaped = (byte == b'%')
+            .then(|| after.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &after[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = after;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
@@ -1,22 +1,82 @@
-use std::path::Path;
+use std::{collections::BTreeSet, io::Write, path::Path, process::ExitCode};
 
 use eira::{
-    EiraVM,
-    compiler::compiler::{Compiler, CompilerOptions},
+    DebugCommand, DebugFrontend, Debugger, EiraVM, VmState, bytecode_file, find_tests, format_named_source,
+    results_to_json, run_test,
+    compiler::{
+        compiler::{Compiler, CompilerOptions},
+        diagnostics::{DEFAULT_ERROR_LIMIT, Diagnostic},
+    },
     project::config::Project,
 };
 
-fn main() {
+const USAGE: &str = "Usage: eira [run|check|build|debug|fmt|test] [scroll.eira] [options]
+
+Commands:
+    run      compile and run the scroll (default), or run a precompiled .eirac scroll
+    check    only parse and weave the scroll, without running it
+    build    precompile the scroll into a .eirac file next to it
+    debug    run the scroll under the debugger, paused on its first line
+    fmt      lay the scroll out in the canonical way, rewriting it in place
+    test     run the *.test.eira scrolls of a folder (the current one by default)
+
+Options:
+    --dump-ast          print the AST (same as --past), --dump-ast=json prints it as JSON
+    --dump-bytecode     print the bytecode (same as --pbc)
+    --quiet             suppress the debug output and banners
+    --max-errors=N      stop after N errors
+    --deny-warnings     fail the compilation on warnings, like on errors
+    --trace             print every instruction the VM runs, with its registers and casts
+    --gc-stress         look for cycles of objects on every write, and tell what was freed at the end
+    --strip-debug       leave the columns and the names of the locals out of the compiled scroll
+    --check             with fmt, only tell whether the scroll is formatted, without rewriting it
+    --json              with test, print the results as JSON for the CI
+    --ptkn, --past=N, --pwast=N, --pinst, --pbc
+                        print tokens, ast, woven ast, instructions or bytecode,
+                        N is the verbosity (0-3) or json";
+
+const DEBUG_HELP: &str = "Commands:
+    c, continue     run until a breakpoint
+    s, step         run until the next line, stepping into the casts
+    n, next         run until the next line, stepping over the casts
+    b, break N      pause on line N, or list the breakpoints without N
+    d, delete N     remove the breakpoint on line N
+    p, print NAME   show the local or global named NAME
+    locals          show the locals in scope
+    globals         show the globals defined so far
+    bt, where       show the casts leading here
+    q, quit         stop the scroll";
+
+#[derive(PartialEq)]
+enum Command {
+    Run,
+    Check,
+    Build,
+    Debug,
+    Fmt,
+    Test,
+}
+
+fn main() -> ExitCode {
     // let start = Instant::now();
-    let mut args = std::env::args().collect::<Vec<String>>();
+    let mut args = std::env::args().skip(1).collect::<Vec<String>>();
 
     let mut compiler_options = CompilerOptions {
         print_tokens: false,
         print_ast: None,
         print_woven_ast: None,
+        ast_json: false,
         print_instructions: false,
         print_bytecode: false,
+        error_limit: DEFAULT_ERROR_LIMIT,
+        debug_info: true,
+        deny_warnings: false,
     };
+    let mut quiet = false;
+    let mut trace = false;
+    let mut gc_stress = false;
+    let mut check_format = false;
+    let mut json = false;
 
     let mut i = 0;
 
@@ -29,22 +89,45 @@ fn main() {
             let arg = &arg.replace("--", "");
             if *arg == "ptkn".to_owned() {
                 compiler_options.print_tokens = true;
-            } else if arg.starts_with("past") {
-                let verbosity = arg
-                    .strip_prefix("past=")
-                    .and_then(|v| v.parse().ok())
-                    .unwrap_or(0);
-                compiler_options.print_ast = Some(verbosity);
+            } else if arg.starts_with("past") || arg.starts_with("dump-ast") {
+                let verbosity = arg.split_once('=').map(|(_, v)| v);
+                compiler_options.ast_json |= verbosity == Some("json");
+                compiler_options.print_ast =
+                    Some(verbosity.and_then(|v| v.parse().ok()).unwrap_or(0));
             } else if arg.starts_with("pwast") {
-                let verbosity = arg
-                    .strip_prefix("pwast=")
-                    .and_then(|v| v.parse().ok())
-                    .unwrap_or(0);
-                compiler_options.print_woven_ast = Some(verbosity);
+                let verbosity = arg.strip_prefix("pwast=");
+                compiler_options.ast_json |= verbosity == Some("json");
+                compiler_options.print_woven_ast =
+                    Some(verbosity.and_then(|v| v.parse().ok()).unwrap_or(0));
             } else if *arg == "pinst".to_owned() {
                 compiler_options.print_instructions = true;
-            } else if *arg == "pbc".to_owned() {
+            } else if *arg == "pbc" || *arg == "dump-bytecode" {
                 compiler_options.print_bytecode = true;
+            } else if *arg == "quiet" {
+                quiet = true;
+            } else if *arg == "trace" {
+                trace = true;
+            } else if *arg == "gc-stress" {
+                gc_stress = true;
+            } else if *arg == "check" {
+                check_format = true;
+            } else if *arg == "json" {
+                json = true;
+            } else if *arg == "deny-warnings" {
+                compiler_options.deny_warnings = true;
+            } else if *arg == "strip-debug" {
+                compiler_options.debug_info = false;
+            } else if arg.starts_with("max-errors") {
+                compiler_options.error_limit = arg
+                    .strip_prefix("max-errors=")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_ERROR_LIMIT);
+            } else if *arg == "help" {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            } else {
+                eprintln!("Unknown option '--{}'.\n\n{}", arg, USAGE);
+                return ExitCode::FAILURE;
             }
             args.remove(i);
         } else {
@@ -52,8 +135,45 @@ fn main() {
         }
     }
 
+    // the command can be left out, running the scroll is what one usually wants
+    let command = match args.first().map(|a| a.as_str()) {
+        Some("check") => {
+            args.remove(0);
+            Command::Check
+        }
+        Some("build") => {
+            args.remove(0);
+            Command::Build
+        }
+        Some("run") => {
+            args.remove(0);
+            Command::Run
+        }
+        Some("debug") => {
+            args.remove(0);
+            Command::Debug
+        }
+        Some("fmt") => {
+            args.remove(0);
+            Command::Fmt
+        }
+        Some("test") => {
+            args.remove(0);
+            Command::Test
+        }
+        _ => Command::Run,
+    };
+
+    if quiet {
+        compiler_options.print_tokens = false;
+        compiler_options.print_ast = None;
+        compiler_options.print_woven_ast = None;
+        compiler_options.print_instructions = false;
+        compiler_options.print_bytecode = false;
+    }
+
     let project_root = Project::find_root(Path::new(
-        &args.get(1).cloned().unwrap_or_else(|| ".".to_string()),
+        &args.first().cloned().unwrap_or_else(|| ".".to_string()),
     ));
 
     let project = if let Some(root) = project_root {
@@ -62,24 +182,276 @@ fn main() {
         None
     };
 
-    let target_file_path = if let Some(path) = args.get(1) {
+    if command == Command::Test {
+        let dir = args.first().cloned().unwrap_or_else(|| ".".to_string());
+        return run_tests(&dir, project, json, quiet);
+    }
+
+    let target_file_path = if let Some(path) = args.first() {
         path.clone()
     } else if let Some(proj) = &project {
         proj.entry_point.clone()
     } else {
-        eprintln!("No source file provided and no project configuration found.");
-        return;
+        eprintln!("No source file provided and no project configuration found.\n\n{}", USAGE);
+        return ExitCode::FAILURE;
     };
 
+    if command == Command::Fmt {
+        return format_scroll(&target_file_path, check_format, quiet);
+    }
+
+    // precompiled scrolls skip the compiler entirely
+    if command != Command::Check && command != Command::Build && target_file_path.ends_with(".eirac") {
+        return match EiraVM::init_from_file(Path::new(&target_file_path)) {
+            Ok(mut vm) => {
+                if command == Command::Debug {
+                    vm.attach_debugger(Debugger::new(CliDebugger::new(None)));
+                }
+                run(vm, trace, gc_stress)
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
     let compiler = Compiler::new(target_file_path.clone(), compiler_options, project);
 
-    let compiled = compiler.compile_to_bytecode();
+    if command == Command::Check {
+        match compiler.check() {
+            Ok(warnings) => print_warnings(&warnings),
+            Err(err) => {
+                eprintln!("{}", err);
+                return ExitCode::FAILURE;
+            }
+        }
+        if !quiet {
+            println!("The scroll '{}' is woven well!", target_file_path);
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let compiled = match compiler.compile_to_bytecode() {
+        Ok(compiled) => {
+            print_warnings(&compiled.warnings);
+            compiled
+        }
+        Err(err) => {
+            if !quiet {
+                eprintln!("The eira was cursed during the compilation of the scroll.");
+            }
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
 
-    if compiled.is_err() {
-        eprintln!("The eira was cursed during the compilation of the scroll.");
-        eprintln!("{}", compiled.err().unwrap().msg);
-        return;
+    if command == Command::Build {
+        let output = Path::new(&target_file_path).with_extension("eirac");
+        if let Err(err) = bytecode_file::write_file(&compiled, &output) {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+        if !quiet {
+            println!("The scroll was bound into '{}'", output.display());
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let mut vm = EiraVM::init(compiled);
+    if command == Command::Debug {
+        let source = std::fs::read_to_string(&target_file_path).ok();
+        vm.attach_debugger(Debugger::new(CliDebugger::new(source)));
     }
+    run(vm, trace, gc_stress)
+}
 
-    EiraVM::init(compiled.ok().unwrap()).start();
+fn format_scroll(path: &str, check: bool, quiet: bool) -> ExitCode {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("Couldn't read the scroll '{}': {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let formatted = match format_named_source(&source, path) {
+        Ok(formatted) => formatted,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if formatted == source {
+        if !quiet {
+            println!("The scroll '{}' is already formatted.", path);
+        }
+        return ExitCode::SUCCESS;
+    }
+    if check {
+        eprintln!("The scroll '{}' isn't formatted.", path);
+        return ExitCode::FAILURE;
+    }
+    if let Err(err) = std::fs::write(path, formatted) {
+        eprintln!("Couldn't write the scroll '{}': {}", path, err);
+        return ExitCode::FAILURE;
+    }
+    if !quiet {
+        println!("The scroll '{}' was formatted.", path);
+    }
+    ExitCode::SUCCESS
+}
+
+fn run_tests(dir: &str, project: Option<Project>, json: bool, quiet: bool) -> ExitCode {
+    let scrolls = match find_tests(Path::new(dir)) {
+        Ok(scrolls) => scrolls,
+        Err(err) => {
+            eprintln!("Couldn't look for the tests in '{}': {}", dir, err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut results = vec![];
+    for scroll in &scrolls {
+        let result = run_test(scroll, project.clone());
+        if !json && (!quiet || !result.passed()) {
+            let verdict = if result.passed() { "ok" } else { "FAILED" };
+            println!("test {} ... {}", scroll.display(), verdict);
+        }
+        results.push(result);
+    }
+
+    let failed: Vec<_> = results.iter().filter(|r| !r.passed()).collect();
+    if json {
+        println!("{}", results_to_json(&results));
+    } else {
+        for result in &failed {
+            println!("\n---- {} ----", result.scroll.display());
+            print!("{}", result.output);
+            println!("{}", result.failure.as_deref().unwrap_or_default());
+        }
+        if scrolls.is_empty() {
+            println!("No tests were found in '{}'.", dir);
+        } else {
+            println!("\n{} passed, {} failed.", results.len() - failed.len(), failed.len());
+        }
+    }
+
+    if failed.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn print_warnings(warnings: &[Diagnostic]) {
+    for warning in warnings {
+        eprintln!("{}", warning);
+    }
+}
+
+fn run(mut vm: EiraVM, trace: bool, gc_stress: bool) -> ExitCode {
+    vm.set_trace(trace);
+    vm.set_gc_stress(gc_stress);
+    let result = vm.start();
+    if gc_stress {
+        vm.collect_garbage();
+        let stats = vm.gc_stats();
+        eprintln!(
+            "Collected {} times, freeing {} objects.",
+            stats.collections, stats.freed
+        );
+    }
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            println!("Oh no! The VM broke down. \n{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Talks to the user debugging the scroll through the terminal
+struct CliDebugger {
+    source: Vec<String>, // the lines of the scroll, when it wasn't precompiled
+}
+
+impl CliDebugger {
+    fn new(source: Option<String>) -> Self {
+        let source = source.map_or(vec![], |source| source.lines().map(str::to_string).collect());
+        CliDebugger { source }
+    }
+
+    fn show_value(name: &str, value: Option<&eira::Value>) {
+        match value {
+            Some(value) => println!("{} = {}", name, eira::values::value::stringify_value(value)),
+            None => println!("Nothing named '{}' is in scope.", name),
+        }
+    }
+}
+
+impl DebugFrontend for CliDebugger {
+    fn paused(&mut self, state: &VmState, breakpoints: &mut BTreeSet<usize>) -> DebugCommand {
+        match self.source.get(state.line.wrapping_sub(1)) {
+            Some(text) => println!("Paused in '{}' at line {}:  {}", state.spell, state.line, text.trim()),
+            None => println!("Paused in '{}' at line {}", state.spell, state.line),
+        }
+
+        loop {
+            print!("(eira) ");
+            let _ = std::io::stdout().flush();
+
+            let mut input = String::new();
+            // once the input runs dry, the scroll runs to its end
+            if std::io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+                breakpoints.clear();
+                return DebugCommand::Continue;
+            }
+
+            let mut words = input.split_whitespace();
+            let command = words.next().unwrap_or("step");
+            let argument = words.next();
+            match (command, argument) {
+                ("c" | "continue", _) => return DebugCommand::Continue,
+                ("s" | "step", _) => return DebugCommand::Step,
+                ("n" | "next", _) => return DebugCommand::StepOver,
+                ("b" | "break", None) => {
+                    let lines: Vec<String> = breakpoints.iter().map(usize::to_string).collect();
+                    println!("Breakpoints: {}", lines.join(", "));
+                }
+                ("b" | "break", Some(line)) => match line.parse() {
+                    Ok(line) => {
+                        breakpoints.insert(line);
+                    }
+                    Err(_) => println!("'{}' isn't a line.", line),
+                },
+                ("d" | "delete", Some(line)) => match line.parse() {
+                    Ok(line) => {
+                        breakpoints.remove(&line);
+                    }
+                    Err(_) => println!("'{}' isn't a line.", line),
+                },
+                ("p" | "print", Some(name)) => {
+                    Self::show_value(name, state.local(name).or_else(|| state.global(name)))
+                }
+                ("locals", _) => {
+                    for (name, value) in state.locals() {
+                        Self::show_value(name, Some(value));
+                    }
+                }
+                ("globals", _) => {
+                    for (name, value) in state.globals() {
+                        Self::show_value(name, Some(value));
+                    }
+                }
+                ("bt" | "where", _) => {
+                    for (name, line) in &state.frames {
+                        println!("    in '{}' at line {}", name, line);
+                    }
+                }
+                ("q" | "quit", _) => std::process::exit(0),
+                _ => println!("{}", DEBUG_HELP),
+            }
+        }
+    }
 }
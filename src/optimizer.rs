@@ -0,0 +1,478 @@
+use crate::{
+    runtime::Instruction,
+    values::{Value, spell::Capture},
+};
+
+/// The optimized instructions, the instructions their jumps land on and their source positions
+pub type Optimized = (Vec<Instruction>, Vec<Option<usize>>, Vec<(usize, usize)>);
+
+/// Peephole passes over the instructions of a spell, run right before they are assembled.
+///
+/// Jumps are followed by the index of the instruction they land on, their byte offsets are only
+/// worked out by the assembler, so instructions can be dropped freely.
+pub struct Optimizer {
+    nodes: Vec<Node>,
+    // whether closures capture registers of the frame, which they may read on any cast
+    captures: bool,
+    // whether the spell mends attempts, which may read whatever the attempt left in its registers
+    mends: bool,
+}
+
+struct Node {
+    inst: Instruction,
+    position: Option<(usize, usize)>, // (line, column) in the source
+    // index of the instruction a jump lands on, the length of the spell when it jumps past the end
+    target: Option<usize>,
+}
+
+impl Optimizer {
+    /// Optimizes [instructions], returns them along with the instructions their jumps land on
+    /// and their source positions
+    pub fn optimize(
+        instructions: &[Instruction],
+        targets: &[Option<usize>],
+        positions: &[(usize, usize)],
+        constants: &[Value],
+    ) -> Optimized {
+        let nodes = instructions
+            .iter()
+            .zip(targets)
+            .enumerate()
+            .map(|(i, (inst, target))| Node {
+                inst: *inst,
+                position: positions.get(i).copied(),
+                target: *target,
+            })
+            .collect();
+        let captures = Self::captures_registers(instructions, constants);
+        let mends = instructions
+            .iter()
+            .any(|inst| matches!(inst, Instruction::Attempt { .. } | Instruction::AttemptLong { .. }));
+        let mut optimizer = Optimizer { nodes, captures, mends };
+
+        // every pass drops at least one instruction when it changes something, so this ends
+        while optimizer.run_passes() {}
+
+        let mut instructions = Vec::with_capacity(optimizer.nodes.len());
+        let mut targets = Vec::with_capacity(optimizer.nodes.len());
+        let mut positions = Vec::with_capacity(optimizer.nodes.len());
+        for node in optimizer.nodes {
+            instructions.push(node.inst);
+            targets.push(node.target);
+            positions.extend(node.position);
+        }
+        (instructions, targets, positions)
+    }
+
+    fn run_passes(&mut self) -> bool {
+        let targets = self.jump_targets();
+        let mut removed = vec![false; self.nodes.len()];
+        let mut changed = false;
+
+        for i in 0..self.nodes.len() {
+            if removed[i] {
+                continue;
+            }
+            let next_joins = i + 1 < self.nodes.len() && !targets[i + 1] && !removed[i + 1];
+
+            match &self.nodes[i].inst {
+                // jumping to the very next instruction does nothing
+                Instruction::Jump { .. }
+                | Instruction::JumpIfFalse { .. }
+                | Instruction::JumpLong { .. }
+                | Instruction::JumpIfFalseLong { .. }
+                    if self.nodes[i].target == Some(i + 1) =>
+                {
+                    removed[i] = true;
+                }
+
+                Instruction::Move { dest, source } if *dest as u16 == *source => {
+                    removed[i] = true;
+                }
+
+                // a load nothing reads again before it is overwritten, an attempt breaking down
+                // midway may jump to a mend reading it though
+                Instruction::Constant { dest, .. }
+                | Instruction::ConstantLong { dest, .. }
+                | Instruction::Move { dest, .. }
+                | Instruction::True { dest }
+                | Instruction::False { dest }
+                | Instruction::Emptiness { dest }
+                    if !self.mends && !self.is_read_after(i + 1, *dest) =>
+                {
+                    removed[i] = true;
+                }
+
+                _ if next_joins => {
+                    let Some(merged) = self.combine(i) else {
+                        continue;
+                    };
+                    self.nodes[i].inst = merged;
+                    removed[i + 1] = true;
+                }
+
+                _ => continue,
+            }
+            changed = true;
+        }
+
+        if changed {
+            self.compact(&removed);
+        }
+        changed
+    }
+
+    /// Merges the instruction at [i] with the one after it, if they can be
+    fn combine(&self, i: usize) -> Option<Instruction> {
+        let next = &self.nodes[i + 1].inst;
+
+        match (&self.nodes[i].inst, next) {
+            // `a >= b` is emitted as `!(a < b)` and so on, these get an opcode of their own
+            (Instruction::Less { dest, r1, r2 }, Instruction::Not { dest: d, r1: s })
+                if d == dest && s == dest =>
+            {
+                Some(Instruction::GreaterEqual { dest: *dest, r1: *r1, r2: *r2 })
+            }
+            (Instruction::Greater { dest, r1, r2 }, Instruction::Not { dest: d, r1: s })
+                if d == dest && s == dest =>
+            {
+                Some(Instruction::LessEqual { dest: *dest, r1: *r1, r2: *r2 })
+            }
+            (Instruction::Equal { dest, r1, r2 }, Instruction::Not { dest: d, r1: s })
+                if d == dest && s == dest =>
+            {
+                Some(Instruction::NotEqual { dest: *dest, r1: *r1, r2: *r2 })
+            }
+
+            // `i = i + 1` loads the step into a temporary on every pass, the step is read from the pool instead
+            (Instruction::Constant { dest: temp, const_index }, Instruction::Add { dest, r1, r2 })
+                if (r1 == temp) != (r2 == temp) =>
+            {
+                let r1 = if r1 == temp { *r2 } else { *r1 };
+                self.frees(i + 2, *temp, *dest)
+                    .then_some(Instruction::AddConst { dest: *dest, r1, const_index: *const_index })
+            }
+            (Instruction::Constant { dest: temp, const_index }, Instruction::Subtract { dest, r1, r2 })
+                if r2 == temp && r1 != temp =>
+            {
+                self.frees(i + 2, *temp, *dest)
+                    .then_some(Instruction::SubtractConst { dest: *dest, r1: *r1, const_index: *const_index })
+            }
+
+            // a temporary that is only moved into a local can be written into the local directly
+            (inst, Instruction::Move { dest, source }) => {
+                let temp = Self::dest(inst)?;
+                if temp as u16 != *source || self.is_read_after(i + 2, temp) {
+                    return None;
+                }
+                Self::retarget(inst, *dest)
+            }
+
+            _ => None,
+        }
+    }
+
+    //--------------- Register flow ---------------
+
+    /// Whether any closure made by [instructions] captures a register of the frame
+    fn captures_registers(instructions: &[Instruction], constants: &[Value]) -> bool {
+        instructions.iter().any(|inst| match inst {
+            Instruction::Constant { .. } | Instruction::ConstantLong { .. } => matches!(
+                inst.loaded_constant().and_then(|index| constants.get(index)),
+                Some(Value::Closure(c)) if c.spell.captures.iter().any(|c| matches!(c, Capture::Local(_)))
+            ),
+            _ => false,
+        })
+    }
+
+    /// Whether the temporary [temp] is done with once [dest] is written, before [start]
+    fn frees(&self, start: usize, temp: u8, dest: u8) -> bool {
+        temp == dest || !self.is_read_after(start, temp)
+    }
+
+    /// Whether [reg] may be read before it is written again, on any path starting at [start]
+    fn is_read_after(&self, start: usize, reg: u8) -> bool {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut pending = vec![start];
+
+        while let Some(i) = pending.pop() {
+            // falling off the end leaves the frame
+            if i >= self.nodes.len() || visited[i] {
+                continue;
+            }
+            visited[i] = true;
+
+            let node = &self.nodes[i];
+            if self.reads(&node.inst, reg) {
+                return true;
+            }
+            if Self::dest(&node.inst) == Some(reg) {
+                continue;
+            }
+
+            match &node.inst {
+                Instruction::Release { .. } | Instruction::Halt { .. } | Instruction::Throw { .. } => {}
+                Instruction::Jump { .. }
+                | Instruction::Loop { .. }
+                | Instruction::JumpLong { .. }
+                | Instruction::LoopLong { .. } => {
+                    pending.extend(node.target);
+                }
+                Instruction::JumpIfFalse { .. }
+                | Instruction::Attempt { .. }
+                | Instruction::JumpIfFalseLong { .. }
+                | Instruction::AttemptLong { .. } => {
+                    pending.extend(node.target);
+                    pending.push(i + 1);
+                }
+                _ => pending.push(i + 1),
+            }
+        }
+        false
+    }
+
+    fn reads(&self, inst: &Instruction, reg: u8) -> bool {
+        match inst {
+            Instruction::Add { r1, r2, .. }
+            | Instruction::Subtract { r1, r2, .. }
+            | Instruction::Multiply { r1, r2, .. }
+            | Instruction::Divide { r1, r2, .. }
+            | Instruction::Mod { r1, r2, .. }
+            | Instruction::AddInt { r1, r2, .. }
+            | Instruction::SubtractInt { r1, r2, .. }
+            | Instruction::MultiplyInt { r1, r2, .. }
+            | Instruction::DivideInt { r1, r2, .. }
+            | Instruction::ModInt { r1, r2, .. }
+            | Instruction::BitAnd { r1, r2, .. }
+            | Instruction::BitOr { r1, r2, .. }
+            | Instruction::BitXor { r1, r2, .. }
+            | Instruction::ShiftLeft { r1, r2, .. }
+            | Instruction::ShiftRight { r1, r2, .. }
+            | Instruction::Equal { r1, r2, .. }
+            | Instruction::Greater { r1, r2, .. }
+            | Instruction::Less { r1, r2, .. }
+            | Instruction::GreaterEqual { r1, r2, .. }
+            | Instruction::LessEqual { r1, r2, .. }
+            | Instruction::NotEqual { r1, r2, .. }
+            | Instruction::Concat { r1, r2, .. } => *r1 == reg || *r2 == reg,
+
+            Instruction::Negate { r1, .. }
+            | Instruction::Not { r1, .. }
+            | Instruction::AddConst { r1, .. }
+            | Instruction::SubtractConst { r1, .. }
+            | Instruction::IsEmptiness { r1, .. }
+            | Instruction::Length { r1, .. }
+            | Instruction::ToText { r1, .. }
+            | Instruction::Print { r1 }
+            | Instruction::PrintRaw { r1 }
+            | Instruction::AssertSafe { r1 } => *r1 == reg,
+
+            Instruction::Constant { .. } | Instruction::ConstantLong { .. } => false,
+
+            Instruction::SetGlobal { src_reg, .. } => *src_reg == reg,
+            Instruction::Move { source, .. } => *source == reg as u16,
+            Instruction::JumpIfFalse { condition_reg, .. }
+            | Instruction::JumpIfFalseLong { condition_reg, .. } => *condition_reg == reg,
+            // the arity isn't known here, so every register from the first reagent on is in use
+            // and the closures it casts may read the registers they captured
+            Instruction::Cast { spell_reg, reg_start, .. } | Instruction::Conjure { spell_reg, reg_start, .. } => {
+                *spell_reg == reg || reg >= *reg_start || self.captures
+            }
+            // the conjurings run on while the frame waits, reading the registers they captured
+            Instruction::Claim { handle, .. } => *handle == reg || self.captures,
+            // the captured registers are closed over as the frame ends
+            Instruction::Release { dest } => *dest == reg || self.captures,
+            Instruction::Halt { src } => *src == reg || self.captures,
+            Instruction::Throw { r1 } => *r1 == reg || self.captures,
+            // the captured registers are closed over as the stream is suspended too
+            Instruction::Offer { src } => *src == reg || self.captures,
+            // the drawn stream may be a closure reading the registers it captured
+            Instruction::Draw { stream, .. } => *stream == reg || self.captures,
+            Instruction::SetUpvalue { src, .. } => *src == reg,
+            Instruction::CloseUpvalues { from } => reg >= *from,
+            Instruction::NewSign { schema_reg, .. } => *schema_reg == reg,
+            Instruction::SetField { sign_reg, val_reg, .. } => *sign_reg == reg || *val_reg == reg,
+            Instruction::GetField { sign_reg, .. }
+            | Instruction::SafeGetField { sign_reg, .. }
+            | Instruction::GetAttunement { sign_reg, .. } => *sign_reg == reg,
+            Instruction::NewDeck { start_reg, count, .. }
+            | Instruction::NewFixedDeck { start_reg, count, .. } => {
+                (*start_reg..start_reg.saturating_add(*count)).contains(&reg)
+            }
+            Instruction::AddToDeck { deck, position, value } => {
+                *deck == reg || *position == reg || *value == reg
+            }
+            Instruction::ExtractFromDeck { deck, index, .. } => *deck == reg || *index == reg,
+            Instruction::Slice { r1, start, end, .. } => *r1 == reg || *start == reg || *end == reg,
+            Instruction::NativeCast { reg_start, args_count, .. } => {
+                (*reg_start..reg_start.saturating_add(*args_count)).contains(&reg)
+            }
+
+            Instruction::True { .. }
+            | Instruction::False { .. }
+            | Instruction::Emptiness { .. }
+            | Instruction::GetGlobal { .. }
+            | Instruction::GetUpvalue { .. }
+            | Instruction::CurrentSpell { .. }
+            | Instruction::Heed { .. }
+            | Instruction::Attempt { .. }
+            | Instruction::EndAttempt {}
+            | Instruction::Jump { .. }
+            | Instruction::Loop { .. }
+            | Instruction::AttemptLong { .. }
+            | Instruction::JumpLong { .. }
+            | Instruction::LoopLong { .. } => false,
+        }
+    }
+
+    /// The register an instruction writes to
+    fn dest(inst: &Instruction) -> Option<u8> {
+        match inst {
+            Instruction::Add { dest, .. }
+            | Instruction::Subtract { dest, .. }
+            | Instruction::Multiply { dest, .. }
+            | Instruction::Divide { dest, .. }
+            | Instruction::Mod { dest, .. }
+            | Instruction::AddInt { dest, .. }
+            | Instruction::SubtractInt { dest, .. }
+            | Instruction::MultiplyInt { dest, .. }
+            | Instruction::DivideInt { dest, .. }
+            | Instruction::ModInt { dest, .. }
+            | Instruction::BitAnd { dest, .. }
+            | Instruction::BitOr { dest, .. }
+            | Instruction::BitXor { dest, .. }
+            | Instruction::ShiftLeft { dest, .. }
+            | Instruction::ShiftRight { dest, .. }
+            | Instruction::AddConst { dest, .. }
+            | Instruction::SubtractConst { dest, .. }
+            | Instruction::Equal { dest, .. }
+            | Instruction::Greater { dest, .. }
+            | Instruction::Less { dest, .. }
+            | Instruction::GreaterEqual { dest, .. }
+            | Instruction::LessEqual { dest, .. }
+            | Instruction::NotEqual { dest, .. }
+            | Instruction::Negate { dest, .. }
+            | Instruction::Not { dest, .. }
+            | Instruction::Constant { dest, .. }
+            | Instruction::ConstantLong { dest, .. }
+            | Instruction::True { dest }
+            | Instruction::False { dest }
+            | Instruction::Emptiness { dest }
+            | Instruction::Concat { dest, .. }
+            | Instruction::GetGlobal { dest, .. }
+            | Instruction::Move { dest, .. }
+            | Instruction::Cast { dest, .. }
+            | Instruction::Draw { dest, .. }
+            | Instruction::Conjure { dest, .. }
+            | Instruction::Claim { dest, .. }
+            | Instruction::NewSign { dest, .. }
+            | Instruction::GetField { dest, .. }
+            | Instruction::SafeGetField { dest, .. }
+            | Instruction::NewDeck { dest, .. }
+            | Instruction::NewFixedDeck { dest, .. }
+            | Instruction::ExtractFromDeck { dest, .. }
+            | Instruction::Length { dest, .. }
+            | Instruction::ToText { dest, .. }
+            | Instruction::Slice { dest, .. }
+            | Instruction::IsEmptiness { dest, .. }
+            | Instruction::NativeCast { dest, .. }
+            | Instruction::GetAttunement { dest, .. }
+            | Instruction::GetUpvalue { dest, .. }
+            | Instruction::Heed { dest }
+            | Instruction::CurrentSpell { dest } => Some(*dest),
+            _ => None,
+        }
+    }
+
+    /// The same instruction writing to [new_dest], for the ones that read all their operands first
+    fn retarget(inst: &Instruction, new_dest: u8) -> Option<Instruction> {
+        let mut inst = *inst;
+        match &mut inst {
+            // a cast writes on release and a new sign is still filled in afterwards
+            Instruction::Cast { .. } | Instruction::NewSign { .. } => return None,
+            Instruction::Add { dest, .. }
+            | Instruction::Subtract { dest, .. }
+            | Instruction::Multiply { dest, .. }
+            | Instruction::Divide { dest, .. }
+            | Instruction::Mod { dest, .. }
+            | Instruction::AddInt { dest, .. }
+            | Instruction::SubtractInt { dest, .. }
+            | Instruction::MultiplyInt { dest, .. }
+            | Instruction::DivideInt { dest, .. }
+            | Instruction::ModInt { dest, .. }
+            | Instruction::BitAnd { dest, .. }
+            | Instruction::BitOr { dest, .. }
+            | Instruction::BitXor { dest, .. }
+            | Instruction::ShiftLeft { dest, .. }
+            | Instruction::ShiftRight { dest, .. }
+            | Instruction::AddConst { dest, .. }
+            | Instruction::SubtractConst { dest, .. }
+            | Instruction::Equal { dest, .. }
+            | Instruction::Greater { dest, .. }
+            | Instruction::Less { dest, .. }
+            | Instruction::GreaterEqual { dest, .. }
+            | Instruction::LessEqual { dest, .. }
+            | Instruction::NotEqual { dest, .. }
+            | Instruction::Negate { dest, .. }
+            | Instruction::Not { dest, .. }
+            | Instruction::Constant { dest, .. }
+            | Instruction::ConstantLong { dest, .. }
+            | Instruction::True { dest }
+            | Instruction::False { dest }
+            | Instruction::Emptiness { dest }
+            | Instruction::Concat { dest, .. }
+            | Instruction::GetGlobal { dest, .. }
+            | Instruction::Move { dest, .. }
+            | Instruction::GetField { dest, .. }
+            | Instruction::SafeGetField { dest, .. }
+            | Instruction::NewDeck { dest, .. }
+            | Instruction::NewFixedDeck { dest, .. }
+            | Instruction::ExtractFromDeck { dest, .. }
+            | Instruction::Length { dest, .. }
+            | Instruction::ToText { dest, .. }
+            | Instruction::Slice { dest, .. }
+            | Instruction::IsEmptiness { dest, .. }
+            | Instruction::NativeCast { dest, .. }
+            | Instruction::GetAttunement { dest, .. }
+            | Instruction::GetUpvalue { dest, .. }
+            | Instruction::Heed { dest }
+            | Instruction::CurrentSpell { dest } => *dest = new_dest,
+            _ => return None,
+        }
+        Some(inst)
+    }
+
+    //--------------- Jumps ---------------
+
+    fn jump_targets(&self) -> Vec<bool> {
+        let mut targets = vec![false; self.nodes.len() + 1];
+        for target in self.nodes.iter().filter_map(|n| n.target) {
+            targets[target] = true;
+        }
+        targets
+    }
+
+    /// Drops the [removed] instructions, a jump onto a dropped one now lands on the one after it
+    fn compact(&mut self, removed: &[bool]) {
+        let mut new_index = Vec::with_capacity(self.nodes.len() + 1);
+        let mut kept = 0;
+        for is_removed in removed {
+            new_index.push(kept);
+            if !is_removed {
+                kept += 1;
+            }
+        }
+        new_index.push(kept);
+
+        let nodes = std::mem::take(&mut self.nodes);
+        self.nodes = nodes
+            .into_iter()
+            .zip(removed)
+            .filter(|(_, is_removed)| !**is_removed)
+            .map(|(mut node, _)| {
+                node.target = node.target.map(|t| new_index[t]);
+                node
+            })
+            .collect();
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use crate::{
+    bytecode_file,
+    compiler::{
+        compiler::{CompiledCode, Compiler, CompilerOptions},
+        diagnostics::EiraError,
+    },
+    runtime::vm::{EiraVM, RuntimeError},
+    values::value::Value,
+};
+
+/// A scroll compiled and ready to be run by a VM
+#[derive(Clone)]
+pub struct Program {
+    pub code: CompiledCode,
+}
+
+impl Program {
+    /// Runs the program from its start, releasing the value of its last expression.
+    /// The VM keeps its globals and registered spells between the runs.
+    pub fn run(&self, vm: &mut EiraVM) -> Result<Value, RuntimeError> {
+        vm.load(self.code.clone());
+        vm.start()
+    }
+
+    /// Packs the program so it can be sent to other threads, see [SharedProgram]
+    pub fn share(&self) -> Result<SharedProgram, String> {
+        let bytes = bytecode_file::serialize(&self.code)?;
+        Ok(SharedProgram { bytes: bytes.into() })
+    }
+}
+
+/// A program packed into the bytes of a .eirac file, which can be sent to other threads and
+/// shared between them. The values of the scrolls and the VM itself are held by `Rc`s, which
+/// can't leave their thread, so every thread unpacks a program of its own to run on its own VM.
+#[derive(Clone)]
+pub struct SharedProgram {
+    bytes: Arc<[u8]>,
+}
+
+impl SharedProgram {
+    /// The program, for the VM of the calling thread
+    pub fn unpack(&self) -> Result<Program, String> {
+        Ok(Program { code: bytecode_file::deserialize(&self.bytes)? })
+    }
+}
+
+/// Compiles the source of a scroll in one go, the entry point for the host programs
+pub fn compile(source: &str) -> Result<Program, EiraError> {
+    let compiler = Compiler::from_source("<scroll>", source, CompilerOptions::default());
+    let code = compiler.compile_to_bytecode()?;
+    Ok(Program { code })
+}
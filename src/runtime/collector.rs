@@ -0,0 +1,287 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use crate::values::{
+    Value,
+    deck::DeckObject,
+    sign::SignObject,
+    spell::{Captured, ClosureObject},
+};
+
+// the candidates gathered before the first collection, later it waits for as many as it last looked at
+const FIRST_THRESHOLD: usize = 1024;
+
+/// What the collector has done since the VM was made
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    pub collections: usize, // the times it looked for cycles
+    pub freed: usize,       // the objects found in cycles nothing else held, which were broken apart
+    pub candidates: usize,  // the containers waiting to be looked at by the next collection
+}
+
+/// Breaks apart the cycles of the objects held by `Rc`s, which would never be dropped otherwise.
+///
+/// Only a container written to after it was made (a sign mark, a deck element or a closed
+/// upvalue) can close a cycle, so those are kept as candidates. A collection looks at everything
+/// reachable from them and subtracts the references the objects hold to each other from their
+/// strong counts. The objects left with a reference from outside, and the ones reachable from
+/// those, are alive. The rest only hold each other and get emptied, which drops them.
+pub struct Collector {
+    candidates: Vec<Candidate>,
+    threshold: usize,
+    stress: bool, // collects on every candidate, to shake out the objects freed too early
+    stats: GcStats,
+}
+
+/// A container written to since it was made, held weakly until the next collection
+enum Candidate {
+    Sign(Weak<RefCell<SignObject>>),
+    Deck(Weak<DeckObject>),
+    Cell(Weak<RefCell<Captured>>),
+}
+
+/// An object reachable from the candidates, held while a collection runs
+enum Node {
+    Sign(Rc<RefCell<SignObject>>),
+    Deck(Rc<DeckObject>),
+    Cell(Rc<RefCell<Captured>>),
+    Closure(Rc<ClosureObject>),
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Collector {
+    pub fn new() -> Self {
+        Collector {
+            candidates: vec![],
+            threshold: FIRST_THRESHOLD,
+            stress: false,
+            stats: GcStats::default(),
+        }
+    }
+
+    pub fn set_stress(&mut self, stress: bool) {
+        self.stress = stress;
+    }
+
+    pub fn stats(&self) -> GcStats {
+        GcStats {
+            candidates: self.candidates.len(),
+            ..self.stats
+        }
+    }
+
+    /// Whether storing the value in a container could close a cycle
+    pub fn holds_objects(value: &Value) -> bool {
+        matches!(value, Value::Sign(_) | Value::Deck(_) | Value::Closure(_))
+    }
+
+    pub fn note_sign(&mut self, sign: &Rc<RefCell<SignObject>>) {
+        self.note(Candidate::Sign(Rc::downgrade(sign)));
+    }
+
+    pub fn note_deck(&mut self, deck: &Rc<DeckObject>) {
+        self.note(Candidate::Deck(Rc::downgrade(deck)));
+    }
+
+    pub fn note_cell(&mut self, cell: &Rc<RefCell<Captured>>) {
+        self.note(Candidate::Cell(Rc::downgrade(cell)));
+    }
+
+    fn note(&mut self, candidate: Candidate) {
+        // a deck filled element by element is noted once
+        if self.candidates.last().map(Candidate::address) == Some(candidate.address()) {
+            return;
+        }
+        self.candidates.push(candidate);
+        if self.stress || self.candidates.len() >= self.threshold {
+            self.collect();
+        }
+    }
+
+    /// Looks for the cycles among the objects reachable from the candidates and breaks them
+    pub fn collect(&mut self) {
+        let mut graph = Graph::default();
+        for candidate in std::mem::take(&mut self.candidates) {
+            if let Some(node) = candidate.upgrade() {
+                graph.add(node);
+            }
+        }
+        let roots = graph.nodes.len();
+        graph.expand();
+
+        let alive = graph.alive();
+        let mut released = vec![]; // dropped once every object is emptied
+        for (node, alive) in graph.nodes.iter().zip(&alive) {
+            if !alive {
+                released.extend(node.clear());
+                self.stats.freed += 1;
+            }
+        }
+
+        // the candidates still alive can be left alone by what holds them later on
+        for (node, alive) in graph.nodes[..roots].iter().zip(&alive) {
+            if *alive {
+                self.candidates.extend(node.candidate());
+            }
+        }
+        self.threshold = FIRST_THRESHOLD.max(graph.nodes.len());
+        self.stats.collections += 1;
+
+        drop(graph);
+        drop(released);
+    }
+}
+
+impl Candidate {
+    fn address(&self) -> *const () {
+        match self {
+            Candidate::Sign(sign) => sign.as_ptr() as *const (),
+            Candidate::Deck(deck) => deck.as_ptr() as *const (),
+            Candidate::Cell(cell) => cell.as_ptr() as *const (),
+        }
+    }
+
+    fn upgrade(&self) -> Option<Node> {
+        match self {
+            Candidate::Sign(sign) => sign.upgrade().map(Node::Sign),
+            Candidate::Deck(deck) => deck.upgrade().map(Node::Deck),
+            Candidate::Cell(cell) => cell.upgrade().map(Node::Cell),
+        }
+    }
+}
+
+impl Node {
+    fn of(value: &Value) -> Option<Node> {
+        match value {
+            Value::Sign(sign) => Some(Node::Sign(sign.clone())),
+            Value::Deck(deck) => Some(Node::Deck(deck.clone())),
+            Value::Closure(closure) => Some(Node::Closure(closure.clone())),
+            _ => None,
+        }
+    }
+
+    fn address(&self) -> *const () {
+        match self {
+            Node::Sign(sign) => Rc::as_ptr(sign) as *const (),
+            Node::Deck(deck) => Rc::as_ptr(deck) as *const (),
+            Node::Cell(cell) => Rc::as_ptr(cell) as *const (),
+            Node::Closure(closure) => Rc::as_ptr(closure) as *const (),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Sign(sign) => Rc::strong_count(sign),
+            Node::Deck(deck) => Rc::strong_count(deck),
+            Node::Cell(cell) => Rc::strong_count(cell),
+            Node::Closure(closure) => Rc::strong_count(closure),
+        }
+    }
+
+    /// The objects held by the node, none when it is borrowed and can't be looked into
+    fn children(&self) -> Option<Vec<Node>> {
+        let children = match self {
+            Node::Sign(sign) => sign.try_borrow().ok()?.marks.iter().filter_map(Node::of).collect(),
+            Node::Deck(deck) => deck.items.try_borrow().ok()?.iter().filter_map(Node::of).collect(),
+            Node::Cell(cell) => match &*cell.try_borrow().ok()? {
+                Captured::Closed(value) => Node::of(value).into_iter().collect(),
+                Captured::Open(_) => vec![], // the value lives on the stack
+            },
+            Node::Closure(closure) => closure
+                .upvalues
+                .iter()
+                .map(|cell| Node::Cell(cell.clone()))
+                .collect(),
+        };
+        Some(children)
+    }
+
+    /// Empties the container, handing back what it held. A closure is left as it is, the cells
+    /// it captured are emptied instead.
+    fn clear(&self) -> Vec<Value> {
+        match self {
+            Node::Sign(sign) => {
+                let mut sign = sign.borrow_mut();
+                let count = sign.marks.len();
+                std::mem::replace(&mut sign.marks, vec![Value::Emptiness; count])
+            }
+            Node::Deck(deck) => std::mem::take(&mut *deck.items.borrow_mut()),
+            Node::Cell(cell) => match cell.replace(Captured::Closed(Value::Emptiness)) {
+                Captured::Closed(value) => vec![value],
+                Captured::Open(_) => vec![],
+            },
+            Node::Closure(_) => vec![],
+        }
+    }
+
+    fn candidate(&self) -> Option<Candidate> {
+        match self {
+            Node::Sign(sign) => Some(Candidate::Sign(Rc::downgrade(sign))),
+            Node::Deck(deck) => Some(Candidate::Deck(Rc::downgrade(deck))),
+            Node::Cell(cell) => Some(Candidate::Cell(Rc::downgrade(cell))),
+            Node::Closure(_) => None,
+        }
+    }
+}
+
+/// The objects reachable from the candidates, each held once, and the ones each of them holds
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    index: HashMap<*const (), usize>,
+    children: Vec<Option<Vec<usize>>>, // none for the nodes which couldn't be looked into
+}
+
+impl Graph {
+    fn add(&mut self, node: Node) -> usize {
+        let address = node.address();
+        if let Some(&at) = self.index.get(&address) {
+            return at;
+        }
+        self.nodes.push(node);
+        self.index.insert(address, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    /// Walks from the nodes added so far to everything they reach
+    fn expand(&mut self) {
+        let mut at = 0;
+        while at < self.nodes.len() {
+            let children = self.nodes[at]
+                .children()
+                .map(|children| children.into_iter().map(|child| self.add(child)).collect());
+            self.children.push(children);
+            at += 1;
+        }
+    }
+
+    /// Which nodes are held from outside of the graph, or reachable from one which is
+    fn alive(&self) -> Vec<bool> {
+        // the graph itself holds every node once
+        let mut outside: Vec<usize> = self.nodes.iter().map(|n| n.strong_count() - 1).collect();
+        for &child in self.children.iter().flatten().flatten() {
+            outside[child] -= 1;
+        }
+
+        let mut alive = vec![false; self.nodes.len()];
+        let mut pending: Vec<usize> = (0..self.nodes.len())
+            .filter(|&at| outside[at] > 0 || self.children[at].is_none())
+            .collect();
+        while let Some(at) = pending.pop() {
+            if alive[at] {
+                continue;
+            }
+            alive[at] = true;
+            pending.extend(self.children[at].iter().flatten().filter(|&&child| !alive[child]));
+        }
+        alive
+    }
+}
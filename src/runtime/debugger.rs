@@ -0,0 +1,84 @@
+use std::collections::BTreeSet;
+
+use crate::runtime::vm::{EiraVM, VmState};
+
+/// What the VM does once the front end of the debugger lets it go on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCommand {
+    /// Runs until a breakpoint is reached
+    Continue,
+    /// Runs until the next line, stepping into the spells cast on the way
+    Step,
+    /// Runs until the next line of the same spell, or of a spell it releases to
+    StepOver,
+}
+
+/// The side of the debugger talking to the user, the CLI implements it with a prompt
+pub trait DebugFrontend {
+    /// Called whenever the scroll pauses, on a breakpoint or after a step. The VM waits until a
+    /// command is returned, the breakpoints can be changed in the meantime.
+    fn paused(&mut self, state: &VmState, breakpoints: &mut BTreeSet<usize>) -> DebugCommand;
+}
+
+/// Pauses the scroll on its breakpoints and steps through it line by line, as the front end commands.
+/// The scroll is paused on its first line, so the breakpoints can be set before it runs.
+pub struct Debugger<F: DebugFrontend> {
+    frontend: F,
+    breakpoints: BTreeSet<usize>, // by source line
+    command: DebugCommand,
+    paused_at: Option<(usize, usize)>, // (frame depth, line) of the last pause
+    last: Option<(usize, usize)>,      // (frame depth, line) of the last instruction run
+}
+
+impl<F: DebugFrontend> Debugger<F> {
+    pub fn new(frontend: F) -> Self {
+        Debugger {
+            frontend,
+            breakpoints: BTreeSet::new(),
+            command: DebugCommand::Step,
+            paused_at: None,
+            last: None,
+        }
+    }
+
+    /// Pauses the scroll whenever it reaches the line
+    pub fn set_breakpoint(&mut self, line: usize) {
+        self.breakpoints.insert(line);
+    }
+
+    pub fn clear_breakpoint(&mut self, line: usize) {
+        self.breakpoints.remove(&line);
+    }
+
+    /// Looks at the instruction about to run, and hands the VM over to the front end when it should pause
+    pub fn step(&mut self, state: &VmState) {
+        let at = (state.frames.len(), state.line);
+        // only the first instruction of a line can pause, the rest of it runs through
+        let new_line = self.last != Some(at);
+        self.last = Some(at);
+        if !new_line || state.line == 0 {
+            return;
+        }
+
+        let pause = match self.command {
+            DebugCommand::Continue => self.breakpoints.contains(&state.line),
+            DebugCommand::Step => self.paused_at != Some(at),
+            DebugCommand::StepOver => {
+                let depth = self.paused_at.map_or(0, |(depth, _)| depth);
+                (at.0 <= depth && self.paused_at != Some(at)) || self.breakpoints.contains(&state.line)
+            }
+        };
+
+        if pause {
+            self.paused_at = Some(at);
+            self.command = self.frontend.paused(state, &mut self.breakpoints);
+        }
+    }
+}
+
+impl EiraVM {
+    /// Runs the scrolls under the debugger, it is called before every instruction
+    pub fn attach_debugger<F: DebugFrontend + 'static>(&mut self, mut debugger: Debugger<F>) {
+        self.set_step_hook(move |state| debugger.step(state));
+    }
+}
@@ -40,6 +40,16 @@ macro_rules! define_instructions {
                     )*
                 }
             }
+
+            /// The opcode named [name] in the listings, like `JUMPIFFALSE`. The case doesn't matter
+            pub fn from_name(name: &str) -> Option<Self> {
+                $(
+                    if name.eq_ignore_ascii_case(stringify!($instr_name)) {
+                        return Some(OpCode::$instr_name);
+                    }
+                )*
+                None
+            }
         }
 
         // Generate Instruction enum
@@ -93,12 +103,99 @@ macro_rules! define_instructions {
                     )*
                 }
             }
+
+            /// The operands of the instruction, in the order they are encoded
+            pub fn operands(&self) -> Vec<u32> {
+                match self {
+                    $(
+                        Instruction::$instr_name { $($field),* } => vec![$(*$field as u32),*],
+                    )*
+                }
+            }
+
+            /// Builds the instruction of [op] out of its [operands], in the order they are encoded
+            #[allow(unused_mut, unused_variables)]
+            pub fn from_operands(op: OpCode, operands: &[u32]) -> Result<Instruction, String> {
+                match op {
+                    $(
+                        OpCode::$instr_name => {
+                            let expected = define_instructions!(@count $($field)*);
+                            if operands.len() != expected {
+                                return Err(format!(
+                                    "{} takes {} operands, but got {}",
+                                    stringify!($instr_name).to_uppercase(),
+                                    expected,
+                                    operands.len()
+                                ));
+                            }
+                            let mut operands = operands.iter().copied();
+                            $(
+                                let $field = define_instructions!(@operand operands, $ty)?;
+                            )*
+                            Ok(Instruction::$instr_name { $($field),* })
+                        }
+                    )*
+                }
+            }
+
+            /// Decodes the instruction at the start of [code], None when it isn't a whole known one
+            #[allow(unused_mut, unused_variables, unused_assignments)]
+            pub fn decode(code: &[u8]) -> Option<Instruction> {
+                let op = OpCode::from_u8(*code.first()?)?;
+                if code.len() < op.inst_len() {
+                    return None;
+                }
+                let mut pos = 1;
+                match op {
+                    $(
+                        OpCode::$instr_name => {
+                            $(
+                                let $field = define_instructions!(@decode_field code, pos, $ty);
+                            )*
+                            Some(Instruction::$instr_name { $($field),* })
+                        }
+                    )*
+                }
+            }
         }
     };
 
     // Type helper
     (@type u8) => { u8 };
     (@type u16) => { u16 };
+    (@type u32) => { u32 };
+
+    // Helper: Count the fields
+    (@count) => { 0 };
+    (@count $head:ident $($tail:ident)*) => { 1 + define_instructions!(@count $($tail)*) };
+
+    // Helper: Take the next operand as a field
+    (@operand $operands:ident, u8) => {
+        u8::try_from($operands.next().unwrap())
+            .map_err(|_| "An operand doesn't fit in a byte".to_string())
+    };
+    (@operand $operands:ident, u16) => {
+        u16::try_from($operands.next().unwrap())
+            .map_err(|_| "An operand doesn't fit in two bytes".to_string())
+    };
+    (@operand $operands:ident, u32) => { Ok::<u32, String>($operands.next().unwrap()) };
+
+    // Helper: Decode a field from the bytes
+    (@decode_field $code:ident, $pos:ident, u8) => {{
+        let value = $code[$pos];
+        $pos += 1;
+        value
+    }};
+    (@decode_field $code:ident, $pos:ident, u16) => {{
+        let value = u16::from_le_bytes([$code[$pos], $code[$pos + 1]]);
+        $pos += 2;
+        value
+    }};
+    (@decode_field $code:ident, $pos:ident, u32) => {{
+        let value = u32::from_le_bytes([$code[$pos], $code[$pos + 1], $code[$pos + 2], $code[$pos + 3]]);
+        $pos += 4;
+        value
+    }};
 
     // Helper: Format instruction string
     (@format_instr $name:expr,) => {
@@ -124,6 +221,9 @@ macro_rules! define_instructions {
     (@encode_field $bytes:ident, $field:expr, u16) => {
         let $bytes = { let mut v = $bytes; v.extend_from_slice(&$field.to_le_bytes()); v };
     };
+    (@encode_field $bytes:ident, $field:expr, u32) => {
+        let $bytes = { let mut v = $bytes; v.extend_from_slice(&$field.to_le_bytes()); v };
+    };
 }
 
 // Usage example - define all your instructions here
@@ -156,13 +256,13 @@ define_instructions! {
     // I/O
     Print(15, 2) { r1: u8 },
 
-    // Globals
-    SetGlobal(16, 4) { src_reg: u8, const_index: u16 },
-    GetGlobal(17, 4) { dest: u8, const_index: u16 },
+    // Globals, addressed by the slot the compiler gave them
+    SetGlobal(16, 4) { src_reg: u8, slot: u16 },
+    GetGlobal(17, 4) { dest: u8, slot: u16 },
 
-    // Locals/Registers
+    // Locals live in the registers of the frame, they are written with a Move.
+    // 19 belonged to the PopStack of the old stack based locals
     Move(18, 4) { dest: u8, source: u16 },
-    PopStack(19, 3) { pop_count: u16 },
 
     // Control flow
     Jump(20, 3) { offset: u16 },
@@ -174,10 +274,10 @@ define_instructions! {
     Release(24, 2) { dest: u8 },
 
     // Termination
-    Halt(25, 1) {},
+    Halt(25, 2) { src: u8 },
 
     // Sign Stuff. schema_reg is the register which has the schema
-    NewSign(26,4) { dest: u8, schema_reg: u8 },
+    NewSign(26, 3) { dest: u8, schema_reg: u8 },
 
     // Set a field to a sign. [field_name] is the string constant's index in the const pool
     // The [val_reg] is the register where the value for the field is stored
@@ -187,7 +287,7 @@ define_instructions! {
     SafeGetField(29, 5) { dest: u8, sign_reg: u8, field_name: u16 },
 
     // Deck operations.
-    NewDeck(30, 6) { dest: u8, start_reg: u8, count: u8 },
+    NewDeck(30, 4) { dest: u8, start_reg: u8, count: u8 },
     NewFixedDeck(31, 6) { dest: u8, start_reg: u8, count: u8, capacity: u16 },
     AddToDeck(32, 4) { deck: u8, position: u8, value: u8 },
     ExtractFromDeck(33, 4) { dest: u8, deck: u8, index: u8 },
@@ -198,4 +298,95 @@ define_instructions! {
 
     // nat_spell_reg is the const_index which stores the name of the native spell
     NativeCast(36, 6) { dest: u8, nat_spell: u16, reg_start: u8, args_count: u8 },
+
+    // Loads the spell attuned to the runtime tome in [sign_reg]. [spell] is the symbol id of the spell's name
+    GetAttunement(37, 5) { dest: u8, sign_reg: u8, spell: u16 },
+
+    // Loads the spell of the running frame, lets local spells cast themselves
+    CurrentSpell(38, 2) { dest: u8 },
+
+    // Negated comparisons, fused out of a comparison and a Not by the optimizer
+    GreaterEqual(39, 4) { dest: u8, r1: u8, r2: u8 },
+    LessEqual(40, 4) { dest: u8, r1: u8, r2: u8 },
+    NotEqual(41, 4) { dest: u8, r1: u8, r2: u8 },
+
+    // Upvalues of the running closure, shared with the frame that declared them
+    GetUpvalue(42, 3) { dest: u8, index: u8 },
+    SetUpvalue(43, 3) { index: u8, src: u8 },
+    // Closes over the captured registers from [from] on, as their scope ends
+    CloseUpvalues(44, 2) { from: u8 },
+
+    // Arithmetic with a number constant, fused out of a Constant and an Add/Subtract by the optimizer
+    AddConst(45, 5) { dest: u8, r1: u8, const_index: u16 },
+    SubtractConst(46, 5) { dest: u8, r1: u8, const_index: u16 },
+
+    // Prints without ending the line, for all but the last value of a chant
+    PrintRaw(47, 2) { r1: u8 },
+
+    // Reads a line from the input of the VM, as a text without its line ending
+    Heed(48, 2) { dest: u8 },
+
+    // Until the matching EndAttempt, an error breaking the VM down jumps [offset] ahead to the
+    // mend instead, leaving the error in [error_reg]
+    Attempt(49, 4) { error_reg: u8, offset: u16 },
+    EndAttempt(50, 1) {},
+    // Breaks the VM down with the chant of [r1] as the message
+    Throw(51, 2) { r1: u8 },
+
+    // The count of elements of the deck, or of letters of the text, in [r1]
+    Length(52, 3) { dest: u8, r1: u8 },
+    // The elements of the deck, or the letters of the text, in [r1] from [start] up to [end]
+    Slice(53, 5) { dest: u8, r1: u8, start: u8, end: u8 },
+
+    // Arithmetic of two ints, breaking the VM down when the result overflows
+    AddInt(54, 4) { dest: u8, r1: u8, r2: u8 },
+    SubtractInt(55, 4) { dest: u8, r1: u8, r2: u8 },
+    MultiplyInt(56, 4) { dest: u8, r1: u8, r2: u8 },
+    DivideInt(57, 4) { dest: u8, r1: u8, r2: u8 },
+    ModInt(58, 4) { dest: u8, r1: u8, r2: u8 },
+
+    // The bits of two ints, a shift moves the bits of [r1] by 0 up to 63 places
+    BitAnd(59, 4) { dest: u8, r1: u8, r2: u8 },
+    BitOr(60, 4) { dest: u8, r1: u8, r2: u8 },
+    BitXor(61, 4) { dest: u8, r1: u8, r2: u8 },
+    ShiftLeft(62, 4) { dest: u8, r1: u8, r2: u8 },
+    ShiftRight(63, 4) { dest: u8, r1: u8, r2: u8 },
+
+    // Runs the stream in [stream] up to its next offer, the value offered goes to [dest] and
+    // [more] is set true. Once the stream has released, [dest] is emptiness and [more] false
+    Draw(64, 4) { dest: u8, stream: u8, more: u8 },
+    // Hands [src] to the drawer of the stream, suspending the flowing spell until the next draw
+    Offer(65, 2) { src: u8 },
+
+    // Casts the spell in [spell_reg] like a cast, but alongside the running spells. [dest] takes
+    // its conjuring at once, the spells take turns until it releases
+    Conjure(66, 4) { dest: u8, spell_reg: u8, reg_start: u8 },
+    // Waits for the conjuring in [handle] to release, [dest] takes what it released
+    Claim(67, 3) { dest: u8, handle: u8 },
+
+    // A Constant reaching past the first 65536 constants of the spell
+    ConstantLong(68, 6) { dest: u8, const_index: u32 },
+
+    // The jumps reaching further than two bytes of offset can, for the spells too long for them
+    JumpLong(69, 5) { offset: u32 },
+    JumpIfFalseLong(70, 6) { condition_reg: u8, offset: u32 },
+    LoopLong(71, 5) { offset: u32 },
+    AttemptLong(72, 6) { error_reg: u8, offset: u32 },
+
+    // The value in [r1] as a text, written the way it is chanted
+    ToText(73, 3) { dest: u8, r1: u8 },
+}
+
+impl Instruction {
+    /// The index of the constant the instruction reads from the pool
+    pub fn loaded_constant(&self) -> Option<usize> {
+        match *self {
+            Instruction::Constant { const_index, .. }
+            | Instruction::AddConst { const_index, .. }
+            | Instruction::SubtractConst { const_index, .. } => Some(const_index as usize),
+            Instruction::ConstantLong { const_index, .. } => Some(const_index as usize),
+            Instruction::NativeCast { nat_spell, .. } => Some(nat_spell as usize),
+            _ => None,
+        }
+    }
 }
@@ -1,6 +1,9 @@
 #[macro_use]
 pub mod instruction_macro;
 
+pub mod collector;
+pub mod debugger;
+pub mod verifier;
 pub mod vm;
 
 // Re-export the macro-generated types
@@ -0,0 +1,295 @@
+use std::{collections::HashSet, rc::Rc};
+
+use crate::{
+    runtime::{Instruction, OpCode},
+    values::{
+        spell::{Capture, Captured, SpellObject},
+        value::Value,
+    },
+};
+
+/// The registers a frame can have, as the instructions address them by a byte
+pub const MAX_REGISTERS: usize = u8::MAX as usize + 1;
+
+/// The deepest the spells can be nested in one another's constants, reading and checking them
+/// goes a level down the stack for each. A scroll written by the compiler never gets near it,
+/// the parser refusing the deep nesting first
+pub const MAX_SPELL_DEPTH: usize = 128;
+
+/// Checks that the bytecode of the scroll [origin], and of every spell among its constants, can
+/// be run by the VM without checking it again: it is made of whole, known instructions, every
+/// jump lands on the start of an instruction, no path runs off the end of the code, the
+/// operands stay within the registers of the frame and the constants of the spell, and the
+/// closures only capture what the spell making them has.
+pub fn verify_scroll(origin: &SpellObject) -> Result<(), String> {
+    verify_spell(origin, None, 0)
+}
+
+/// Checks the [spell] made in the frame of its [enclosing] one, None for the origin
+fn verify_spell(spell: &SpellObject, enclosing: Option<&SpellObject>, depth: usize) -> Result<(), String> {
+    let name = spell.name.as_deref().unwrap_or("<origin>");
+    if depth >= MAX_SPELL_DEPTH {
+        return Err(format!("The spell '{}' is nested deeper than {} spells.", name, MAX_SPELL_DEPTH));
+    }
+    verify_captures(spell, enclosing).map_err(|e| format!("The spell '{}' is malformed. {}", name, e))?;
+    verify_code(spell, enclosing.is_none(), depth)
+}
+
+/// Checks the bytecode of [spell] and the spells among its constants
+fn verify_code(spell: &SpellObject, origin: bool, depth: usize) -> Result<(), String> {
+    let name = spell.name.as_deref().unwrap_or("<origin>");
+    verify_bytecode(spell, origin).map_err(|e| format!("The spell '{}' is malformed. {}", name, e))?;
+
+    for constant in &spell.constants {
+        match constant {
+            Value::Spell(inner) => verify_spell(inner, Some(spell), depth + 1)?,
+            Value::Closure(closure) => verify_spell(&closure.spell, Some(spell), depth + 1)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks the spells within a [value] the host hands to the VM, which didn't come from the
+/// verified scroll: the closures, and the ones held by the decks, the signs, the streams, the
+/// conjurings and the upvalues of the closures. Their captures are bound already, so only their
+/// code is checked
+pub fn verify_value(value: &Value) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    let mut pending = vec![value.clone()];
+    while let Some(value) = pending.pop() {
+        // the values can hold one another in a cycle, each is walked once
+        let address = match &value {
+            Value::Closure(closure) => Rc::as_ptr(closure) as usize,
+            Value::Spell(spell) => Rc::as_ptr(spell) as usize,
+            Value::Deck(deck) => Rc::as_ptr(deck) as usize,
+            Value::Sign(sign) => Rc::as_ptr(sign) as *const u8 as usize,
+            Value::Stream(stream) => Rc::as_ptr(stream) as usize,
+            Value::Conjuring(conjuring) => Rc::as_ptr(conjuring) as usize,
+            _ => continue,
+        };
+        if !seen.insert(address) {
+            continue;
+        }
+
+        match value {
+            Value::Closure(closure) => {
+                verify_code(&closure.spell, false, 0)?;
+                for cell in &closure.upvalues {
+                    if let Captured::Closed(value) = &*cell.borrow() {
+                        pending.push(value.clone());
+                    }
+                }
+            }
+            Value::Spell(spell) => verify_code(&spell, false, 0)?,
+            Value::Deck(deck) => pending.extend(deck.items.borrow().iter().cloned()),
+            Value::Sign(sign) => pending.extend(sign.borrow().marks.iter().cloned()),
+            Value::Stream(stream) => pending.push(Value::Closure(stream.closure.clone())),
+            Value::Conjuring(conjuring) => pending.push(Value::Closure(conjuring.closure.clone())),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the captures of [spell] point at the registers and the upvalues of the [enclosing]
+/// spell, the origin has nothing to capture from
+fn verify_captures(spell: &SpellObject, enclosing: Option<&SpellObject>) -> Result<(), String> {
+    let (registers, upvalues) = enclosing.map_or((0, 0), |e| (frame_size(e), e.captures.len()));
+    for capture in &spell.captures {
+        match *capture {
+            Capture::Local(reg) if reg as usize >= registers => {
+                return Err(format!("It captures the register {}, past the {} of the frame making it.", reg, registers));
+            }
+            Capture::Enclosing(index) if index as usize >= upvalues => {
+                return Err(format!("It captures the upvalue {}, past the {} of the spell making it.", index, upvalues));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The registers of the frame of [spell], its reagents take the first ones
+fn frame_size(spell: &SpellObject) -> usize {
+    spell.register_count.max(spell.arity as usize)
+}
+
+fn verify_bytecode(spell: &SpellObject, origin: bool) -> Result<(), String> {
+    let code = &spell.bytecode;
+    if spell.register_count > MAX_REGISTERS {
+        return Err(format!("Its frame needs {} registers, more than the {} there can be.", spell.register_count, MAX_REGISTERS));
+    }
+    let registers = frame_size(spell);
+
+    // the offsets where the instructions start
+    let mut starts = vec![false; code.len()];
+    let mut pos = 0;
+    while pos < code.len() {
+        let Some(op) = OpCode::from_u8(code[pos]) else {
+            return Err(format!("Unknown opcode {} at {}.", code[pos], pos));
+        };
+        let Some(instruction) = Instruction::decode(&code[pos..]) else {
+            return Err(format!("The instruction at {} is cut short.", pos));
+        };
+        // the scroll has no caster to release to
+        if origin && op == OpCode::Release {
+            return Err(format!("The scroll releases at {}, outside of a spell.", pos));
+        }
+        if !spell.flowing && op == OpCode::Offer {
+            return Err(format!("It offers at {}, but isn't a flowing spell.", pos));
+        }
+        verify_operands(&instruction, registers, &spell.constants)
+            .map_err(|e| format!("The {} at {} {}", instruction.to_string(), pos, e))?;
+        starts[pos] = true;
+        pos += op.inst_len();
+    }
+
+    // walk every path through the code, checking where it leads
+    let mut visited = vec![false; code.len()];
+    let mut pending = vec![0];
+    while let Some(pos) = pending.pop() {
+        if pos >= code.len() {
+            return Err("The code can run past its end.".to_string());
+        }
+        if visited[pos] {
+            continue;
+        }
+        visited[pos] = true;
+
+        let op = OpCode::from_u8(code[pos]).unwrap();
+        let end = pos + op.inst_len();
+        let read_u16 = |at: usize| u16::from_le_bytes([code[at], code[at + 1]]) as usize;
+        let read_u32 = |at: usize| u32::from_le_bytes([code[at], code[at + 1], code[at + 2], code[at + 3]]) as usize;
+
+        let target = match op {
+            OpCode::Halt | OpCode::Release | OpCode::Throw => continue,
+            OpCode::Jump => end.checked_add(read_u16(pos + 1)),
+            OpCode::JumpIfFalse | OpCode::Attempt => {
+                pending.push(end);
+                end.checked_add(read_u16(pos + 2))
+            }
+            OpCode::Loop => end.checked_sub(read_u16(pos + 1)),
+            OpCode::JumpLong => end.checked_add(read_u32(pos + 1)),
+            OpCode::JumpIfFalseLong | OpCode::AttemptLong => {
+                pending.push(end);
+                end.checked_add(read_u32(pos + 2))
+            }
+            OpCode::LoopLong => end.checked_sub(read_u32(pos + 1)),
+            _ => {
+                pending.push(end);
+                continue;
+            }
+        };
+
+        match target {
+            Some(target) if target < code.len() && starts[target] => pending.push(target),
+            _ => return Err(format!("The jump at {} lands outside of the code.", pos)),
+        }
+    }
+    Ok(())
+}
+
+/// Checks that [instruction] only addresses the [registers] of its frame and the [constants] of its
+/// spell, and that a native cast casts a native spell with the reagents it takes
+fn verify_operands(instruction: &Instruction, registers: usize, constants: &[Value]) -> Result<(), String> {
+    use Instruction as I;
+
+    let one = |reg: u8| (reg as usize, 1);
+    // the registers used, as (first, count), and the constant loaded
+    let (spans, constant): (Vec<(usize, usize)>, Option<usize>) = match *instruction {
+        I::Add { dest, r1, r2 }
+        | I::Subtract { dest, r1, r2 }
+        | I::Multiply { dest, r1, r2 }
+        | I::Divide { dest, r1, r2 }
+        | I::Mod { dest, r1, r2 }
+        | I::Equal { dest, r1, r2 }
+        | I::Greater { dest, r1, r2 }
+        | I::Less { dest, r1, r2 }
+        | I::GreaterEqual { dest, r1, r2 }
+        | I::LessEqual { dest, r1, r2 }
+        | I::NotEqual { dest, r1, r2 }
+        | I::Concat { dest, r1, r2 }
+        | I::AddInt { dest, r1, r2 }
+        | I::SubtractInt { dest, r1, r2 }
+        | I::MultiplyInt { dest, r1, r2 }
+        | I::DivideInt { dest, r1, r2 }
+        | I::ModInt { dest, r1, r2 }
+        | I::BitAnd { dest, r1, r2 }
+        | I::BitOr { dest, r1, r2 }
+        | I::BitXor { dest, r1, r2 }
+        | I::ShiftLeft { dest, r1, r2 }
+        | I::ShiftRight { dest, r1, r2 } => (vec![one(dest), one(r1), one(r2)], None),
+        I::Negate { dest, r1 } | I::Not { dest, r1 } | I::IsEmptiness { dest, r1 } | I::Length { dest, r1 } | I::ToText { dest, r1 } => {
+            (vec![one(dest), one(r1)], None)
+        }
+        I::True { dest }
+        | I::False { dest }
+        | I::Emptiness { dest }
+        | I::Heed { dest }
+        | I::CurrentSpell { dest }
+        | I::Release { dest }
+        | I::GetGlobal { dest, .. }
+        | I::GetUpvalue { dest, .. } => (vec![one(dest)], None),
+        I::Print { r1 } | I::PrintRaw { r1 } | I::AssertSafe { r1 } | I::Throw { r1 } | I::Offer { src: r1 } => {
+            (vec![one(r1)], None)
+        }
+        I::Halt { src } | I::SetUpvalue { src, .. } | I::SetGlobal { src_reg: src, .. } => (vec![one(src)], None),
+        I::JumpIfFalse { condition_reg: reg, .. }
+        | I::Attempt { error_reg: reg, .. }
+        | I::JumpIfFalseLong { condition_reg: reg, .. }
+        | I::AttemptLong { error_reg: reg, .. } => (vec![one(reg)], None),
+        I::Jump { .. }
+        | I::Loop { .. }
+        | I::JumpLong { .. }
+        | I::LoopLong { .. }
+        | I::EndAttempt {}
+        | I::CloseUpvalues { .. } => (vec![], None),
+        I::Constant { dest, const_index } => (vec![one(dest)], Some(const_index as usize)),
+        I::ConstantLong { dest, const_index } => (vec![one(dest)], Some(const_index as usize)),
+        I::AddConst { dest, r1, const_index } | I::SubtractConst { dest, r1, const_index } => {
+            (vec![one(dest), one(r1)], Some(const_index as usize))
+        }
+        I::Move { dest, source } => (vec![one(dest), (source as usize, 1)], None),
+        // the reagents are counted by the spell cast, which is only known once it runs
+        I::Cast { dest, spell_reg, reg_start } | I::Conjure { dest, spell_reg, reg_start } => {
+            (vec![one(dest), one(spell_reg), (reg_start as usize, 0)], None)
+        }
+        I::NewSign { dest, schema_reg } => (vec![one(dest), one(schema_reg)], None),
+        I::SetField { sign_reg, val_reg, .. } => (vec![one(sign_reg), one(val_reg)], None),
+        I::GetField { dest, sign_reg, .. }
+        | I::SafeGetField { dest, sign_reg, .. }
+        | I::GetAttunement { dest, sign_reg, .. } => (vec![one(dest), one(sign_reg)], None),
+        I::NewDeck { dest, start_reg, count } | I::NewFixedDeck { dest, start_reg, count, .. } => {
+            (vec![one(dest), (start_reg as usize, count as usize)], None)
+        }
+        I::AddToDeck { deck, position, value } => (vec![one(deck), one(position), one(value)], None),
+        I::ExtractFromDeck { dest, deck, index } => (vec![one(dest), one(deck), one(index)], None),
+        I::NativeCast { dest, nat_spell, reg_start, args_count } => {
+            (vec![one(dest), (reg_start as usize, args_count as usize)], Some(nat_spell as usize))
+        }
+        I::Slice { dest, r1, start, end } => (vec![one(dest), one(r1), one(start), one(end)], None),
+        I::Draw { dest, stream, more } => (vec![one(dest), one(stream), one(more)], None),
+        I::Claim { dest, handle } => (vec![one(dest), one(handle)], None),
+    };
+
+    if spans.iter().any(|(first, count)| first + count > registers) {
+        return Err(format!("reaches past the {} registers of its frame.", registers));
+    }
+    if let Some(index) = constant
+        && index >= constants.len()
+    {
+        return Err(format!("loads a constant past the {} of its spell.", constants.len()));
+    }
+    if let I::NativeCast { nat_spell, args_count, .. } = *instruction {
+        let Value::NativeSpell(native) = &constants[nat_spell as usize] else {
+            return Err("casts a constant which isn't a native spell.".to_string());
+        };
+        if let Some(arity) = native.arity()
+            && arity != args_count as usize
+        {
+            return Err(format!("casts a native spell taking {} reagents with {}.", arity, args_count));
+        }
+    }
+    Ok(())
+}
@@ -1,42 +1,201 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    io::{BufRead, Write},
+    path::Path,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use crate::{
     SpellObject,
+    bytecode_file,
     compiler::compiler::CompiledCode,
-    runtime::OpCode,
+    runtime::{
+        Instruction, OpCode,
+        collector::{Collector, GcStats},
+        verifier::{verify_scroll, verify_value},
+    },
     values::{
         Value,
+        conjuring::{ConjuringObject, ConjuringState},
         deck::DeckObject,
-        native_spell::dispatch,
-        print_value,
+        native_spell::{HostSpellFn, dispatch},
+        native_spells::random::Fate,
         sign::SignObject,
-        spell::{ClosureObject, UpValue},
+        spell::{Capture, Captured, ClosureObject, LocalSymbol},
+        stream::{StreamObject, StreamState},
+        value::stringify_value,
     },
 };
 
-pub enum InterpretResult {
-    CompileError,
-    RuntimeError,
-    InterpretOk,
+/// Why the VM broke down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorKind {
+    /// The scroll did something it can't, an attempt can mend these
+    #[default]
+    Broken,
+    /// The scroll ran more instructions than the fuel it was given
+    OutOfFuel,
+    /// The scroll ran for longer than its time limit
+    OutOfTime,
+}
+
+/// The error that broke the VM down while running a scroll
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub line: usize,
+    pub column: usize, // 0 when the scroll was compiled without debug info
+    pub trace: Vec<(String, usize)>, // (spell name, line) of the casts, innermost first
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Error: {} at line {}", self.message, self.line)?;
+        if self.column > 0 {
+            write!(f, ", column {}", self.column)?;
+        }
+        // a runaway recursion repeats the same cast over and over, it is shown once
+        let mut casts = self.trace.iter().peekable();
+        while let Some((name, line)) = casts.next() {
+            write!(f, "\n    in '{}' at line {}", name, line)?;
+            let mut repeats = 0;
+            while casts.next_if(|cast| cast.0 == *name && cast.1 == *line).is_some() {
+                repeats += 1;
+            }
+            if repeats > 0 {
+                write!(f, "\n    ... repeated {} more times", repeats)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The deepest the casts can go before the realm overflows
+pub const DEFAULT_MAX_FRAMES: usize = 4096;
+
+/// The most registers all the frames can hold together before the realm overflows
+pub const DEFAULT_MAX_STACK: usize = 1 << 20;
+
+/// How many instructions are run between two checks of the fuel and the time limit
+pub const LIMIT_CHECK_INTERVAL: u32 = 1024;
+
+/// A look at the VM right before it runs an instruction, given to the step hook
+#[derive(Debug)]
+pub struct VmState<'a> {
+    pub instruction: Instruction,
+    pub ip: usize, // where the instruction starts in the bytecode of its spell
+    pub line: usize,
+    pub column: usize, // 0 without debug info
+    pub spell: &'a str, // the name of the spell being run
+    pub registers: &'a [Value], // the register window of the running frame
+    pub frames: Vec<(String, usize)>, // (spell name, line) of the casts, innermost first
+    locals: &'a [LocalSymbol],
+    globals: &'a [Option<Value>],
+    global_names: &'a [String],
+}
+
+impl VmState<'_> {
+    /// The locals of the running spell in scope on the line, by their names.
+    /// A shadowed local gives way to the one declared after it.
+    pub fn locals(&self) -> Vec<(&str, &Value)> {
+        let mut locals: Vec<(&str, &Value)> = vec![];
+        let in_scope = self
+            .locals
+            .iter()
+            .filter(|local| local.lines.0 <= self.line && self.line <= local.lines.1);
+        for local in in_scope {
+            let Some(value) = self.registers.get(local.register as usize) else {
+                continue;
+            };
+            locals.retain(|(name, _)| *name != local.name);
+            locals.push((&local.name, value));
+        }
+        locals
+    }
+
+    /// The value of the local named [name], if one is in scope on the line
+    pub fn local(&self, name: &str) -> Option<&Value> {
+        self.locals().into_iter().find(|(local, _)| *local == name).map(|(_, value)| value)
+    }
+
+    /// The globals defined so far, by their names
+    pub fn globals(&self) -> Vec<(&str, &Value)> {
+        self.global_names
+            .iter()
+            .zip(self.globals)
+            .filter_map(|(name, value)| Some((name.as_str(), value.as_ref()?)))
+            .collect()
+    }
+
+    /// The value of the global named [name], if it is defined yet
+    pub fn global(&self, name: &str) -> Option<&Value> {
+        let slot = self.global_names.iter().position(|global| global == name)?;
+        self.globals[slot].as_ref()
+    }
+}
+
+impl std::fmt::Display for VmState<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let registers: Vec<String> = self.registers.iter().map(stringify_value).collect();
+        let casts: Vec<String> = self.frames.iter().rev().map(|(name, line)| format!("{}:{}", name, line)).collect();
+        write!(
+            f,
+            "{:04}  {:<24} [{}]  {}",
+            self.ip,
+            self.instruction.to_string(),
+            registers.join(", "),
+            casts.join(" > ")
+        )
+    }
+}
+
+/// Called with the state of the VM before every instruction it runs
+pub type StepHook = Box<dyn FnMut(&VmState)>;
+
+/// Where an attempt is mended, should it break down
+#[derive(Debug)]
+struct Handler {
+    ip: usize, // the start of the mend
+    error_reg: u8,
 }
 
 #[derive(Debug)]
 struct CallFrame {
     ip: usize,
+    // the bytecode of the closure's spell, kept alive by [closure] for as long as the frame is
+    code: *const u8,
     closure: Rc<ClosureObject>,
     // slot_start: usize,
     return_reg: u8,
     reg_base: usize,
     caller_reg_base: usize,
+    handlers: Vec<Handler>, // the attempts the frame is inside of, innermost last
+    stream: Option<(Rc<StreamObject>, u8)>, // the stream drawn from by the frame, and the register told whether it offered
+}
 
-    // Track which registers in this frame are upvalues and where they point in parent
-    upvalue_mappings: Vec<(usize, usize)>, // (local_reg, parent_stack_idx)
+/// A spell waiting for its turn, with the frames and the registers it left the VM with.
+/// The scroll itself is one too, while a conjured spell has the VM.
+struct Task {
+    conjuring: Option<Rc<ConjuringObject>>, // none for the scroll
+    frames: Vec<CallFrame>,
+    stack: Vec<Value>,
+    // the captured registers still in scope, closed over while the task waits so the
+    // other spells see their values. They are opened again once it has the VM back
+    open_upvalues: Vec<(Rc<RefCell<Captured>>, usize)>,
+    stop_depth: usize,
 }
 
+// The bytecode is verified before the VM starts, and the spells the host hands over are verified
+// as they come in, so the ip always sits inside a whole instruction and the operands are read
+// without bounds checks.
 impl CallFrame {
     #[inline(always)]
     pub fn read_byte(&mut self) -> u8 {
-        let b = self.closure.spell.bytecode[self.ip];
+        // SAFETY: verified bytecode keeps the ip in bounds
+        let b = unsafe { *self.code.add(self.ip) };
         self.ip += 1;
         b
     }
@@ -44,21 +203,33 @@ impl CallFrame {
     #[inline(always)]
     pub fn read_three_bytes(&mut self) -> (u8, u8, u8) {
         let ip = self.ip;
-        let b1 = self.closure.spell.bytecode[ip];
-        let b2 = self.closure.spell.bytecode[ip + 1];
-        let b3 = self.closure.spell.bytecode[ip + 2];
+        // SAFETY: verified bytecode keeps the ip in bounds
+        let (b1, b2, b3) = unsafe { (*self.code.add(ip), *self.code.add(ip + 1), *self.code.add(ip + 2)) };
         self.ip += 3;
         (b1, b2, b3)
     }
 
     #[inline(always)]
     pub fn read_u16(&mut self) -> u16 {
-        let a = self.closure.spell.bytecode[self.ip];
-        let b = self.closure.spell.bytecode[self.ip + 1];
+        // SAFETY: verified bytecode keeps the ip in bounds
+        let (a, b) = unsafe { (*self.code.add(self.ip), *self.code.add(self.ip + 1)) };
         self.ip += 2;
         u16::from_le_bytes([a, b])
     }
 
+    pub fn read_u32(&mut self) -> u32 {
+        // SAFETY: verified bytecode keeps the ip in bounds
+        let bytes = unsafe { std::ptr::read_unaligned(self.code.add(self.ip) as *const [u8; 4]) };
+        self.ip += 4;
+        u32::from_le_bytes(bytes)
+    }
+
+    /// The opcode at the ip, None for a byte which isn't one
+    #[inline(always)]
+    pub fn read_opcode(&mut self) -> Option<OpCode> {
+        OpCode::from_u8(self.read_byte())
+    }
+
     #[inline(always)]
     pub fn read_constant(&mut self) -> &Value {
         let ind = self.read_u16();
@@ -69,60 +240,718 @@ impl CallFrame {
 pub struct EiraVM {
     frames: Vec<CallFrame>,
 
-    globals: HashMap<String, Value>,
+    globals: Vec<Option<Value>>, // by the slots of the loaded scroll, empty until defined
+    global_names: Vec<String>,
+    stashed_globals: HashMap<String, Value>, // globals of earlier scrolls the loaded one doesn't use
+    symbols: Vec<String>, // the interned names of the loaded scroll, by their symbol ids
     pub stack: Vec<Value>,
+    open_upvalues: Vec<Rc<RefCell<Captured>>>, // the captured registers that are still in scope
+    collector: Collector, // breaks apart the cycles the objects leave behind
+
+    host_spells: HashMap<String, (usize /* arity */, HostSpellFn)>,
+
+    output: Box<dyn Write>, // where the chants go
+    input: Box<dyn BufRead>, // where the heeded lines come from
+
+    started: Instant, // what `clock` counts from
+    fate: Fate,       // rolls the numbers of `fate_roll`
+
+    max_frames: usize,
+    max_stack: usize,
+
+    fuel: Option<u64>,          // the instructions a start can run
+    time_limit: Option<Duration>, // how long a start can run
+    fuel_left: Option<u64>,
+    deadline: Option<Instant>,
+    countdown: u32, // the instructions left until the limits are checked again
+    counted: u32,   // what the countdown started from
+
+    stop_depth: usize, // the frames left once the spell called by the host released
+
+    conjuring: Option<Rc<ConjuringObject>>, // the conjured spell that has the VM, none for the scroll
+    tasks: VecDeque<Task>,                  // the spells waiting for their turn, the next one first
+
+    trace: bool, // prints every instruction run to the stderr
+    step_hook: Option<StepHook>,
+    observed: bool, // whether the instructions are traced or hooked at all, checked once per instruction
+}
+
+impl Default for EiraVM {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl EiraVM {
+    /// The line of the instruction last read by the frame
+    fn frame_line(frame: &CallFrame) -> usize {
+        frame.closure.spell.line_at(frame.ip.saturating_sub(1))
+    }
+
     pub fn init(compiled_code: CompiledCode) -> Self {
-        let mut vm = EiraVM {
-            globals: HashMap::new(),
+        let mut vm = EiraVM::new();
+        vm.load(compiled_code);
+        vm
+    }
+
+    /// A VM without any scroll, scrolls are given to it with `load`
+    pub fn new() -> Self {
+        EiraVM {
+            globals: vec![],
+            global_names: vec![],
+            stashed_globals: HashMap::new(),
+            symbols: vec![],
             stack: Vec::with_capacity(256),
+            open_upvalues: vec![],
+            collector: Collector::new(),
             frames: Vec::with_capacity(256), // initally
-        };
+            host_spells: HashMap::new(),
+            output: Box::new(std::io::stdout()),
+            input: Box::new(std::io::BufReader::new(std::io::stdin())),
+            started: Instant::now(),
+            fate: Fate::unseeded(),
+            max_frames: DEFAULT_MAX_FRAMES,
+            max_stack: DEFAULT_MAX_STACK,
+            fuel: None,
+            time_limit: None,
+            fuel_left: None,
+            deadline: None,
+            countdown: 0,
+            counted: 0,
+            stop_depth: 0,
+            conjuring: None,
+            tasks: VecDeque::new(),
+            trace: false,
+            step_hook: None,
+            observed: false,
+        }
+    }
+
+    /// Sends the chants of the scrolls to the given writer instead of the stdout
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.output = Box::new(output);
+    }
+
+    /// Lets the scrolls heed the lines of the given reader instead of the stdin
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.input = Box::new(input);
+    }
+
+    /// Limits how deep the casts can go, casting deeper breaks the VM down with a realm overflow
+    pub fn set_max_frames(&mut self, max_frames: usize) {
+        self.max_frames = max_frames;
+    }
+
+    /// Limits the registers all the frames can hold together, going over breaks the VM down with a realm overflow
+    pub fn set_max_stack(&mut self, max_stack: usize) {
+        self.max_stack = max_stack;
+    }
+
+    /// Limits the instructions a start can run, running more breaks the VM down with [ErrorKind::OutOfFuel].
+    /// No attempt can mend it, so an untrusted scroll can't loop forever.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    /// Limits how long a start can run, running longer breaks the VM down with [ErrorKind::OutOfTime].
+    /// It's checked every [LIMIT_CHECK_INTERVAL] instructions, so a host spell taking long can overrun it.
+    pub fn set_time_limit(&mut self, limit: Option<Duration>) {
+        self.time_limit = limit;
+    }
+
+    /// Looks for cycles every time a container is given an object, instead of once enough of them
+    /// piled up. Slow, but an object freed while it's still held shows up right where it happens.
+    pub fn set_gc_stress(&mut self, stress: bool) {
+        self.collector.set_stress(stress);
+    }
+
+    /// Breaks apart the cycles of objects nothing holds anymore, right now
+    pub fn collect_garbage(&mut self) {
+        self.collector.collect();
+    }
+
+    pub fn gc_stats(&self) -> GcStats {
+        self.collector.stats()
+    }
+
+    /// Prepares the VM to run the compiled code from its start.
+    /// The globals and the registered spells of the earlier scrolls are kept.
+    pub fn load(&mut self, compiled_code: CompiledCode) {
+        self.bind_globals(compiled_code.globals);
+        self.symbols = compiled_code.symbols;
+        self.dispel_conjurings();
+        self.abandon_frames(0);
+        self.stack.clear();
+        self.open_upvalues.clear();
+        self.stack
+            .resize(compiled_code.register_count, Value::Emptiness);
 
         let closure = ClosureObject {
             spell: Rc::new(SpellObject {
                 arity: 0,
                 bytecode: compiled_code.bytecode,
+                flowing: false,
                 constants: compiled_code.constants,
+                lines: compiled_code.lines,
+                register_count: compiled_code.register_count,
+                debug: compiled_code.debug,
                 name: None,
-                upvalue_count: 0,
+                captures: vec![],
             }),
             upvalues: vec![],
         };
 
+        let closure = Rc::new(closure);
         let frame = CallFrame {
-            closure: Rc::new(closure),
+            code: closure.spell.bytecode.as_ptr(),
+            closure,
             ip: 0,
             // slot_start: 0,
             return_reg: 0,
             reg_base: 0,
             caller_reg_base: 0,
-            upvalue_mappings: vec![],
+            handlers: vec![],
+            stream: None,
         };
 
-        vm.frames.push(frame);
+        self.frames.push(frame);
+    }
 
-        vm
+    /// Lays the globals out by the slots of the scroll being loaded, carrying over the
+    /// values the earlier scrolls left under the same names
+    fn bind_globals(&mut self, names: Vec<String>) {
+        let earlier = self.global_names.drain(..).zip(self.globals.drain(..));
+        for (name, value) in earlier {
+            if let Some(value) = value {
+                self.stashed_globals.insert(name, value);
+            }
+        }
+
+        self.globals = names.iter().map(|name| self.stashed_globals.remove(name)).collect();
+        self.global_names = names;
+    }
+
+    /// Loads a scroll precompiled into a .eirac file
+    pub fn init_from_file(path: &Path) -> Result<Self, String> {
+        Ok(Self::init(bytecode_file::read_file(path)?))
+    }
+
+    /// Exposes a rust function to the scripts as a native spell.
+    /// The compiler should be told about its weaves with `Compiler::declare_native`.
+    pub fn register_native(&mut self, name: &str, arity: usize, spell: HostSpellFn) {
+        self.host_spells.insert(name.to_string(), (arity, spell));
+    }
+
+    /// Prints every instruction to the stderr before it is run, along with the register window
+    /// of its frame and the casts leading to it
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+        self.observed = self.trace || self.step_hook.is_some();
+    }
+
+    /// Calls the [hook] before every instruction the VM runs. The VM waits on the hook,
+    /// so a debugger can pause there and look around as long as it likes.
+    pub fn set_step_hook(&mut self, hook: impl FnMut(&VmState) + 'static) {
+        self.step_hook = Some(Box::new(hook));
+        self.observed = true;
     }
 
-    fn runtime_error(&mut self, msg: &str) {
-        println!(
-            "Oh no! The VM broke down. \nError: {} at line {}:{}",
-            msg, 0, 0
-        );
+    /// Stops calling the step hook
+    pub fn clear_step_hook(&mut self) {
+        self.step_hook = None;
+        self.observed = self.trace;
+    }
 
-        // ignore other instructions.
-        // while self.ip < self.bytecode.len()-1 {
-        //     self.ip += 1;
-        // }
+    /// Seeds the rolls of `fate_roll`, so that runs with the same seed roll the same numbers
+    pub fn set_seed(&mut self, seed: u64) {
+        self.fate = Fate::seeded(seed);
     }
 
-    pub fn start(&mut self) -> InterpretResult {
+    pub(crate) fn started_at(&self) -> Instant {
+        self.started
+    }
+
+    pub(crate) fn fate(&mut self) -> &mut Fate {
+        &mut self.fate
+    }
+
+    /// Reads the next line of the input without its line ending, chanting the [prompt] first.
+    /// Once the input has run dry, the line is empty.
+    pub(crate) fn heed_line(&mut self, prompt: Option<&str>) -> Result<String, String> {
+        if let Some(prompt) = prompt {
+            write!(self.output, "{}", prompt).map_err(|e| format!("The chant couldn't be heard. {}", e))?;
+        }
+        // the chants asking for the line are shown before waiting on it
+        let _ = self.output.flush();
+
+        let mut line = String::new();
+        self.input
+            .read_line(&mut line)
+            .map_err(|e| format!("Couldn't heed the line. {}", e))?;
+        let len = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(len);
+        Ok(line)
+    }
+
+    pub(crate) fn cast_host_spell(&mut self, name: &str, args: &[Value]) -> Result<Value, String> {
+        let Some((arity, spell)) = self.host_spells.get(name) else {
+            return Err(format!("The native spell '{}' was never registered to the VM!", name));
+        };
+
+        if *arity != args.len() {
+            return Err(format!(
+                "The native spell '{}' expected {} reagents, but got {}",
+                name,
+                arity,
+                args.len()
+            ));
+        }
+
+        // the host can hand back a closure of its own making, which the scroll is yet to run
+        let released = spell(args);
+        verify_value(&released).map_err(|e| format!("The native spell '{}' released a malformed spell. {}", name, e))?;
+        Ok(released)
+    }
+
+    /// The cell of the register at [stack_idx], shared by all the closures capturing it while it is in scope
+    fn capture_register(&mut self, stack_idx: usize) -> Rc<RefCell<Captured>> {
+        let open = self
+            .open_upvalues
+            .iter()
+            .find(|cell| matches!(*cell.borrow(), Captured::Open(idx) if idx == stack_idx));
+        if let Some(cell) = open {
+            return cell.clone();
+        }
+
+        let cell = Rc::new(RefCell::new(Captured::Open(stack_idx)));
+        self.open_upvalues.push(cell.clone());
+        cell
+    }
+
+    /// Closes over the captured registers from the stack index [from] on, their scope has ended
+    fn close_upvalues(&mut self, from: usize) {
+        let stack = &self.stack;
+        let mut closed = vec![]; // the cells closed over an object, which could close a cycle
+        self.open_upvalues.retain(|cell| {
+            let mut captured = cell.borrow_mut();
+            match *captured {
+                Captured::Open(idx) if idx >= from => {
+                    if Collector::holds_objects(&stack[idx]) {
+                        closed.push(cell.clone());
+                    }
+                    *captured = Captured::Closed(stack[idx].clone());
+                    false
+                }
+                _ => true,
+            }
+        });
+        for cell in closed {
+            self.collector.note_cell(&cell);
+        }
+    }
+
+    /// Shows the instruction the running frame is about to run to the tracer and the step hook
+    #[cold]
+    #[inline(never)]
+    fn observe(&mut self) {
+        let frame = self.frames.last().unwrap();
+        let spell = &frame.closure.spell;
+        let Some(instruction) = Instruction::decode(&spell.bytecode[frame.ip..]) else {
+            return;
+        };
+        let end = (frame.reg_base + spell.register_count).min(self.stack.len());
+        let (line, column) = spell.position_at(frame.ip);
+        let state = VmState {
+            instruction,
+            ip: frame.ip,
+            line,
+            column,
+            spell: spell.name.as_deref().unwrap_or("<origin>"),
+            registers: &self.stack[frame.reg_base.min(end)..end],
+            frames: self
+                .frames
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(depth, frame)| {
+                    let name = frame.closure.spell.name.as_deref().unwrap_or("<origin>");
+                    // the running frame hasn't read its instruction yet, the others sit past their casts
+                    let line = if depth == 0 { frame.closure.spell.line_at(frame.ip) } else { Self::frame_line(frame) };
+                    (name.to_string(), line)
+                })
+                .collect(),
+            locals: spell.debug.as_ref().map_or(&[], |debug| &debug.locals),
+            globals: &self.globals,
+            global_names: &self.global_names,
+        };
+
+        if self.trace {
+            eprintln!("{}", state);
+        }
+        if let Some(hook) = self.step_hook.as_mut() {
+            hook(&state);
+        }
+    }
+
+    fn runtime_error(&self, msg: &str) -> RuntimeError {
+        let (line, column) = self
+            .frames
+            .last()
+            .map_or((0, 0), |frame| frame.closure.spell.position_at(frame.ip.saturating_sub(1)));
+        RuntimeError {
+            kind: ErrorKind::Broken,
+            message: msg.to_string(),
+            line,
+            column,
+            // trace the casts that lead to the error, innermost first
+            trace: self
+                .frames
+                .iter()
+                .rev()
+                .map(|frame| {
+                    let name = frame.closure.spell.name.as_deref().unwrap_or("<origin>");
+                    (name.to_string(), Self::frame_line(frame))
+                })
+                .collect(),
+        }
+    }
+
+    /// Runs the loaded scroll, releasing the value it halted with
+    pub fn start(&mut self) -> Result<Value, RuntimeError> {
+        // the dispatch loop trusts the bytecode it reads, so it is checked once up front
+        if let Some(frame) = self.frames.first() {
+            verify_scroll(&frame.closure.spell).map_err(|e| self.runtime_error(&e))?;
+        }
+        self.stop_depth = 0;
+        self.resume()
+    }
+
+    /// Casts the spell the scroll bound to the global [name] with the [args] as its reagents,
+    /// releasing what the spell released. The scroll defining the spell has to be started first.
+    /// The spell and the spells within the reagents are verified before it's cast.
+    pub fn call_spell(&mut self, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+        let spell = match self.get_global(name) {
+            Some(Value::Closure(spell)) => spell.clone(),
+            Some(value) => {
+                return Err(self.runtime_error(&format!("The global '{}' is not a spell, it is {:?}", name, value)));
+            }
+            None => return Err(self.runtime_error(&format!("No spell named '{}' was bound by the scroll", name))),
+        };
+        // the spell and its reagents can be ones the host made, which the scroll never verified
+        for value in std::iter::once(Value::Closure(spell.clone())).chain(args.iter().cloned()) {
+            verify_value(&value).map_err(|e| self.runtime_error(&format!("The spell '{}' can't be cast. {}", name, e)))?;
+        }
+        let arity = spell.spell.arity as usize;
+        if args.len() != arity {
+            return Err(self.runtime_error(&format!(
+                "The spell '{}' takes {} reagents, but was given {}",
+                name,
+                arity,
+                args.len()
+            )));
+        }
+        if spell.spell.flowing {
+            return Ok(Value::Stream(Rc::new(StreamObject::new(spell, args.to_vec()))));
+        }
+
+        let depth = self.frames.len();
+        let reg_base = self.stack.len();
+        let frame_size = spell.spell.register_count.max(arity);
+        if depth >= self.max_frames {
+            return Err(self.runtime_error(&format!("Realm overflow! The casts went deeper than {} spells", self.max_frames)));
+        }
+        if reg_base + frame_size > self.max_stack {
+            return Err(self.runtime_error(&format!("Realm overflow! The spells needed more than {} registers", self.max_stack)));
+        }
+        self.stack.resize(reg_base + frame_size, Value::Emptiness);
+        self.stack[reg_base..reg_base + arity].clone_from_slice(args);
+
+        self.frames.push(CallFrame {
+            ip: 0,
+            code: spell.spell.bytecode.as_ptr(),
+            closure: spell,
+            return_reg: 0,
+            reg_base,
+            caller_reg_base: reg_base,
+            handlers: vec![],
+            stream: None,
+        });
+
+        let stop_depth = std::mem::replace(&mut self.stop_depth, depth);
+        let result = self.resume();
+        self.stop_depth = stop_depth;
+
+        // a breakdown leaves the frames of the call behind
+        if result.is_err() {
+            self.close_upvalues(reg_base);
+            self.abandon_frames(depth);
+            self.stack.truncate(reg_base);
+        }
+        result
+    }
+
+    /// The value of the global [name], whether the loaded scroll uses it or an earlier one left it
+    pub fn get_global(&self, name: &str) -> Option<&Value> {
+        match self.global_names.iter().position(|global| global == name) {
+            Some(slot) => self.globals[slot].as_ref(),
+            None => self.stashed_globals.get(name),
+        }
+    }
+
+    /// Gives the global [name] a value, kept for the scrolls loaded later too.
+    /// The compiler is told about the globals of the host with `Compiler::declare_global`.
+    /// The scroll can cast the spells within the value, so they are verified first, and a
+    /// malformed one is refused leaving the global as it was.
+    pub fn set_global(&mut self, name: &str, value: Value) -> Result<(), RuntimeError> {
+        verify_value(&value)
+            .map_err(|e| self.runtime_error(&format!("The global '{}' can't be given the value. {}", name, e)))?;
+        match self.global_names.iter().position(|global| global == name) {
+            Some(slot) => self.globals[slot] = Some(value),
+            None => {
+                self.stashed_globals.insert(name.to_string(), value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the frames on the VM until the scroll halts, or the spell called by the host releases
+    fn resume(&mut self) -> Result<Value, RuntimeError> {
+        // the limits count from every start and call anew
+        self.fuel_left = self.fuel;
+        self.deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        self.countdown = 0;
+        self.counted = 0;
+
+        loop {
+            match self.run() {
+                Err(error) if self.mend(&error) => continue,
+                // a conjured spell breaking down is told to whoever claims it, the rest run on
+                Err(error) if self.conjuring.is_some() && error.kind == ErrorKind::Broken => {
+                    self.end_conjuring(ConjuringState::Broken(error.message));
+                }
+                result => {
+                    // the host gets the VM back with the frames of the scroll on it
+                    while self.conjuring.is_some() {
+                        self.switch_task();
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// Unwinds the frames down to the innermost attempt and jumps to its mend with the [error].
+    /// Returns false when no attempt is there to mend it.
+    fn mend(&mut self, error: &RuntimeError) -> bool {
+        // running out of the limits can't be mended, the host wants the scroll stopped
+        if error.kind != ErrorKind::Broken {
+            return false;
+        }
+        // the attempts of the frames below a spell called by the host are not part of the call
+        let called = &self.frames[self.stop_depth..];
+        let Some(depth) = called.iter().rposition(|frame| !frame.handlers.is_empty()) else {
+            return false;
+        };
+        let depth = self.stop_depth + depth;
+
+        // the frames cast from within the attempt are abandoned, like they released
+        if let Some(abandoned) = self.frames.get(depth + 1) {
+            let from = abandoned.reg_base;
+            self.close_upvalues(from);
+            self.stack.truncate(from);
+        }
+        self.abandon_frames(depth + 1);
+
+        let frame = self.frames.last_mut().unwrap();
+        let handler = frame.handlers.pop().unwrap();
+        frame.ip = handler.ip;
+        let error_idx = frame.reg_base + handler.error_reg as usize;
+        self.stack[error_idx] = Value::Error(Rc::new(error.message.clone()));
+        true
+    }
+
+    /// Drops the frames from [depth] on, the streams they were drawing from run dry
+    fn abandon_frames(&mut self, depth: usize) {
+        for frame in self.frames.drain(depth..) {
+            if let Some((stream, _)) = frame.stream {
+                *stream.state.borrow_mut() = StreamState::Dry;
+            }
+        }
+    }
+
+    /// Runs the [stream] on from where it offered last, in a frame of its own. What it offers
+    /// next goes to the [dest] register of the drawing frame, with [more] telling whether it did.
+    fn draw(&mut self, stream: Rc<StreamObject>, dest: u8, more: u8) -> Result<(), RuntimeError> {
+        let caller_reg_base = self.frames.last().map_or(0, |frame| frame.reg_base);
+        let spell = stream.closure.spell.clone();
+        let frame_size = spell.register_count.max(spell.arity as usize);
+        let reg_base = self.stack.len();
+        if self.frames.len() >= self.max_frames {
+            return Err(self.runtime_error(&format!("Realm overflow! The casts went deeper than {} spells", self.max_frames)));
+        }
+        if reg_base + frame_size > self.max_stack {
+            return Err(self.runtime_error(&format!("Realm overflow! The spells needed more than {} registers", self.max_stack)));
+        }
+
+        let state = stream.state.replace(StreamState::Flowing);
+        let (ip, mut registers, attempts) = match state {
+            StreamState::Fresh(reagents) => (0, reagents, vec![]),
+            StreamState::Suspended { ip, registers, attempts } => (ip, registers, attempts),
+            StreamState::Flowing => {
+                return Err(self.runtime_error("The stream is already being drawn from, it can't draw from itself"));
+            }
+            StreamState::Dry => {
+                stream.state.replace(StreamState::Dry);
+                self.stack[caller_reg_base + dest as usize] = Value::Emptiness;
+                self.stack[caller_reg_base + more as usize] = Value::Bool(false);
+                return Ok(());
+            }
+        };
+        registers.resize(frame_size, Value::Emptiness);
+        self.stack.extend(registers);
+
+        self.frames.push(CallFrame {
+            ip,
+            code: spell.bytecode.as_ptr(),
+            closure: stream.closure.clone(),
+            return_reg: dest,
+            reg_base,
+            caller_reg_base,
+            handlers: attempts.into_iter().map(|(ip, error_reg)| Handler { ip, error_reg }).collect(),
+            stream: Some((stream, more)),
+        });
+        Ok(())
+    }
+
+    /// Starts the [closure] with the [reagents] as a task of its own, waiting for its turn
+    fn conjure(&mut self, closure: Rc<ClosureObject>, reagents: Vec<Value>) -> Rc<ConjuringObject> {
+        let spell = closure.spell.clone();
+        let mut stack = reagents;
+        stack.resize(spell.register_count.max(stack.len()), Value::Emptiness);
+
+        let conjuring = Rc::new(ConjuringObject::new(closure.clone()));
+        self.tasks.push_back(Task {
+            conjuring: Some(conjuring.clone()),
+            frames: vec![CallFrame {
+                ip: 0,
+                code: spell.bytecode.as_ptr(),
+                closure,
+                return_reg: 0,
+                reg_base: 0,
+                caller_reg_base: 0,
+                handlers: vec![],
+                stream: None,
+            }],
+            stack,
+            open_upvalues: vec![],
+            stop_depth: 0,
+        });
+        conjuring
+    }
+
+    /// Hands the VM to the next task in line, the running one waits at the end of the line
+    fn switch_task(&mut self) {
+        let Some(next) = self.tasks.pop_front() else {
+            return;
+        };
+
+        let stack = &self.stack;
+        let open_upvalues = std::mem::take(&mut self.open_upvalues)
+            .into_iter()
+            .filter_map(|cell| {
+                let Captured::Open(idx) = *cell.borrow() else {
+                    return None;
+                };
+                cell.replace(Captured::Closed(stack[idx].clone()));
+                Some((cell, idx))
+            })
+            .collect();
+        let waiting = Task {
+            conjuring: self.conjuring.take(),
+            frames: std::mem::take(&mut self.frames),
+            stack: std::mem::take(&mut self.stack),
+            open_upvalues,
+            stop_depth: self.stop_depth,
+        };
+        self.tasks.push_back(waiting);
+        self.resume_task(next);
+    }
+
+    /// Ends the running conjured spell with the [state], the next task in line takes the VM
+    fn end_conjuring(&mut self, state: ConjuringState) {
+        self.close_upvalues(0);
+        self.abandon_frames(0);
+        self.stack.clear();
+        if let Some(conjuring) = self.conjuring.take() {
+            *conjuring.state.borrow_mut() = state;
+        }
+        // the scroll waits in the line while a conjured spell runs
+        let next = self.tasks.pop_front().unwrap();
+        self.resume_task(next);
+    }
+
+    fn resume_task(&mut self, task: Task) {
+        self.conjuring = task.conjuring;
+        self.frames = task.frames;
+        self.stack = task.stack;
+        self.stop_depth = task.stop_depth;
+        for (cell, idx) in task.open_upvalues {
+            if let Captured::Closed(value) = cell.replace(Captured::Open(idx)) {
+                self.stack[idx] = value;
+            }
+            self.open_upvalues.push(cell);
+        }
+    }
+
+    /// Drops the conjured spells still running, the scroll they were conjured by is gone
+    fn dispel_conjurings(&mut self) {
+        while self.conjuring.is_some() {
+            self.switch_task();
+        }
+        // the registers they captured were closed over as they were left waiting
+        for task in self.tasks.drain(..) {
+            if let Some(conjuring) = task.conjuring {
+                *conjuring.state.borrow_mut() = ConjuringState::Broken("The scroll was unloaded before the spell released".to_string());
+            }
+        }
+    }
+
+    /// Takes the instructions run since the last check off the fuel, and looks at the clock.
+    /// Called once the countdown runs out, which is set so the fuel can't be overrun.
+    #[cold]
+    #[inline(never)]
+    fn check_limits(&mut self) -> Result<(), RuntimeError> {
+        if let Some(fuel) = self.fuel_left.as_mut() {
+            *fuel -= self.counted as u64;
+            if *fuel == 0 {
+                let msg = format!("The scroll ran out of its fuel of {} instructions", self.fuel.unwrap_or_default());
+                return Err(RuntimeError { kind: ErrorKind::OutOfFuel, ..self.runtime_error(&msg) });
+            }
+        }
+        if let Some(deadline) = self.deadline
+            && Instant::now() >= deadline
+        {
+            let limit = self.time_limit.unwrap_or_default();
+            let msg = format!("The scroll ran for longer than its time limit of {:?}", limit);
+            return Err(RuntimeError { kind: ErrorKind::OutOfTime, ..self.runtime_error(&msg) });
+        }
+
+        let interval = LIMIT_CHECK_INTERVAL as u64;
+        self.counted = self.fuel_left.map_or(interval, |fuel| fuel.min(interval)) as u32;
+        self.countdown = self.counted;
+
+        // the conjured spells take turns with the scroll, one interval each
+        self.switch_task();
+        Ok(())
+    }
+
+    /// The dispatch loop, running until the scroll halts or the VM breaks down
+    fn run(&mut self) -> Result<Value, RuntimeError> {
         macro_rules! set_register {
             ($base:expr, $index:expr, $value:expr) => {{
                 let idx = $base + $index as usize;
+                // frames are sized by the register count of their spell, growing here means it was miscounted
+                debug_assert!(idx < self.stack.len(), "register {} is outside of its frame", $index);
                 if idx >= self.stack.len() {
                     self.stack.resize(idx + 1, Value::Emptiness);
                 }
@@ -134,6 +963,19 @@ impl EiraVM {
             ($base:expr, $index:expr) => {{ &self.stack[$base + $index as usize] }};
         }
 
+        // the number held by a register, to index or slice with
+        macro_rules! number_register {
+            ($base:expr, $index:expr) => {{
+                let value = get_register!($base, $index);
+                match value.extract_number() {
+                    Some(n) => n,
+                    None => {
+                        return Err(self.runtime_error(&format!("Expected a number to index with! Got {:?}", value)));
+                    }
+                }
+            }};
+        }
+
         macro_rules! frame {
             () => {
                 self.frames.last_mut().unwrap()
@@ -151,8 +993,134 @@ impl EiraVM {
                         set_register!(frame!().reg_base, dest, Value::from(r));
                     }
                     _ => {
-                        self.runtime_error(&format!("Operands should be 2 numbers! Got {:?} and {:?}", v1, v2));
-                        return InterpretResult::RuntimeError;
+                        return Err(self.runtime_error(&format!("Operands should be 2 numbers! Got {:?} and {:?}", v1, v2)));
+                    }
+                }
+            }};
+        }
+
+        // numbers are ordered by their value, texts letter by letter
+        macro_rules! ordinal_op {
+            ($op:tt) => {{
+                let (dest, r1, r2) = frame!().read_three_bytes();
+                let v1 = get_register!(frame!().reg_base, r1);
+                let v2 = get_register!(frame!().reg_base, r2);
+                let r = match (v1, v2) {
+                    (Value::Number(n1), Value::Number(n2)) => n1 $op n2,
+                    (Value::Int(i1), Value::Int(i2)) => i1 $op i2,
+                    (Value::String(s1), Value::String(s2)) => s1 $op s2,
+                    _ => {
+                        return Err(self.runtime_error(&format!("Operands should be 2 numbers or 2 texts! Got {:?} and {:?}", v1, v2)));
+                    }
+                };
+                set_register!(frame!().reg_base, dest, Value::Bool(r));
+            }};
+        }
+
+        // dividing by zero breaks the VM down, instead of quietly releasing an infinity or NaN
+        macro_rules! divisive_op {
+            ($op:tt) => {{
+                let (dest, r1, r2) = frame!().read_three_bytes();
+                let v1 = get_register!(frame!().reg_base, r1);
+                let v2 = get_register!(frame!().reg_base, r2);
+                match (v1, v2) {
+                    (Value::Number(n1), Value::Number(n2)) if *n2 == 0.0 => {
+                        return Err(self.runtime_error(&format!("Tried to divide by zero, {} {} 0", n1, stringify!($op))));
+                    }
+                    (Value::Number(n1), Value::Number(n2)) => {
+                        let r = n1 $op n2;
+                        set_register!(frame!().reg_base, dest, Value::from(r));
+                    }
+                    _ => {
+                        return Err(self.runtime_error(&format!("Operands should be 2 numbers! Got {:?} and {:?}", v1, v2)));
+                    }
+                }
+            }};
+        }
+
+        // ints are exact, so an overflowing result breaks the VM down instead of wrapping around
+        macro_rules! int_op {
+            ($method:ident, $op:tt) => {{
+                let (dest, r1, r2) = frame!().read_three_bytes();
+                let v1 = get_register!(frame!().reg_base, r1);
+                let v2 = get_register!(frame!().reg_base, r2);
+                match (v1, v2) {
+                    (Value::Int(i1), Value::Int(i2)) => {
+                        let (i1, i2) = (*i1, *i2);
+                        // only dividing can fail on a zero
+                        let Some(r) = i1.$method(i2) else {
+                            let msg = if i2 == 0 {
+                                format!("Tried to divide by zero, {} {} 0", i1, stringify!($op))
+                            } else {
+                                format!("The int {} {} {} overflows.", i1, stringify!($op), i2)
+                            };
+                            return Err(self.runtime_error(&msg));
+                        };
+                        set_register!(frame!().reg_base, dest, Value::Int(r));
+                    }
+                    _ => {
+                        return Err(self.runtime_error(&format!("Operands should be 2 ints! Got {:?} and {:?}", v1, v2)));
+                    }
+                }
+            }};
+        }
+
+        // the bits of two ints
+        macro_rules! bitwise_op {
+            ($op:tt) => {{
+                let (dest, r1, r2) = frame!().read_three_bytes();
+                let v1 = get_register!(frame!().reg_base, r1);
+                let v2 = get_register!(frame!().reg_base, r2);
+                match (v1, v2) {
+                    (Value::Int(i1), Value::Int(i2)) => {
+                        let r = i1 $op i2;
+                        set_register!(frame!().reg_base, dest, Value::Int(r));
+                    }
+                    _ => {
+                        return Err(self.runtime_error(&format!("Operands should be 2 ints! Got {:?} and {:?}", v1, v2)));
+                    }
+                }
+            }};
+        }
+
+        // shifting the bits of an int past its 64 bits, or backwards, breaks the VM down
+        macro_rules! shift_op {
+            ($method:ident) => {{
+                let (dest, r1, r2) = frame!().read_three_bytes();
+                let v1 = get_register!(frame!().reg_base, r1);
+                let v2 = get_register!(frame!().reg_base, r2);
+                match (v1, v2) {
+                    (Value::Int(i1), Value::Int(i2)) => {
+                        let Some(r) = u32::try_from(*i2).ok().and_then(|by| i1.$method(by)) else {
+                            return Err(self.runtime_error(&format!("Can't shift the bits of an int by {} places.", i2)));
+                        };
+                        set_register!(frame!().reg_base, dest, Value::Int(r));
+                    }
+                    _ => {
+                        return Err(self.runtime_error(&format!("Operands should be 2 ints! Got {:?} and {:?}", v1, v2)));
+                    }
+                }
+            }};
+        }
+
+        // counters step by a constant, which is read right out of the constant pool
+        macro_rules! constant_op {
+            ($op:tt) => {{
+                let (dest, r1) = (frame!().read_byte(), frame!().read_byte());
+                let n2 = match frame!().read_constant() {
+                    Value::Number(n) => *n,
+                    c => {
+                        let msg = format!("The constant operand should be a number! Got {:?}", c);
+                        return Err(self.runtime_error(&msg));
+                    }
+                };
+                match get_register!(frame!().reg_base, r1) {
+                    Value::Number(n1) => {
+                        let r = n1 $op n2;
+                        set_register!(frame!().reg_base, dest, Value::from(r));
+                    }
+                    v1 => {
+                        return Err(self.runtime_error(&format!("Operands should be 2 numbers! Got {:?} and {:?}", v1, n2)));
                     }
                 }
             }};
@@ -161,22 +1129,57 @@ impl EiraVM {
         // Keep this on to profile execution
         // let mut instruction_count: u32 = 0;
 
-        loop {
+        let result = loop {
+            if self.observed {
+                self.observe();
+            }
+            if self.countdown == 0 {
+                self.check_limits()?;
+            }
+            self.countdown -= 1;
             let base = frame!().reg_base;
-            let op = OpCode::try_from(frame!().read_byte()).unwrap();
+            let Some(op) = frame!().read_opcode() else {
+                let ip = frame!().ip - 1;
+                return Err(self.runtime_error(&format!("Unknown opcode at {}, the bytecode is malformed", ip)));
+            };
             // instruction_count += 1;
             match op {
                 OpCode::Add => binary_op!(+),
                 OpCode::Subtract => binary_op!(-),
-                OpCode::Divide => binary_op!(/),
+                OpCode::Divide => divisive_op!(/),
                 OpCode::Multiply => binary_op!(*),
-                OpCode::Mod => binary_op!(%),
+                OpCode::Mod => divisive_op!(%),
+                OpCode::AddInt => int_op!(checked_add, +),
+                OpCode::SubtractInt => int_op!(checked_sub, -),
+                OpCode::MultiplyInt => int_op!(checked_mul, *),
+                OpCode::DivideInt => int_op!(checked_div, /),
+                OpCode::ModInt => int_op!(checked_rem, %),
+                OpCode::BitAnd => bitwise_op!(&),
+                OpCode::BitOr => bitwise_op!(|),
+                OpCode::BitXor => bitwise_op!(^),
+                OpCode::ShiftLeft => shift_op!(checked_shl),
+                OpCode::ShiftRight => shift_op!(checked_shr),
+                OpCode::AddConst => constant_op!(+),
+                OpCode::SubtractConst => constant_op!(-),
                 OpCode::Concat => {
                     let (dest, r1, r2) = frame!().read_three_bytes();
                     let v1 = get_register!(base, r1);
                     let v2 = get_register!(base, r2);
-                    let r = v1.extract_string().unwrap() + &v2.extract_string().unwrap();
-                    set_register!(base, dest, Value::String(Rc::new(r)));
+                    match (v1, v2) {
+                        (Value::String(s1), Value::String(s2)) => {
+                            // build the result once from the borrowed halves, rather than cloning both
+                            let mut r = String::with_capacity(s1.len() + s2.len());
+                            r.push_str(s1);
+                            r.push_str(s2);
+                            set_register!(base, dest, Value::String(Rc::new(r)));
+                        }
+                        _ => {
+                            return Err(self.runtime_error(&format!(
+                                "Only texts can be concatenated! Got {:?} and {:?}",
+                                v1, v2
+                            )));
+                        }
+                    }
                 }
                 OpCode::Equal => {
                     let (dest, r1, r2) = frame!().read_three_bytes();
@@ -186,10 +1189,23 @@ impl EiraVM {
                     set_register!(base, dest, Value::Bool(r));
                 }
                 OpCode::Greater => {
-                    binary_op!(>)
+                    ordinal_op!(>)
                 }
                 OpCode::Less => {
-                    binary_op!(<)
+                    ordinal_op!(<)
+                }
+                OpCode::GreaterEqual => {
+                    ordinal_op!(>=)
+                }
+                OpCode::LessEqual => {
+                    ordinal_op!(<=)
+                }
+                OpCode::NotEqual => {
+                    let (dest, r1, r2) = frame!().read_three_bytes();
+                    let a = get_register!(base, r1);
+                    let b = get_register!(base, r2);
+                    let r = !a.equals(b);
+                    set_register!(base, dest, Value::Bool(r));
                 }
                 OpCode::False => {
                     let dest = frame!().read_byte();
@@ -208,9 +1224,15 @@ impl EiraVM {
                             let num = *n;
                             set_register!(base, dest, Value::Number(-num));
                         }
+                        Value::Int(i) => {
+                            let Some(negated) = i.checked_neg() else {
+                                let msg = format!("The int -({}) overflows.", i);
+                                return Err(self.runtime_error(&msg));
+                            };
+                            set_register!(base, dest, Value::Int(negated));
+                        }
                         _ => {
-                            self.runtime_error("What???!! Negation needs a number operand.");
-                            return InterpretResult::RuntimeError;
+                            return Err(self.runtime_error("What???!! Negation needs a number operand."));
                         }
                     }
                 }
@@ -219,37 +1241,40 @@ impl EiraVM {
                     let src_ind = frame!().read_byte();
                     let source = get_register!(base, src_ind);
                     match source {
-                        Value::Bool(b) => {
-                            let boo = *b;
-                            set_register!(base, dest, Value::Bool(!boo));
+                        Value::Bool(_) | Value::Emptiness => {
+                            let boo = source.is_falsey();
+                            set_register!(base, dest, Value::Bool(boo));
                         }
                         _ => {
-                            self.runtime_error("What???!! Not needs a boolean operand.");
-                            return InterpretResult::RuntimeError;
+                            return Err(self.runtime_error("What???!! Not needs a boolean operand."));
                         }
                     }
                 }
-                OpCode::Constant => {
+                OpCode::Constant | OpCode::ConstantLong => {
                     let dest = frame!().read_byte();
-                    let cval = frame!().read_constant().clone();
+                    let cval = if op == OpCode::Constant {
+                        frame!().read_constant().clone()
+                    } else {
+                        let index = frame!().read_u32();
+                        frame!().closure.spell.constants[index as usize].clone()
+                    };
                     match cval {
                         Value::Closure(c) => {
-                            // Eagerly bind upvalues: capture from current frame into a fresh ClosureObject
-                            let upcount = c.spell.upvalue_count as usize;
-                            let mut new_upvalues: Vec<UpValue> = Vec::with_capacity(upcount);
-                            for i in 0..upcount {
-                                let u = &c.upvalues[i];
-                                let capture_idx = frame!().reg_base + u.index;
-                                let captured = if capture_idx < self.stack.len() {
-                                    self.stack[capture_idx].clone()
-                                } else {
-                                    Value::Emptiness
+                            // the closure shares the variables it captures with this frame, not copies of them
+                            let mut new_upvalues = Vec::with_capacity(c.spell.captures.len());
+                            for capture in &c.spell.captures {
+                                let cell = match *capture {
+                                    Capture::Local(reg) => self.capture_register(base + reg as usize),
+                                    Capture::Enclosing(index) => {
+                                        match self.frames.last().unwrap().closure.upvalues.get(index as usize) {
+                                            Some(cell) => cell.clone(),
+                                            None => {
+                                                return Err(self.runtime_error(&format!("Fatal: The upvalue {} doesn't exist.", index)));
+                                            }
+                                        }
+                                    }
                                 };
-                                new_upvalues.push(UpValue {
-                                    index: u.index,
-                                    depth: u.depth,
-                                    closed: std::cell::RefCell::new(captured),
-                                });
+                                new_upvalues.push(cell);
                             }
 
                             let new_closure = ClosureObject {
@@ -265,37 +1290,127 @@ impl EiraVM {
                 }
                 OpCode::Print => {
                     let i = frame!().read_byte();
-                    let val = get_register!(base, i);
-                    print_value(val.clone())
+                    let text = stringify_value(get_register!(base, i));
+                    if let Err(e) = writeln!(self.output, "{}", text) {
+                        return Err(self.runtime_error(&format!("The chant couldn't be heard. {}", e)));
+                    }
+                }
+                OpCode::Heed => {
+                    let dest = frame!().read_byte();
+                    let line = match self.heed_line(None) {
+                        Ok(line) => line,
+                        Err(e) => return Err(self.runtime_error(&e)),
+                    };
+                    set_register!(base, dest, Value::String(Rc::new(line)));
+                }
+                OpCode::Attempt => {
+                    let error_reg = frame!().read_byte();
+                    let offset = frame!().read_u16() as usize;
+                    let ip = frame!().ip + offset;
+                    frame!().handlers.push(Handler { ip, error_reg });
+                }
+                OpCode::AttemptLong => {
+                    let error_reg = frame!().read_byte();
+                    let offset = frame!().read_u32() as usize;
+                    let ip = frame!().ip + offset;
+                    frame!().handlers.push(Handler { ip, error_reg });
+                }
+                OpCode::EndAttempt => {
+                    frame!().handlers.pop();
+                }
+                OpCode::Throw => {
+                    let r1 = frame!().read_byte();
+                    let message = stringify_value(get_register!(base, r1));
+                    return Err(self.runtime_error(&message));
+                }
+                OpCode::ToText => {
+                    let dest = frame!().read_byte();
+                    let r1 = frame!().read_byte();
+                    let text = stringify_value(get_register!(base, r1));
+                    set_register!(base, dest, Value::String(Rc::new(text)));
+                }
+                OpCode::Length => {
+                    let dest = frame!().read_byte();
+                    let r1 = frame!().read_byte();
+                    let length = match get_register!(base, r1) {
+                        Value::Deck(d) => d.items.borrow().len(),
+                        Value::String(s) => s.chars().count(),
+                        _ => {
+                            return Err(self.runtime_error(
+                                "Value is neither a Deck nor a Text to perform 'LENGTH' Operation",
+                            ));
+                        }
+                    };
+                    set_register!(base, dest, Value::Number(length as f64));
+                }
+                OpCode::Slice => {
+                    let dest = frame!().read_byte();
+                    let r1 = frame!().read_byte();
+                    let start_reg = frame!().read_byte();
+                    let end_reg = frame!().read_byte();
+                    let start = number_register!(base, start_reg);
+                    let end = number_register!(base, end_reg);
+
+                    let value = get_register!(base, r1).clone();
+                    let length = match &value {
+                        Value::Deck(d) => d.items.borrow().len(),
+                        Value::String(s) => s.chars().count(),
+                        _ => {
+                            return Err(self.runtime_error(
+                                "Value is neither a Deck nor a Text to perform 'SLICE' Operation",
+                            ));
+                        }
+                    };
+                    if start < 0.0 || start > end || end > length as f64 {
+                        return Err(self.runtime_error(&format!(
+                            "The slice {}..{} doesn't fit in the {} elements sliced.",
+                            start, end, length
+                        )));
+                    }
+                    let (start, end) = (start as usize, end as usize);
+
+                    let sliced = match value {
+                        Value::Deck(d) => {
+                            let items = d.items.borrow()[start..end].to_vec();
+                            Value::Deck(Rc::new(DeckObject::new(items, None)))
+                        }
+                        Value::String(s) => {
+                            Value::String(Rc::new(s.chars().skip(start).take(end - start).collect()))
+                        }
+                        _ => unreachable!(),
+                    };
+                    set_register!(base, dest, sliced);
+                }
+                OpCode::PrintRaw => {
+                    let i = frame!().read_byte();
+                    let text = stringify_value(get_register!(base, i));
+                    if let Err(e) = write!(self.output, "{}", text) {
+                        return Err(self.runtime_error(&format!("The chant couldn't be heard. {}", e)));
+                    }
                 }
                 OpCode::SetGlobal => {
                     let src_reg_ind = frame!().read_byte();
-                    let var_name_value = frame!().read_constant().clone();
-                    let value = get_register!(base, src_reg_ind);
-                    if let Value::String(name) = var_name_value {
-                        self.globals.insert(name.to_string(), value.clone());
-                    } else {
-                        self.runtime_error(
-                            "Fatal: A string was expected for the global variable name.",
-                        );
-                        return InterpretResult::RuntimeError;
+                    let slot = frame!().read_u16() as usize;
+                    let value = get_register!(base, src_reg_ind).clone();
+                    match self.globals.get_mut(slot) {
+                        Some(global) => *global = Some(value),
+                        None => {
+                            return Err(self.runtime_error(&format!("Fatal: The global slot {} doesn't exist.", slot)));
+                        }
                     }
                 }
                 OpCode::GetGlobal => {
                     let dest_reg = frame!().read_byte();
-                    let val = frame!().read_constant().clone();
-                    if let Value::String(name) = val {
-                        let global = self.globals.get(&name.to_string());
-                        if let Some(value) = global {
-                            set_register!(base, dest_reg, value.clone());
-                        } else {
-                            self.runtime_error(&format!("The mark '{}' was undefined", name));
+                    let slot = frame!().read_u16() as usize;
+                    match self.globals.get(slot) {
+                        Some(Some(value)) => set_register!(base, dest_reg, value.clone()),
+                        Some(None) => {
+                            let name = &self.global_names[slot];
+                            return Err(self.runtime_error(&format!("The mark '{}' was undefined", name)));
+                        }
+                        None => {
+                            return Err(self.runtime_error(&format!("Fatal: The global slot {} doesn't exist.", slot)));
                         }
-                    } else {
-                        self.runtime_error(
-                            "Fatal: A string was expected for the global variable name.",
-                        );
-                        return InterpretResult::RuntimeError;
                     }
                 }
                 OpCode::Move => {
@@ -309,13 +1424,6 @@ impl EiraVM {
                     let dest_reg = frame!().read_byte();
                     set_register!(base, dest_reg, Value::Emptiness);
                 }
-                OpCode::PopStack => {
-                    let mut count = frame!().read_u16();
-                    while count > 0 {
-                        self.stack.pop();
-                        count -= 1;
-                    }
-                }
                 OpCode::Jump => {
                     let offset = frame!().read_u16();
                     frame!().ip += offset as usize;
@@ -332,7 +1440,32 @@ impl EiraVM {
                     let offset = frame!().read_u16();
                     frame!().ip -= offset as usize;
                 }
-                OpCode::Halt => break,
+                OpCode::JumpLong => {
+                    let offset = frame!().read_u32();
+                    frame!().ip += offset as usize;
+                }
+                OpCode::JumpIfFalseLong => {
+                    let condition_reg = frame!().read_byte();
+                    let offset = frame!().read_u32();
+
+                    if get_register!(base, condition_reg).is_falsey() {
+                        frame!().ip += offset as usize;
+                    }
+                }
+                OpCode::LoopLong => {
+                    let offset = frame!().read_u32();
+                    frame!().ip -= offset as usize;
+                }
+                OpCode::Halt => {
+                    // the scroll waits for the spells it conjured before it halts
+                    if !self.tasks.is_empty() {
+                        frame!().ip -= 1;
+                        self.switch_task();
+                        continue;
+                    }
+                    let src = frame!().read_byte();
+                    break get_register!(base, src).clone();
+                }
                 OpCode::Release => {
                     let ret_reg = frame!().read_byte();
                     let ret_idx = frame!().reg_base + ret_reg as usize;
@@ -340,18 +1473,28 @@ impl EiraVM {
 
                     let finished = self.frames.pop().unwrap();
 
-                    // Sync upvalue changes back to parent frame before truncating stack
-                    for (local_reg, parent_stack_idx) in &finished.upvalue_mappings {
-                        let local_stack_idx = finished.reg_base + local_reg;
-                        if local_stack_idx < self.stack.len()
-                            && *parent_stack_idx < self.stack.len()
-                        {
-                            let val = self.stack[local_stack_idx].clone();
-                            *finished.closure.upvalues[*local_reg].closed.borrow_mut() = val;
-                        }
+                    // the closures made by the frame keep what they captured from it
+                    self.close_upvalues(finished.reg_base);
+                    self.stack.truncate(finished.reg_base);
+
+                    // a conjured spell is done, it is claimed for what it released
+                    if self.frames.is_empty() && self.conjuring.is_some() {
+                        self.end_conjuring(ConjuringState::Released(ret_val));
+                        continue;
                     }
 
-                    self.stack.truncate(finished.reg_base);
+                    // the spell called by the host releases back to it
+                    if self.frames.len() == self.stop_depth {
+                        break ret_val;
+                    }
+
+                    // a stream releasing has run dry, its drawer is told there is no more
+                    if let Some((stream, more)) = finished.stream {
+                        *stream.state.borrow_mut() = StreamState::Dry;
+                        self.stack[finished.caller_reg_base + finished.return_reg as usize] = Value::Emptiness;
+                        self.stack[finished.caller_reg_base + more as usize] = Value::Bool(false);
+                        continue;
+                    }
 
                     let dest_idx = finished.caller_reg_base + finished.return_reg as usize;
                     if dest_idx >= self.stack.len() {
@@ -368,64 +1511,158 @@ impl EiraVM {
 
                     let callee_idx = frame!().reg_base + spell_reg as usize;
                     if callee_idx >= self.stack.len() {
-                        self.runtime_error(&format!("Cast: spell register {} (stack index {}) out of bounds (stack size: {})", spell_reg, callee_idx, self.stack.len()));
-                        return InterpretResult::RuntimeError;
+                        return Err(self.runtime_error(&format!("Cast: spell register {} (stack index {}) out of bounds (stack size: {})", spell_reg, callee_idx, self.stack.len())));
                     }
                     let callee_val = self.stack[callee_idx].clone();
                     let spell = match callee_val {
                         Value::Closure(c) => c,
                         _ => {
-                            self.runtime_error(&format!("Attempted to cast a non-spell value: {:?} at register {} (stack[{}])", callee_val, spell_reg, callee_idx));
-                            return InterpretResult::RuntimeError;
+                            return Err(self.runtime_error(&format!("Attempted to cast a non-spell value: {:?} at register {} (stack[{}])", callee_val, spell_reg, callee_idx)));
                         }
                     };
 
                     let arity = spell.spell.arity as usize;
+                    // the reagents have to be within the frame of the caster
+                    if frame!().reg_base + reg_start as usize + arity > frame_slot_start {
+                        return Err(self.runtime_error(&format!("Cast: the {} reagents from register {} reach past the frame of the caster", arity, reg_start)));
+                    }
 
-                    let upvalues_count = spell.spell.upvalue_count as usize;
-                    let total = upvalues_count + arity;
-
-                    // Grow the stack if needed
-                    if frame_slot_start + total > self.stack.len() {
-                        self.stack
-                            .resize(frame_slot_start + total, Value::Emptiness);
+                    // a flowing spell isn't run yet, it runs as its values are drawn
+                    if spell.spell.flowing {
+                        let from = frame!().reg_base + reg_start as usize;
+                        let reagents = self.stack[from..from + arity].to_vec();
+                        set_register!(base, dest, Value::Stream(Rc::new(StreamObject::new(spell, reagents))));
+                        continue;
                     }
 
-                    // Track upvalue mappings for this frame (unused with eager capture)
-                    let upvalue_mappings = Vec::new();
-                    // Populate upvalue registers from eagerly captured values
-                    for i in 0..upvalues_count {
-                        let v = spell.upvalues[i].closed.borrow().clone();
-                        self.stack[frame_slot_start + i] = v;
+                    // Make room for the whole frame of the spell
+                    let frame_size = spell.spell.register_count.max(arity);
+                    if self.frames.len() >= self.max_frames {
+                        return Err(self.runtime_error(&format!("Realm overflow! The casts went deeper than {} spells", self.max_frames)));
                     }
+                    if frame_slot_start + frame_size > self.max_stack {
+                        return Err(self.runtime_error(&format!("Realm overflow! The spells needed more than {} registers", self.max_stack)));
+                    }
+                    self.stack
+                        .resize(frame_slot_start + frame_size, Value::Emptiness);
 
                     for i in 0..arity {
-                        self.stack[frame_slot_start + upvalues_count + i] =
+                        self.stack[frame_slot_start + i] =
                             self.stack[frame!().reg_base + (reg_start as usize) + i].clone();
                     }
 
                     let new_frame = CallFrame {
                         ip: 0,
+                        code: spell.spell.bytecode.as_ptr(),
                         closure: spell,
                         // slot_start: frame_slot_start,
                         return_reg: dest,
                         reg_base: frame_slot_start, // Unified: registers start at same place as slots (params are reg 0..arity)
                         caller_reg_base: frame!().reg_base,
-                        upvalue_mappings,
+                        handlers: vec![],
+                        stream: None,
                     };
                     self.frames.push(new_frame);
                 }
+                OpCode::Conjure => {
+                    let (dest, spell_reg, reg_start) = frame!().read_three_bytes();
+                    let spell = match get_register!(base, spell_reg) {
+                        Value::Closure(spell) if !spell.spell.flowing => spell.clone(),
+                        value => {
+                            return Err(self.runtime_error(&format!("Only a spell can be conjured, and not a flowing one! Got {:?}", value)));
+                        }
+                    };
+                    let arity = spell.spell.arity as usize;
+                    let from = base + reg_start as usize;
+                    if from + arity > self.stack.len() {
+                        return Err(self.runtime_error(&format!("Conjure: the {} reagents from register {} reach past the frame of the conjurer", arity, reg_start)));
+                    }
+                    let conjuring = self.conjure(spell, self.stack[from..from + arity].to_vec());
+                    set_register!(base, dest, Value::Conjuring(conjuring));
+                }
+                OpCode::Claim => {
+                    let dest = frame!().read_byte();
+                    let handle = frame!().read_byte();
+                    let conjuring = match get_register!(base, handle) {
+                        Value::Conjuring(conjuring) => conjuring.clone(),
+                        value => {
+                            return Err(self.runtime_error(&format!("Only a conjuring can be claimed! Got {:?}", value)));
+                        }
+                    };
+                    let released = match &*conjuring.state.borrow() {
+                        ConjuringState::Released(value) => Ok(value.clone()),
+                        ConjuringState::Broken(message) => Err(message.clone()),
+                        ConjuringState::Running => {
+                            // nothing is left to run if the spells wait on each other
+                            let mut waited = Some(conjuring.clone());
+                            while let Some(on) = waited {
+                                if self.conjuring.as_ref().is_some_and(|current| Rc::ptr_eq(current, &on)) {
+                                    return Err(self.runtime_error("The conjured spells are claiming each other, none of them would ever release"));
+                                }
+                                waited = on.waiting_on.borrow().clone();
+                            }
+
+                            // the claim is run again once the others have had their turn
+                            if let Some(current) = &self.conjuring {
+                                current.waiting_on.replace(Some(conjuring.clone()));
+                            }
+                            frame!().ip -= 3;
+                            self.switch_task();
+                            continue;
+                        }
+                    };
+                    if let Some(current) = &self.conjuring {
+                        current.waiting_on.replace(None);
+                    }
+                    match released {
+                        Ok(value) => set_register!(base, dest, value),
+                        Err(message) => {
+                            let name = conjuring.closure.spell.name.as_deref().unwrap_or("<origin>");
+                            return Err(self.runtime_error(&format!("The conjured spell '{}' broke down: {}", name, message)));
+                        }
+                    }
+                }
+                OpCode::Draw => {
+                    let (dest, stream_reg, more) = frame!().read_three_bytes();
+                    let stream = match get_register!(base, stream_reg) {
+                        Value::Stream(stream) => stream.clone(),
+                        value => {
+                            return Err(self.runtime_error(&format!("Only a stream can be drawn from! Got {:?}", value)));
+                        }
+                    };
+                    self.draw(stream, dest, more)?;
+                }
+                OpCode::Offer => {
+                    let src = frame!().read_byte();
+                    let value = get_register!(base, src).clone();
+                    let Some((stream, more)) = frame!().stream.clone() else {
+                        return Err(self.runtime_error("Only a spell drawn from as a stream can offer"));
+                    };
+                    let finished = self.frames.pop().unwrap();
+
+                    // the frame waits in the stream for the next draw. The closures made by it keep
+                    // what they captured so far, as the registers leave the stack
+                    self.close_upvalues(finished.reg_base);
+                    let registers = self.stack.split_off(finished.reg_base);
+                    *stream.state.borrow_mut() = StreamState::Suspended {
+                        ip: finished.ip,
+                        registers,
+                        attempts: finished.handlers.iter().map(|h| (h.ip, h.error_reg)).collect(),
+                    };
+
+                    self.stack[finished.caller_reg_base + finished.return_reg as usize] = value;
+                    self.stack[finished.caller_reg_base + more as usize] = Value::Bool(true);
+                }
                 OpCode::NewSign => {
                     let dest = frame!().read_byte();
                     let value = frame!().read_byte();
                     let schema = match get_register!(base, value).clone() {
                         Value::SignSchema(sc) => sc,
                         _ => {
-                            self.runtime_error(&format!(
+                            return Err(self.runtime_error(&format!(
                                 "The constant {:?} in the constant table is not a sign!",
                                 value
-                            ));
-                            return InterpretResult::RuntimeError;
+                            )));
                         }
                     };
 
@@ -441,9 +1678,13 @@ impl EiraVM {
 
                     let sign_idx = base + sign_reg as usize;
                     if let Value::Sign(s) = &self.stack[sign_idx] {
+                        let holds_objects = Collector::holds_objects(&val);
                         let _ = s.borrow_mut().set_field(field_name_idx as usize, val);
+                        if holds_objects {
+                            self.collector.note_sign(s);
+                        }
                     } else {
-                        return InterpretResult::RuntimeError;
+                        return Err(self.runtime_error("SET_FIELD Operation was used with a non 'Sign' value"));
                     }
                 }
                 OpCode::GetField => {
@@ -457,8 +1698,16 @@ impl EiraVM {
                             let val = s.borrow().get_field(field_name as usize);
                             set_register!(base, dest, val);
                         }
-                        _ => self
-                            .runtime_error("GET_FIELD Operation was used with a non 'Sign' value"),
+                        // the only thing an error carries is its message
+                        Value::Error(message) => {
+                            let message = Value::String(message.clone());
+                            set_register!(base, dest, message);
+                        }
+                        _ => {
+                            return Err(self.runtime_error(
+                                "GET_FIELD Operation was used with a non 'Sign' value",
+                            ));
+                        }
                     }
                 }
                 OpCode::SafeGetField => {
@@ -475,9 +1724,11 @@ impl EiraVM {
                             let val = s.borrow().get_field(field_name as usize);
                             set_register!(base, dest, val);
                         }
-                        _ => self.runtime_error(
-                            "SAFE_GET_FIELD Operation was used with a non 'Sign' value",
-                        ),
+                        _ => {
+                            return Err(self.runtime_error(
+                                "SAFE_GET_FIELD Operation was used with a non 'Sign' value",
+                            ));
+                        }
                     }
                 }
                 OpCode::NewDeck => {
@@ -521,7 +1772,7 @@ impl EiraVM {
                     let deck_reg = frame!().read_byte();
                     let position = frame!().read_byte();
                     let val = get_register!(base, frame!().read_byte()).clone();
-                    let idx = get_register!(base, position).extract_number().unwrap() as usize;
+                    let idx = number_register!(base, position) as usize;
 
                     let deck_val = get_register!(base, deck_reg).clone();
 
@@ -531,29 +1782,31 @@ impl EiraVM {
 
                             if let Some(cap) = d.capacity {
                                 if idx >= cap {
-                                    self.runtime_error(
+                                    return Err(self.runtime_error(
                                     &format!("Index out of bounds while adding element to a deck. Tried to add at {} while deck capacity is {}.", idx, cap),
-                                );
-                                    return InterpretResult::RuntimeError;
+                                ));
                                 }
                             }
 
                             if idx > len {
-                                self.runtime_error(
+                                return Err(self.runtime_error(
                                     &format!("Index out of bounds while adding element to a deck. Tried to add at {} while deck size is {}.", idx, len),
-                                );
-                                return InterpretResult::RuntimeError;
-                            } else if idx == len {
+                                ));
+                            }
+
+                            if Collector::holds_objects(&val) {
+                                self.collector.note_deck(&d);
+                            }
+                            if idx == len {
                                 d.items.borrow_mut().push(val);
                             } else {
                                 d.items.borrow_mut()[idx] = val;
                             }
                         }
                         _ => {
-                            self.runtime_error(
+                            return Err(self.runtime_error(
                                 "Value is not a Deck to perform 'ADD_TO_DECK Operation'",
-                            );
-                            return InterpretResult::RuntimeError;
+                            ));
                         }
                     }
                 }
@@ -565,24 +1818,33 @@ impl EiraVM {
 
                     let deck_val = get_register!(base, deck_reg).clone();
 
-                    let idx = get_register!(base, index).clone().extract_number().unwrap() as usize;
+                    let index = number_register!(base, index);
+                    // a negative or a fractional index is out of bounds, rather than cut down to a whole one
+                    let idx = if index < 0.0 || index.fract() != 0.0 { usize::MAX } else { index as usize };
                     match deck_val {
                         Value::Deck(d) => {
                             if idx < d.items.borrow().len() {
                                 let val = d.items.borrow()[idx].clone();
                                 set_register!(base, dest, val);
                             } else {
-                                self.runtime_error(
-                                    &format!("Index out of bounds while extracting element from a deck. Tried to access {} while deck size is {}.", idx,d.items.borrow().len()),
-                                );
-                                return InterpretResult::RuntimeError;
+                                return Err(self.runtime_error(
+                                    &format!("Index out of bounds while extracting element from a deck. Tried to access {} while deck size is {}.", index, d.items.borrow().len()),
+                                ));
                             }
                         }
+                        // a text is indexed by its letters, not its bytes
+                        Value::String(s) => match s.chars().nth(idx) {
+                            Some(c) => set_register!(base, dest, Value::String(Rc::new(c.to_string()))),
+                            None => {
+                                return Err(self.runtime_error(
+                                    &format!("Index out of bounds while extracting a letter from a text. Tried to access {} while the text has {} letters.", index, s.chars().count()),
+                                ));
+                            }
+                        },
                         _ => {
-                            self.runtime_error(
+                            return Err(self.runtime_error(
                                 "Value is not a Deck to perform 'EXTRACT_FROM_DECK' Operation'",
-                            );
-                            return InterpretResult::RuntimeError;
+                            ));
                         }
                     }
                 }
@@ -597,8 +1859,7 @@ impl EiraVM {
                     let r1 = frame!().read_byte();
                     let val = get_register!(base, r1).clone();
                     if val.is_emptiness() {
-                        self.runtime_error("Safe Assertion failed: value is empty.");
-                        return InterpretResult::RuntimeError;
+                        return Err(self.runtime_error("Safe Assertion failed: value is empty."));
                     }
                 }
 
@@ -611,25 +1872,95 @@ impl EiraVM {
                     let res = match spell {
                         Value::NativeSpell(ns) => {
                             let start_idx = base + arg_start as usize;
-                            dispatch(self, ns, start_idx, argc as usize)
+                            dispatch(self, &ns, start_idx, argc as usize)
                         }
                         _ => {
-                            self.runtime_error("Expected a NativeSpell value to be casted!");
-                            return InterpretResult::RuntimeError;
+                            return Err(self.runtime_error("Expected a NativeSpell value to be casted!"));
                         }
                     };
 
                     match res {
                         Ok(v) => set_register!(base, dest, v),
                         Err(e) => {
-                            self.runtime_error(&format!("Error running a native spell.\n{}", e));
-                            return InterpretResult::RuntimeError;
+                            return Err(self.runtime_error(&format!("Error running a native spell.\n{}", e)));
+                        }
+                    }
+                }
+                OpCode::CurrentSpell => {
+                    let dest = frame!().read_byte();
+                    let closure = frame!().closure.clone();
+                    set_register!(base, dest, Value::Closure(closure));
+                }
+                OpCode::GetUpvalue => {
+                    let dest = frame!().read_byte();
+                    let index = frame!().read_byte();
+                    let Some(cell) = self.frames.last().unwrap().closure.upvalues.get(index as usize) else {
+                        return Err(self.runtime_error(&format!("Fatal: The upvalue {} doesn't exist.", index)));
+                    };
+                    let value = match &*cell.borrow() {
+                        Captured::Open(idx) => self.stack[*idx].clone(),
+                        Captured::Closed(value) => value.clone(),
+                    };
+                    set_register!(base, dest, value);
+                }
+                OpCode::SetUpvalue => {
+                    let index = frame!().read_byte();
+                    let src = frame!().read_byte();
+                    let value = get_register!(base, src).clone();
+                    let Some(cell) = self.frames.last().unwrap().closure.upvalues.get(index as usize) else {
+                        return Err(self.runtime_error(&format!("Fatal: The upvalue {} doesn't exist.", index)));
+                    };
+                    let cell = cell.clone();
+                    let holds_objects = Collector::holds_objects(&value);
+                    let closed = match &mut *cell.borrow_mut() {
+                        Captured::Open(idx) => {
+                            self.stack[*idx] = value;
+                            false
+                        }
+                        Captured::Closed(closed) => {
+                            *closed = value;
+                            true
+                        }
+                    };
+                    if closed && holds_objects {
+                        self.collector.note_cell(&cell);
+                    }
+                }
+                OpCode::CloseUpvalues => {
+                    let from = frame!().read_byte();
+                    self.close_upvalues(base + from as usize);
+                }
+                OpCode::GetAttunement => {
+                    let dest = frame!().read_byte();
+                    let sign_reg = frame!().read_byte();
+                    let spell = frame!().read_u16();
+
+                    let schema = match get_register!(base, sign_reg) {
+                        Value::Sign(s) => s.borrow().schema.clone(),
+                        _ => {
+                            return Err(self.runtime_error("GET_ATTUNEMENT Operation was used with a non 'Tome' value"));
+                        }
+                    };
+
+                    // attunements live in globals, the tome's schema knows their slots
+                    let attuned = schema
+                        .attuned_slot(spell)
+                        .and_then(|slot| self.globals.get(slot as usize))
+                        .and_then(Option::as_ref);
+                    match attuned {
+                        Some(attuned) => set_register!(base, dest, attuned.clone()),
+                        None => {
+                            let spell_name = self.symbols.get(spell as usize).map_or("?", String::as_str);
+                            return Err(self.runtime_error(&format!(
+                                "The tome '{}' is not attuned to a spell '{}'",
+                                schema.name, spell_name
+                            )));
                         }
                     }
                 }
             }
-        }
+        };
         // println!("Program completed after {} instructions.", instruction_count);
-        InterpretResult::InterpretOk
+        Ok(result)
     }
 }
@@ -0,0 +1,131 @@
+use std::{
+    cell::RefCell,
+    fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+
+use crate::{
+    EiraVM,
+    compiler::compiler::{Compiler, CompilerOptions},
+    project::config::Project,
+};
+
+/// The scrolls ending with this hold the tests
+pub const TEST_SUFFIX: &str = ".test.eira";
+
+/// The instructions a test can run before it's broken down, so a test looping forever fails
+/// instead of hanging the whole run
+pub const TEST_FUEL: u64 = 100_000_000;
+
+/// How long a test can run before it's broken down
+pub const TEST_TIME_LIMIT: Duration = Duration::from_secs(10);
+
+/// How a test scroll fared. It passes when it runs to its end, an `expect` which isn't met
+/// (or any other doom) fails it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub scroll: PathBuf,
+    pub failure: Option<String>, // why it failed, the compile errors or the doom which broke it
+    pub output: String,          // what it chanted
+    pub duration: Duration,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// The test scrolls in [dir] and all the folders within, in the order of their paths.
+/// A test scroll given in place of the folder is the only one found. The links within aren't
+/// followed, so a link back to a folder above can't make the search go round forever
+pub fn find_tests(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    if dir.is_file() {
+        return Ok(vec![dir.to_path_buf()]);
+    }
+
+    let mut tests = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            tests.extend(find_tests(&path)?);
+        } else if path.to_string_lossy().ends_with(TEST_SUFFIX) {
+            tests.push(path);
+        }
+    }
+    tests.sort();
+    Ok(tests)
+}
+
+/// Compiles and runs the test scroll on a VM of its own, so no globals are left over from the
+/// tests before it. Its chants are collected rather than printed, and it heeds no input so a
+/// test can't wait on the terminal. It runs within [TEST_FUEL] and [TEST_TIME_LIMIT]
+pub fn run_test(scroll: &Path, project: Option<Project>) -> TestResult {
+    let start = Instant::now();
+    let output = Captured::default();
+
+    let options = CompilerOptions::default();
+    let compiler = Compiler::new(scroll.to_string_lossy().into_owned(), options, project);
+    let failure = match compiler.compile_to_bytecode() {
+        Ok(compiled) => {
+            let mut vm = EiraVM::init(compiled);
+            vm.set_output(output.clone());
+            vm.set_input(io::Cursor::new(Vec::new()));
+            vm.set_fuel(Some(TEST_FUEL));
+            vm.set_time_limit(Some(TEST_TIME_LIMIT));
+            vm.start().err().map(|err| err.to_string())
+        }
+        Err(err) => Some(format!("The scroll was cursed during the compilation.\n{}", err)),
+    };
+
+    TestResult {
+        scroll: scroll.to_path_buf(),
+        failure,
+        output: String::from_utf8_lossy(&output.0.take()).into_owned(),
+        duration: start.elapsed(),
+    }
+}
+
+/// The results as JSON for the CI to read, the counts along with every test
+pub fn results_to_json(results: &[TestResult]) -> String {
+    let passed = results.iter().filter(|r| r.passed()).count();
+    let tests: Vec<_> = results
+        .iter()
+        .map(|r| {
+            json!({
+                "scroll": r.scroll.display().to_string(),
+                "passed": r.passed(),
+                "failure": r.failure,
+                "output": r.output,
+                "duration_ms": r.duration.as_secs_f64() * 1000.0,
+            })
+        })
+        .collect();
+
+    json!({
+        "passed": passed,
+        "failed": results.len() - passed,
+        "tests": tests,
+    })
+    .to_string()
+}
+
+/// Collects the chants of a test
+#[derive(Clone, Default)]
+struct Captured(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
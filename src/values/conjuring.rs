@@ -0,0 +1,31 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::values::{spell::ClosureObject, value::Value};
+
+/// A spell conjured to run alongside the scroll, claimed once it has released
+#[derive(Debug)]
+pub struct ConjuringObject {
+    pub closure: Rc<ClosureObject>,
+    pub state: RefCell<ConjuringState>,
+    pub waiting_on: RefCell<Option<Rc<ConjuringObject>>>, // the conjuring it is claiming, while it waits
+}
+
+#[derive(Debug)]
+pub enum ConjuringState {
+    /// Still running, or waiting for its turn
+    Running,
+    /// What the spell released
+    Released(Value),
+    /// The message of the breakdown no attempt of the spell mended
+    Broken(String),
+}
+
+impl ConjuringObject {
+    pub fn new(closure: Rc<ClosureObject>) -> ConjuringObject {
+        ConjuringObject {
+            closure,
+            state: RefCell::new(ConjuringState::Running),
+            waiting_on: RefCell::new(None),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+/// Represents a variant of a Glyph (or enum in general terms) in Eira.
+/// Glyphs carry no data, so a variant is known completely at compile time.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GlyphObject {
+    pub glyph: String,
+    pub variant: String,
+    pub index: usize,
+}
+
+/// Represents the compile time information of a Glyph
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphInfo {
+    pub name: String,
+    pub variants: Vec<String>,
+}
+
+impl GlyphInfo {
+    pub fn get_variant(&self, name: &str) -> Option<GlyphObject> {
+        let index = self.variants.iter().position(|v| v == name)?;
+        Some(GlyphObject {
+            glyph: self.name.clone(),
+            variant: name.to_owned(),
+            index,
+        })
+    }
+}
@@ -0,0 +1,36 @@
+use std::{collections::HashMap, rc::Rc};
+
+/// The id of an interned text, as written into the bytecode
+pub type SymbolId = u32;
+
+/// Keeps a single shared copy of every distinct text and numbers them, so that the names
+/// can be compared by their ids and the equal texts by their pointers
+#[derive(Debug, Default)]
+pub struct Interner {
+    ids: HashMap<String, SymbolId>,
+    texts: Vec<Rc<String>>,
+}
+
+impl Interner {
+    /// The id of [text], interning it if it wasn't already
+    pub fn intern(&mut self, text: &str) -> SymbolId {
+        if let Some(id) = self.ids.get(text) {
+            return *id;
+        }
+
+        let id = self.texts.len() as SymbolId;
+        self.ids.insert(text.to_string(), id);
+        self.texts.push(Rc::new(text.to_string()));
+        id
+    }
+
+    /// The shared copy of the text interned as [id]
+    pub fn text(&self, id: SymbolId) -> &Rc<String> {
+        &self.texts[id as usize]
+    }
+
+    /// The interned texts, indexed by their ids
+    pub fn texts(&self) -> Vec<String> {
+        self.texts.iter().map(|text| text.to_string()).collect()
+    }
+}
@@ -1,6 +1,10 @@
+pub mod conjuring;
 pub mod deck;
+pub mod glyph;
+pub mod interner;
 pub mod sign;
 pub mod spell;
+pub mod stream;
 pub mod value;
 pub mod native_spell;
 
@@ -1,52 +1,145 @@
+use std::rc::Rc;
+
 use crate::{
     EiraVM, Value,
     compiler::{reagents::WovenReagent, weaves::Weave},
-    values::{native_spells::{io::read_line, math::{self}}, spell::SpellInfo},
+    values::{native_spells::{convert, deck, io::read_line, math::{self}, time}, spell::SpellInfo},
 };
 
+/// Signature of the spells provided by the host program through `EiraVM::register_native`
+pub type HostSpellFn = fn(&[Value]) -> Value;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum NativeSpell {
     Time(TimeSpells),
     Math(MathSpells),
     Io(IoSpells),
+    Deck(DeckSpells),
+    Convert(ConvertSpells),
+    Random(RandomSpells),
+    Host(SpellInfo), // spells registered by the host program, resolved by name at runtime
+}
+
+/// Builds the signature of a native spell
+fn signature(name: &str, reagents: Vec<Weave>, release_weave: Weave) -> SpellInfo {
+    SpellInfo {
+        name: name.to_string(),
+        reagents: reagents
+            .into_iter()
+            .map(WovenReagent::new)
+            .collect(),
+        release_weave,
+        upvalues: vec![],
+    }
 }
 
 impl NativeSpell {
     pub fn resolve(name: &str) -> Result<NativeSpell, String> {
+        // an Empty weave in the reagents accepts a value of any weave
         match name {
-            "listen" => Ok(NativeSpell::Io(IoSpells::Listen(SpellInfo {
-                name: "listen".to_string(),
-                reagents: vec![],
-                release_weave: Weave::Text,
-                upvalues: vec![],
-            }))),
-            "ask" => Ok(NativeSpell::Io(IoSpells::Ask(SpellInfo {
-                name: "ask".to_string(),
-                reagents: vec![WovenReagent { weave: Weave::Text }],
-                release_weave: Weave::Text,
-                upvalues: vec![],
-            }))),
-            "floor" => Ok(NativeSpell::Math(MathSpells::Floor(SpellInfo {
-                name: "floor".to_string(),
-                reagents: vec![WovenReagent { weave: Weave::Num }],
-                release_weave: Weave::Num,
-                upvalues: vec![],
-            }))),
-            "ceil" => Ok(NativeSpell::Math(MathSpells::Ceil(SpellInfo {
-                name: "ceil".to_string(),
-                reagents: vec![WovenReagent { weave: Weave::Num }],
-                release_weave: Weave::Num,
-                upvalues: vec![],
-            }))),
+            "listen" => Ok(NativeSpell::Io(IoSpells::Listen(signature(name, vec![], Weave::Text)))),
+            "read_line" => Ok(NativeSpell::Io(IoSpells::Listen(signature(name, vec![], Weave::Text)))),
+            "ask" => Ok(NativeSpell::Io(IoSpells::Ask(signature(
+                name,
+                vec![Weave::Text],
+                Weave::Text,
+            )))),
+            "clock" => Ok(NativeSpell::Time(TimeSpells::Clock(signature(name, vec![], Weave::Num)))),
+            "now" => Ok(NativeSpell::Time(TimeSpells::Now(signature(name, vec![], Weave::Num)))),
+            "fate_roll" => Ok(NativeSpell::Random(RandomSpells::Roll(signature(
+                name,
+                vec![Weave::Num],
+                Weave::Num,
+            )))),
+            "floor" => Ok(NativeSpell::Math(MathSpells::Floor(signature(
+                name,
+                vec![Weave::Num],
+                Weave::Num,
+            )))),
+            "ceil" => Ok(NativeSpell::Math(MathSpells::Ceil(signature(
+                name,
+                vec![Weave::Num],
+                Weave::Num,
+            )))),
+            "sqrt" => Ok(NativeSpell::Math(MathSpells::Sqrt(signature(
+                name,
+                vec![Weave::Num],
+                Weave::Num,
+            )))),
+            "abs" => Ok(NativeSpell::Math(MathSpells::Abs(signature(
+                name,
+                vec![Weave::Num],
+                Weave::Num,
+            )))),
+            "len" => Ok(NativeSpell::Deck(DeckSpells::Len(signature(
+                name,
+                vec![Weave::Deck(Box::new(Weave::Empty), None)],
+                Weave::Num,
+            )))),
+            "to_text" => Ok(NativeSpell::Convert(ConvertSpells::ToText(signature(
+                name,
+                vec![Weave::Empty],
+                Weave::Text,
+            )))),
+            "to_num" => Ok(NativeSpell::Convert(ConvertSpells::ToNum(signature(
+                name,
+                vec![Weave::Text],
+                Weave::Maybe(Box::new(Weave::Num)),
+            )))),
+            "as_int" => Ok(NativeSpell::Convert(ConvertSpells::AsInt(signature(
+                name,
+                vec![Weave::Num],
+                Weave::Int,
+            )))),
+            "as_num" => Ok(NativeSpell::Convert(ConvertSpells::AsNum(signature(
+                name,
+                vec![Weave::Int],
+                Weave::Num,
+            )))),
+            "format" => Ok(NativeSpell::Convert(ConvertSpells::Format(signature(
+                name,
+                vec![Weave::Num, Weave::Num],
+                Weave::Text,
+            )))),
             _ => Err(format!("Could'nt find a native spell for '{}'", name).to_string()),
         }
     }
 
+    /// The count of the reagents the spell takes. None for a spell of the host, whose count is
+    /// only known once it's registered to the VM, which checks it as the spell is cast
+    pub fn arity(&self) -> Option<usize> {
+        let info = match self {
+            NativeSpell::Time(TimeSpells::Clock(si) | TimeSpells::Now(si)) => si,
+            NativeSpell::Math(MathSpells::Floor(si) | MathSpells::Ceil(si) | MathSpells::Sqrt(si) | MathSpells::Abs(si)) => si,
+            NativeSpell::Io(IoSpells::Listen(si) | IoSpells::Ask(si)) => si,
+            NativeSpell::Deck(DeckSpells::Len(si)) => si,
+            NativeSpell::Convert(
+                ConvertSpells::ToText(si)
+                | ConvertSpells::ToNum(si)
+                | ConvertSpells::AsInt(si)
+                | ConvertSpells::AsNum(si)
+                | ConvertSpells::Format(si),
+            ) => si,
+            NativeSpell::Random(RandomSpells::Roll(si)) => si,
+            NativeSpell::Host(_) => return None,
+        };
+        Some(info.reagents.len())
+    }
+
+    /// A spell registered by the host, only its name is needed to cast it
+    pub fn host(name: &str) -> NativeSpell {
+        NativeSpell::Host(signature(name, vec![], Weave::Empty))
+    }
+
     pub fn get_spell_info(spell: NativeSpell) -> Result<SpellInfo, String> {
         match spell {
             NativeSpell::Io(ios) => IoSpells::get_spell_info(ios),
             NativeSpell::Math(math) => MathSpells::get_spell_info(math),
             NativeSpell::Time(time) => TimeSpells::get_spell_info(time),
+            NativeSpell::Deck(deck) => DeckSpells::get_spell_info(deck),
+            NativeSpell::Convert(convert) => ConvertSpells::get_spell_info(convert),
+            NativeSpell::Random(random) => RandomSpells::get_spell_info(random),
+            NativeSpell::Host(si) => Ok(si),
         }
     }
 }
@@ -67,11 +160,30 @@ impl IoSpells {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum TimeSpells {}
+pub enum TimeSpells {
+    Clock(SpellInfo),
+    Now(SpellInfo),
+}
 
 impl TimeSpells {
-    pub fn get_spell_info(_spell: TimeSpells) -> Result<SpellInfo, String> {
-        todo!("yet to be implemented")
+    pub fn get_spell_info(spell: TimeSpells) -> Result<SpellInfo, String> {
+        match spell {
+            TimeSpells::Clock(si) => Ok(si),
+            TimeSpells::Now(si) => Ok(si),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RandomSpells {
+    Roll(SpellInfo),
+}
+
+impl RandomSpells {
+    pub fn get_spell_info(spell: RandomSpells) -> Result<SpellInfo, String> {
+        match spell {
+            RandomSpells::Roll(si) => Ok(si),
+        }
     }
 }
 
@@ -79,6 +191,8 @@ impl TimeSpells {
 pub enum MathSpells {
     Floor(SpellInfo),
     Ceil(SpellInfo),
+    Sqrt(SpellInfo),
+    Abs(SpellInfo),
 }
 
 impl MathSpells {
@@ -86,6 +200,42 @@ impl MathSpells {
         match spell {
             MathSpells::Floor(si) => Ok(si),
             MathSpells::Ceil(si) => Ok(si),
+            MathSpells::Sqrt(si) => Ok(si),
+            MathSpells::Abs(si) => Ok(si),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeckSpells {
+    Len(SpellInfo),
+}
+
+impl DeckSpells {
+    pub fn get_spell_info(spell: DeckSpells) -> Result<SpellInfo, String> {
+        match spell {
+            DeckSpells::Len(si) => Ok(si),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConvertSpells {
+    ToText(SpellInfo),
+    ToNum(SpellInfo),
+    AsInt(SpellInfo),
+    AsNum(SpellInfo),
+    Format(SpellInfo),
+}
+
+impl ConvertSpells {
+    pub fn get_spell_info(spell: ConvertSpells) -> Result<SpellInfo, String> {
+        match spell {
+            ConvertSpells::ToText(si) => Ok(si),
+            ConvertSpells::ToNum(si) => Ok(si),
+            ConvertSpells::AsInt(si) => Ok(si),
+            ConvertSpells::AsNum(si) => Ok(si),
+            ConvertSpells::Format(si) => Ok(si),
         }
     }
 }
@@ -93,31 +243,104 @@ impl MathSpells {
 // dispatcher fucntion for native spells
 pub fn dispatch(
     _vm: &mut EiraVM,
-    spell: NativeSpell,
+    spell: &NativeSpell,
     arg_start_idx: usize,
     _argc: usize,
 ) -> Result<Value, String> {
     match spell {
-        NativeSpell::Time(_spells) => todo!("yet to be implemented"),
+        NativeSpell::Time(spells) => match spells {
+            TimeSpells::Clock(_) => Ok(Value::Number(time::clock(_vm.started_at()))),
+            TimeSpells::Now(_) => Ok(Value::Number(time::now())),
+        },
+        NativeSpell::Random(spells) => match spells {
+            RandomSpells::Roll(_) => {
+                let max = num_reagent(_vm, arg_start_idx)?.floor();
+                if max < 1.0 {
+                    return Err(format!("fate_roll needs at least 1 face to roll, got {}", max));
+                }
+                Ok(Value::Number(_vm.fate().roll(max as u64) as f64))
+            }
+        },
+        NativeSpell::Host(info) => {
+            let args = _vm
+                .stack
+                .get(arg_start_idx..arg_start_idx + _argc)
+                .map(|a| a.to_vec())
+                .unwrap_or_default();
+            _vm.cast_host_spell(&info.name, &args)
+        }
         NativeSpell::Io(spells) => match spells {
-            IoSpells::Listen(_) => read_line(None),
+            IoSpells::Listen(_) => read_line(_vm, None),
             IoSpells::Ask(_) => {
-                let prompt_val = _vm.stack[arg_start_idx].clone();
-                let prompt_str = prompt_val.extract_string().unwrap();
-                read_line(Some(&prompt_str))
+                let prompt = text_reagent(_vm, arg_start_idx)?;
+                read_line(_vm, Some(&prompt))
             }
         },
+        NativeSpell::Deck(spells) => match spells {
+            DeckSpells::Len(_) => match reagent(_vm, arg_start_idx)? {
+                Value::Deck(d) => Ok(Value::Number(deck::len(d) as f64)),
+                Value::String(s) => Ok(Value::Number(s.chars().count() as f64)),
+                other => Err(format!("Expected a deck or a text to measure, got {:?}", other)),
+            },
+        },
         NativeSpell::Math(spells) => match spells {
             MathSpells::Floor(_) => {
-                let arg_val = _vm.stack[arg_start_idx].clone();
-                let arg_num = arg_val.extract_number().unwrap();
+                let arg_num = num_reagent(_vm, arg_start_idx)?;
                 Ok(Value::Number(math::floor(arg_num)))
             }
             MathSpells::Ceil(_) => {
-                let arg_val = _vm.stack[arg_start_idx].clone();
-                let arg_num = arg_val.extract_number().unwrap();
+                let arg_num = num_reagent(_vm, arg_start_idx)?;
                 Ok(Value::Number(math::ceil(arg_num)))
             }
+            MathSpells::Sqrt(_) => {
+                let arg_num = num_reagent(_vm, arg_start_idx)?;
+                Ok(Value::Number(math::sqrt(arg_num)))
+            }
+            MathSpells::Abs(_) => {
+                let arg_num = num_reagent(_vm, arg_start_idx)?;
+                Ok(Value::Number(math::abs(arg_num)))
+            }
+        },
+        NativeSpell::Convert(spells) => match spells {
+            ConvertSpells::ToText(_) => {
+                let text = convert::to_text(reagent(_vm, arg_start_idx)?);
+                Ok(Value::String(Rc::new(text)))
+            }
+            ConvertSpells::ToNum(_) => {
+                let arg_str = text_reagent(_vm, arg_start_idx)?;
+                Ok(convert::to_num(&arg_str).map_or(Value::Emptiness, Value::Number))
+            }
+            ConvertSpells::AsInt(_) => {
+                let arg_num = num_reagent(_vm, arg_start_idx)?;
+                convert::as_int(arg_num)
+                    .map(Value::Int)
+                    .ok_or_else(|| format!("The Num {} can't be held by an Int", arg_num))
+            }
+            ConvertSpells::AsNum(_) => {
+                let arg_num = num_reagent(_vm, arg_start_idx)?;
+                Ok(Value::Number(arg_num))
+            }
+            ConvertSpells::Format(_) => {
+                let arg_num = num_reagent(_vm, arg_start_idx)?;
+                let decimals = num_reagent(_vm, arg_start_idx + 1)?;
+                convert::format(arg_num, decimals).map(|text| Value::String(Rc::new(text)))
+            }
         },
     }
 }
+
+/// The reagent at [idx] on the stack. The verifier holds the count of the reagents to the one
+/// the spell takes, but not their weaves, so a malformed scroll breaks down here with an error
+fn reagent(vm: &EiraVM, idx: usize) -> Result<&Value, String> {
+    vm.stack.get(idx).ok_or_else(|| format!("The reagent at {} is missing", idx))
+}
+
+fn num_reagent(vm: &EiraVM, idx: usize) -> Result<f64, String> {
+    let value = reagent(vm, idx)?;
+    value.extract_number().ok_or_else(|| format!("Expected a Num reagent, got {:?}", value))
+}
+
+fn text_reagent(vm: &EiraVM, idx: usize) -> Result<String, String> {
+    let value = reagent(vm, idx)?;
+    value.extract_string().ok_or_else(|| format!("Expected a Text reagent, got {:?}", value))
+}
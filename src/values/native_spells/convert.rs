@@ -0,0 +1,36 @@
+use crate::{
+    Value,
+    values::value::{stringify_number, stringify_value},
+};
+
+pub fn to_text(value: &Value) -> String {
+    stringify_value(value)
+}
+
+/// Reads a number out of the text, None if the text isn't a number
+pub fn to_num(text: &str) -> Option<f64> {
+    text.trim().parse::<f64>().ok()
+}
+
+/// The whole part of the number, None when an int can't hold it (or it isn't a number at all)
+pub fn as_int(number: f64) -> Option<i64> {
+    let whole = number.trunc();
+    // i64::MAX rounds up to 2^63 as a float, which is already past it
+    (whole >= i64::MIN as f64 && whole < i64::MAX as f64).then_some(whole as i64)
+}
+
+/// The number with exactly [decimals] digits after its point, rounded to them
+pub fn format(number: f64, decimals: f64) -> Result<String, String> {
+    if decimals.fract() != 0.0 || !(0.0..=100.0).contains(&decimals) {
+        return Err(format!("A Num is formatted with 0 up to 100 decimals, got {}", stringify_number(decimals)));
+    }
+    if !number.is_finite() {
+        return Ok(stringify_number(number));
+    }
+    let text = format!("{:.*}", decimals as usize, number);
+    // a negative number rounded down to zero loses its sign
+    match text.strip_prefix('-') {
+        Some(digits) if digits.chars().all(|c| c == '0' || c == '.') => Ok(digits.to_string()),
+        _ => Ok(text),
+    }
+}
@@ -0,0 +1,6 @@
+use crate::values::deck::DeckObject;
+
+#[inline(always)]
+pub fn len(deck: &DeckObject) -> usize {
+    deck.items.borrow().len()
+}
@@ -6,4 +6,14 @@ pub fn floor(value: f64) -> f64 {
 #[inline(always)]
 pub fn ceil(value: f64) -> f64 {
     value.ceil()
+}
+
+#[inline(always)]
+pub fn sqrt(value: f64) -> f64 {
+    value.sqrt()
+}
+
+#[inline(always)]
+pub fn abs(value: f64) -> f64 {
+    value.abs()
 }
\ No newline at end of file
@@ -1,3 +1,6 @@
+pub mod convert;
+pub mod deck;
 pub mod io;
 pub mod math;
+pub mod random;
 pub mod time;
\ No newline at end of file
@@ -0,0 +1,40 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The random numbers of the VM, a small xorshift generator. Good enough for games, not for secrets.
+#[derive(Debug, Clone)]
+pub struct Fate {
+    state: u64,
+}
+
+impl Fate {
+    /// The same seed always rolls the same numbers
+    pub fn seeded(seed: u64) -> Self {
+        // splitmix spreads the seed over the bits, so even 0 gives a usable (non zero) state
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Fate { state: z ^ (z >> 31) }
+    }
+
+    /// Seeded by the current time
+    pub fn unseeded() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Self::seeded(nanos)
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A whole number from 1 to [max], like the roll of a die with [max] faces
+    pub fn roll(&mut self, max: u64) -> u64 {
+        1 + self.next() % max
+    }
+}
@@ -0,0 +1,13 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// The seconds passed since [start]
+pub fn clock(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64()
+}
+
+/// The milliseconds passed since the unix epoch
+pub fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_millis() as f64)
+}
@@ -42,6 +42,7 @@ pub struct SignSchema {
     pub field_names: Vec<String>,
     field_indices: HashMap<String, usize>,
     // pub field_weaves: Vec<Weave>,
+    attunements: Vec<(u16, u16)>, // (symbol id of the spell's name, global slot of the spell), by symbol id
 }
 
 impl SignSchema {
@@ -51,6 +52,7 @@ impl SignSchema {
             field_indices: HashMap::new(),
             field_names: vec![],
             // field_weaves: vec![],
+            attunements: vec![],
         }
     }
 
@@ -69,6 +71,23 @@ impl SignSchema {
     pub fn field_count(&self) -> usize {
         self.field_names.len()
     }
+
+    pub fn set_attunements(&mut self, mut attunements: Vec<(u16, u16)>) {
+        attunements.sort();
+        self.attunements = attunements;
+    }
+
+    pub fn attunements(&self) -> &[(u16, u16)] {
+        &self.attunements
+    }
+
+    /// The global slot of the spell attuned under the symbol id [spell]
+    pub fn attuned_slot(&self, spell: u16) -> Option<u16> {
+        self.attunements
+            .binary_search_by_key(&spell, |(symbol, _)| *symbol)
+            .ok()
+            .map(|i| self.attunements[i].1)
+    }
 }
 
 impl Hash for SignSchema {
@@ -85,5 +104,8 @@ impl Hash for SignSchema {
 pub struct SignInfo {
     pub schema: SignSchema,
     pub marks: HashMap<String, Weave>,
+    pub defaults: HashMap<String, Value>, // the values of the marks left out when drawing the sign
     pub attunements: HashMap<String, String>, // maps to method name -> global name
+    pub tome: bool,                           // tomes dispatch their attunements at runtime
+    pub origin: Option<String>,               // the tome this one refers (inherits from)
 }
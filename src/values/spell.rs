@@ -9,9 +9,51 @@ use crate::{
 pub struct SpellObject {
     pub name: Option<String>,
     pub arity: u8, // 255 should be enough. (please seek help if its not for you)
-    pub upvalue_count: i32,
+    pub captures: Vec<Capture>, // where the closures of the spell take their upvalues from
     pub constants: Vec<Value>,
-    pub bytecode: Vec<u8>, // asynchronous: bool,
+    pub bytecode: Vec<u8>,
+    pub flowing: bool, // casting it makes a stream, run as its values are drawn
+    pub lines: Vec<(usize, usize)>, // (bytecode offset, source line) where the line starts
+    pub register_count: usize,      // the size of the frame, in registers
+    pub debug: Option<DebugInfo>,   // left out when the scroll is compiled without debug info
+}
+
+impl SpellObject {
+    /// The source line of the instruction at [offset]
+    pub fn line_at(&self, offset: usize) -> usize {
+        match self.lines.binary_search_by(|(o, _)| o.cmp(&offset)) {
+            Ok(i) => self.lines[i].1,
+            Err(0) => 0,
+            Err(i) => self.lines[i - 1].1,
+        }
+    }
+
+    /// The (line, column) of the instruction at [offset], the column is 0 without debug info
+    pub fn position_at(&self, offset: usize) -> (usize, usize) {
+        let Some(debug) = &self.debug else {
+            return (self.line_at(offset), 0);
+        };
+        match debug.positions.binary_search_by(|(o, _, _)| o.cmp(&offset)) {
+            Ok(i) => (debug.positions[i].1, debug.positions[i].2),
+            Err(0) => (0, 0),
+            Err(i) => (debug.positions[i - 1].1, debug.positions[i - 1].2),
+        }
+    }
+}
+
+/// What the compiler knows about the source of a spell, for the error messages and the debugger
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DebugInfo {
+    pub positions: Vec<(usize, usize, usize)>, // (bytecode offset, line, column) where the position changes
+    pub locals: Vec<LocalSymbol>,
+}
+
+/// A local of a spell as the debugger sees it, the register holding it and the lines it is in scope on
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalSymbol {
+    pub name: String,
+    pub register: u8,
+    pub lines: (usize, usize), // (first, last) source line of its scope
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -41,12 +83,29 @@ pub struct SpellInfo {
 #[derive(Debug, Clone)]
 pub struct ClosureObject {
     pub spell: Rc<SpellObject>,
-    pub upvalues: Vec<UpValue>,
+    pub upvalues: Vec<Rc<RefCell<Captured>>>, // shared with the frame and the other closures capturing them
 }
 
+/// A variable of an enclosing scope used by a spell, as resolved by the analyzer
 #[derive(Debug, Clone, PartialEq)]
 pub struct UpValue {
-    pub index: usize, // will contain the absolute slot index!
+    pub index: usize, // the slot of the variable
     pub depth: usize,
-    pub closed: RefCell<Value>,
+}
+
+/// Where a closure takes one of its upvalues from when it is created
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capture {
+    /// A register of the frame creating the closure
+    Local(u8),
+    /// One of the upvalues of the spell creating the closure
+    Enclosing(u8),
+}
+
+/// A captured variable. It stays open, living in its register on the stack, while its
+/// scope runs and is closed over (moved in here) once the scope ends
+#[derive(Debug, Clone, PartialEq)]
+pub enum Captured {
+    Open(usize), // the index of the register on the stack
+    Closed(Value),
 }
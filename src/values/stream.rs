@@ -0,0 +1,35 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::values::{spell::ClosureObject, value::Value};
+
+/// A flowing spell that has been cast, run only as far as its values are drawn
+#[derive(Debug)]
+pub struct StreamObject {
+    pub closure: Rc<ClosureObject>,
+    pub state: RefCell<StreamState>,
+}
+
+#[derive(Debug)]
+pub enum StreamState {
+    /// Cast with its reagents, not drawn from yet
+    Fresh(Vec<Value>),
+    /// Waiting at an offer, with the registers of its frame and the attempts it is inside of
+    Suspended {
+        ip: usize,
+        registers: Vec<Value>,
+        attempts: Vec<(usize, u8)>, // (start of the mend, error register)
+    },
+    /// Being drawn from right now
+    Flowing,
+    /// Released or broken down, there is nothing more to draw
+    Dry,
+}
+
+impl StreamObject {
+    pub fn new(closure: Rc<ClosureObject>, reagents: Vec<Value>) -> StreamObject {
+        StreamObject {
+            closure,
+            state: RefCell::new(StreamState::Fresh(reagents)),
+        }
+    }
+}
@@ -1,13 +1,18 @@
 use std::{cell::RefCell, hash::{Hash, Hasher}, rc::Rc};
 
-use crate::values::{deck::DeckObject, native_spell::NativeSpell};
+use serde::{Serialize, Serializer};
+
+use crate::values::{deck::DeckObject, glyph::GlyphObject, native_spell::NativeSpell};
 use crate::values::sign::{SignObject, SignSchema};
 use crate::values::spell::{ClosureObject, SpellObject};
+use crate::values::stream::StreamObject;
+use crate::values::conjuring::ConjuringObject;
 
 /// The value's container for runtime
 #[derive(Debug, Clone)]
 pub enum Value {
     Number(f64),
+    Int(i64),
     String(Rc<String>),
     Bool(bool),
     Closure(Rc<ClosureObject>),
@@ -15,7 +20,11 @@ pub enum Value {
     Sign(Rc<RefCell<SignObject>>),
     SignSchema(Rc<SignSchema>),
     Deck(Rc<DeckObject>),
-    NativeSpell(NativeSpell),
+    Glyph(Rc<GlyphObject>),
+    NativeSpell(Rc<NativeSpell>),
+    Stream(Rc<StreamObject>),
+    Conjuring(Rc<ConjuringObject>),
+    Error(Rc<String>), // the message of the error a mend was given
     Emptiness,
 }
 
@@ -23,6 +32,7 @@ impl Value {
     pub fn get_type(&self) -> ValueType {
         match self {
             Self::Number(_) => ValueType::Number,
+            Self::Int(_) => ValueType::Int,
             Self::String(_) => ValueType::String,
             Self::Bool(_) => ValueType::Bool,
             Self::Closure(_) => ValueType::Closure,
@@ -31,7 +41,11 @@ impl Value {
             Self::Sign(_) => ValueType::Sign,
             Self::SignSchema(_) => ValueType::Sign,
             Self::Deck(_) => ValueType::Deck,
+            Self::Glyph(_) => ValueType::Glyph,
             Self::NativeSpell(_) => ValueType::NativeSpell,
+            Self::Stream(_) => ValueType::Stream,
+            Self::Conjuring(_) => ValueType::Conjuring,
+            Self::Error(_) => ValueType::Error,
         }
     }
 
@@ -51,8 +65,9 @@ impl Value {
         matches!(self, Self::Emptiness)
     }
 
+    /// Only false and emptiness are falsey, every other value (0 and empty texts too) is truthy
     pub fn is_falsey(&self) -> bool {
-        matches!(self, Self::Bool(false))
+        matches!(self, Self::Bool(false) | Self::Emptiness)
     }
 
     pub fn is_closure(&self) -> bool {
@@ -75,11 +90,12 @@ impl Value {
         matches!(self, Self::Deck(_))
     }
 
+    /// The number a value holds, an int is widened into one (indices are read this way)
     pub fn extract_number(&self) -> Option<f64> {
-        if let Value::Number(n) = self {
-            Some(*n)
-        } else {
-            None
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::Int(i) => Some(*i as f64),
+            _ => None,
         }
     }
 
@@ -91,13 +107,26 @@ impl Value {
         }
     }
 
+    /// The equality of `==` and `!=`. Plain values are equal by what they hold and emptiness is
+    /// equal to itself, while spells and signs are only equal to the very same one.
+    /// Values of different types are never equal.
     pub fn equals(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Number(a), Self::Number(b)) => a == b,
+            (Self::Int(a), Self::Int(b)) => a == b,
             (Self::Bool(a), Self::Bool(b)) => a == b,
             (Self::String(a), Self::String(b)) => a == b,
+            (Self::Emptiness, Self::Emptiness) => true,
             (Self::SignSchema(a), Self::SignSchema(b)) => a == b,
             (Self::Deck(a), Self::Deck(b)) => a == b,
+            (Self::Glyph(a), Self::Glyph(b)) => a == b,
+            (Self::Closure(a), Self::Closure(b)) => Rc::ptr_eq(a, b),
+            (Self::Spell(a), Self::Spell(b)) => Rc::ptr_eq(a, b),
+            (Self::Sign(a), Self::Sign(b)) => Rc::ptr_eq(a, b),
+            (Self::NativeSpell(a), Self::NativeSpell(b)) => a == b,
+            (Self::Stream(a), Self::Stream(b)) => Rc::ptr_eq(a, b),
+            (Self::Conjuring(a), Self::Conjuring(b)) => Rc::ptr_eq(a, b),
+            (Self::Error(a), Self::Error(b)) => a == b,
             _ => false,
         }
     }
@@ -106,13 +135,9 @@ impl Value {
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            // Compare numbers by their bits to handle all cases consistently
+            // Compare numbers by their bits, so that the constants can be hashed (NaN finds itself)
             (Self::Number(a), Self::Number(b)) => a.to_bits() == b.to_bits(),
-            (Self::String(a), Self::String(b)) => a == b,
-            (Self::Bool(a), Self::Bool(b)) => a == b,
-            (Self::Emptiness, Self::Emptiness) => true,
-            // Closures are unique runtime objects and should not be considered equal
-            _ => false,
+            _ => self.equals(other),
         }
     }
 }
@@ -125,6 +150,7 @@ impl Hash for Value {
         match self {
             // For numbers, hash their raw bit representation
             Self::Number(n) => n.to_bits().hash(state),
+            Self::Int(i) => i.hash(state),
             Self::String(s) => s.hash(state),
             Self::Bool(b) => b.hash(state),
             Self::Emptiness => {}  //hmm
@@ -133,7 +159,11 @@ impl Hash for Value {
             Self::Sign(_) => {}    // not a compile time const
             Self::SignSchema(s) => s.hash(state),
             Self::Deck(d) => d.items.borrow().hash(state),
+            Self::Glyph(g) => g.hash(state),
             Self::NativeSpell(_) => {}
+            Self::Stream(_) => {} // not a compile time const
+            Self::Conjuring(_) => {} // not a compile time const
+            Self::Error(message) => message.hash(state),
         }
     }
 }
@@ -150,6 +180,12 @@ impl From<f64> for Value {
     }
 }
 
+impl From<i64> for Value {
+    fn from(val: i64) -> Value {
+        Value::Int(val)
+    }
+}
+
 impl From<String> for Value {
     fn from(val: String) -> Value {
         Value::String(Rc::new(val))
@@ -158,20 +194,87 @@ impl From<String> for Value {
 
 // responsible for converting the value to a identifiable string (like toString())
 pub fn print_value(value: Value) {
+    println!("{}", stringify_value(&value));
+}
+
+/// A Num as it is chanted: whole ones go without a fraction, the rest with the fewest digits that
+/// still read back as the same Num. The very large and the very small ones are written with an
+/// exponent, like `1e21` and `1.5e-7`
+pub fn stringify_number(number: f64) -> String {
+    if number.is_nan() {
+        return "NaN".to_string();
+    }
+    if number.is_infinite() {
+        return if number > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    // there's no telling -0 apart from 0 by its digits
+    if number == 0.0 {
+        return "0".to_string();
+    }
+    let magnitude = number.abs();
+    if !(1e-6..1e21).contains(&magnitude) {
+        format!("{:e}", number)
+    } else {
+        number.to_string()
+    }
+}
+
+pub fn stringify_value(value: &Value) -> String {
     match value {
-        Value::Bool(value) => println!("{}", value),
-        Value::Emptiness => println!("Emptiness"),
-        Value::Number(value) => println!("{}", value),
-        Value::String(value) => println!("{}", value),
-        Value::Closure(closure) => println!("Spell '{}'", closure.spell.name.clone().unwrap()),
-        Value::Spell(spell) => println!("Spell '{}'", spell.name.clone().unwrap()),
+        Value::Bool(value) => value.to_string(),
+        Value::Emptiness => "Emptiness".to_string(),
+        Value::Number(value) => stringify_number(*value),
+        Value::Int(value) => value.to_string(),
+        Value::String(value) => value.to_string(),
+        Value::Closure(closure) => format!("Spell '{}'", closure.spell.name.clone().unwrap()),
+        Value::Spell(spell) => format!("Spell '{}'", spell.name.clone().unwrap()),
         Value::Sign(sign) => {
             let sign = sign.borrow();
-            println!("Sign '{}' {:?}", sign.schema.name.clone(), sign.marks)
+            format!("Sign '{}' {:?}", sign.schema.name.clone(), sign.marks)
+        }
+        Value::SignSchema(schema) => format!("SignSchema '{}'", schema.name.clone()),
+        Value::Deck(deck) => stringify_deck(deck, &mut vec![]),
+        Value::Glyph(glyph) => format!("{}.{}", glyph.glyph, glyph.variant),
+        Value::NativeSpell(ns) => format!("NativeSpell '{:?}'", ns),
+        Value::Stream(stream) => format!("Stream '{}'", stream.closure.spell.name.as_deref().unwrap_or("<origin>")),
+        Value::Conjuring(conjuring) => {
+            format!("Conjuring '{}'", conjuring.closure.spell.name.as_deref().unwrap_or("<origin>"))
+        }
+        Value::Error(message) => message.to_string(),
+    }
+}
+
+/// The elements of the [deck] chanted between brackets. A deck held within itself is chanted as
+/// `[...]` where it comes round again, the decks [within] are the ones being chanted
+fn stringify_deck(deck: &Rc<DeckObject>, within: &mut Vec<*const DeckObject>) -> String {
+    if within.contains(&Rc::as_ptr(deck)) {
+        return "[...]".to_string();
+    }
+    within.push(Rc::as_ptr(deck));
+    let items: Vec<String> = deck
+        .items
+        .borrow()
+        .iter()
+        .map(|item| match item {
+            Value::Deck(inner) => stringify_deck(inner, within),
+            other => stringify_value(other),
+        })
+        .collect();
+    within.pop();
+    format!("[{}]", items.join(", "))
+}
+
+// the literals of the AST dumps, the values without a counterpart are shown as they are chanted
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Number(n) => serializer.serialize_f64(*n),
+            Value::Int(i) => serializer.serialize_i64(*i),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Emptiness => serializer.serialize_unit(),
+            other => serializer.serialize_str(&stringify_value(other)),
         }
-        Value::SignSchema(schema) => println!("SignSchema '{}'", schema.name.clone()),
-        Value::Deck(deck) => println!("Deck '{:?}'", deck.items.borrow()),
-        Value::NativeSpell(ns) => println!("NativeSpell '{:?}'", ns)
     }
 }
 
@@ -184,12 +287,17 @@ impl Default for Value {
 pub enum ValueType {
     String,
     Number,
+    Int,
     Bool,
     Closure,
     Spell,
     Sign,
     SignSchema,
     Deck,
+    Glyph,
     NativeSpell,
+    Stream,
+    Conjuring,
+    Error,
     Emptiness,
 }
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod formatter_test {
+    use eira::format_source;
+
+    #[test]
+    fn test_scrolls_are_laid_out_canonically() {
+        let src = r#"// the header stays
+mark nums:Deck<Num,3> = [1,2,3];
+spell add( a:Num,b:Num )::Num{release a+b ;}
+
+
+fate nums[0]==1{chant "one @(nums[1])"; } // says one
+divert { chant -1; }
+sign Point{x:Num,y:Num=0}
+bind p = ~Point with {x: 1, y: 2,};
+bind o = ~Point with {1};"#;
+
+        let expected = r#"// the header stays
+mark nums: Deck<Num, 3> = [1, 2, 3];
+spell add(a: Num, b: Num):: Num {
+    release a + b;
+}
+
+fate nums[0] == 1 {
+    chant "one @(nums[1])";
+} // says one
+divert {
+    chant -1;
+}
+sign Point {
+    x: Num,
+    y: Num = 0
+}
+bind p = ~Point with { x: 1, y: 2 };
+bind o = ~Point with { 1 };
+"#;
+
+        let formatted = format_source(src).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_block_comments_keep_their_place() {
+        let src =
+            "mark a = 1; /* trailing */ chant a /* inline */ + 1;\n/* own\n   line */\nchant a;";
+        let expected =
+            "mark a = 1; /* trailing */\nchant a /* inline */ + 1;\n/* own\n   line */\nchant a;\n";
+
+        let formatted = format_source(src).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_broken_scrolls_are_not_formatted() {
+        let err = format_source("mark a = ;").expect_err("missing initializer");
+        assert!(!err.diagnostics().is_empty());
+    }
+}
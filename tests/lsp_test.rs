@@ -0,0 +1,197 @@
+#[cfg(test)]
+mod lsp_test {
+    use eira::lsp::{LanguageServer, read_message, serve};
+    use serde_json::{Value, json};
+
+    const URI: &str = "file:///scrolls/potion.eira";
+
+    fn open(server: &mut LanguageServer, text: &str) -> Vec<Value> {
+        server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": URI, "languageId": "eira", "version": 1, "text": text } },
+        }))
+    }
+
+    fn request(server: &mut LanguageServer, method: &str, line: usize, character: usize) -> Value {
+        let replies = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": method,
+            "params": {
+                "textDocument": { "uri": URI },
+                "position": { "line": line, "character": character },
+            },
+        }));
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0]["id"], 7);
+        replies[0]["result"].clone()
+    }
+
+    fn labels(completions: &Value) -> Vec<&str> {
+        completions.as_array().unwrap().iter().map(|c| c["label"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn the_problems_are_published_as_the_scroll_changes() {
+        let mut server = LanguageServer::new();
+        let published = open(&mut server, "bind a: Num = \"one\";\nmark unused = 1;");
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0]["method"], "textDocument/publishDiagnostics");
+
+        let diagnostics = published[0]["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0]["severity"], 1);
+        // the value given is underlined whole
+        assert_eq!(
+            diagnostics[0]["range"],
+            json!({ "start": { "line": 0, "character": 14 }, "end": { "line": 0, "character": 19 } })
+        );
+        assert_eq!(diagnostics[1]["severity"], 2);
+        assert!(diagnostics[1]["message"].as_str().unwrap().contains("'unused' is never read"));
+
+        let published = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didChange",
+            "params": {
+                "textDocument": { "uri": URI, "version": 2 },
+                "contentChanges": [{ "text": "bind a: Num = 1;\nchant a;" }],
+            },
+        }));
+        assert_eq!(published[0]["params"]["diagnostics"], json!([]));
+    }
+
+    #[test]
+    fn names_lead_to_their_declarations() {
+        let mut server = LanguageServer::new();
+        open(
+            &mut server,
+            "sign Potion { strength: Num }\nspell brew(power: Num) :: Potion {\n    release ~Potion with { power };\n}\nchant (cast brew with 2).strength;",
+        );
+
+        // `power` read in the body of the spell
+        let location = request(&mut server, "textDocument/definition", 2, 28);
+        assert_eq!(location["uri"], URI);
+        assert_eq!(
+            location["range"],
+            json!({ "start": { "line": 1, "character": 11 }, "end": { "line": 1, "character": 16 } })
+        );
+
+        // the sign drawn, and the one released
+        let drawn = request(&mut server, "textDocument/definition", 2, 14);
+        assert_eq!(drawn["range"]["start"], json!({ "line": 0, "character": 5 }));
+        let released = request(&mut server, "textDocument/definition", 1, 27);
+        assert_eq!(released["range"]["start"], json!({ "line": 0, "character": 5 }));
+
+        // the cursor right after the name
+        let cast = request(&mut server, "textDocument/definition", 4, 15);
+        assert_eq!(cast["range"]["start"], json!({ "line": 1, "character": 6 }));
+
+        assert_eq!(request(&mut server, "textDocument/definition", 4, 0), Value::Null);
+    }
+
+    #[test]
+    fn completions_hold_the_names_in_scope() {
+        let mut server = LanguageServer::new();
+        open(
+            &mut server,
+            "mark gold = 1;\nspell spend(coins: Num) {\n    bind left = gold - coins;\n    chant left;\n}\nchant gold;",
+        );
+
+        let inside = request(&mut server, "textDocument/completion", 3, 4);
+        let inside = labels(&inside);
+        for label in ["fate", "Num", "gold", "spend", "coins", "left"] {
+            assert!(inside.contains(&label), "{} is in scope", label);
+        }
+
+        let outside = request(&mut server, "textDocument/completion", 5, 0);
+        let outside = labels(&outside);
+        assert!(outside.contains(&"gold") && outside.contains(&"spend"));
+        assert!(!outside.contains(&"coins") && !outside.contains(&"left"));
+    }
+
+    #[test]
+    fn names_are_known_past_the_statements_that_dont_parse() {
+        let mut server = LanguageServer::new();
+        let published = open(&mut server, "mark gold = 1;\nchant gold +;\nchant gold;");
+        let diagnostics = published[0]["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+
+        let location = request(&mut server, "textDocument/definition", 2, 7);
+        assert_eq!(location["range"]["start"], json!({ "line": 0, "character": 5 }));
+    }
+
+    #[test]
+    fn the_characters_are_counted_in_utf16_units() {
+        let mut server = LanguageServer::new();
+        // the moon takes two units of UTF-16, and a single letter
+        let published = open(&mut server, "chant \"🌙\"; bind moon: Num = \"x\";\nchant \"🌙\"; chant moon;");
+        let diagnostics = published[0]["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(
+            diagnostics[0]["range"],
+            json!({ "start": { "line": 0, "character": 29 }, "end": { "line": 0, "character": 32 } })
+        );
+
+        // the cursor right after the name
+        let location = request(&mut server, "textDocument/definition", 1, 22);
+        assert_eq!(
+            location["range"],
+            json!({ "start": { "line": 0, "character": 17 }, "end": { "line": 0, "character": 21 } })
+        );
+    }
+
+    #[test]
+    fn the_server_talks_over_its_streams() {
+        let messages = [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+            json!({ "jsonrpc": "2.0", "method": "exit" }),
+        ];
+        let input: String = messages
+            .iter()
+            .map(|m| format!("Content-Length: {}\r\n\r\n{}", m.to_string().len(), m))
+            .collect();
+
+        let mut output = vec![];
+        serve(input.as_bytes(), &mut output).expect("serves");
+
+        let mut replies = output.as_slice();
+        let initialized = read_message(&mut replies).unwrap().unwrap();
+        assert_eq!(initialized["result"]["capabilities"]["definitionProvider"], true);
+        let unknown = read_message(&mut replies).unwrap().unwrap();
+        assert_eq!(unknown["error"]["code"], -32601);
+        let shutdown = read_message(&mut replies).unwrap().unwrap();
+        assert_eq!(shutdown["id"], 3);
+        assert!(read_message(&mut replies).unwrap().is_none());
+    }
+
+    #[test]
+    fn the_server_keeps_serving_past_malformed_messages() {
+        let initialize = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }).to_string();
+        let shutdown = json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }).to_string();
+        let input = format!(
+            "Content-Length: 9\r\n\r\nnot json!Content-Type: application/json\r\n\r\n{{\"id\": 3}}Content-Length: {}\r\n\r\n{}Content-Length: {}\r\n\r\n{}",
+            initialize.len(),
+            initialize,
+            shutdown.len(),
+            shutdown
+        );
+
+        let mut output = vec![];
+        serve(input.as_bytes(), &mut output).expect("serves");
+
+        let mut replies = output.as_slice();
+        // the body which isn't JSON, and the one without its length
+        for _ in 0..2 {
+            let malformed = read_message(&mut replies).unwrap().unwrap();
+            assert_eq!(malformed["id"], Value::Null);
+            assert_eq!(malformed["error"]["code"], -32700);
+        }
+        let initialized = read_message(&mut replies).unwrap().unwrap();
+        assert_eq!(initialized["id"], 1);
+        let shutdown = read_message(&mut replies).unwrap().unwrap();
+        assert_eq!(shutdown["id"], 2);
+        assert!(read_message(&mut replies).unwrap().is_none());
+    }
+}
@@ -1,8 +1,12 @@
 #[cfg(test)]
 mod parser_test {
     use eira::{
-        Parser, Scanner, Value,
-        compiler::{Expr, Stmt, token_type::TokenType},
+        Parser, Scanner, Value, ast_to_json,
+        compiler::{
+            Expr, Stmt,
+            diagnostics::{CompilationPhase, Severity},
+            token_type::TokenType,
+        },
     };
 
     fn parse_helper(source: &str) -> Vec<Stmt> {
@@ -29,9 +33,10 @@ mod parser_test {
             mutable,
             initializer,
             weave: _,
+            ..
         } = &statements[0]
         {
-            assert_eq!(name.lexeme, "a");
+            assert_eq!(&*name.lexeme, "a");
             assert_eq!(*mutable, true);
             assert!(initializer.is_some());
         } else {
@@ -44,9 +49,10 @@ mod parser_test {
             mutable,
             initializer,
             weave: _,
+            ..
         } = &statements[1]
         {
-            assert_eq!(name.lexeme, "b");
+            assert_eq!(&*name.lexeme, "b");
             assert_eq!(*mutable, false);
             assert!(initializer.is_some());
         } else {
@@ -62,11 +68,12 @@ mod parser_test {
         assert_eq!(stmts.len(), 1);
 
         // fragile check, but OK
-        if let Stmt::ExprStmt { expr } = &stmts[0] {
+        if let Stmt::ExprStmt { expr, .. } = &stmts[0] {
             if let Expr::Binary {
                 left,
                 operator,
                 right,
+                ..
             } = expr
             {
                 assert_eq!(operator.token_type, TokenType::Plus);
@@ -84,6 +91,30 @@ mod parser_test {
         }
     }
 
+    #[test]
+    fn test_modulo_precedence() {
+        // Should parse as: 1 + (7 % 3)
+        let source = "1 + 7 % 3;";
+        let stmts = parse_helper(source);
+
+        if let Stmt::ExprStmt {
+            expr: Expr::Binary {
+                operator, right, ..
+            },
+            ..
+        } = &stmts[0]
+        {
+            assert_eq!(operator.token_type, TokenType::Plus);
+            if let Expr::Binary { operator, .. } = &**right {
+                assert_eq!(operator.token_type, TokenType::Percent);
+            } else {
+                panic!("Expected '%' to bind tighter than '+'");
+            }
+        } else {
+            panic!("Expected a binary expression statement");
+        }
+    }
+
     #[test]
     fn test_grouping() {
         // Should parse as: (1 + 2) * 3
@@ -91,11 +122,12 @@ mod parser_test {
         let stmts = parse_helper(source);
         assert_eq!(stmts.len(), 1);
 
-        if let Stmt::ExprStmt { expr } = &stmts[0] {
+        if let Stmt::ExprStmt { expr, .. } = &stmts[0] {
             if let Expr::Binary {
                 left,
                 operator,
                 right,
+                ..
             } = expr
             {
                 assert_eq!(operator.token_type, TokenType::Star);
@@ -109,6 +141,28 @@ mod parser_test {
         }
     }
 
+    #[test]
+    fn test_interpolation() {
+        // Should parse as: ("sum: " + @(1 + 2)) + "!"
+        let stmts = parse_helper(r#""sum: @(1 + 2)!";"#);
+
+        let Stmt::ExprStmt {
+            expr: Expr::Binary { left, .. },
+            ..
+        } = &stmts[0]
+        else {
+            panic!("Expected the text to be joined");
+        };
+        let Expr::Binary { right, .. } = &**left else {
+            panic!("Expected the interpolation to be joined to the text before it");
+        };
+        let Expr::Interpolation { value, token, .. } = &**right else {
+            panic!("Expected the interpolated value to be marked");
+        };
+        assert_eq!(token.token_type, TokenType::InterpolateStart);
+        assert!(matches!(**value, Expr::Binary { .. }));
+    }
+
     #[test]
     fn test_fate_statement() {
         let source = "fate true { chant 1; } divert { chant 2; }";
@@ -119,13 +173,15 @@ mod parser_test {
             condition,
             then_branch,
             else_branch,
+            ..
         } = &stmts[0]
         {
             assert!(matches!(
                 condition,
                 Expr::Literal {
                     value: Value::Bool(true),
-                    token: _
+                    token: _,
+                    ..
                 }
             ));
             assert!(matches!(**then_branch, Stmt::Block { .. }));
@@ -144,11 +200,316 @@ mod parser_test {
         let stmts = parse_helper(source);
         assert_eq!(stmts.len(), 1);
 
-        if let Stmt::While { condition, body } = &stmts[0] {
+        if let Stmt::While {
+            label: None,
+            condition,
+            body,
+            ..
+        } = &stmts[0]
+        {
             assert!(matches!(condition, Expr::Binary { .. }));
             assert!(matches!(**body, Stmt::Block { .. }));
         } else {
             panic!("Expected a While statement.");
         }
     }
+
+    #[test]
+    fn test_named_loops() {
+        let source = "while outer: x < 5 { cycle inner: i from 0 to 2 { sever outer; } flow; }";
+        let stmts = parse_helper(source);
+        assert_eq!(stmts.len(), 1);
+
+        let Stmt::While {
+            label: Some(label),
+            condition,
+            body,
+            ..
+        } = &stmts[0]
+        else {
+            panic!("Expected a named While statement.");
+        };
+        assert_eq!(&*label.lexeme, "outer");
+        assert!(matches!(condition, Expr::Binary { .. }));
+
+        let Stmt::Block { statements, .. } = &**body else {
+            panic!("Expected the body block.");
+        };
+        let Stmt::Cycle {
+            label: Some(inner),
+            variable,
+            body,
+            ..
+        } = &statements[0]
+        else {
+            panic!("Expected a named Cycle statement.");
+        };
+        assert_eq!((inner.lexeme.as_ref(), variable.lexeme.as_ref()), ("inner", "i"));
+        assert!(matches!(
+            &**body,
+            Stmt::Block { statements, .. } if matches!(&statements[0], Stmt::Sever { label: Some(l), .. } if &*l.lexeme == "outer")
+        ));
+        assert!(matches!(statements[1], Stmt::Flow { label: None, .. }));
+    }
+
+    #[test]
+    fn test_circle_statement() {
+        let source = "circle { x = x + 1; } until x > 5;";
+        let stmts = parse_helper(source);
+        assert_eq!(stmts.len(), 1);
+
+        if let Stmt::Circle {
+            label: None,
+            body,
+            condition,
+            ..
+        } = &stmts[0]
+        {
+            assert!(matches!(**body, Stmt::Block { .. }));
+            assert!(matches!(condition, Expr::Binary { .. }));
+        } else {
+            panic!("Expected a Circle statement.");
+        }
+    }
+
+    #[test]
+    fn test_cycle_statement() {
+        let source = "cycle i from 0 to 10 { chant i; }";
+        let stmts = parse_helper(source);
+        assert_eq!(stmts.len(), 1);
+
+        if let Stmt::Cycle {
+            variable,
+            start,
+            end,
+            body,
+            ..
+        } = &stmts[0]
+        {
+            assert_eq!(&*variable.lexeme, "i");
+            assert!(matches!(start, Expr::Literal { .. }));
+            assert!(matches!(end, Expr::Literal { .. }));
+            assert!(matches!(**body, Stmt::Block { .. }));
+        } else {
+            panic!("Expected a Cycle statement.");
+        }
+    }
+
+    #[test]
+    fn test_gathered_reagent() {
+        let stmts = parse_helper("spell log(level: Text, parts: ...Text) {}");
+        let Stmt::Spell { reagents, .. } = &stmts[0] else {
+            panic!("Expected a Spell declaration.");
+        };
+        assert!(!reagents[0].gathered);
+        assert!(reagents[1].gathered);
+        assert_eq!(&*reagents[1].weave.base.lexeme, "Text");
+
+        let tokens = Scanner::init("spell log(parts: ...Text, level: Text) {}").tokenize();
+        let err = Parser::new(tokens, "parser_test".to_string())
+            .parse()
+            .expect_err("the gathered reagent comes last");
+        assert!(err.diagnostics()[0].message.contains("last one"));
+    }
+
+    #[test]
+    fn test_named_reagents() {
+        let stmts = parse_helper("cast brew with 1, color: \"red\", size: c ? 1 : 2;");
+        let Stmt::ExprStmt {
+            expr: Expr::Cast { reagents, named, .. },
+            ..
+        } = &stmts[0]
+        else {
+            panic!("Expected a Cast expression.");
+        };
+        assert_eq!(reagents.len(), 1);
+        let names: Vec<&str> = named.iter().map(|(name, _)| name.lexeme.as_ref()).collect();
+        assert_eq!(names, vec!["color", "size"]);
+        assert!(matches!(named[1].1, Expr::Conditional { .. }));
+
+        // the `:` belongs to the conditional around the cast
+        let stmts = parse_helper("mark x = c ? cast f with a : b;");
+        assert!(matches!(
+            &stmts[0],
+            Stmt::VarDeclaration {
+                initializer: Some(Expr::Conditional { .. }),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_tome_declaration() {
+        let source = r#"
+            tome Dragon refers Creature {
+                fire: Num,
+                spell roar() { chant "roar"; }
+            }
+        "#;
+        let stmts = parse_helper(source);
+        assert_eq!(stmts.len(), 1);
+
+        if let Stmt::Tome {
+            name,
+            origin,
+            marks,
+            spells,
+            ..
+        } = &stmts[0]
+        {
+            assert_eq!(&*name.lexeme, "Dragon");
+            assert_eq!(&*origin.as_ref().unwrap().lexeme, "Creature");
+            assert_eq!(marks.len(), 1);
+            assert_eq!(spells.len(), 1);
+        } else {
+            panic!("Expected a Tome statement.");
+        }
+    }
+
+    #[test]
+    fn test_glyph_declaration() {
+        let source = "glyph Element { Fire, Water, Earth }";
+        let stmts = parse_helper(source);
+        assert_eq!(stmts.len(), 1);
+
+        if let Stmt::Glyph { name, variants, .. } = &stmts[0] {
+            assert_eq!(&*name.lexeme, "Element");
+            let names: Vec<&str> = variants.iter().map(|v| v.lexeme.as_ref()).collect();
+            assert_eq!(names, vec!["Fire", "Water", "Earth"]);
+        } else {
+            panic!("Expected a Glyph statement.");
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_are_diagnostics() {
+        let source = "mark x = 1\nchant x;";
+        let tokens = Scanner::init(source).tokenize();
+        let err = Parser::new(tokens, "parser_test".to_string())
+            .parse()
+            .expect_err("missing semicolon");
+
+        let diagnostic = &err.diagnostics()[0];
+        assert_eq!(diagnostic.phase, CompilationPhase::Parse);
+        assert_eq!(diagnostic.severity, Severity::Error);
+
+        let location = diagnostic.location.as_ref().unwrap();
+        assert_eq!((location.line, location.column), (2, 5));
+        assert!(err.to_string().contains("parser_test:2:5"));
+    }
+
+    #[test]
+    fn test_parser_recovers_after_errors() {
+        let source = "mark x = 1\nchant x\nmark y = 2;\nchant (y;";
+        let tokens = Scanner::init(source).tokenize();
+        let err = Parser::new(tokens, "parser_test".to_string())
+            .parse()
+            .expect_err("broken statements");
+
+        let lines: Vec<usize> = err
+            .diagnostics()
+            .iter()
+            .map(|d| d.location.as_ref().unwrap().line)
+            .collect();
+        assert_eq!(lines, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_parser_recovers_inside_blocks() {
+        let source = "spell f() {\n    mark x = ;\n    chant 1;\n    chant (2;\n}\nchant 3;";
+        let tokens = Scanner::init(source).tokenize();
+        let (statements, diagnostics) = Parser::new(tokens, "parser_test".to_string()).parse_partial();
+
+        let lines: Vec<usize> = diagnostics
+            .iter()
+            .map(|d| d.location.as_ref().unwrap().line)
+            .collect();
+        assert_eq!(lines, vec![2, 4]);
+
+        // the spell keeps the statement between the broken ones, and the scroll goes on after it
+        assert_eq!(statements.len(), 2);
+        let Stmt::Spell { body, .. } = &statements[0] else {
+            panic!("Expected the spell");
+        };
+        let Stmt::Block { statements: body, .. } = body.as_ref() else {
+            panic!("Expected the body of the spell");
+        };
+        assert!(matches!(body.as_slice(), [Stmt::Chant { .. }]));
+    }
+
+    #[test]
+    fn test_ast_dumps_as_json() {
+        let statements = parse_helper("mark a = 1 + 2;");
+        let json: serde_json::Value = serde_json::from_str(&ast_to_json(&statements)).unwrap();
+
+        let declaration = &json[0];
+        assert_eq!(declaration["kind"], "VarDeclaration");
+        assert_eq!(declaration["name"]["lexeme"], "a");
+        assert_eq!(declaration["initializer"]["kind"], "Binary");
+        assert_eq!(declaration["initializer"]["operator"]["lexeme"], "+");
+        assert_eq!(declaration["initializer"]["right"]["value"], 2.0);
+    }
+
+    #[test]
+    fn test_deep_nesting_is_refused() {
+        let source = format!("mark x = {}1{};", "(".repeat(10_000), ")".repeat(10_000));
+        let tokens = Scanner::init(&source).tokenize();
+        let err = Parser::new(tokens, "parser_test".to_string())
+            .parse()
+            .expect_err("too deep");
+        assert!(err.to_string().contains("The scroll nests deeper than 64 levels here."));
+
+        let source = format!("{}chant 1;{}", "{".repeat(65), "}".repeat(65));
+        let tokens = Scanner::init(&source).tokenize();
+        assert!(Parser::new(tokens, "parser_test".to_string()).parse().is_err());
+    }
+
+    #[test]
+    fn test_long_chains_are_not_nesting() {
+        let source = format!("mark x = 1{};", " + 1".repeat(199));
+        assert!(Parser::new(Scanner::init(&source).tokenize(), "parser_test".to_string()).parse().is_ok());
+
+        let source = format!("mark x = 1{};", " + 1".repeat(4097));
+        let err = Parser::new(Scanner::init(&source).tokenize(), "parser_test".to_string())
+            .parse()
+            .expect_err("too long");
+        assert!(err.to_string().contains("chains more than 4096 operators"));
+    }
+
+    #[test]
+    fn test_tokens_without_eof_end_the_scroll() {
+        let statements = Parser::new(vec![], "parser_test".to_string()).parse().unwrap();
+        assert!(statements.is_empty());
+
+        let mut tokens = Scanner::init("chant 1;").tokenize();
+        tokens.pop();
+        assert_eq!(Parser::new(tokens, "parser_test".to_string()).parse().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_nodes_span_all_of_their_tokens() {
+        let source = "mark total = (1 + 2) * cast add with 3, 4;\nchant \"sum @(total)!\";";
+        let statements = parse_helper(source);
+        let text = |span: eira::compiler::scanner::Span| &source[span.start..span.end];
+
+        assert_eq!(text(statements[0].span()), "mark total = (1 + 2) * cast add with 3, 4;");
+        let Stmt::VarDeclaration {
+            initializer: Some(initializer),
+            ..
+        } = &statements[0]
+        else {
+            panic!("Expected a variable declaration");
+        };
+        assert_eq!(text(initializer.span()), "(1 + 2) * cast add with 3, 4");
+        let Expr::Binary { left, right, .. } = initializer else {
+            panic!("Expected a binary expression");
+        };
+        assert_eq!(text(left.span()), "(1 + 2)");
+        assert_eq!(text(right.span()), "cast add with 3, 4");
+
+        let Stmt::Chant { expressions, .. } = &statements[1] else {
+            panic!("Expected a chant");
+        };
+        assert_eq!(text(expressions[0].span()), "\"sum @(total)!\"");
+    }
 }
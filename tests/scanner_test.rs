@@ -1,10 +1,16 @@
 #[cfg(test)]
 mod scanner_test {
-    use eira::{Scanner, compiler::token_type::TokenType};
+    use eira::{
+        Scanner,
+        compiler::{
+            scanner::{Span, TokenClass, number_value},
+            token_type::TokenType,
+        },
+    };
 
     #[test]
     fn test_single_character_tokens() {
-        let source = "(){};:.,+-/*";
+        let source = "(){};:.,+-/ *%&|^"; // `/*` would open a block comment
         let scanner = Scanner::init(source);
         let tokens = scanner.tokenize();
 
@@ -21,6 +27,10 @@ mod scanner_test {
             TokenType::Minus,
             TokenType::Slash,
             TokenType::Star,
+            TokenType::Percent,
+            TokenType::And,
+            TokenType::Or,
+            TokenType::Caret,
             TokenType::Eof,
         ];
 
@@ -32,7 +42,7 @@ mod scanner_test {
 
     #[test]
     fn test_multi_character_tokens() {
-        let source = "! != = == > >= < <=";
+        let source = "! != = == > >= < <= >> <<";
         let scanner = Scanner::init(source);
         let tokens = scanner.tokenize();
 
@@ -45,6 +55,8 @@ mod scanner_test {
             TokenType::GreaterEqual,
             TokenType::Less,
             TokenType::LessEqual,
+            TokenType::GreaterGreater,
+            TokenType::LessLess,
             TokenType::Eof,
         ];
 
@@ -62,7 +74,7 @@ mod scanner_test {
 
         assert_eq!(tokens.len(), 2);
         assert_eq!(tokens[0].token_type, TokenType::String);
-        assert_eq!(tokens[0].lexeme, "hello world");
+        assert_eq!(&*tokens[0].lexeme, "hello world");
     }
 
     #[test]
@@ -82,9 +94,33 @@ mod scanner_test {
 
         assert_eq!(tokens.len(), 3);
         assert_eq!(tokens[0].token_type, TokenType::Number);
-        assert_eq!(tokens[0].lexeme, "123");
+        assert_eq!(&*tokens[0].lexeme, "123");
         assert_eq!(tokens[1].token_type, TokenType::Number);
-        assert_eq!(tokens[1].lexeme, "45.67");
+        assert_eq!(&*tokens[1].lexeme, "45.67");
+    }
+
+    #[test]
+    fn test_prefixed_and_grouped_numbers() {
+        let tokens = Scanner::init("0xFF 0b1010 1_000_000 1.234_5").tokenize();
+        let values: Vec<f64> = tokens[..4]
+            .iter()
+            .map(|t| number_value(&t.lexeme))
+            .collect();
+        assert_eq!(values, vec![255.0, 10.0, 1_000_000.0, 1.234_5]);
+        assert_eq!(&*tokens[2].lexeme, "1_000_000");
+
+        for (source, error) in [
+            ("0x;", "Expected the digits of the number after '0x'."),
+            ("0b102", "'2' is not a binary digit."),
+            (
+                "1__000",
+                "An '_' in a number has to sit between two digits.",
+            ),
+        ] {
+            let tokens = Scanner::init(source).tokenize();
+            assert_eq!(tokens[0].token_type, TokenType::Error);
+            assert_eq!(&*tokens[0].lexeme, error);
+        }
     }
 
     #[test]
@@ -105,7 +141,7 @@ mod scanner_test {
         assert_eq!(tokens[0].token_type, TokenType::Spell);
         assert_eq!(tokens[1].token_type, TokenType::Cast);
         assert_eq!(tokens[2].token_type, TokenType::Identifier);
-        assert_eq!(tokens[2].lexeme, "my_spell");
+        assert_eq!(&*tokens[2].lexeme, "my_spell");
         assert_eq!(tokens[3].token_type, TokenType::Bind);
     }
 
@@ -136,6 +172,36 @@ mod scanner_test {
         assert_eq!(tokens[0].line, 3);
     }
 
+    #[test]
+    fn test_block_comments_nest() {
+        let source = "mark /* a /* nested\n */ whisper */ x = 1;";
+        let tokens = Scanner::init(source).tokenize();
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::Mark,
+                TokenType::Identifier,
+                TokenType::Equal,
+                TokenType::Number,
+                TokenType::SemiColon,
+                TokenType::Eof,
+            ]
+        );
+        assert_eq!(tokens[1].line, 2);
+
+        let tokens = Scanner::init("mark x = 1;\n/* never /* closed */").tokenize();
+        let error = tokens
+            .iter()
+            .find(|t| t.token_type == TokenType::Error)
+            .unwrap();
+        assert_eq!(
+            &*error.lexeme,
+            "The block comment opened on line 2 was never closed."
+        );
+        assert_eq!(error.line, 2);
+    }
+
     #[test]
     fn test_string_interpolation_identifier() {
         let source = r#""x is @x""#;
@@ -156,8 +222,8 @@ mod scanner_test {
             assert_eq!(tokens[i].token_type, *token_type);
         }
 
-        assert_eq!(tokens[0].lexeme, "x is ");
-        assert_eq!(tokens[2].lexeme, "x");
+        assert_eq!(&*tokens[0].lexeme, "x is ");
+        assert_eq!(&*tokens[2].lexeme, "x");
     }
 
     #[test]
@@ -190,7 +256,7 @@ mod scanner_test {
         let tokens = scanner.tokenize();
 
         assert_eq!(tokens[0].token_type, TokenType::String);
-        assert_eq!(tokens[0].lexeme, "@home");
+        assert_eq!(&*tokens[0].lexeme, "@home");
     }
 
     #[test]
@@ -229,4 +295,86 @@ mod scanner_test {
         assert_eq!(tokens[3].token_type, TokenType::Number);
         assert_eq!(tokens[4].token_type, TokenType::Error);
     }
+
+    /// The classes of [source], each with the text it spans
+    fn classes(source: &str) -> Vec<(&str, TokenClass)> {
+        Scanner::classify(source)
+            .into_iter()
+            .map(|(Span { start, end }, class)| (&source[start..end], class))
+            .collect()
+    }
+
+    #[test]
+    fn test_classify_keeps_comments_and_whitespace() {
+        let source = "mark x = 10; // a whisper\n/* and /* a nested */ one */_";
+        assert_eq!(
+            classes(source),
+            vec![
+                ("mark", TokenClass::Keyword),
+                (" ", TokenClass::Whitespace),
+                ("x", TokenClass::Identifier),
+                (" ", TokenClass::Whitespace),
+                ("=", TokenClass::Operator),
+                (" ", TokenClass::Whitespace),
+                ("10", TokenClass::Number),
+                (";", TokenClass::Punctuation),
+                (" ", TokenClass::Whitespace),
+                ("// a whisper", TokenClass::Comment),
+                ("\n", TokenClass::Whitespace),
+                ("/* and /* a nested */ one */", TokenClass::Comment),
+                ("_", TokenClass::Identifier),
+            ]
+        );
+
+        // the spans cover the whole source, one after another
+        let spans = Scanner::classify(source);
+        assert_eq!(spans.first().map(|(s, _)| s.start), Some(0));
+        assert_eq!(spans.last().map(|(s, _)| s.end), Some(source.len()));
+        assert!(spans.windows(2).all(|w| w[0].0.end == w[1].0.start));
+    }
+
+    #[test]
+    fn test_classify_strings_and_interpolations() {
+        assert_eq!(
+            classes(r#"chant "hi @name, @(n + 1)!";"#),
+            vec![
+                ("chant", TokenClass::Keyword),
+                (" ", TokenClass::Whitespace),
+                ("\"hi @", TokenClass::String),
+                ("name", TokenClass::Identifier),
+                (", @(", TokenClass::String),
+                ("n", TokenClass::Identifier),
+                (" ", TokenClass::Whitespace),
+                ("+", TokenClass::Operator),
+                (" ", TokenClass::Whitespace),
+                ("1", TokenClass::Number),
+                (")!\"", TokenClass::String),
+                (";", TokenClass::Punctuation),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classify_marks_what_does_not_scan() {
+        assert_eq!(
+            classes("bind s = \"open"),
+            vec![
+                ("bind", TokenClass::Keyword),
+                (" ", TokenClass::Whitespace),
+                ("s", TokenClass::Identifier),
+                (" ", TokenClass::Whitespace),
+                ("=", TokenClass::Operator),
+                (" ", TokenClass::Whitespace),
+                ("\"open", TokenClass::Error),
+            ]
+        );
+        assert_eq!(
+            classes("1 /* never closed"),
+            vec![
+                ("1", TokenClass::Number),
+                (" ", TokenClass::Whitespace),
+                ("/* never closed", TokenClass::Comment),
+            ]
+        );
+    }
 }
@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod test_runner_test {
+    use std::{fs, path::PathBuf};
+
+    use eira::{find_tests, results_to_json, run_test};
+
+    /// A folder of test scrolls of its own, for every test here
+    fn folder(name: &str, scrolls: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("eira_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, source) in scrolls {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn finds_the_test_scrolls_within_the_folders() {
+        let dir = folder(
+            "finds",
+            &[
+                ("b.test.eira", "chant 1;"),
+                ("a.test.eira", "chant 1;"),
+                ("inner/c.test.eira", "chant 1;"),
+                ("main.eira", "chant 1;"),
+            ],
+        );
+
+        let found: Vec<PathBuf> = find_tests(&dir).unwrap();
+        let names: Vec<String> = found
+            .iter()
+            .map(|p| p.strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        assert_eq!(names, vec!["a.test.eira", "b.test.eira", "inner/c.test.eira"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unmet_expectations_fail_the_test() {
+        let dir = folder(
+            "expects",
+            &[
+                ("met.test.eira", "chant \"fine\";\nexpect 1 + 1 == 2;"),
+                ("unmet.test.eira", "chant \"before\";\nexpect 1 + 1 == 3, \"one and one make two\";\nchant \"after\";"),
+                ("cursed.test.eira", "mark x: Num = \"a\";"),
+            ],
+        );
+
+        let results: Vec<_> = find_tests(&dir).unwrap().iter().map(|t| run_test(t, None)).collect();
+        let [cursed, met, unmet] = results.as_slice() else {
+            panic!("Expected three results");
+        };
+
+        assert!(met.passed());
+        assert_eq!(met.output, "fine\n");
+
+        assert!(!unmet.passed());
+        assert_eq!(unmet.output, "before\n");
+        assert!(unmet.failure.as_ref().unwrap().contains("one and one make two at line 2"));
+
+        assert!(cursed.failure.as_ref().unwrap().contains("was woven as 'Num' but was given 'Text'"));
+
+        let json: serde_json::Value = serde_json::from_str(&results_to_json(&results)).unwrap();
+        assert_eq!((json["passed"].as_u64(), json["failed"].as_u64()), (Some(1), Some(2)));
+        assert_eq!(json["tests"][1]["passed"], true);
+        assert_eq!(json["tests"][2]["output"], "before\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_to_the_folders_are_not_followed() {
+        let dir = folder("links", &[("inner/a.test.eira", "chant 1;")]);
+        std::os::unix::fs::symlink(&dir, dir.join("inner/back")).unwrap();
+
+        assert_eq!(find_tests(&dir).unwrap(), vec![dir.join("inner/a.test.eira")]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tests_heed_no_input() {
+        let dir = folder("heeds", &[("heed.test.eira", "bind line = heed;\nexpect line == \"\";")]);
+
+        let result = run_test(&dir.join("heed.test.eira"), None);
+        assert!(result.passed(), "{:?}", result.failure);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,1956 @@
+#[cfg(test)]
+mod vm_test {
+    use std::{cell::RefCell, collections::BTreeSet, io::Write, rc::Rc, time::Duration};
+
+    use eira::{
+        DebugCommand, DebugFrontend, Debugger, EiraVM, ErrorKind, Value, VmState, assemble,
+        assembler::Assembler,
+        bytecode_file, compile,
+        compiler::{
+            compiler::{CompiledCode, Compiler, CompilerOptions},
+            diagnostics::DEFAULT_ERROR_LIMIT,
+            weaves::Weave,
+        },
+        disassemble, disassemble_code,
+        runtime::Instruction,
+        values::{
+            deck::DeckObject,
+            native_spell::NativeSpell,
+            spell::{Capture, ClosureObject, SpellObject},
+            value::stringify_value,
+        },
+    };
+
+    thread_local! {
+        static RECORDED: RefCell<Vec<Value>> = const { RefCell::new(vec![]) };
+    }
+
+    fn record(args: &[Value]) -> Value {
+        RECORDED.with(|r| r.borrow_mut().push(args[0].clone()));
+        Value::Emptiness
+    }
+
+    fn twice(args: &[Value]) -> Value {
+        Value::Number(args[0].extract_number().unwrap() * 2.0)
+    }
+
+    /// Collects the chants of a scroll
+    #[derive(Clone, Default)]
+    struct Captured(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Drives the debugger with the commands given to it, noting where it paused and the locals there
+    struct Scripted {
+        commands: Vec<DebugCommand>,
+        pauses: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl DebugFrontend for Scripted {
+        fn paused(&mut self, state: &VmState, breakpoints: &mut BTreeSet<usize>) -> DebugCommand {
+            let locals: Vec<String> = state
+                .locals()
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, stringify_value(value)))
+                .collect();
+            self.pauses.borrow_mut().push(format!("{} {} {}", state.spell, state.line, locals.join(" ")));
+            breakpoints.insert(3);
+            self.commands.remove(0)
+        }
+    }
+
+    fn options() -> CompilerOptions {
+        CompilerOptions {
+            print_tokens: false,
+            print_ast: None,
+            print_woven_ast: None,
+            ast_json: false,
+            print_instructions: false,
+            print_bytecode: false,
+            error_limit: DEFAULT_ERROR_LIMIT,
+            debug_info: true,
+            deny_warnings: false,
+        }
+    }
+
+    fn scroll(name: &str, source: &str) -> String {
+        let path = std::env::temp_dir().join(format!("eira_vm_test_{}.eira", name));
+        std::fs::write(&path, source).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    /// Runs the scroll with 'record' spell available, returns the recorded values
+    fn run_recorded(name: &str, source: &str) -> Vec<Value> {
+        let mut compiler = Compiler::new(scroll(name, source), options(), None);
+        compiler.declare_native("record", vec![Weave::Empty], Weave::Empty);
+        let compiled = compiler.compile_to_bytecode().ok().expect("compiles");
+
+        let mut vm = EiraVM::init(compiled);
+        vm.register_native("record", 1, record);
+
+        assert!(vm.start().is_ok());
+        RECORDED.with(|r| r.borrow_mut().drain(..).collect())
+    }
+
+    #[test]
+    fn host_registered_spells_can_be_cast() {
+        let path = scroll("host_spells", "cast record with cast twice with 21;");
+
+        let mut compiler = Compiler::new(path, options(), None);
+        compiler.declare_native("twice", vec![Weave::Num], Weave::Num);
+        compiler.declare_native("record", vec![Weave::Num], Weave::Empty);
+        let compiled = compiler.compile_to_bytecode().ok().expect("compiles");
+
+        let mut vm = EiraVM::init(compiled);
+        vm.register_native("twice", 1, twice);
+        vm.register_native("record", 1, record);
+
+        assert!(vm.start().is_ok());
+        RECORDED.with(|r| assert_eq!(*r.borrow(), vec![Value::Number(42.0)]));
+    }
+
+    #[test]
+    fn unregistered_host_spell_is_a_runtime_error() {
+        let path = scroll("missing_host_spell", "chant cast twice with 1;");
+
+        let mut compiler = Compiler::new(path, options(), None);
+        compiler.declare_native("twice", vec![Weave::Num], Weave::Num);
+        let compiled = compiler.compile_to_bytecode().ok().expect("compiles");
+
+        let mut vm = EiraVM::init(compiled);
+        assert!(vm.start().is_err());
+    }
+
+    #[test]
+    fn release_from_every_branch() {
+        let src = r#"
+            spell sign_of(n: Num):: Text {
+                fate n < 0 {
+                    release "negative";
+                } divert {
+                    release "positive";
+                }
+            }
+            spell nothing() { }
+            cast record with cast sign_of with -1;
+            cast record with cast sign_of with 1;
+            cast record with cast nothing;
+        "#;
+        let recorded = run_recorded("release_branches", src);
+        assert_eq!(
+            recorded,
+            vec![
+                Value::String("negative".to_string().into()),
+                Value::String("positive".to_string().into()),
+                Value::Emptiness,
+            ]
+        );
+    }
+
+    #[test]
+    fn spells_cast_themselves() {
+        let src = r#"
+            spell fib(n: Num):: Num {
+                fate n < 2 { release n; }
+                release (cast fib with n - 1) + (cast fib with n - 2);
+            }
+            spell outer() {
+                spell fact(n: Num):: Num {
+                    fate n < 2 { release 1; }
+                    release n * (cast fact with n - 1);
+                }
+                cast record with cast fact with 5;
+            }
+            cast record with cast fib with 10;
+            cast outer;
+        "#;
+        let recorded = run_recorded("recursion", src);
+        assert_eq!(recorded, vec![Value::Number(55.0), Value::Number(120.0)]);
+    }
+
+    #[test]
+    fn line_table_follows_the_source() {
+        let src = "mark a = 1;\n\nchant a + 2;\nspell f() {\n    chant \"f\";\n}\n";
+        let compiler = Compiler::new(scroll("line_table", src), options(), None);
+        let compiled = compiler.compile_to_bytecode().ok().expect("compiles");
+
+        let lines: Vec<usize> = compiled.lines.iter().map(|(_, l)| *l).collect();
+        // the spell is hoisted above the statements around it
+        assert_eq!(lines, vec![4, 1, 3]);
+
+        let spell = compiled
+            .constants
+            .iter()
+            .find_map(|c| match c {
+                Value::Closure(c) => Some(c.spell.clone()),
+                _ => None,
+            })
+            .expect("spell constant");
+        assert_eq!(spell.line_at(0), 5);
+    }
+
+    #[test]
+    fn precompiled_scrolls_run_the_same() {
+        let src = r#"
+            glyph Element { Fire, Water }
+            spell fact(n: Num):: Num {
+                fate n < 2 { release 1; }
+                release n * (cast fact with n - 1);
+            }
+            cast record with cast fact with 5;
+            cast record with cast to_text with 4.5;
+            cast record with Element.Water == Element.Water;
+        "#;
+        let mut compiler = Compiler::new(scroll("precompiled", src), options(), None);
+        compiler.declare_native("record", vec![Weave::Empty], Weave::Empty);
+        let compiled = compiler.compile_to_bytecode().expect("compiles");
+
+        let path = std::env::temp_dir().join("eira_vm_test_precompiled.eirac");
+        bytecode_file::write_file(&compiled, &path).expect("writes");
+
+        let mut vm = EiraVM::init_from_file(&path).expect("loads");
+        vm.register_native("record", 1, record);
+        assert!(vm.start().is_ok());
+
+        let recorded: Vec<Value> = RECORDED.with(|r| r.borrow_mut().drain(..).collect());
+        assert_eq!(
+            recorded,
+            vec![
+                Value::Number(120.0),
+                Value::String("4.5".to_string().into()),
+                Value::Bool(true),
+            ]
+        );
+    }
+
+    #[test]
+    fn foreign_bytes_are_not_precompiled_scrolls() {
+        assert!(bytecode_file::deserialize(b"NOPE").is_err());
+
+        let compiled = Compiler::new(scroll("truncated", "chant 1;"), options(), None)
+            .compile_to_bytecode()
+            .expect("compiles");
+        let bytes = bytecode_file::serialize(&compiled).expect("serializes");
+        assert!(bytecode_file::deserialize(&bytes).is_ok());
+        assert!(bytecode_file::deserialize(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn malformed_bytecode_is_refused() {
+        let compiled = Compiler::new(scroll("malformed", "chant 1;"), options(), None)
+            .compile_to_bytecode()
+            .expect("compiles");
+        assert!(EiraVM::init(compiled.clone()).start().is_ok());
+
+        let mut unknown_opcode = compiled.clone();
+        unknown_opcode.bytecode[0] = 0xFF;
+        assert!(EiraVM::init(unknown_opcode).start().is_err());
+
+        // the retired PopStack, locals are moved between registers instead
+        let mut pop_stack = compiled.clone();
+        pop_stack.bytecode[0] = 19;
+        assert!(EiraVM::init(pop_stack).start().is_err());
+
+        // without the halt, the code runs off its end
+        let mut no_halt = compiled.clone();
+        no_halt.bytecode.truncate(no_halt.bytecode.len() - 2);
+        assert!(EiraVM::init(no_halt).start().is_err());
+
+        let mut cut_short = compiled;
+        cut_short.bytecode.pop();
+        assert!(EiraVM::init(cut_short).start().is_err());
+    }
+
+    #[test]
+    fn operands_outside_the_spell_are_refused() {
+        let compiled = Compiler::new(scroll("operands", "chant 1;"), options(), None)
+            .compile_to_bytecode()
+            .expect("compiles");
+        let code = |instructions: &[Instruction]| {
+            let mut compiled = compiled.clone();
+            compiled.bytecode = instructions.iter().flat_map(|i| i.get_byte_code()).collect();
+            compiled
+        };
+        let halt = Instruction::Halt { src: 0 };
+        assert!(EiraVM::init(code(&[Instruction::Constant { dest: 0, const_index: 0 }, halt])).start().is_ok());
+
+        let far_constant = code(&[Instruction::Constant { dest: 0, const_index: 300 }, halt]);
+        assert!(EiraVM::init(far_constant).start().unwrap_err().message.contains("constant"));
+
+        let far_register = code(&[Instruction::Print { r1: 200 }, halt]);
+        assert!(EiraVM::init(far_register).start().unwrap_err().message.contains("registers"));
+
+        let far_deck = code(&[Instruction::NewDeck { dest: 0, start_reg: 0, count: 100 }, halt]);
+        assert!(EiraVM::init(far_deck).start().is_err());
+
+        // the scroll has no caster to release to
+        let release = code(&[Instruction::Release { dest: 0 }]);
+        assert!(EiraVM::init(release).start().is_err());
+    }
+
+    /// A spell releasing its first register, capturing [captures] and holding [constants]
+    fn inner_spell(captures: Vec<Capture>, constants: Vec<Value>) -> SpellObject {
+        SpellObject {
+            name: Some("inner".to_string()),
+            arity: 0,
+            captures,
+            constants,
+            bytecode: Instruction::Release { dest: 0 }.get_byte_code(),
+            flowing: false,
+            lines: vec![],
+            register_count: 1,
+            debug: None,
+        }
+    }
+
+    /// A spell the host made itself, with a byte which isn't an opcode for its code
+    fn malformed_closure() -> Value {
+        let spell = SpellObject { bytecode: vec![0xff], ..inner_spell(vec![], vec![]) };
+        Value::Closure(Rc::new(ClosureObject { spell: Rc::new(spell), upvalues: vec![] }))
+    }
+
+    fn malformed(_: &[Value]) -> Value {
+        malformed_closure()
+    }
+
+    #[test]
+    fn spells_released_by_the_host_are_verified() {
+        let src = "bind s = cast malformed;\nchant cast s;";
+        let mut compiler = Compiler::new(scroll("host_release", src), options(), None);
+        let release = Weave::Spell { reagents: Some(vec![]), release: Box::new(Weave::Num) };
+        compiler.declare_native("malformed", vec![], release);
+        let compiled = compiler.compile_to_bytecode().expect("compiles");
+
+        let mut vm = EiraVM::init(compiled);
+        vm.register_native("malformed", 0, malformed);
+        let err = vm.start().unwrap_err();
+        assert!(err.message.contains("released a malformed spell"), "{}", err.message);
+        assert!(err.message.contains("Unknown opcode 255"), "{}", err.message);
+    }
+
+    #[test]
+    fn spells_called_by_the_host_are_verified() {
+        let src = "spell apply(f: Spell<Num>):: Num { release cast f; }";
+        let mut vm = EiraVM::new();
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let err = vm.call_spell("apply", &[malformed_closure()]).unwrap_err();
+        assert!(err.message.contains("The spell 'apply' can't be cast"), "{}", err.message);
+        assert!(err.message.contains("Unknown opcode 255"), "{}", err.message);
+
+        // nor can the host hand one to the scroll through a global, even within a deck
+        let deck = Value::Deck(Rc::new(DeckObject::new(vec![malformed_closure()], None)));
+        let err = vm.set_global("bad", deck).unwrap_err();
+        assert!(err.message.contains("The global 'bad' can't be given the value"), "{}", err.message);
+        assert!(err.message.contains("Unknown opcode 255"), "{}", err.message);
+        assert_eq!(vm.get_global("bad"), None);
+    }
+
+    #[test]
+    fn captures_outside_the_enclosing_spell_are_refused() {
+        let compiled = Compiler::new(scroll("captures", "chant 1;"), options(), None)
+            .compile_to_bytecode()
+            .expect("compiles");
+        let making = |captures: Vec<Capture>| {
+            let mut compiled = compiled.clone();
+            let closure = ClosureObject { spell: Rc::new(inner_spell(captures, vec![])), upvalues: vec![] };
+            compiled.constants = vec![Value::Closure(Rc::new(closure))];
+            compiled.bytecode = [Instruction::Constant { dest: 0, const_index: 0 }, Instruction::Halt { src: 0 }]
+                .iter()
+                .flat_map(|i| i.get_byte_code())
+                .collect();
+            EiraVM::init(compiled).start()
+        };
+
+        assert!(making(vec![Capture::Local(0)]).is_ok());
+        let far_register = making(vec![Capture::Local(250)]).unwrap_err();
+        assert!(far_register.message.contains("captures the register 250"));
+        // the origin has no upvalues to hand down
+        let far_upvalue = making(vec![Capture::Enclosing(0)]).unwrap_err();
+        assert!(far_upvalue.message.contains("captures the upvalue 0"));
+    }
+
+    #[test]
+    fn native_casts_are_verified() {
+        let compiled = Compiler::new(scroll("native_casts", "chant 1;"), options(), None)
+            .compile_to_bytecode()
+            .expect("compiles");
+        let casting = |constant: Value, args_count: u8| {
+            let mut compiled = compiled.clone();
+            compiled.constants = vec![constant, Value::String(Rc::new("four".to_string()))];
+            compiled.bytecode = [
+                Instruction::Constant { dest: 1, const_index: 1 },
+                Instruction::NativeCast { dest: 0, nat_spell: 0, reg_start: 1, args_count },
+                Instruction::Halt { src: 0 },
+            ]
+            .iter()
+            .flat_map(|i| i.get_byte_code())
+            .collect();
+            compiled.register_count = 3;
+            EiraVM::init(compiled).start()
+        };
+        let native = |name: &str| Value::NativeSpell(Rc::new(NativeSpell::resolve(name).unwrap()));
+
+        let not_native = casting(Value::Number(1.0), 1).unwrap_err();
+        assert!(not_native.message.contains("isn't a native spell"), "{}", not_native.message);
+        let miscounted = casting(native("format"), 1).unwrap_err();
+        assert!(miscounted.message.contains("taking 2 reagents with 1"), "{}", miscounted.message);
+        // the weaves of the reagents are only known as it runs, a wrong one breaks the scroll down
+        let mistyped = casting(native("sqrt"), 1).unwrap_err();
+        assert!(mistyped.message.contains("Expected a Num reagent"), "{}", mistyped.message);
+    }
+
+    #[test]
+    fn spells_nested_too_deep_are_refused() {
+        // every level is an empty spell holding the next one as its only constant
+        let mut bytes = bytecode_file::MAGIC.to_vec();
+        bytes.extend(bytecode_file::FORMAT_VERSION.to_le_bytes());
+        for _ in 0..500_000 {
+            bytes.extend([0, 0, 0]); // no name, no reagents, not flowing
+            for count in [1u32, 0, 0, 0] {
+                bytes.extend(count.to_le_bytes()); // registers, captures, bytecode and lines
+            }
+            bytes.push(0); // no debug info
+            bytes.extend(1u32.to_le_bytes());
+            bytes.push(4); // a spell constant
+        }
+        let err = bytecode_file::deserialize(&bytes).err().expect("too deep to read");
+        assert!(err.contains("deeper than 128"));
+
+        let mut nested = inner_spell(vec![], vec![]);
+        for _ in 0..200 {
+            nested = inner_spell(vec![], vec![Value::Spell(Rc::new(nested))]);
+        }
+        let mut compiled = Compiler::new(scroll("nested", "chant 1;"), options(), None)
+            .compile_to_bytecode()
+            .expect("compiles");
+        compiled.constants.push(Value::Spell(Rc::new(nested)));
+        let err = EiraVM::init(compiled).start().unwrap_err();
+        assert!(err.message.contains("nested deeper than 128 spells"));
+    }
+
+    #[test]
+    fn long_chains_compile() {
+        let sum = format!("chant 1{};", " + 1".repeat(199));
+        let mut diverts = "mark x = 99;\nfate x == 0 { chant 0; }\n".to_string();
+        for i in 1..100 {
+            diverts += &format!("divert fate x == {} {{ chant {}; }}\n", i, i);
+        }
+        diverts += "divert { chant \"none\"; }";
+
+        for (src, chanted) in [(sum, "200\n"), (diverts, "99\n")] {
+            let captured = Captured::default();
+            let mut vm = EiraVM::new();
+            vm.set_output(captured.clone());
+            compile(&src).expect("compiles").run(&mut vm).expect("runs");
+            assert_eq!(String::from_utf8(captured.0.take()).unwrap(), chanted);
+        }
+    }
+
+    #[test]
+    fn jumps_over_signs_and_decks() {
+        let src = "sign Point {\n    x: Num,\n}\nfate 1 > 2 {\n    bind d: Deck<Num, 2> = [1, 2];\n    bind p = ~Point with { x: 3 };\n    chant d[0] + p.x;\n} divert {\n    chant \"skipped\";\n}\nchant \"after\";";
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        assert_eq!(String::from_utf8(captured.0.take()).unwrap(), "skipped\nafter\n");
+    }
+
+    #[test]
+    fn signs_are_drawn_with_their_defaults() {
+        let src = r#"
+sign Potion {
+    name: Text,
+    strength: Num = 1,
+    price: Int = -3,
+}
+tome Flask {
+    size: Num = 2,
+}
+tome Vial refers Flask {
+    glass: Text = "clear",
+}
+bind a = ~Potion with { "Mist" };
+bind b = ~Potion with { "Ember", 4 };
+bind c = ~Potion with { "Frost", price: 7 };
+bind v = ~Vial;
+chant a.name + " " + a.strength + " " + a.price;
+chant b.strength + " " + b.price;
+chant c.strength + " " + c.price;
+chant v.size + " " + v.glass;
+"#;
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        assert_eq!(
+            String::from_utf8(captured.0.take()).unwrap(),
+            "Mist 1 -3\n4 -3\n1 7\n2 clear\n"
+        );
+    }
+
+    #[test]
+    fn marks_and_casts_chain_through_materials() {
+        let src = r#"
+tome Node {
+    value: Num,
+    spell grow(by: Num) :: Node {
+        release ~Node with { ego.value + by };
+    }
+}
+sign Pair { left: Node, right: Node }
+sign Bag { nodes: Deck<Node> }
+bind p = ~Pair with { ~Node with { 1 }, ~Node with { 2 } };
+bind b = ~Bag with { [p.left, p.right] };
+p.right.value = 5;
+b.nodes[0].value = 3;
+chant p.left.value + " " + b.nodes[1].value;
+chant (cast p.left.grow with 3).value;
+chant (cast (cast b.nodes[1].grow with 1).grow with 2).value;
+"#;
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        assert_eq!(String::from_utf8(captured.0.take()).unwrap(), "3 5\n6\n8\n");
+    }
+
+    #[test]
+    fn spells_can_be_called_by_the_host() {
+        let src = "mark calls = 0;
+spell fib(n: Num):: Num {
+    fate n < 2 {
+        release n;
+    }
+    release (cast fib with n - 1) + (cast fib with n - 2);
+}
+spell greet(name: Text):: Text {
+    calls = calls + 1;
+    release \"hello \" + name;
+}
+spell split(mana: Num, mages: Num):: Num {
+    attempt {
+        release mana / mages;
+    } mend (e) {
+        release 0;
+    }
+}
+spell crumble() {
+    bind d: Deck<Num> = [1];
+    chant d[3];
+}";
+        let mut vm = EiraVM::new();
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+        let registers = vm.stack.len();
+
+        assert_eq!(vm.call_spell("fib", &[Value::Number(10.0)]), Ok(Value::Number(55.0)));
+        let greeting = vm.call_spell("greet", &[Value::String(Rc::new("eira".to_string()))]);
+        assert_eq!(greeting, Ok(Value::String(Rc::new("hello eira".to_string()))));
+        assert_eq!(vm.call_spell("split", &[Value::Number(12.0), Value::Number(0.0)]), Ok(Value::Number(0.0)));
+
+        assert!(vm.call_spell("crumble", &[]).is_err());
+        assert!(vm.call_spell("fib", &[]).is_err());
+        assert!(vm.call_spell("calls", &[]).is_err());
+        assert!(vm.call_spell("missing", &[]).is_err());
+
+        // a breakdown leaves the VM as it was, ready for the next call
+        assert_eq!(vm.call_spell("fib", &[Value::Number(6.0)]), Ok(Value::Number(8.0)));
+        assert_eq!(vm.stack.len(), registers);
+    }
+
+    #[test]
+    fn globals_are_shared_with_the_host() {
+        let src = "mark total = 0;\ncycle i from 0 to rounds {\n    total = total + step;\n}\nstep = 0;";
+        let mut compiler = Compiler::new(scroll("host_globals", src), options(), None);
+        compiler.declare_global("rounds", Weave::Num);
+        compiler.declare_global("step", Weave::Num);
+        let compiled = compiler.compile_to_bytecode().expect("compiles");
+
+        let mut vm = EiraVM::new();
+        vm.set_global("rounds", Value::Number(4.0)).expect("sets");
+        vm.set_global("step", Value::Number(2.5)).expect("sets");
+        vm.load(compiled.clone());
+        vm.start().expect("runs");
+        assert_eq!(vm.get_global("total"), Some(&Value::Number(10.0)));
+        assert_eq!(vm.get_global("step"), Some(&Value::Number(0.0)));
+
+        // set after loading, for the next start
+        vm.set_global("step", Value::Number(1.0)).expect("sets");
+        vm.load(compiled.clone());
+        vm.start().expect("runs");
+        assert_eq!(vm.get_global("total"), Some(&Value::Number(4.0)));
+        assert_eq!(vm.get_global("missing"), None);
+
+        // the weave of a host global is checked like any other mark
+        let mut compiler = Compiler::new(scroll("host_globals_weave", "rounds = \"many\";"), options(), None);
+        compiler.declare_global("rounds", Weave::Num);
+        assert!(compiler.compile_to_bytecode().is_err());
+
+        // a global left without a value breaks down when read
+        let mut vm = EiraVM::init(compiled);
+        assert!(vm.start().is_err());
+    }
+
+    #[test]
+    fn shared_programs_run_on_many_threads() {
+        let src = "spell fib(n: Num):: Num {\n    fate n < 2 {\n        release n;\n    }\n    release (cast fib with n - 1) + (cast fib with n - 2);\n}\ncast fib with 15;";
+        let shared = compile(src).expect("compiles").share().expect("packs");
+
+        let released: Vec<f64> = std::thread::scope(|scope| {
+            let runs: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let program = shared.unpack().expect("unpacks");
+                        match program.run(&mut EiraVM::new()) {
+                            Ok(Value::Number(n)) => n,
+                            other => panic!("released {:?}", other),
+                        }
+                    })
+                })
+                .collect();
+            runs.into_iter().map(|run| run.join().unwrap()).collect()
+        });
+        assert_eq!(released, vec![610.0; 4]);
+
+        // moved to another thread rather than shared
+        let moved = std::thread::spawn(move || shared.unpack().is_ok());
+        assert!(moved.join().unwrap());
+    }
+
+    #[test]
+    fn compiled_programs_release_their_last_expression() {
+        let src = "mark a = 20;\nspell twice(n: Num):: Num { release n * 2; }\n(cast twice with a) + 2;";
+        let program = compile(src).expect("compiles");
+        let mut vm = EiraVM::new();
+        assert_eq!(program.run(&mut vm), Ok(Value::Number(42.0)));
+
+        let program = compile("mark a = 1;").expect("compiles");
+        assert_eq!(program.run(&mut vm), Ok(Value::Emptiness));
+    }
+
+    #[test]
+    fn compiled_programs_report_runtime_errors() {
+        assert!(compile("mark a = ;").is_err());
+
+        let program = compile("spell boom() {\n    bind d: Deck<Num, 1> = [1];\n    d[3] = 2;\n}\ncast boom;").expect("compiles");
+        let err = program.run(&mut EiraVM::new()).expect_err("breaks down");
+        assert_eq!(err.line, 3);
+        assert_eq!(err.trace[0].0, "boom");
+    }
+
+    #[test]
+    fn start_releases_the_scroll_value() {
+        let src = "spell greet(name: Text):: Text { release \"hi \" + name; }\ncast greet with \"mage\";";
+        let compiled = Compiler::new(scroll("scroll_value", src), options(), None)
+            .compile_to_bytecode()
+            .expect("compiles");
+
+        let mut vm = EiraVM::init(compiled);
+        assert_eq!(vm.start(), Ok(Value::String("hi mage".to_string().into())));
+    }
+
+    #[test]
+    fn chants_go_to_the_output() {
+        let program = compile("chant 1 + 1;\nchant \"mage\";\nchant 2 > 3;").expect("compiles");
+        let captured = Captured::default();
+
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        program.run(&mut vm).expect("runs");
+
+        assert_eq!(String::from_utf8(captured.0.take()).unwrap(), "2\nmage\nfalse\n");
+    }
+
+    #[test]
+    fn numbers_are_chanted_alike_everywhere() {
+        let src = "bind third = 10 / 3;
+chant 3.0, \" \", 0.1 + 0.2, \" \", 0 - 0.0, \" \", 1000000000000 * 1000000000, \" \", 0.0000015;
+chant \"a third is @(third), or @(cast format with third, 2)\";
+chant (cast format with 2.675, 1), \" \", (cast format with 0 - 0.001, 2), \" \", (cast format with 7, 0);
+chant \"@(true) and @(cast as_int with 4.2)\";
+cast format with 1, 0.5;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        let err = compile(src).expect("compiles").run(&mut vm).expect_err("takes whole decimals");
+        assert!(err.message.contains("A Num is formatted with 0 up to 100 decimals, got 0.5"));
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(
+            output,
+            "3 0.30000000000000004 0 1e21 0.0000015\na third is 3.3333333333333335, or 3.33\n2.7 0.00 7\ntrue and 4\n"
+        );
+    }
+
+    #[test]
+    fn decks_are_chanted_by_their_elements() {
+        let src = "bind xs = [1.5, 2.0, 0.1 + 0.2];\nbind nested = [[\"a\"], [\"b\", \"c\"]];\nchant xs;\nchant \"xs: @(xs) @(nested)\";";
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        assert_eq!(
+            String::from_utf8(captured.0.take()).unwrap(),
+            "[1.5, 2, 0.30000000000000004]\nxs: [1.5, 2, 0.30000000000000004] [[a], [b, c]]\n"
+        );
+
+        // a deck held within itself comes round again
+        let deck = Rc::new(DeckObject::new(vec![Value::Number(1.0)], None));
+        deck.items.borrow_mut().push(Value::Deck(deck.clone()));
+        assert_eq!(stringify_value(&Value::Deck(deck.clone())), "[1, [...]]");
+        deck.items.borrow_mut().clear();
+    }
+
+    #[test]
+    fn values_join_texts_as_they_are_chanted() {
+        let src = "bind count = 3;\nchant \"count: \" + count, \" \", 1.5 + \"x\", \" \", \"ok? \" + (count > 2);";
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        assert_eq!(String::from_utf8(captured.0.take()).unwrap(), "count: 3 1.5x ok? true\n");
+    }
+
+    #[test]
+    fn temporaries_reuse_registers() {
+        // every statement needs a few temporaries, far more than 256 of them all together
+        let mut src = String::from("mark total = 0;\n");
+        for i in 0..300 {
+            src.push_str(&format!("mark v{} = (total + {}) * 2 - total;\n", i, i));
+            src.push_str(&format!("chant v{} - (total * 1);\n", i));
+        }
+        src.push_str("fate v299 == 598 { chant \"done\"; }\n");
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(&src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert!(output.ends_with("596\n598\ndone\n"));
+    }
+
+    #[test]
+    fn expression_statements_free_their_registers() {
+        let mut src = String::from("spell count():: Num {\n    mark total = 0;\n");
+        for _ in 0..1000 {
+            src.push_str("    total = total + 1;\n");
+        }
+        src.push_str("    release total;\n}\ncast count;");
+
+        let program = compile(&src).expect("compiles");
+        assert_eq!(program.run(&mut EiraVM::new()), Ok(Value::Number(1000.0)));
+
+        let spell = program
+            .code
+            .constants
+            .iter()
+            .find_map(|c| match c {
+                Value::Closure(c) => Some(c.spell.clone()),
+                _ => None,
+            })
+            .expect("spell constant");
+        assert!(spell.register_count < 8);
+    }
+
+    #[test]
+    fn negated_comparisons_are_fused() {
+        let src = "mark total = 0;
+cycle i from 0 to 6 {
+    fate i >= 2 {
+        total = total + i;
+    }
+}
+chant total;
+chant total <= 14;
+chant total != 14;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        let program = compile(src).expect("compiles");
+        program.run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "14\ntrue\nfalse\n");
+
+        let instructions = &program.code.instructions;
+        assert!(!instructions.iter().any(|i| matches!(i, Instruction::Not { .. })));
+        assert!(instructions.iter().any(|i| matches!(i, Instruction::GreaterEqual { .. })));
+        assert!(instructions.iter().any(|i| matches!(i, Instruction::LessEqual { .. })));
+        assert!(instructions.iter().any(|i| matches!(i, Instruction::NotEqual { .. })));
+    }
+
+    #[test]
+    fn counters_step_by_their_constant() {
+        let src = "mark total = 0;
+{
+    mark i = 0;
+    mark j = 10;
+    while i < 4 {
+        i = 1 + i;
+        j = j - 2;
+        total = total + 1;
+    }
+    chant i;
+    chant j;
+}
+chant total;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        let program = compile(src).expect("compiles");
+        program.run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "4\n2\n4\n");
+
+        let instructions = &program.code.instructions;
+        assert!(!instructions.iter().any(|i| matches!(i, Instruction::Add { .. } | Instruction::Subtract { .. })));
+        assert!(instructions.iter().any(|i| matches!(i, Instruction::AddConst { .. })));
+        assert!(instructions.iter().any(|i| matches!(i, Instruction::SubtractConst { .. })));
+    }
+
+    #[test]
+    fn seals_are_inlined_where_read() {
+        let src = "seal PI = 3;
+seal TURN: Num = -PI;
+spell scaled(n: Num, by: Num = PI):: Num { release n * by; }
+chant cast scaled with 2;
+chant TURN;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        let program = compile(src).expect("compiles");
+        program.run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "6\n-3\n");
+        assert!(!program.code.globals.iter().any(|g| g == "PI" || g == "TURN"));
+    }
+
+    #[test]
+    fn nested_and_sibling_blocks_keep_their_locals_apart() {
+        let src = "{
+    mark a = 1;
+    {
+        mark b = 2;
+        chant a + b;
+    }
+    {
+        mark c = 3;
+        mark a = 10;
+        chant a + c;
+    }
+    cycle i from 0 to 2 {
+        mark d = i * 100;
+        chant a + d;
+    }
+    chant a;
+}";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "3\n13\n1\n101\n1\n");
+    }
+
+    #[test]
+    fn texts_are_ordered_letter_by_letter() {
+        let src = "chant \"apple\" < \"banana\";
+chant \"b\" > \"abc\";
+chant \"mage\" <= \"mage\";
+chant \"Zed\" >= \"ant\";";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "true\ntrue\ntrue\nfalse\n");
+        assert!(compile("chant 1 < \"one\";").is_err());
+    }
+
+    #[test]
+    fn emptiness_equals_itself() {
+        let src = "mark a: Maybe<Num>;\nmark b: Maybe<Num>;\nchant a == b;\nb = 1;\nchant a == b;\nchant a != b;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "true\nfalse\ntrue\n");
+        assert!(Value::Emptiness.equals(&Value::Emptiness));
+        assert!(!Value::Number(0.0).equals(&Value::Bool(false)));
+    }
+
+    #[test]
+    fn empty_truths_are_falsey() {
+        let src = "mark seen: Maybe<Truth>;
+fate seen { chant \"seen\"; } divert { chant \"unseen\"; }
+chant !seen;
+seen = true;
+chant seen ? \"seen\" : \"unseen\";";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "unseen\ntrue\nseen\n");
+        assert!(Value::Emptiness.is_falsey());
+        assert!(!Value::Number(0.0).is_falsey());
+        assert!(compile("mark n: Maybe<Num>;\nfate n { chant 1; }").is_err());
+    }
+
+    #[test]
+    fn chants_take_several_values() {
+        let src = "bind mana = 12;
+chant \"mana: \", mana, \"/\", 20;
+cycle i from 0 to 3 {
+    chant_raw i, \" \";
+}
+chant \"liftoff\";";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "mana: 12/20\n0 1 2 liftoff\n");
+    }
+
+    #[test]
+    fn flows_walk_decks_and_texts() {
+        let src = "mark total = 0;
+flow x through [1, 2, 3, 9] {
+    fate x == 2 { flow; }
+    fate x > 5 { sever; }
+    total = total + x;
+}
+chant total;
+flow letter through \"héllo\" {
+    chant_raw letter, \" \";
+}
+mark spells: Deck<Spell<Num>> = [];
+mark i = 0;
+flow row through [[10], [20, 30]] {
+    flow n through row {
+        spell get():: Num { release n; }
+        spells[i] = get;
+        i = i + 1;
+    }
+}
+flow s through spells {
+    chant_raw cast s, \" \";
+}";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "4\nh é l l o 10 20 30 ");
+        assert!(compile("flow x through 42 { chant x; }").is_err());
+    }
+
+    #[test]
+    fn texts_are_indexed_and_sliced_by_letters() {
+        let src = "bind s = \"héllo wörld\";
+chant s[1], \" \", s[6..11], \" \", cast len with s;
+bind xs = [1, 2, 3, 4];
+chant xs[1..3][1] + 1, \" \", s[3..3] == \"\";
+chant s[4..20];";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        let err = compile(src)
+            .expect("compiles")
+            .run(&mut vm)
+            .expect_err("the last slice is too long");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "é wörld 11\n4 true\n");
+        assert_eq!(
+            err.message,
+            "The slice 4..20 doesn't fit in the 11 elements sliced."
+        );
+        assert!(compile("bind n = 42;\nchant n[0..1];").is_err());
+    }
+
+    #[test]
+    fn negative_and_fractional_indices_are_out_of_bounds() {
+        for (src, letter) in [("bind s = \"abc\";\nchant s[-1];", "-1"), ("bind s = \"abc\";\nchant s[1.7];", "1.7")] {
+            let err = compile(src).expect("compiles").run(&mut EiraVM::new()).expect_err("out of bounds");
+            assert_eq!(
+                err.message,
+                format!("Index out of bounds while extracting a letter from a text. Tried to access {} while the text has 3 letters.", letter)
+            );
+        }
+        let err = compile("bind xs = [1, 2];\nchant xs[0.5];").expect("compiles").run(&mut EiraVM::new()).unwrap_err();
+        assert!(err.message.contains("Tried to access 0.5 while deck size is 2"), "{}", err.message);
+    }
+
+    #[test]
+    fn ints_stay_exact() {
+        let src = "mark big: Int = 9007199254740993;
+big = big + 2;
+bind i: Int = 7;
+chant big, \" \", i / 2, \" \", -i % 3, \" \", i < 10;
+cycle k from i to 9 { chant_raw k, \" \"; }
+chant cast as_int with -3.9;
+chant cast as_num with i;
+bind most: Int = 0x7fff_ffff_ffff_ffff;
+chant most + 1;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        let err = compile(src)
+            .expect("compiles")
+            .run(&mut vm)
+            .expect_err("the last sum overflows");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "9007199254740995 3 -1 true\n7 8 -3\n7\n");
+        assert_eq!(err.message, "The int 9223372036854775807 + 1 overflows.");
+    }
+
+    #[test]
+    fn ints_are_worked_bit_by_bit() {
+        let src = "bind flags: Int = 0b1100;
+chant flags & 0b1010, \" \", flags | 3, \" \", flags ^ 0xf, \" \", -16 >> 2;
+chant 1 + 2 << 1, \" \", flags & 4 == 4;
+bind nested: Deck<Deck<Int>> = [[1 << 62]];
+chant nested[0][0] >> 60;
+chant 1 << 64;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        let err = compile(src)
+            .expect("compiles")
+            .run(&mut vm)
+            .expect_err("an int has only 64 bits");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "8 15 3 -4\n6 true\n4\n");
+        assert_eq!(err.message, "Can't shift the bits of an int by 64 places.");
+    }
+
+    #[test]
+    fn judge_runs_the_first_arm_holding_the_value() {
+        let src = "spell name(n: Int):: Text {
+    judge n {
+        1 -> { release \"one\"; }
+        2 -> { release \"two\"; }
+        2 -> { release \"never\"; }
+        divert -> { release \"many\"; }
+    }
+}
+cycle i from 0 to 3 {
+    chant_raw cast name with cast as_int with i;
+    chant_raw \" \";
+}
+judge \"b\" {
+    \"a\" -> { chant \"alpha\"; }
+    \"b\" -> { chant \"beta\"; }
+}
+judge 3.5 {
+    1 -> { chant \"no\"; }
+}";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "many one two beta\n");
+    }
+
+    #[test]
+    fn named_loops_are_severed_and_flowed_from_inside() {
+        let src = "mark found = 0;
+cycle rows: i from 0 to 4 {
+    cycle j from 0 to 4 {
+        fate j > i { flow rows; }
+        fate i * j == 6 {
+            found = i * 10 + j;
+            sever rows;
+        }
+    }
+}
+chant found;
+mark n = 0;
+while outer: n < 10 {
+    n = n + 1;
+    flow x through [1, 2, 3] {
+        attempt {
+            fate x == 2 { flow outer; }
+            fate n == 3 { sever outer; }
+        } mend (e) { chant e; }
+        chant_raw x;
+    }
+}
+chant \" \", n;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "32\n11 3\n");
+    }
+
+    #[test]
+    fn circles_run_before_their_condition() {
+        let src = "mark n = 10;
+mark word: Text;
+circle {
+    n = n + 1;
+    word = \"round\";
+} until true;
+mark i = 0;
+circle outer: {
+    i = i + 1;
+    cycle j from 0 to 5 {
+        fate j == 1 { flow outer; }
+        fate i == 4 { sever outer; }
+    }
+} until i > 10;
+chant n, \" \", i, \" \", word;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "11 4 round\n");
+    }
+
+    #[test]
+    fn spells_are_cast_before_their_declaration() {
+        let src = "chant cast is_even with 10;
+spell is_even(n: Num):: Truth {
+    fate n == 0 { release true; }
+    release cast is_odd with n - 1;
+}
+spell is_odd(n: Num):: Truth {
+    fate n == 0 { release false; }
+    release cast is_even with n - 1;
+}
+chant cast is_odd with 4;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "true\nfalse\n");
+    }
+
+    #[test]
+    fn spells_of_a_block_cast_each_other() {
+        let src = "spell collatz(n: Int):: Int {
+    mark steps: Int = 0;
+    spell step(n: Int):: Int {
+        steps = steps + 1;
+        fate n == 1 { release steps; }
+        fate n % 2 == 0 { release cast halve with n; }
+        release cast triple with n;
+    }
+    spell halve(n: Int):: Int { release cast step with n / 2; }
+    spell triple(n: Int):: Int { release cast step with n * 3 + 1; }
+    release cast step with n;
+}
+chant cast collatz with 6;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "9\n");
+    }
+
+    #[test]
+    fn shadowed_marks_come_back_after_their_block() {
+        let src = "mark a = 1;
+{
+    mark a = \"inner\";
+    chant a;
+}
+cycle i from 0 to 2 {
+    mark i = i * 10;
+    mark a = i + a;
+    chant a;
+}
+attempt { invoke doom \"broke\"; } mend (e) {
+    mark e = \"mended\";
+    chant e;
+}
+chant a;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "inner\n1\n11\nmended\n1\n");
+    }
+
+    #[test]
+    fn gathered_reagents_take_the_rest_of_the_cast() {
+        let src = "spell log(level: Text, parts: ...Text) {
+    chant level, \" \", cast len with parts;
+    flow part through parts { chant part; }
+}
+cast log with \"quiet\";
+cast log with \"loud\", \"a\", \"b\";";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "quiet 0\nloud 2\na\nb\n");
+    }
+
+    #[test]
+    fn reagents_are_given_by_their_names() {
+        let src = "spell brew(potency: Num, color: Text = \"clear\", size: Num = 1) {
+    chant potency, \" \", color, \" \", size;
+}
+cast brew with potency: 3, color: \"red\";
+cast brew with 2, size: 5;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "3 red 1\n2 clear 5\n");
+    }
+
+    #[test]
+    fn cycles_of_spells_are_collected() {
+        // the spells of every round capture each other, which Rc alone never frees
+        let src = "mark total: Int = 0;
+cycle i from 0 to 10 {
+    spell ping(n: Int):: Int {
+        fate n == 0 { release 0; }
+        release cast pong with n - 1;
+    }
+    spell pong(n: Int):: Int {
+        fate n == 0 { release 1; }
+        release cast ping with n - 1;
+    }
+    total = total + cast ping with 3;
+}
+chant total;";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        vm.set_gc_stress(true);
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+        vm.collect_garbage();
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "10\n");
+        let stats = vm.gc_stats();
+        assert_eq!(stats.freed, 40); // two closures and the two cells holding them, every round
+        assert_eq!(stats.candidates, 0);
+    }
+
+    #[test]
+    fn heed_reads_the_lines_given() {
+        let src = "bind name = heed;
+bind mana = cast to_num with heed;
+chant name, \": \", mana! * 2;
+chant cast ask with \"again? \";
+chant heed == \"\";";
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        vm.set_input(std::io::Cursor::new("eira\r\n21\n yes \n"));
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "eira: 42\nagain? yes\ntrue\n");
+    }
+
+    #[test]
+    fn attempts_mend_what_breaks_down() {
+        let src = "bind d: Deck<Num> = [1, 2];
+spell split(mana: Num, mages: Num):: Num {
+    release mana / mages;
+}
+mark total = 0;
+cycle i from 0 to 5 {
+    attempt {
+        fate i == 3 { sever; }
+        total = total + cast split with 12, i;
+    } mend (e) {
+        chant e.message;
+    }
+}
+attempt {
+    chant d[4];
+} mend (e) {
+    chant total;
+}";
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let output = String::from_utf8(captured.0.take()).unwrap();
+        assert_eq!(output, "Tried to divide by zero, 12 / 0\n18\n");
+
+        // once the attempt has ended, the breakdowns are no longer mended
+        let src = "bind d: Deck<Num> = [1];\nattempt { chant d[0]; } mend (e) { chant e; }\nchant d[1];";
+        let err = compile(src).expect("compiles").run(&mut vm).expect_err("breaks down");
+        assert_eq!(err.line, 3);
+    }
+
+    #[test]
+    fn dooms_unwind_until_mended() {
+        let src = "spell withdraw(mana: Num, cost: Num):: Num {
+    fate cost > mana {
+        invoke doom \"Not enough mana\";
+    }
+    release mana - cost;
+}
+spell spend():: Num {
+    release cast withdraw with 1, 5;
+}
+attempt {
+    chant cast spend;
+} mend (e) {
+    chant e.message;
+}
+cast spend;";
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        let err = compile(src).expect("compiles").run(&mut vm).expect_err("the last doom isn't mended");
+
+        assert_eq!(String::from_utf8(captured.0.take()).unwrap(), "Not enough mana\n");
+        assert_eq!(err.message, "Not enough mana");
+        assert_eq!(err.line, 3);
+        let casts: Vec<&str> = err.trace.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(casts, ["withdraw", "spend", "<origin>"]);
+    }
+
+    #[test]
+    fn unmet_expectations_break_down() {
+        let src = "expect 2 > 1, \"never chanted\";\nattempt {\n    expect 1 > 2, \"one isn't more than two\";\n} mend (e) {\n    chant e;\n}\nexpect false;";
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        let err = compile(src).expect("compiles").run(&mut vm).expect_err("the last one isn't met");
+
+        assert_eq!(String::from_utf8(captured.0.take()).unwrap(), "one isn't more than two\n");
+        assert_eq!(err.message, "The expectation was not met");
+        assert_eq!(err.line, 7);
+        assert!(compile("expect 3;").is_err());
+    }
+
+    #[test]
+    fn step_hooks_see_every_instruction() {
+        let src = "spell twice(n: Num):: Num {\n    release n * 2;\n}\nchant cast twice with 3;";
+        let steps = Rc::new(RefCell::new(vec![]));
+        let seen = steps.clone();
+
+        let mut vm = EiraVM::new();
+        vm.set_output(Captured::default());
+        vm.set_step_hook(move |state| {
+            let casts: Vec<&str> = state.frames.iter().map(|(name, _)| name.as_str()).collect();
+            seen.borrow_mut().push(format!("{} {} {}", casts.join("<"), state.line, state.instruction.to_string()));
+        });
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        let steps = steps.take();
+        assert!(steps.first().unwrap().starts_with("<origin> 1 CONSTANT"));
+        assert!(steps.contains(&"twice<<origin> 2 MULTIPLY 1 0 1".to_string()));
+        assert!(steps.last().unwrap().starts_with("<origin> 4 HALT"));
+    }
+
+    #[test]
+    fn debuggers_pause_on_breakpoints_and_steps() {
+        let src = "spell twice(n: Num):: Num {\n    mark d = n * 2;\n    release d;\n}\nmark total = 0;\ncycle i from 0 to 2 {\n    total = total + cast twice with i;\n}";
+        let pauses = Rc::new(RefCell::new(vec![]));
+        let commands = vec![DebugCommand::Continue, DebugCommand::StepOver, DebugCommand::Continue, DebugCommand::Continue];
+
+        let mut vm = EiraVM::new();
+        vm.attach_debugger(Debugger::new(Scripted { commands, pauses: pauses.clone() }));
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+
+        assert_eq!(
+            pauses.take(),
+            vec!["<origin> 1 ", "twice 3 n=0 d=0", "<origin> 7 i=0", "twice 3 n=1 d=2"]
+        );
+    }
+
+    #[test]
+    fn runtime_errors_point_at_their_column() {
+        let src = "mark a = 5;\nchant a % (0);";
+        let compiled = Compiler::new(scroll("columns", src), options(), None)
+            .compile_to_bytecode()
+            .expect("compiles");
+        assert!(disassemble_code(&compiled).contains("      ; 2:9\n"));
+
+        let err = EiraVM::init(compiled).start().expect_err("divides by zero");
+        assert_eq!((err.line, err.column), (2, 9));
+        assert_eq!(err.to_string().lines().next(), Some("Error: Tried to divide by zero, 5 % 0 at line 2, column 9"));
+
+        // without the debug info, only the line is known
+        let stripped = CompilerOptions { debug_info: false, ..options() };
+        let compiled = Compiler::new(scroll("columns", src), stripped, None)
+            .compile_to_bytecode()
+            .expect("compiles");
+        assert!(compiled.debug.is_none());
+        let err = EiraVM::init(compiled).start().expect_err("divides by zero");
+        assert_eq!((err.line, err.column), (2, 0));
+    }
+
+    #[test]
+    fn disassembled_scrolls_assemble_back() {
+        let src = "spell twice(n: Num):: Num { release n * 2; }\nmark i = 0;\nwhile i < 3 {\n    chant cast twice with i;\n    i = i + 1;\n}";
+        let compiled = Compiler::new(scroll("disassemble", src), options(), None)
+            .compile_to_bytecode()
+            .expect("compiles");
+
+        let listing = disassemble(&compiled.bytecode, &compiled.constants);
+        assert!(listing.starts_with("== <origin> ==\n0000  CONSTANT 0 0"));
+        assert!(listing.contains("; Spell 'twice'"));
+        assert!(listing.contains("\nL0:\n") && listing.contains("LOOP L0"));
+        assert!(listing.contains("\n== twice ==\n"));
+
+        assert_eq!(assemble(&listing), Ok(compiled.bytecode));
+        assert!(assemble("JUMP L9").is_err());
+    }
+
+    #[test]
+    fn hand_assembled_bytecode_runs() {
+        let listing = "
+            CONSTANT 0 0      ; 0
+        L0:
+            CONSTANT 1 1      ; 3
+            LESS 2 0 1
+            JUMPIFFALSE 2 L1
+            PRINT 0
+            ADDCONST 0 0 2    ; 1
+            LOOP L0
+        L1:
+            HALT 0";
+        let compiled = CompiledCode {
+            bytecode: assemble(listing).expect("assembles"),
+            instructions: vec![],
+            constants: vec![Value::Number(0.0), Value::Number(3.0), Value::Number(1.0)],
+            lines: vec![],
+            register_count: 3,
+            debug: None,
+            globals: vec![],
+            symbols: vec![],
+            warnings: vec![],
+        };
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::init(compiled);
+        vm.set_output(captured.clone());
+        assert_eq!(vm.start(), Ok(Value::Number(3.0)));
+        assert_eq!(String::from_utf8(captured.0.take()).unwrap(), "0\n1\n2\n");
+    }
+
+    #[test]
+    fn seeded_rolls_repeat() {
+        let src = "cycle i from 0 to 20 {\n    bind roll = cast fate_roll with 6;\n    chant_raw roll, \" \";\n}\nbind seconds = cast clock;\nchant seconds >= 0;";
+        let program = compile(src).expect("compiles");
+        let roll = |seed: u64| {
+            let captured = Captured::default();
+            let mut vm = EiraVM::new();
+            vm.set_output(captured.clone());
+            vm.set_seed(seed);
+            program.run(&mut vm).expect("runs");
+            String::from_utf8(captured.0.take()).unwrap()
+        };
+
+        let first = roll(7);
+        assert_eq!(first, roll(7));
+        assert_ne!(first, roll(8));
+        assert!(first.ends_with("true\n"));
+        let rolls = first.split_whitespace().filter_map(|r| r.parse::<f64>().ok());
+        assert!(rolls.clone().count() == 20 && rolls.clone().all(|r| (1.0..=6.0).contains(&r)));
+    }
+
+    #[test]
+    fn globals_are_addressed_by_slot() {
+        let src = "mark total = 0;\nspell add(n: Num) { total = total + n; }\ncycle i from 0 to 4 { cast add with i; }\ntotal;";
+        let program = compile(src).expect("compiles");
+        assert_eq!(program.run(&mut EiraVM::new()), Ok(Value::Number(6.0)));
+
+        assert_eq!(program.code.globals, vec!["total".to_string(), "add".to_string()]);
+        // the names aren't needed in the constants to reach the globals
+        assert!(!program.code.constants.contains(&Value::String("total".to_string().into())));
+    }
+
+    #[test]
+    fn values_stay_compact() {
+        // every register, move and clone pays for the size of a value
+        assert!(std::mem::size_of::<Value>() <= 16);
+    }
+
+    #[test]
+    fn precompiled_tomes_find_their_attunements() {
+        let src = r#"
+            tome Creature {
+                hp: Num,
+                spell heal(by: Num) :: Num {
+                    ego.hp = ego.hp + by;
+                    release ego.hp;
+                }
+                spell cry() { cast record with "..."; }
+            }
+            tome Dragon refers Creature {
+                spell cry() { cast record with "roar"; }
+            }
+            bind d = ~Dragon with { hp: 10 };
+            bind c = ~Creature with { hp: 1 };
+            cast d.cry;
+            cast c.cry;
+            cast record with cast d.heal with 5;
+        "#;
+        let mut compiler = Compiler::new(scroll("precompiled_tomes", src), options(), None);
+        compiler.declare_native("record", vec![Weave::Empty], Weave::Empty);
+        let compiled = compiler.compile_to_bytecode().expect("compiles");
+        let bytes = bytecode_file::serialize(&compiled).expect("serializes");
+
+        let mut vm = EiraVM::init(bytecode_file::deserialize(&bytes).expect("deserializes"));
+        vm.register_native("record", 1, record);
+        assert!(vm.start().is_ok());
+
+        let recorded: Vec<Value> = RECORDED.with(|r| r.borrow_mut().drain(..).collect());
+        assert_eq!(
+            recorded,
+            vec![
+                Value::String("roar".to_string().into()),
+                Value::String("...".to_string().into()),
+                Value::Number(15.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn equal_texts_share_one_constant() {
+        let src = "spell a():: Text { release \"mage\"; }\nspell b():: Text { release \"mage\"; }\n(cast a) == (cast b);";
+        let program = compile(src).expect("compiles");
+        assert_eq!(program.run(&mut EiraVM::new()), Ok(Value::Bool(true)));
+
+        let texts: Vec<_> = program
+            .code
+            .constants
+            .iter()
+            .filter_map(|c| match c {
+                Value::Closure(c) => c.spell.constants.iter().find_map(|c| match c {
+                    Value::String(s) => Some(s.clone()),
+                    _ => None,
+                }),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts.len(), 2);
+        assert!(Rc::ptr_eq(&texts[0], &texts[1]));
+    }
+
+    #[test]
+    fn closures_share_their_captured_variables() {
+        let src = r#"
+            spell counter():: Spell<Num> {
+                mark count = 0;
+                spell inc():: Num {
+                    count = count + 1;
+                    release count;
+                }
+                spell peek():: Num {
+                    spell inner():: Num { release count; }
+                    release cast inner;
+                }
+                count = 10;
+                cast record with cast peek;
+                release inc;
+            }
+
+            bind a = cast counter;
+            bind b = cast counter;
+            cast record with cast a;
+            cast record with cast a;
+            cast record with cast b;
+        "#;
+        let recorded = run_recorded("closure_cells", src);
+        let expected = [10.0, 10.0, 11.0, 12.0, 11.0].map(Value::Number);
+        assert_eq!(recorded, expected);
+    }
+
+    #[test]
+    fn closures_made_in_loops_keep_their_own_locals() {
+        let src = r#"
+            spell zero():: Num { release 0; }
+            mark first = zero;
+            mark second = zero;
+            cycle i from 0 to 4 {
+                mark v = i * 10;
+                spell get():: Num { release v; }
+                fate i == 1 { first = get; flow; }
+                fate i == 2 { second = get; sever; }
+            }
+            cast record with cast first;
+            cast record with cast second;
+        "#;
+        let recorded = run_recorded("closure_loops", src);
+        assert_eq!(recorded, vec![Value::Number(10.0), Value::Number(20.0)]);
+    }
+
+    #[test]
+    fn spells_nest_at_any_depth() {
+        let src = r#"
+            spell helper():: Num { release 1; }
+            spell a(x: Num):: Num {
+                mark local = 100;
+                spell helper():: Num { release 2; }
+                spell b(y: Num):: Num {
+                    spell c(z: Num):: Num {
+                        fate z < 1 { release x + y + (cast helper); }
+                        release cast c with z - 1;
+                    }
+                    release cast c with 3;
+                }
+                release (cast b with 20) + local;
+            }
+            cast record with cast a with 10;
+            cast record with cast helper;
+        "#;
+        let recorded = run_recorded("nested_spells", src);
+        assert_eq!(recorded, vec![Value::Number(132.0), Value::Number(1.0)]);
+    }
+
+    #[test]
+    fn runaway_recursion_overflows_the_realm() {
+        let src = "spell down(n: Num):: Num {\n    release (cast down with n + 1) + 1;\n}\ncast down with 0;";
+        let program = compile(src).expect("compiles");
+
+        let mut vm = EiraVM::new();
+        vm.set_max_frames(64);
+        let err = program.run(&mut vm).expect_err("overflows");
+        assert!(err.message.starts_with("Realm overflow!"));
+        assert_eq!(err.trace.len(), 64);
+        assert!(err.to_string().contains("repeated 62 more times"));
+
+        let mut vm = EiraVM::new();
+        vm.set_max_stack(100);
+        let err = program.run(&mut vm).expect_err("overflows");
+        assert!(err.message.starts_with("Realm overflow!"));
+    }
+
+    #[test]
+    fn endless_scrolls_run_out_of_their_limits() {
+        let endless = compile("mark i = 0;\nwhile true {\n    attempt {\n        i = i + 1;\n    } mend (e) {\n        chant e;\n    }\n}").expect("compiles");
+
+        let mut vm = EiraVM::new();
+        vm.set_fuel(Some(10_000));
+        let err = endless.run(&mut vm).expect_err("runs out of fuel");
+        assert_eq!(err.kind, ErrorKind::OutOfFuel);
+
+        let mut vm = EiraVM::new();
+        vm.set_time_limit(Some(Duration::from_millis(50)));
+        let err = endless.run(&mut vm).expect_err("runs out of time");
+        assert_eq!(err.kind, ErrorKind::OutOfTime);
+
+        // the fuel is counted exactly, and anew on every start
+        let short = compile("mark a = 1;\nchant a + 1;").expect("compiles");
+        let steps = Rc::new(RefCell::new(0));
+        let mut vm = EiraVM::new();
+        vm.set_output(Captured::default());
+        {
+            let steps = steps.clone();
+            vm.set_step_hook(move |_| *steps.borrow_mut() += 1);
+        }
+        short.run(&mut vm).expect("runs");
+        let steps = *steps.borrow();
+
+        let mut vm = EiraVM::new();
+        vm.set_output(Captured::default());
+        vm.set_fuel(Some(steps - 1));
+        assert_eq!(short.run(&mut vm).expect_err("runs out of fuel").kind, ErrorKind::OutOfFuel);
+        vm.set_fuel(Some(steps));
+        assert!(short.run(&mut vm).is_ok());
+        assert!(short.run(&mut vm).is_ok());
+    }
+
+    #[test]
+    fn conditional_expressions_run_one_arm() {
+        let src = r#"
+            spell collatz(n: Num):: Num {
+                release n % 2 == 0 ? n / 2 : n * 3 + 1;
+            }
+            mark calls = 0;
+            spell count(n: Num):: Num {
+                calls = calls + 1;
+                release n;
+            }
+            cast record with cast collatz with 6;
+            cast record with cast collatz with 5;
+            cast record with calls > 0 ? cast count with 1 : 3 < 2 ? 20 : 30;
+            cast record with true ? cast count with 4 : cast count with 5;
+            cast record with calls;
+        "#;
+        let recorded = run_recorded("conditional_expr", src);
+        let expected = [3.0, 16.0, 30.0, 4.0, 1.0].map(Value::Number);
+        assert_eq!(recorded, expected);
+    }
+
+    #[test]
+    fn left_out_reagents_take_their_defaults() {
+        let src = r#"
+            spell shift(n: Num, by: Num = -3, twice: Truth = false):: Num {
+                release twice ? n + by * 2 : n + by;
+            }
+            cast record with cast shift with 10;
+            cast record with cast shift with 10, 1;
+            cast record with cast shift with 10, 1, true;
+            mark moved = shift;
+            cast record with cast moved with 1;
+        "#;
+        let recorded = run_recorded("reagent_defaults", src);
+        let expected = [7.0, 11.0, 12.0, -2.0].map(Value::Number);
+        assert_eq!(recorded, expected);
+    }
+
+    #[test]
+    fn dividing_by_zero_breaks_down() {
+        let program = compile("mark a = 0;\nchant 1 / 2;\nchant 1 / a;").expect("compiles");
+        let err = program.run(&mut EiraVM::new()).expect_err("breaks down");
+        assert!(err.message.contains("divide by zero"));
+        assert_eq!(err.line, 3);
+
+        let program = compile("mark a = 0;\n5 % a;").expect("compiles");
+        assert!(program.run(&mut EiraVM::new()).is_err());
+    }
+
+    #[test]
+    fn flowing_spells_offer_as_they_are_drawn() {
+        let src = r#"
+            spell evens(limit: Num):: Stream<Num> {
+                cycle i from 0 to limit {
+                    fate i % 2 == 0 { offer i; }
+                }
+            }
+            spell doubled(s: Stream<Num>):: Stream<Num> {
+                flow x through s { offer x * 2; }
+            }
+            flow n through cast doubled with (cast evens with 7) {
+                cast record with n;
+            }
+
+            mark s = cast evens with 9;
+            flow n through s {
+                cast record with n;
+                fate n == 2 { sever; }
+            }
+            flow n through s { cast record with n + 100; }
+            flow n through s { cast record with -1; }
+
+            spell breaks():: Stream<Num> {
+                offer 1;
+                invoke doom "dry";
+            }
+            mark b = cast breaks;
+            attempt {
+                flow n through b { cast record with n; }
+            } mend (e) {
+                cast record with 42;
+            }
+            flow n through b { cast record with -1; }
+        "#;
+        let recorded = run_recorded("flowing_spells", src);
+        let expected = [0.0, 4.0, 8.0, 12.0, 0.0, 2.0, 104.0, 106.0, 108.0, 1.0, 42.0].map(Value::Number);
+        assert_eq!(recorded, expected);
+    }
+
+    #[test]
+    fn the_host_gets_the_stream_of_a_flowing_spell() {
+        let src = "spell letters():: Stream<Text> {\n    offer \"a\";\n}";
+        let mut vm = EiraVM::new();
+        compile(src).expect("compiles").run(&mut vm).expect("runs");
+        let stream = vm.call_spell("letters", &[]).expect("casts");
+        assert_eq!(stringify_value(&stream), "Stream 'letters'");
+    }
+
+    #[test]
+    fn conjured_spells_take_turns_until_claimed() {
+        let src = r#"
+            spell count(id: Num, rounds: Num):: Num {
+                mark total = 0;
+                cycle i from 0 to rounds {
+                    total = total + i;
+                }
+                cast record with id;
+                release total;
+            }
+            bind long = conjure cast count with 1, 5000;
+            bind short = conjure cast count with 2, 10;
+            cast record with claim long;
+            cast record with claim short;
+            cast record with claim short;
+
+            spell crumble():: Num {
+                invoke doom "crumbled";
+                release 1;
+            }
+            bind broken = conjure cast crumble;
+            attempt {
+                cast record with claim broken;
+            } mend (e) {
+                cast record with -1;
+            }
+
+            spell late() {
+                cast record with 3;
+            }
+            conjure cast late;
+        "#;
+        let recorded = run_recorded("conjured_spells", src);
+        let expected = [2.0, 1.0, 12497500.0, 45.0, 45.0, -1.0, 3.0].map(Value::Number);
+        assert_eq!(recorded, expected);
+    }
+
+    #[test]
+    fn conjured_spells_share_the_marks_they_captured() {
+        let src = "spell outer():: Num {
+    mark count = 0;
+    spell add(n: Num):: Num {
+        cycle i from 0 to n { count = count + 1; }
+        release count;
+    }
+    bind added = conjure cast add with 2000;
+    count = count + 100000;
+    claim added;
+    release count;
+}
+chant cast outer;
+mark other: Maybe<Conjuring<Num>>;
+spell first():: Num {
+    mark spin = 0;
+    cycle i from 0 to 2000 { spin = spin + 1; }
+    release claim other!;
+}
+bind a = conjure cast first;
+spell second(c: Conjuring<Num>):: Num { release claim c; }
+other = conjure cast second with a;
+claim a;";
+        let captured = Captured::default();
+        let mut vm = EiraVM::new();
+        vm.set_output(captured.clone());
+        let err = compile(src).expect("compiles").run(&mut vm).expect_err("they claim each other");
+        assert!(err.message.contains("The conjured spells are claiming each other"));
+        assert_eq!(String::from_utf8(captured.0.take()).unwrap(), "102000\n");
+    }
+
+    #[test]
+    fn spells_reach_past_65536_constants() {
+        let mut src = String::from("mark total = 0;\n");
+        for i in 0..66000 {
+            src.push_str(&format!("total = total + {i}.5;\n"));
+        }
+        src.push_str("chant total;");
+        let compiled = Compiler::new(scroll("many_constants", &src), options(), None)
+            .compile_to_bytecode()
+            .expect("compiles");
+        assert!(disassemble(&compiled.bytecode, &compiled.constants).contains("CONSTANTLONG"));
+
+        let bytes = bytecode_file::serialize(&compiled).expect("serializes");
+        let captured = Captured::default();
+        let mut vm = EiraVM::init(bytecode_file::deserialize(&bytes).expect("deserializes"));
+        vm.set_output(captured.clone());
+        assert!(vm.start().is_ok());
+        assert_eq!(String::from_utf8(captured.0.take()).unwrap(), "2178000000\n");
+    }
+
+    #[test]
+    fn spells_jump_over_long_bodies() {
+        let body: String = (0..20000).map(|i| format!("    total = total + {}.5;\n", i % 7)).collect();
+        let src = format!(
+            "mark total = 0;\nmark i = 0;\nwhile i < 2 {{\n{body}    i = i + 1;\n}}\nattempt {{\n{body}    invoke doom \"broke\";\n}} mend (e) {{ chant e; }}\nchant total;"
+        );
+        let compiled = Compiler::new(scroll("long_jumps", &src), options(), None)
+            .compile_to_bytecode()
+            .expect("compiles");
+        let listing = disassemble(&compiled.bytecode, &compiled.constants);
+        assert!(listing.contains("JUMPIFFALSELONG") && listing.contains("LOOPLONG"));
+        assert!(listing.contains("ATTEMPTLONG"));
+
+        let captured = Captured::default();
+        let mut vm = EiraVM::init(compiled);
+        vm.set_output(captured.clone());
+        assert!(vm.start().is_ok());
+        assert_eq!(String::from_utf8(captured.0.take()).unwrap(), "broke\n209991\n");
+    }
+
+    #[test]
+    fn the_assembler_lands_jumps_on_their_targets() {
+        let constant = Instruction::Constant { dest: 1, const_index: 0 };
+        let mut instructions = vec![Instruction::JumpIfFalse { condition_reg: 0, offset: 0 }, constant];
+        instructions.push(Instruction::Loop { offset: 0 });
+        let linked = Assembler::link_jumps(&instructions, &[Some(3), None, Some(0)]).expect("links");
+        assert_eq!(linked[0], Instruction::JumpIfFalse { condition_reg: 0, offset: 7 });
+        assert_eq!(linked[2], Instruction::Loop { offset: 11 });
+
+        // a jump over more than 65535 bytes takes the long form, moving the loop back further too
+        let mut instructions = vec![Instruction::JumpIfFalse { condition_reg: 0, offset: 0 }];
+        instructions.extend(std::iter::repeat_n(constant, 20000));
+        instructions.push(Instruction::Loop { offset: 0 });
+        let mut targets = vec![None; instructions.len()];
+        targets[0] = Some(instructions.len());
+        targets[20001] = Some(0);
+        let linked = Assembler::link_jumps(&instructions, &targets).expect("links");
+        assert_eq!(linked[0], Instruction::JumpIfFalseLong { condition_reg: 0, offset: 80005 });
+        assert_eq!(linked[20001], Instruction::LoopLong { offset: 80011 });
+    }
+}
@@ -4,7 +4,8 @@ mod weave_analyser_test {
         Parser, Scanner, WeaveAnalyzer,
         compiler::{
             WovenExpr, WovenStmt,
-            strand::{ADDITIVE_STRAND, CONDITIONAL_STRAND, MULTIPLICATIVE_STRAND}, weave_analyser::WeaveAnalyzerContext,
+            strand::{ADDITIVE_STRAND, CONCATINABLE_STRAND, CONDITIONAL_STRAND, MULTIPLICATIVE_STRAND}, weave_analyser::WeaveAnalyzerContext,
+            weaves::Weave,
         },
     };
 
@@ -17,15 +18,15 @@ mod weave_analyser_test {
             .map_err(|e| format!("Parse error: {:?}", e))?;
         let mut context = WeaveAnalyzerContext::new("weave_test.eira".to_string(), None, false);
         let mut wa = WeaveAnalyzer::new(&mut context);
-        wa.analyze(ast).map_err(|e| format!("{}", e.msg))
+        wa.analyze(ast).map_err(|e| e.message())
     }
 
     fn first_expr(stmts: &Vec<WovenStmt>) -> &WovenExpr {
         // Prefer the first Chant or ExprStmt found
         for stmt in stmts {
             match stmt {
-                WovenStmt::ExprStmt { expr } => return expr,
-                WovenStmt::Chant { expression } => return expression,
+                WovenStmt::ExprStmt { expr, .. } => return expr,
+                WovenStmt::Chant { expressions, .. } => return &expressions[0],
                 _ => {}
             }
         }
@@ -47,9 +48,36 @@ mod weave_analyser_test {
 
     #[test]
     fn binary_add_mismatch_error() {
-        let src = "chant 1 + \"s\";";
+        let src = "chant 1 + true;";
         let err = analyze_helper(src).err().expect("should error");
-        assert!(err.contains("Cannot perform '+' operation"));
+        assert!(err.contains("Cannot perform '+' on 'Num' and 'Truth'"));
+    }
+
+    #[test]
+    fn values_joined_to_texts_are_chanted() {
+        let stmts = analyze_helper("chant 1 + \"s\";").expect("a Num joins a Text");
+        let expr = first_expr(&stmts);
+        let WovenExpr::Binary { left, weave, .. } = expr else {
+            panic!("Expected Binary expr");
+        };
+        assert!(matches!(**left, WovenExpr::ToText { .. }));
+        assert_eq!(*weave, Weave::Text);
+
+        let err = analyze_helper("chant \"xs: \" + [1, 2];").expect_err("a Deck has no plain chant");
+        assert!(err.contains("A 'Deck<Num>' can't be joined to a Text with '+', turn it into one with 'to_text' first."));
+        analyze_helper("chant \"xs: \" + (cast to_text with [1, 2]);").expect("turned into a text first");
+    }
+
+    #[test]
+    fn binary_add_texts_concatenates() {
+        let src = "chant \"a\" + \"b\";";
+        let stmts = analyze_helper(src).expect("weave analyze ok");
+        let expr = first_expr(&stmts);
+        if let WovenExpr::Binary { weave, .. } = expr {
+            assert!(weave.get_tapestry().has_strand(CONCATINABLE_STRAND));
+        } else {
+            panic!("Expected Binary expr");
+        }
     }
 
     #[test]
@@ -79,13 +107,39 @@ mod weave_analyser_test {
         assert!(err.contains("operand does not contain the 'CONDITIONAL'"));
     }
 
+    #[test]
+    fn unary_minus_needs_negatable_strand() {
+        let err = analyze_helper("chant -\"runes\";").err().expect("should error for -text");
+        assert!(err.contains("operand does not contain the 'NEGATABLE'"));
+    }
+
+    #[test]
+    fn conditional_arms_share_a_weave() {
+        let stmts = analyze_helper("chant 1 < 2 ? \"yes\" : \"no\";").expect("analyze ok");
+        assert!(matches!(first_expr(&stmts).weave(), Weave::Text));
+
+        let err = analyze_helper("chant true ? 1 : \"one\";").expect_err("arms differ");
+        assert!(err.contains("woven differently"));
+        let err = analyze_helper("chant 1 ? 1 : 2;").expect_err("condition is not a truth");
+        assert!(err.contains("'Conditional' strand"));
+    }
+
+    #[test]
+    fn annotated_declarations_check_their_initializer() {
+        analyze_helper("mark a: Num; a = 3; chant a;").expect("uninitialized annotated mark");
+        analyze_helper("mark m: Maybe<Num> = 5;").expect("a Num fits a Maybe<Num>");
+
+        let err = analyze_helper("mark a: Num = \"three\";").expect_err("initializer is not a Num");
+        assert!(err.contains("woven as 'Num' but was given 'Text'"));
+    }
+
     #[test]
     fn var_decl_and_resolution_ok() {
         let src = "mark a = 1; chant a;";
         let stmts = analyze_helper(src).expect("analyze ok");
         // second stmt uses variable
         match &stmts[1] {
-            WovenStmt::Chant { expression } => match expression {
+            WovenStmt::Chant { expressions, .. } => match &expressions[0] {
                 WovenExpr::Variable { weave, .. } => {
                     // at least not empty
                     assert!(weave.get_tapestry().0 != 0);
@@ -107,7 +161,7 @@ mod weave_analyser_test {
     fn assignment_type_mismatch_errors() {
         let src = "mark x = 1; x = \"s\";";
         let err = analyze_helper(src).err().expect("should error");
-        assert!(err.contains("different Weaves"));
+        assert!(err.contains("The mark 'x' is woven as 'Num' and can't be given 'Text'"));
     }
 
     #[test]
@@ -166,6 +220,68 @@ mod weave_analyser_test {
         assert!(err.contains("was expected to be"));
     }
 
+    #[test]
+    fn casting_a_spell_weave_releases_its_inner_weave() {
+        let src = r#"
+            spell twice(n: Num):: Num { release n * 2; }
+            spell make():: Spell<Num> { release twice; }
+            spell apply(f: Spell<Num>, n: Num):: Num { release cast f with n; }
+            bind made = cast make;
+            chant cast made with 2;
+            chant cast apply with twice, 3;
+        "#;
+        let stmts = analyze_helper(src).expect("spell weaves cast");
+        for stmt in &stmts[4..] {
+            let WovenStmt::Chant { expressions, .. } = stmt else {
+                panic!("Expected chant");
+            };
+            assert_eq!(expressions[0].weave(), Weave::Num);
+        }
+
+        let err = analyze_helper("spell one():: Num { release 1; }\nmark t: Spell<Text> = one;")
+            .expect_err("releases differ");
+        assert!(err.contains("woven as 'Spell<Text>' but was given 'Spell<Num>'"));
+    }
+
+    #[test]
+    fn spell_marks_keep_their_reagents() {
+        let spell = "spell add(a: Num, b: Num):: Num { release a + b; }\nmark a = add;\n";
+        analyze_helper(&format!("{}chant cast a with 1, 2;", spell)).expect("matching reagents");
+
+        let err = analyze_helper(&format!("{}chant cast a with 1;", spell)).expect_err("missing reagent");
+        assert!(err.contains("expected 2 reagents"));
+        let err = analyze_helper(&format!("{}chant cast a with 1, true;", spell)).expect_err("wrong reagent");
+        assert!(err.contains("reagent #2 was expected to be Num"));
+    }
+
+    #[test]
+    fn reagent_defaults_trail_and_fit_their_weave() {
+        let err = analyze_helper("spell f(a: Num = 1, b: Num) {}").expect_err("b has no default");
+        assert!(err.contains("'b' needs a default"));
+        let err = analyze_helper("spell f(a: Num = \"one\") {}").expect_err("default is not a Num");
+        assert!(err.contains("expected to be Num, but got Text"));
+
+        let err = analyze_helper("spell f(a: Num, b: Num = 1) {}\ncast f;").expect_err("a is required");
+        assert!(err.contains("expected 1 to 2 reagents"));
+    }
+
+    #[test]
+    fn seals_hold_literals_and_stay_sealed() {
+        let err = analyze_helper("seal PI = 3.14;\nPI = 3;").expect_err("seals can't be reassigned");
+        assert!(err.contains("Seals cannot be reassigned"));
+        let err = analyze_helper("mark r = 1;\nseal R = r;").expect_err("r isn't a literal");
+        assert!(err.contains("must be given a literal value"));
+    }
+
+    #[test]
+    fn mends_are_given_an_error() {
+        let _ = analyze_helper("attempt { chant 1; } mend (e) { bind m: Text = e.message; }").expect("ok");
+        let err = analyze_helper("attempt { chant 1; } mend (e) { chant e.code; }").expect_err("no code");
+        assert!(err.contains("only carries its 'message'"));
+        let err = analyze_helper("attempt { chant 1; } mend (e) { }\nchant e;").expect_err("e is out of scope");
+        assert!(err.contains("'e'"));
+    }
+
     #[test]
     fn runtime_spell_via_variable_ok() {
         let src = r#"
@@ -199,6 +315,18 @@ mod weave_analyser_test {
         assert!(err.contains("was undefined"));
     }
 
+    #[test]
+    fn misspelt_names_get_suggestions() {
+        let err = analyze_helper("mark count = 1; chant cont;").expect_err("should error");
+        assert!(err.contains("Did you mean 'count'?"));
+
+        let err = analyze_helper("mark a: Nmu = 1;").expect_err("should error");
+        assert!(err.contains("Did you mean 'Num'?"));
+
+        let err = analyze_helper("chant nothing_alike;").expect_err("should error");
+        assert!(!err.contains("Did you mean"));
+    }
+
     #[test]
     fn release_type_mismatch_error() {
         let src = r#"
@@ -225,6 +353,13 @@ mod weave_analyser_test {
         let _ = analyze_helper(ok2).expect("divide ok");
     }
 
+    #[test]
+    fn modulo_requires_divisive_strand() {
+        let _ = analyze_helper("chant 7 % 3;").expect("modulo on nums ok");
+        let err = analyze_helper("chant \"a\" % 3;").err().expect("should error");
+        assert!(err.contains("DIVISIVE"));
+    }
+
     #[test]
     fn comparison_operators_return_truth_weave() {
         let src = "mark result = 5 > 3;";
@@ -280,4 +415,429 @@ mod weave_analyser_test {
         "#;
         let _ = analyze_helper(src).expect("upvalue capture ok");
     }
+
+    #[test]
+    fn cycle_mark_resolves_in_body() {
+        let src = r#"
+            mark total = 0;
+            cycle i from 0 to 10 {
+                fate i == 3 { flow; }
+                total = total + i;
+            }
+        "#;
+        let _ = analyze_helper(src).expect("cycle ok");
+    }
+
+    #[test]
+    fn cycle_bounds_require_ordinal_weave() {
+        let src = r#"cycle i from "a" to 10 { chant i; }"#;
+        let err = analyze_helper(src).err().expect("should error");
+        assert!(err.contains("bounds of a cycle"));
+    }
+
+    #[test]
+    fn tome_refers_inherits_marks_and_spells() {
+        let src = r#"
+            tome Creature {
+                name: Text,
+                spell describe() { chant ego.name; }
+            }
+            tome Dragon refers Creature {
+                fire: Num,
+                spell describe() {
+                    cast origin.describe;
+                    chant ego.fire;
+                }
+            }
+            spell introduce(c: Creature) { cast c.describe; }
+            bind d = ~Dragon with { name: "Ignis", fire: 3 };
+            chant d.name;
+            cast introduce with d;
+        "#;
+        let _ = analyze_helper(src).expect("tome inheritance ok");
+    }
+
+    #[test]
+    fn drawn_signs_are_given_every_mark_once() {
+        let sign = "sign Potion { name: Text, strength: Num = 1 }\n";
+        let draw = |marks: &str| analyze_helper(&format!("{}bind p = ~Potion{};", sign, marks));
+
+        assert!(draw(" with { \"Mist\" }").is_ok());
+        assert!(draw(" with { strength: 2, name: \"Mist\" }").is_ok());
+
+        let err = draw("").expect_err("name has no default");
+        assert!(err.contains("without the marks 'name'"));
+        let err = draw(" with { \"Mist\", 2, 3 }").expect_err("too many");
+        assert!(err.contains("inscribes 2 marks, but 3 were given in order"));
+        let err = draw(" with { \"Mist\", name: \"Dawn\" }").expect_err("given twice");
+        assert!(err.contains("'name' was given more than once"));
+        let err = draw(" with { 2 }").expect_err("wrong weave");
+        assert!(err.contains("expected to have weave 'Text'"));
+
+        let err = analyze_helper("sign Potion { strength: Num = \"much\" }").expect_err("bad default");
+        assert!(err.contains("woven as 'Num' but its default is a 'Text'"));
+        let err = analyze_helper("bind s = 1;\nsign Potion { strength: Num = s }").expect_err("not a literal");
+        assert!(err.contains("must be a literal value or a seal"));
+    }
+
+    #[test]
+    fn marks_are_set_through_chains_of_materials() {
+        let sign = "sign Node { value: Num }\nsign Pair { left: Node, count: Num }\n";
+        let pair = "bind p = ~Pair with { ~Node with { 1 }, 2 };\n";
+        let set = |stmt: &str| analyze_helper(&format!("{}{}{}", sign, pair, stmt));
+
+        assert!(set("p.left.value = 4;").is_ok());
+
+        let err = set("p.left.value = \"four\";").expect_err("wrong weave");
+        assert!(err.contains("'value' is woven as 'Num' but was given a 'Text'"));
+        let err = set("p.count.value = 4;").expect_err("not a material");
+        assert!(err.contains("Only the marks of a sign can be set with '.'"));
+        let err = set("p.left.weight = 4;").expect_err("no such mark");
+        assert!(err.contains("The mark 'weight' is not defined for 'Node'"));
+    }
+
+    #[test]
+    fn tome_can_only_refer_tomes() {
+        let src = r#"
+            sign Rock { weight: Num }
+            tome Golem refers Rock { }
+        "#;
+        let err = analyze_helper(src).expect_err("should error");
+        assert!(err.contains("can only refer another tome"));
+    }
+
+    #[test]
+    fn len_measures_decks_of_any_weave() {
+        let src = r#"
+            bind count: Num = cast len with [1, 2, 3];
+            chant cast len with ["a", "b"];
+        "#;
+        let _ = analyze_helper(src).expect("len on decks ok");
+
+        let err = analyze_helper("chant cast len with 5;").expect_err("should error");
+        assert!(err.contains("expected to be Deck<Empty>"));
+    }
+
+    #[test]
+    fn glyph_variants_compare_within_their_glyph() {
+        let src = r#"
+            glyph Element { Fire, Water }
+            mark e: Element = Element.Fire;
+            chant e == Element.Water;
+        "#;
+        let _ = analyze_helper(src).expect("glyph equality ok");
+
+        let src = r#"
+            glyph Element { Fire, Water }
+            glyph Mood { Calm }
+            chant Element.Fire == Mood.Calm;
+        "#;
+        let err = analyze_helper(src).expect_err("should error");
+        assert!(err.contains("Cannot compare"));
+    }
+
+    #[test]
+    fn glyph_unknown_variant_error() {
+        let src = r#"
+            glyph Element { Fire, Water }
+            chant Element.Air;
+        "#;
+        let err = analyze_helper(src).expect_err("should error");
+        assert!(err.contains("not carved"));
+    }
+
+    #[test]
+    fn prelude_conversions_are_woven() {
+        let src = r#"
+            bind root: Num = cast sqrt with 9;
+            bind text: Text = cast to_text with [1, 2];
+            chant cast to_num with "4";
+        "#;
+        let stmts = analyze_helper(src).expect("prelude spells ok");
+        let expr = first_expr(&stmts);
+        assert_eq!(expr.weave(), Weave::Maybe(Box::new(Weave::Num)));
+
+        let err = analyze_helper("chant cast to_num with 4;").expect_err("should error");
+        assert!(err.contains("expected to be Text"));
+    }
+
+    #[test]
+    fn errors_are_collected_up_to_the_limit() {
+        let src = r#"
+            mark a = 1 + true;
+            spell f(n: Num):: Num {
+                chant missing;
+                release n;
+            }
+            bind b = 2;
+            b = 3;
+            chant cast f with 2;
+        "#;
+        let analyze = |limit: usize| {
+            let ast = Parser::new(Scanner::init(src).tokenize(), "weave_test.eira".to_string())
+                .parse()
+                .expect("parses");
+            let mut context = WeaveAnalyzerContext::new("weave_test.eira".to_string(), None, false);
+            context.error_limit = limit;
+            WeaveAnalyzer::new(&mut context)
+                .analyze(ast)
+                .expect_err("should error")
+        };
+
+        let lines: Vec<usize> = analyze(20)
+            .diagnostics()
+            .iter()
+            .map(|d| d.location.as_ref().unwrap().line)
+            .collect();
+        assert_eq!(lines, vec![2, 4, 8]);
+
+        assert_eq!(analyze(2).diagnostics().len(), 2);
+    }
+
+    #[test]
+    fn literal_zero_divisor_warns() {
+        let scanner = Scanner::init("mark a = 4;\nchant a / 0;\nchant a % 2;");
+        let ast = Parser::new(scanner.tokenize(), "weave_test.eira".to_string())
+            .parse()
+            .expect("parses");
+        let mut context = WeaveAnalyzerContext::new("weave_test.eira".to_string(), None, false);
+        let mut wa = WeaveAnalyzer::new(&mut context);
+        wa.analyze(ast).expect("a zero divisor is only a warning");
+
+        let warnings = wa.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("zero"));
+        assert_eq!(warnings[0].location.as_ref().map(|l| l.line), Some(2));
+    }
+
+    #[test]
+    fn weave_errors_underline_the_whole_expression() {
+        let src = "bind a: Num = \"one\" + \"two\";\nfate 1 + 2 {\n    chant a;\n}";
+        let ast = Parser::new(Scanner::init(src).tokenize(), "weave_test.eira".to_string())
+            .parse()
+            .expect("parses");
+        let mut context = WeaveAnalyzerContext::new("weave_test.eira".to_string(), None, false);
+        let error = WeaveAnalyzer::new(&mut context)
+            .analyze(ast)
+            .expect_err("should error");
+
+        let underlined: Vec<(usize, usize)> = error
+            .diagnostics()
+            .iter()
+            .map(|d| d.location.as_ref().unwrap().span_on_line(src).unwrap())
+            .collect();
+        // (start, length) on the line: the value given to `a`, then the condition of the fate
+        assert_eq!(underlined, vec![(14, 13), (5, 5)]);
+    }
+
+    #[test]
+    fn weave_mismatches_name_both_weaves_under_the_expression() {
+        let src = "mark total = 0;\ntotal = \"a\" + \"b\";\nchant total * true;";
+        let ast = Parser::new(Scanner::init(src).tokenize(), "weave_test.eira".to_string())
+            .parse()
+            .expect("parses");
+        let mut context = WeaveAnalyzerContext::new("weave_test.eira".to_string(), None, false);
+        let error = WeaveAnalyzer::new(&mut context)
+            .analyze(ast)
+            .expect_err("should error");
+
+        let diagnostics = error.diagnostics();
+        let labels: Vec<Option<&str>> = diagnostics.iter().map(|d| d.label.as_deref()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                Some("expected 'Num', found 'Text'"),
+                Some("expected a weave with the 'MULTIPLICATIVE' strand, found 'Truth'"),
+            ]
+        );
+
+        let rendered = diagnostics[0].render(src);
+        assert!(rendered.contains("2 | total = \"a\" + \"b\";"));
+        assert!(rendered.contains("  |         ^^^^^^^^^ expected 'Num', found 'Text'"));
+        assert!(diagnostics[1].render(src).contains("               ^^^^ expected"));
+    }
+
+    #[test]
+    fn comparing_unrelated_weaves_warns() {
+        let scanner = Scanner::init("mark m: Maybe<Num>;\nchant m == 3;\nchant 1 == \"1\";\nchant m == m;");
+        let ast = Parser::new(scanner.tokenize(), "weave_test.eira".to_string())
+            .parse()
+            .expect("parses");
+        let mut context = WeaveAnalyzerContext::new("weave_test.eira".to_string(), None, false);
+        let mut wa = WeaveAnalyzer::new(&mut context);
+        wa.analyze(ast).expect("an always false comparison is only a warning");
+
+        let warnings = wa.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("always false"));
+        assert_eq!(warnings[0].location.as_ref().map(|l| l.line), Some(3));
+    }
+
+    #[test]
+    fn suspicious_code_warns() {
+        let src = "spell f(n: Num):: Num {\n    mark unused = 1;\n    mark _quiet = 2;\n    fate n > 1 {\n        mark n = 2;\n        chant n;\n    }\n    release n;\n    chant n;\n}\ncast f with 1;";
+        let ast = Parser::new(Scanner::init(src).tokenize(), "weave_test.eira".to_string())
+            .parse()
+            .expect("parses");
+        let mut context = WeaveAnalyzerContext::new("weave_test.eira".to_string(), None, false);
+        let mut wa = WeaveAnalyzer::new(&mut context);
+        wa.analyze(ast).expect("only warnings");
+
+        let warnings: Vec<(String, usize)> = wa
+            .take_warnings()
+            .into_iter()
+            .map(|w| (w.message, w.location.map_or(0, |l| l.line)))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                ("The mark 'n' shadows another one from an outer realm.".to_string(), 5),
+                ("The statements after this one are never reached.".to_string(), 8),
+                ("The mark 'unused' is never read.".to_string(), 2),
+                ("The 'Num' released by the spell is ignored.".to_string(), 11),
+            ]
+        );
+    }
+
+    #[test]
+    fn unused_spells_and_binds_warn() {
+        let src = "spell lonely() {}\nspell _spare() {}\nspell used() {\n    spell inner() {}\n}\nbind answer = 42;\ncast used;";
+        let ast = Parser::new(Scanner::init(src).tokenize(), "weave_test.eira".to_string())
+            .parse()
+            .expect("parses");
+        let mut context = WeaveAnalyzerContext::new("weave_test.eira".to_string(), None, false);
+        let mut wa = WeaveAnalyzer::new(&mut context);
+        wa.analyze(ast).expect("only warnings");
+
+        let warnings: Vec<String> = wa.take_warnings().into_iter().map(|w| w.message).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "The spell 'inner' is never cast.",
+                "The spell 'lonely' is never cast.",
+                "The bind 'answer' is never read.",
+            ]
+        );
+    }
+
+    #[test]
+    fn marks_are_assigned_on_every_path_before_read() {
+        analyze_helper("mark a: Num;\nfate true { a = 1; } divert { a = 2; }\nchant a;")
+            .expect("assigned on both branches");
+        analyze_helper(
+            "spell f(c: Truth):: Num {\n    mark a: Num;\n    fate c { a = 1; } divert { release 0; }\n    release a;\n}\nchant cast f with true;",
+        )
+        .expect("the other branch releases");
+
+        for src in [
+            "mark a: Num;\nfate true { a = 1; }\nchant a;",
+            "mark a: Num;\nwhile false { a = 1; }\nchant a;",
+            "mark a: Num;\nchant a;",
+        ] {
+            let err = analyze_helper(src).expect_err("read before assignment");
+            assert!(err.contains("The mark 'a' is read before it is surely given a value!"));
+        }
+    }
+
+    #[test]
+    fn spells_release_on_every_path() {
+        analyze_helper(
+            "spell sign_of(n: Num):: Num {\n    fate n < 0 { release -1; } divert { release 1; }\n}",
+        )
+        .expect("both branches release");
+        analyze_helper(
+            "spell risky():: Num {\n    attempt { release 1; } mend (e) { invoke doom e; }\n}",
+        )
+        .expect("the mend dooms the scroll");
+
+        let err =
+            analyze_helper("spell sign_of(n: Num):: Num {\n    fate n < 0 { release -1; }\n}")
+                .expect_err("falls off the end");
+        assert!(err.contains(
+            "Not every path through the spell 'sign_of' releases the 'Num' it promises!"
+        ));
+    }
+
+    #[test]
+    fn deck_elements_keep_their_weave() {
+        analyze_helper("mark ys = [[1], [2]];\nys[0][0] = 3;\nchant ys[1][0] + 1;")
+            .expect("nested elements are Nums");
+
+        let err = analyze_helper("mark xs = [1, 2];\nchant xs[0] + true;").expect_err("Num + Truth");
+        assert!(err.contains("Cannot perform '+' on 'Num' and 'Truth'"));
+        let err = analyze_helper("mark xs = [1, 2];\nxs[0] = \"s\";").expect_err("a Text in Nums");
+        assert!(err.contains("The deck 'xs' holds 'Num' but was given 'Text'"));
+    }
+
+    #[test]
+    fn whole_literals_are_ints_where_ints_are_expected() {
+        analyze_helper("bind i: Int = 3;\nbind xs: Deck<Int> = [1, -2];\nchant xs[i] * 2 + i;")
+            .expect("the literals are Ints");
+
+        let err = analyze_helper("bind i: Int = 3;\nchant i + 0.5;").expect_err("Int + Num");
+        assert!(err.contains("Cannot mix 'Int' with 'Num' in '+'"));
+        let err = analyze_helper("bind i: Int = 2.5;").expect_err("a fraction");
+        assert!(err.contains("was woven as 'Int' but was given 'Num'"));
+    }
+
+    #[test]
+    fn judge_arms_share_the_judged_weave() {
+        analyze_helper("bind n: Int = 2;\nmark s: Text;\njudge n {\n    1 -> { s = \"one\"; }\n    divert -> { s = \"many\"; }\n}\nchant s;")
+            .expect("the divert assigns too");
+
+        let err = analyze_helper("judge 2 { \"two\" -> { chant 2; } }").expect_err("Text arm");
+        assert!(err.contains("The arm holds a 'Text' while the judged value is a 'Num'."));
+        let err = analyze_helper("judge [1] { [1] -> { chant 1; } }").expect_err("a Deck");
+        assert!(err.contains("does not contain the 'Equatable' strand"));
+    }
+
+    #[test]
+    fn only_the_loops_around_can_be_named() {
+        let err = analyze_helper("while outer: true {\n    while true { sever outr; }\n}")
+            .expect_err("no such loop");
+        assert!(
+            err.contains("There is no loop named 'outr' around the 'sever'. Did you mean 'outer'?")
+        );
+        let err =
+            analyze_helper("while outer: true {\n    spell f() { sever outer; }\n    cast f;\n}")
+                .expect_err("a spell can't sever the loop around it");
+        assert!(err.contains("'sever' cannot be used outside a loop circle!"));
+    }
+
+    #[test]
+    fn a_broken_attuned_spell_can_still_be_cast() {
+        let err = analyze_helper(
+            "tome Creature {\n    hp: Num,\n    spell heal(by: Nmu) :: Num {\n        release ego.hp;\n    }\n}\nbind c = ~Creature with { hp: 1 };\nchant cast c.heal with 5;",
+        )
+        .expect_err("Nmu is not a weave");
+        assert!(!err.is_empty());
+    }
+
+    #[test]
+    fn only_flowing_spells_offer() {
+        analyze_helper("spell f():: Stream<Num> {\n    offer 1;\n}\nflow n through cast f { chant n + 1; }")
+            .expect("a stream flows with its offers");
+
+        let err = analyze_helper("spell f():: Num {\n    offer 1;\n    release 2;\n}").expect_err("not flowing");
+        assert!(err.contains("The spell 'f' isn't flowing"));
+        let err = analyze_helper("spell f():: Stream<Num> {\n    offer \"one\";\n}").expect_err("a Text");
+        assert!(err.contains("expected to offer 'Num' but 'Text' was offered"));
+        let err = analyze_helper("spell f():: Stream<Num> {\n    release 1;\n}").expect_err("released a value");
+        assert!(err.contains("it can only release without one to end its stream"));
+        let err = analyze_helper("offer 1;").expect_err("outside a spell");
+        assert!(err.contains("Usage of 'offer' outside the spell scope."));
+    }
+
+    #[test]
+    fn only_conjurings_are_claimed() {
+        let stmts = analyze_helper("spell f():: Num {\n    release 1;\n}\nchant claim conjure cast f;")
+            .expect("a conjured cast is claimed for its release");
+        assert_eq!(first_expr(&stmts).weave(), Weave::Num);
+
+        let err = analyze_helper("chant claim 1;").expect_err("not a conjuring");
+        assert!(err.contains("Only a conjuring can be claimed, but got a 'Num' weave."));
+        let err = analyze_helper("spell f():: Stream<Num> {\n    offer 1;\n}\nconjure cast f;").expect_err("flowing");
+        assert!(err.contains("A flowing spell can't be conjured"));
+    }
 }
+